@@ -1,10 +1,8 @@
-extern crate serde_json;
-extern crate serde_ignored;
-extern crate ethjson;
+extern crate ethcore;
 
-use std::collections::BTreeSet;
+use std::io::Read;
 use std::{fs, env, process};
-use ethjson::spec::Spec;
+use ethcore::spec::{check_spec, SpecProblem};
 
 fn quit(s: &str) -> ! {
 	println!("{}", s);
@@ -20,28 +18,21 @@ fn main() {
 	}
 
 	let path = args.nth(1).expect("args.len() == 2; qed");
-	let file = match fs::File::open(&path) {
-		Ok(file) => file,
-		Err(_) => quit(&format!("{} could not be opened", path)),
-	};
-
-	let mut unused = BTreeSet::new();
-	let mut deserializer = serde_json::Deserializer::from_reader(file);
-
-	let spec: Result<Spec, _> = serde_ignored::deserialize(&mut deserializer, |field| {
-		unused.insert(field.to_string());
-	});
-
-	if let Err(err) = spec {
-		quit(&format!("{} {}", path, err.to_string()));
+	let mut json = Vec::new();
+	if fs::File::open(&path).and_then(|mut file| file.read_to_end(&mut json)).is_err() {
+		quit(&format!("{} could not be opened", path));
 	}
 
-	if !unused.is_empty() {
-		let err = unused.into_iter()
-			.map(|field| format!("{} unexpected field `{}`", path, field))
-			.collect::<Vec<_>>()
-			.join("\n");
-		quit(&err);
+	let problems = check_spec(&json);
+	let report = problems.iter()
+		.map(|problem| format!("{} {}", path, problem))
+		.collect::<Vec<_>>()
+		.join("\n");
+	if problems.iter().any(SpecProblem::is_error) {
+		quit(&report);
+	}
+	if !problems.is_empty() {
+		println!("{}", report);
 	}
 
 	println!("{} is valid", path);
@@ -27,6 +27,7 @@ use super::vmtype::VMType;
 pub struct Factory {
 	evm: VMType,
 	evm_cache: Arc<SharedCache>,
+	force_u256: bool,
 }
 
 impl Factory {
@@ -38,7 +39,7 @@ impl Factory {
 			VMType::Jit => {
 				Box::new(super::jit::JitEvm::default())
 			},
-			VMType::Interpreter => if Self::can_fit_in_usize(gas) {
+			VMType::Interpreter => if !self.force_u256 && Self::can_fit_in_usize(gas) {
 				Box::new(super::interpreter::Interpreter::<usize>::new(self.evm_cache.clone()))
 			} else {
 				Box::new(super::interpreter::Interpreter::<U256>::new(self.evm_cache.clone()))
@@ -51,7 +52,7 @@ impl Factory {
 	#[cfg(not(feature = "jit"))]
 	pub fn create(&self, gas: U256) -> Box<Vm> {
 		match self.evm {
-			VMType::Interpreter => if Self::can_fit_in_usize(gas) {
+			VMType::Interpreter => if !self.force_u256 && Self::can_fit_in_usize(gas) {
 				Box::new(super::interpreter::Interpreter::<usize>::new(self.evm_cache.clone()))
 			} else {
 				Box::new(super::interpreter::Interpreter::<U256>::new(self.evm_cache.clone()))
@@ -65,6 +66,16 @@ impl Factory {
 		Factory {
 			evm: evm,
 			evm_cache: Arc::new(SharedCache::new(cache_size)),
+			force_u256: false,
+		}
+	}
+
+	/// Create new instance of specific `VMType` factory, which runs the interpreter
+	/// with `U256` gas even if the supplied gas fits in `usize`.
+	pub fn new_u256(evm: VMType, cache_size: usize) -> Self {
+		Factory {
+			force_u256: true,
+			..Self::new(evm, cache_size)
 		}
 	}
 
@@ -80,6 +91,7 @@ impl Default for Factory {
 		Factory {
 			evm: VMType::Jit,
 			evm_cache: Arc::new(SharedCache::default()),
+			force_u256: false,
 		}
 	}
 
@@ -89,6 +101,7 @@ impl Default for Factory {
 		Factory {
 			evm: VMType::Interpreter,
 			evm_cache: Arc::new(SharedCache::default()),
+			force_u256: false,
 		}
 	}
 }
@@ -98,6 +111,11 @@ fn test_create_vm() {
 	let _vm = Factory::default().create(U256::zero());
 }
 
+#[test]
+fn test_create_u256_vm() {
+	let _vm = Factory::new_u256(VMType::Interpreter, 1024).create(U256::zero());
+}
+
 /// Create tests by injecting different VM factories
 #[macro_export]
 macro_rules! evm_test(
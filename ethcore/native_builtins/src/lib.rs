@@ -0,0 +1,32 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain-specific native builtins.
+//!
+//! Parity built with the `native-builtins` feature registers every builtin returned by
+//! `builtins` at startup, so that chain specs can refer to them by name. Chains needing
+//! their own precompiles add them here, or replace this crate with a `[patch]` section,
+//! instead of changing `ethcore`.
+
+extern crate ethcore;
+
+use std::sync::Arc;
+use ethcore::builtin::Impl;
+
+/// Names and implementations of the builtins to register.
+pub fn builtins() -> Vec<(&'static str, Arc<Impl>)> {
+	Vec::new()
+}
@@ -31,6 +31,7 @@ const VALIDATOR_SET_ABI: &'static str = include_str!("res/validator_set.json");
 const VALIDATOR_REPORT_ABI: &'static str = include_str!("res/validator_report.json");
 const PEER_SET_ABI: &'static str = include_str!("res/peer_set.json");
 const TX_ACL_ABI: &'static str = include_str!("res/tx_acl.json");
+const BLOCK_REWARD_ABI: &'static str = include_str!("res/block_reward.json");
 
 const TEST_VALIDATOR_SET_ABI: &'static str = include_str!("res/test_validator_set.json");
 
@@ -59,6 +60,7 @@ fn main() {
 	build_file("ValidatorReport", VALIDATOR_REPORT_ABI, "validator_report.rs");
 	build_file("PeerSet", PEER_SET_ABI, "peer_set.rs");
 	build_file("TransactAcl", TX_ACL_ABI, "tx_acl.rs");
+	build_file("BlockReward", BLOCK_REWARD_ABI, "block_reward.rs");
 
 	build_test_contracts();
 }
@@ -432,6 +432,7 @@ impl<'x> OpenBlock<'x> {
 		self.set_extra_data(header.extra_data().clone()).unwrap_or_else(|e| warn!("Couldn't set extradata: {}. Ignoring.", e));
 		self.set_uncles_hash(header.uncles_hash().clone());
 		self.set_transactions_root(header.transactions_root().clone());
+		self.block.header.set_seal(header.seal().to_vec());
 	}
 
 	/// Turn this into a `ClosedBlock`.
@@ -528,6 +529,8 @@ impl ClosedBlock {
 		// revert rewards (i.e. set state back at last transaction's state).
 		let mut block = self.block;
 		block.state = self.unclosed_state;
+		// the engine may have filled in parts of the seal on close.
+		block.header.set_seal(Vec::new());
 		OpenBlock {
 			block: block,
 			engine: engine,
@@ -542,12 +545,14 @@ impl LockedBlock {
 	/// Provide a valid seal in order to turn this into a `SealedBlock`.
 	///
 	/// NOTE: This does not check the validity of `seal` with the engine.
-	pub fn seal(self, engine: &EthEngine, seal: Vec<Bytes>) -> Result<SealedBlock, BlockError> {
+	pub fn seal(self, engine: &EthEngine, seal: Vec<Bytes>) -> Result<SealedBlock, Error> {
 		let mut s = self;
-		if seal.len() != engine.seal_fields() {
-			return Err(BlockError::InvalidSealArity(Mismatch{expected: engine.seal_fields(), found: seal.len()}));
+		let expected_seal_fields = engine.seal_fields(s.header());
+		if seal.len() != expected_seal_fields {
+			return Err(BlockError::InvalidSealArity(Mismatch{expected: expected_seal_fields, found: seal.len()}).into());
 		}
 		s.block.header.set_seal(seal);
+		engine.on_seal_block(&mut s.block)?;
 		Ok(SealedBlock { block: s.block, uncle_bytes: s.uncle_bytes })
 	}
 
@@ -561,6 +566,9 @@ impl LockedBlock {
 	) -> Result<SealedBlock, (Error, LockedBlock)> {
 		let mut s = self;
 		s.block.header.set_seal(seal);
+		if let Err(e) = engine.on_seal_block(&mut s.block) {
+			return Err((e, s));
+		}
 
 		// TODO: passing state context to avoid engines owning it?
 		match engine.verify_local_seal(&s.block.header) {
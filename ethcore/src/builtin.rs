@@ -14,10 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Built-in contracts.
+//!
+//! Besides the Ethereum builtins, chain specs may refer to native contracts registered with
+//! `register_builtin`. Binaries embedding chain-specific precompiles should register them at
+//! startup, before any spec using them is loaded; Parity does so for the builtins of the
+//! `native-builtins` crate when built with the feature of the same name.
+
 use std::cmp::{max, min};
+use std::collections::HashMap;
 use std::io::{self, Read};
+use std::sync::Arc;
 
-use byteorder::{ByteOrder, BigEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
 use crypto::sha2::Sha256 as Sha256Digest;
 use crypto::ripemd160::Ripemd160 as Ripemd160Digest;
 use crypto::digest::Digest;
@@ -28,7 +37,9 @@ use ethereum_types::{H256, U256};
 use bytes::BytesRef;
 use ethkey::{Signature, recover as ec_recover};
 use ethjson;
+use parking_lot::RwLock;
 
+/// Execution error of a built-in contract.
 #[derive(Debug)]
 pub struct Error(pub &'static str);
 
@@ -86,6 +97,22 @@ impl Pricer for AltBn128PairingPricer {
 	}
 }
 
+/// A BLAKE2b compression function pricing model. This computes a price per round.
+struct Blake2FPricer {
+	gas_per_round: u64,
+}
+
+impl Pricer for Blake2FPricer {
+	fn cost(&self, input: &[u8]) -> U256 {
+		// inputs too short to hold the rounds are rejected on execution.
+		if input.len() < 4 {
+			return U256::zero();
+		}
+		let rounds = BigEndian::read_u32(&input[..4]);
+		U256::from(self.gas_per_round) * U256::from(rounds)
+	}
+}
+
 impl Pricer for ModexpPricer {
 	fn cost(&self, input: &[u8]) -> U256 {
 		let mut reader = input.chain(io::repeat(0));
@@ -158,6 +185,7 @@ impl ModexpPricer {
 pub struct Builtin {
 	pricer: Box<Pricer>,
 	native: Box<Impl>,
+	name: String,
 	activate_at: u64,
 }
 
@@ -170,12 +198,20 @@ impl Builtin {
 		self.native.execute(input, output)
 	}
 
+	/// Name of the builtin as given in the chain spec.
+	pub fn name(&self) -> &str { &self.name }
+
 	/// Whether the builtin is activated at the given block number.
 	pub fn is_active(&self, at: u64) -> bool { at >= self.activate_at }
 }
 
-impl From<ethjson::spec::Builtin> for Builtin {
-	fn from(b: ethjson::spec::Builtin) -> Self {
+impl Builtin {
+	/// Create a builtin from its chain spec definition.
+	///
+	/// Fails if the name refers neither to an Ethereum builtin nor to one registered
+	/// with `register_builtin`.
+	pub fn from_json(b: ethjson::spec::Builtin) -> Result<Builtin, Error> {
+		let native = ethereum_builtin(&b.name).ok_or(Error("Unknown builtin name"))?;
 		let pricer: Box<Pricer> = match b.pricing {
 			ethjson::spec::Pricing::Linear(linear) => {
 				Box::new(Linear {
@@ -199,19 +235,57 @@ impl From<ethjson::spec::Builtin> for Builtin {
 					pair: pricer.pair,
 				})
 			}
+			ethjson::spec::Pricing::Blake2F(pricer) => {
+				Box::new(Blake2FPricer {
+					gas_per_round: pricer.gas_per_round,
+				})
+			}
 		};
 
-		Builtin {
+		Ok(Builtin {
 			pricer: pricer,
-			native: ethereum_builtin(&b.name),
+			native: native,
+			name: b.name,
 			activate_at: b.activate_at.map(Into::into).unwrap_or(0),
-		}
+		})
 	}
 }
 
-// Ethereum builtin creator.
-fn ethereum_builtin(name: &str) -> Box<Impl> {
-	match name {
+lazy_static! {
+	static ref REGISTERED: RwLock<HashMap<String, Arc<Impl>>> = RwLock::new(HashMap::new());
+}
+
+/// Register a native implementation of a built-in contract under the given name.
+///
+/// Chain specs can then refer to it like to any of the Ethereum builtins. Names of Ethereum
+/// builtins and names which are already registered are rejected.
+pub fn register_builtin(name: &str, native: Arc<Impl>) -> Result<(), Error> {
+	if native_ethereum_builtin(name).is_some() {
+		return Err(Error("Builtin name is reserved for an Ethereum builtin"));
+	}
+
+	let mut registered = REGISTERED.write();
+	if registered.contains_key(name) {
+		return Err(Error("Builtin name is already registered"));
+	}
+	registered.insert(name.to_owned(), native);
+	Ok(())
+}
+
+/// Whether a builtin contract with the given name exists.
+pub fn is_builtin_name(name: &str) -> bool {
+	ethereum_builtin(name).is_some()
+}
+
+// Ethereum builtin creator, falling back to registered builtins.
+fn ethereum_builtin(name: &str) -> Option<Box<Impl>> {
+	native_ethereum_builtin(name).or_else(|| {
+		REGISTERED.read().get(name).map(|native| Box::new(Registered(native.clone())) as Box<Impl>)
+	})
+}
+
+fn native_ethereum_builtin(name: &str) -> Option<Box<Impl>> {
+	let native = match name {
 		"identity" => Box::new(Identity) as Box<Impl>,
 		"ecrecover" => Box::new(EcRecover) as Box<Impl>,
 		"sha256" => Box::new(Sha256) as Box<Impl>,
@@ -220,8 +294,10 @@ fn ethereum_builtin(name: &str) -> Box<Impl> {
 		"alt_bn128_add" => Box::new(Bn128AddImpl) as Box<Impl>,
 		"alt_bn128_mul" => Box::new(Bn128MulImpl) as Box<Impl>,
 		"alt_bn128_pairing" => Box::new(Bn128PairingImpl) as Box<Impl>,
-		_ => panic!("invalid builtin name: {}", name),
-	}
+		"blake2_f" => Box::new(Blake2F) as Box<Impl>,
+		_ => return None,
+	};
+	Some(native)
 }
 
 // Ethereum builtins:
@@ -231,6 +307,8 @@ fn ethereum_builtin(name: &str) -> Box<Impl> {
 // - sha256
 // - ripemd160
 // - modexp (EIP198)
+// - alt_bn128 addition, multiplication and pairing check (EIP196, EIP197)
+// - BLAKE2b compression function F (EIP152)
 
 #[derive(Debug)]
 struct Identity;
@@ -256,6 +334,18 @@ struct Bn128MulImpl;
 #[derive(Debug)]
 struct Bn128PairingImpl;
 
+#[derive(Debug)]
+struct Blake2F;
+
+// A builtin registered with `register_builtin`.
+struct Registered(Arc<Impl>);
+
+impl Impl for Registered {
+	fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), Error> {
+		self.0.execute(input, output)
+	}
+}
+
 impl Impl for Identity {
 	fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), Error> {
 		output.write(0, input);
@@ -546,9 +636,109 @@ impl Bn128PairingImpl {
 	}
 }
 
+/// Length of the BLAKE2b compression function input: rounds, state, message, offset counters and final block flag.
+const BLAKE2_F_INPUT_LEN: usize = 4 + 64 + 128 + 16 + 1;
+
+const BLAKE2B_IV: [u64; 8] = [
+	0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+	0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+	[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+	[14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+	[11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+	[7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+	[9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+	[2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+	[12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+	[13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+	[6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+	[10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+// BLAKE2b mixing function G, as defined in RFC 7693.
+fn blake2b_g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+	v[d] = (v[d] ^ v[a]).rotate_right(32);
+	v[c] = v[c].wrapping_add(v[d]);
+	v[b] = (v[b] ^ v[c]).rotate_right(24);
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+	v[d] = (v[d] ^ v[a]).rotate_right(16);
+	v[c] = v[c].wrapping_add(v[d]);
+	v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+// BLAKE2b compression function F, as defined in RFC 7693, with a variable number of rounds.
+fn blake2b_f(h: &mut [u64; 8], m: &[u64; 16], t: &[u64; 2], f: bool, rounds: u32) {
+	let mut v = [0u64; 16];
+	v[..8].copy_from_slice(&h[..]);
+	v[8..].copy_from_slice(&BLAKE2B_IV);
+	v[12] ^= t[0];
+	v[13] ^= t[1];
+	if f {
+		v[14] = !v[14];
+	}
+
+	for round in 0..rounds as usize {
+		let s = &BLAKE2B_SIGMA[round % 10];
+		blake2b_g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+		blake2b_g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+		blake2b_g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+		blake2b_g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+		blake2b_g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+		blake2b_g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+		blake2b_g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+		blake2b_g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+	}
+
+	for i in 0..8 {
+		h[i] ^= v[i] ^ v[i + 8];
+	}
+}
+
+impl Impl for Blake2F {
+	fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), Error> {
+		if input.len() != BLAKE2_F_INPUT_LEN {
+			return Err("Invalid input length, must be 213 bytes".into());
+		}
+
+		let f = match input[BLAKE2_F_INPUT_LEN - 1] {
+			0 => false,
+			1 => true,
+			_ => return Err("Invalid final block indicator flag, must be 0 or 1".into()),
+		};
+
+		let rounds = BigEndian::read_u32(&input[..4]);
+
+		// state, message and offset counters are little-endian words.
+		let mut h = [0u64; 8];
+		for (i, word) in h.iter_mut().enumerate() {
+			*word = LittleEndian::read_u64(&input[4 + i * 8..]);
+		}
+		let mut m = [0u64; 16];
+		for (i, word) in m.iter_mut().enumerate() {
+			*word = LittleEndian::read_u64(&input[68 + i * 8..]);
+		}
+		let t = [LittleEndian::read_u64(&input[196..]), LittleEndian::read_u64(&input[204..])];
+
+		blake2b_f(&mut h, &m, &t, f, rounds);
+
+		let mut out = [0u8; 64];
+		for (i, word) in h.iter().enumerate() {
+			LittleEndian::write_u64(&mut out[i * 8..], *word);
+		}
+		output.write(0, &out);
+
+		Ok(())
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{Builtin, Linear, ethereum_builtin, Pricer, ModexpPricer, modexp as me};
+	use std::sync::Arc;
+	use super::{Builtin, Linear, ethereum_builtin, Pricer, ModexpPricer, Blake2FPricer, modexp as me};
+	use super::{Impl, Error, register_builtin, is_builtin_name};
 	use ethjson;
 	use ethereum_types::U256;
 	use bytes::BytesRef;
@@ -590,7 +780,7 @@ mod tests {
 
 	#[test]
 	fn identity() {
-		let f = ethereum_builtin("identity");
+		let f = ethereum_builtin("identity").unwrap();
 
 		let i = [0u8, 1, 2, 3];
 
@@ -610,7 +800,7 @@ mod tests {
 
 	#[test]
 	fn sha256() {
-		let f = ethereum_builtin("sha256");
+		let f = ethereum_builtin("sha256").unwrap();
 
 		let i = [0u8; 0];
 
@@ -633,7 +823,7 @@ mod tests {
 
 	#[test]
 	fn ripemd160() {
-		let f = ethereum_builtin("ripemd160");
+		let f = ethereum_builtin("ripemd160").unwrap();
 
 		let i = [0u8; 0];
 
@@ -652,7 +842,7 @@ mod tests {
 
 	#[test]
 	fn ecrecover() {
-		let f = ethereum_builtin("ecrecover");
+		let f = ethereum_builtin("ecrecover").unwrap();
 
 		let i = FromHex::from_hex("47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000000000000000000000000000000000000000000000000000001b650acf9d3f5f0a2c799776a1254355d5f4061762a237396a99a0e0e3fc2bcd6729514a0dacb2e623ac4abd157cb18163ff942280db4d5caad66ddf941ba12e03").unwrap();
 
@@ -705,7 +895,8 @@ mod tests {
 
 		let f = Builtin {
 			pricer: Box::new(ModexpPricer { divisor: 20 }),
-			native: ethereum_builtin("modexp"),
+			native: ethereum_builtin("modexp").unwrap(),
+			name: "modexp".to_owned(),
 			activate_at: 0,
 		};
 
@@ -817,7 +1008,8 @@ mod tests {
 
 		let f = Builtin {
 			pricer: Box::new(Linear { base: 0, word: 0 }),
-			native: ethereum_builtin("alt_bn128_add"),
+			native: ethereum_builtin("alt_bn128_add").unwrap(),
+			name: "alt_bn128_add".to_owned(),
 			activate_at: 0,
 		};
 
@@ -878,7 +1070,8 @@ mod tests {
 
 		let f = Builtin {
 			pricer: Box::new(Linear { base: 0, word: 0 }),
-			native: ethereum_builtin("alt_bn128_mul"),
+			native: ethereum_builtin("alt_bn128_mul").unwrap(),
+			name: "alt_bn128_mul".to_owned(),
 			activate_at: 0,
 		};
 
@@ -918,7 +1111,8 @@ mod tests {
 	fn builtin_pairing() -> Builtin {
 		Builtin {
 			pricer: Box::new(Linear { base: 0, word: 0 }),
-			native: ethereum_builtin("alt_bn128_pairing"),
+			native: ethereum_builtin("alt_bn128_pairing").unwrap(),
+			name: "alt_bn128_pairing".to_owned(),
 			activate_at: 0,
 		}
 	}
@@ -992,9 +1186,149 @@ mod tests {
 	}
 
 	#[test]
-	#[should_panic]
+	fn blake2_f_cost() {
+		let pricer = Blake2FPricer { gas_per_round: 2 };
+		let mut input = vec![0u8; 213];
+		input[3] = 12;
+		assert_eq!(pricer.cost(&input), U256::from(24));
+		input[0] = 0xff;
+		assert_eq!(pricer.cost(&input), U256::from(0xff00000cu64) * U256::from(2));
+		assert_eq!(pricer.cost(&[0u8; 3]), U256::zero());
+	}
+
+	#[test]
+	fn blake2_f() {
+		let f = Builtin {
+			pricer: Box::new(Blake2FPricer { gas_per_round: 1 }),
+			native: ethereum_builtin("blake2_f").unwrap(),
+			name: "blake2_f".to_owned(),
+			activate_at: 0,
+		};
+
+		// rounds: 0
+		{
+			let input = FromHex::from_hex("\
+				0000000048c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+				19cde05b616263000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				000000000300000000000000000000000000000001").unwrap();
+			let expected = FromHex::from_hex("\
+				08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b").unwrap();
+
+			let mut output = vec![0u8; 64];
+			f.execute(&input[..], &mut BytesRef::Fixed(&mut output[..])).expect("Builtin should not fail");
+			assert_eq!(output, expected);
+			assert_eq!(f.cost(&input[..]), U256::from(0));
+		}
+
+		// rounds: 12, blake2b("abc")
+		{
+			let input = FromHex::from_hex("\
+				0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+				19cde05b616263000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				000000000300000000000000000000000000000001").unwrap();
+			let expected = FromHex::from_hex("\
+				ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap();
+
+			let mut output = vec![0u8; 64];
+			f.execute(&input[..], &mut BytesRef::Fixed(&mut output[..])).expect("Builtin should not fail");
+			assert_eq!(output, expected);
+			assert_eq!(f.cost(&input[..]), U256::from(12));
+		}
+
+		// rounds: 12, not the final block
+		{
+			let input = FromHex::from_hex("\
+				0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+				19cde05b616263000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				000000000300000000000000000000000000000000").unwrap();
+			let expected = FromHex::from_hex("\
+				75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d2875298743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735").unwrap();
+
+			let mut output = vec![0u8; 64];
+			f.execute(&input[..], &mut BytesRef::Fixed(&mut output[..])).expect("Builtin should not fail");
+			assert_eq!(output, expected);
+			assert_eq!(f.cost(&input[..]), U256::from(12));
+		}
+
+		// rounds: 1
+		{
+			let input = FromHex::from_hex("\
+				0000000148c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+				19cde05b616263000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+				000000000300000000000000000000000000000001").unwrap();
+			let expected = FromHex::from_hex("\
+				b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fba551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421").unwrap();
+
+			let mut output = vec![0u8; 64];
+			f.execute(&input[..], &mut BytesRef::Fixed(&mut output[..])).expect("Builtin should not fail");
+			assert_eq!(output, expected);
+			assert_eq!(f.cost(&input[..]), U256::from(1));
+		}
+
+		// empty input
+		let mut output = vec![0u8; 64];
+		let res = f.execute(&[], &mut BytesRef::Fixed(&mut output[..]));
+		assert!(res.is_err());
+		assert_eq!(res.unwrap_err().0, "Invalid input length, must be 213 bytes");
+	}
+
+	#[test]
+	fn blake2_f_invalid_input() {
+		let f = || Builtin {
+			pricer: Box::new(Blake2FPricer { gas_per_round: 1 }),
+			native: ethereum_builtin("blake2_f").unwrap(),
+			name: "blake2_f".to_owned(),
+			activate_at: 0,
+		};
+		let input: Vec<u8> = FromHex::from_hex("\
+			0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+			19cde05b616263000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+			00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+			000000000300000000000000000000000000000001").unwrap();
+
+		let execute = |input: &[u8]| {
+			let mut output = vec![0u8; 64];
+			f().execute(input, &mut BytesRef::Fixed(&mut output[..]))
+		};
+
+		// well formed
+		assert!(execute(&input).is_ok());
+
+		// one byte too short
+		let res = execute(&input[1..]);
+		assert!(res.is_err());
+		assert_eq!(res.unwrap_err().0, "Invalid input length, must be 213 bytes");
+
+		// one byte too long
+		let mut long = input.clone();
+		long.push(0);
+		let res = execute(&long);
+		assert!(res.is_err());
+		assert_eq!(res.unwrap_err().0, "Invalid input length, must be 213 bytes");
+
+		// final block flag is neither 0 nor 1
+		let mut flag = input.clone();
+		flag[212] = 2;
+		let res = execute(&flag);
+		assert!(res.is_err());
+		assert_eq!(res.unwrap_err().0, "Invalid final block indicator flag, must be 0 or 1");
+	}
+
+	#[test]
 	fn from_unknown_linear() {
-		let _ = ethereum_builtin("foo");
+		assert!(ethereum_builtin("foo").is_none());
+		assert!(Builtin::from_json(ethjson::spec::Builtin {
+			name: "foo".to_owned(),
+			pricing: ethjson::spec::Pricing::Linear(ethjson::spec::Linear {
+				base: 10,
+				word: 20,
+			}),
+			activate_at: None,
+		}).is_err());
 	}
 
 	#[test]
@@ -1002,7 +1336,8 @@ mod tests {
 		let pricer = Box::new(Linear { base: 10, word: 20} );
 		let b = Builtin {
 			pricer: pricer as Box<Pricer>,
-			native: ethereum_builtin("identity"),
+			native: ethereum_builtin("identity").unwrap(),
+			name: "identity".to_owned(),
 			activate_at: 100_000,
 		};
 
@@ -1016,7 +1351,8 @@ mod tests {
 		let pricer = Box::new(Linear { base: 10, word: 20 });
 		let b = Builtin {
 			pricer: pricer as Box<Pricer>,
-			native: ethereum_builtin("identity"),
+			native: ethereum_builtin("identity").unwrap(),
+			name: "identity".to_owned(),
 			activate_at: 1,
 		};
 
@@ -1033,14 +1369,14 @@ mod tests {
 
 	#[test]
 	fn from_json() {
-		let b = Builtin::from(ethjson::spec::Builtin {
+		let b = Builtin::from_json(ethjson::spec::Builtin {
 			name: "identity".to_owned(),
 			pricing: ethjson::spec::Pricing::Linear(ethjson::spec::Linear {
 				base: 10,
 				word: 20,
 			}),
 			activate_at: None,
-		});
+		}).unwrap();
 
 		assert_eq!(b.cost(&[0; 0]), U256::from(10));
 		assert_eq!(b.cost(&[0; 1]), U256::from(30));
@@ -1052,4 +1388,45 @@ mod tests {
 		b.execute(&i[..], &mut BytesRef::Fixed(&mut o[..])).expect("Builtin should not fail");
 		assert_eq!(i, o);
 	}
+
+	struct Reverse;
+
+	impl Impl for Reverse {
+		fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), Error> {
+			let reversed: Vec<u8> = input.iter().rev().cloned().collect();
+			output.write(0, &reversed);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn registered_builtin() {
+		assert!(!is_builtin_name("test_reverse"));
+		register_builtin("test_reverse", Arc::new(Reverse)).unwrap();
+		assert!(is_builtin_name("test_reverse"));
+
+		let b = Builtin::from_json(ethjson::spec::Builtin {
+			name: "test_reverse".to_owned(),
+			pricing: ethjson::spec::Pricing::Linear(ethjson::spec::Linear {
+				base: 10,
+				word: 20,
+			}),
+			activate_at: None,
+		}).unwrap();
+
+		assert_eq!(b.cost(&[0; 1]), U256::from(30));
+
+		let i = [0u8, 1, 2, 3];
+		let mut o = [255u8; 4];
+		b.execute(&i[..], &mut BytesRef::Fixed(&mut o[..])).expect("Builtin should not fail");
+		assert_eq!(o, [3u8, 2, 1, 0]);
+	}
+
+	#[test]
+	fn register_builtin_rejects_known_names() {
+		assert!(register_builtin("identity", Arc::new(Reverse)).is_err());
+
+		register_builtin("test_duplicate", Arc::new(Reverse)).unwrap();
+		assert!(register_builtin("test_duplicate", Arc::new(Reverse)).is_err());
+	}
 }
@@ -45,6 +45,7 @@ use client::{
 };
 use encoded;
 use engines::{EthEngine, EpochTransition};
+use engines::evidence::Evidence;
 use error::{ImportError, ExecutionError, CallError, BlockError, ImportResult, Error as EthcoreError};
 use vm::{EnvInfo, LastHashes};
 use evm::{Factory as EvmFactory, Schedule};
@@ -68,7 +69,7 @@ use state::{self, State};
 use trace;
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace::FlatTransactionTraces;
-use transaction::{self, LocalizedTransaction, UnverifiedTransaction, SignedTransaction, Transaction, PendingTransaction, Action};
+use transaction::{LocalizedTransaction, UnverifiedTransaction, SignedTransaction, Transaction, PendingTransaction, Action};
 use types::filter::Filter;
 use types::mode::Mode as IpcMode;
 use verification;
@@ -189,6 +190,7 @@ impl Client {
 			vm: EvmFactory::new(config.vm_type.clone(), config.jump_table_size),
 			trie: trie_factory,
 			accountdb: Default::default(),
+			wasm: Default::default(),
 		};
 
 		let journal_db = journaldb::new(db.clone(), config.pruning, ::db::COL_STATE);
@@ -1163,11 +1165,13 @@ impl Client {
 
 		let state_diff = analytics.state_diffing;
 
-		match (analytics.transaction_tracing, analytics.vm_tracing) {
-			(true, true) => call(state, env_info, machine, state_diff, t, TransactOptions::with_tracing_and_vm_tracing()),
-			(true, false) => call(state, env_info, machine, state_diff, t, TransactOptions::with_tracing()),
-			(false, true) => call(state, env_info, machine, state_diff, t, TransactOptions::with_vm_tracing()),
-			(false, false) => call(state, env_info, machine, state_diff, t, TransactOptions::with_no_tracing()),
+		match (analytics.transaction_tracing, analytics.vm_tracing, analytics.debug_logging) {
+			(true, true, _) => call(state, env_info, machine, state_diff, t, TransactOptions::with_tracing_and_vm_tracing()),
+			(true, false, true) => call(state, env_info, machine, state_diff, t, TransactOptions::with_tracing_and_debug_logging()),
+			(true, false, false) => call(state, env_info, machine, state_diff, t, TransactOptions::with_tracing()),
+			(false, true, _) => call(state, env_info, machine, state_diff, t, TransactOptions::with_vm_tracing()),
+			(false, false, true) => call(state, env_info, machine, state_diff, t, TransactOptions::with_debug_logging()),
+			(false, false, false) => call(state, env_info, machine, state_diff, t, TransactOptions::with_no_tracing()),
 		}
 	}
 
@@ -1193,11 +1197,22 @@ impl snapshot::DatabaseRestore for Client {
 		let mut tracedb = self.tracedb.write();
 		self.miner.clear();
 		let db = self.db.write();
+		let genesis_transition = chain.epoch_transition(0, chain.genesis_hash());
 		db.restore(new_db)?;
 
 		let cache_size = state_db.cache_size();
 		*state_db = StateDB::new(journaldb::new(db.clone(), self.pruning, ::db::COL_STATE), cache_size);
 		*chain = Arc::new(BlockChain::new(self.config.blockchain.clone(), &[], db.clone()));
+
+		// restorations which don't carry the genesis epoch keep the proven one.
+		if let Some(transition) = genesis_transition {
+			if chain.epoch_transition(0, transition.block_hash).is_none() {
+				let mut batch = DBTransaction::new();
+				chain.insert_epoch_transition(&mut batch, 0, transition);
+				db.write_buffered(batch);
+				db.flush().map_err(ClientError::Database)?;
+			}
+		}
 		*tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
 		Ok(())
 	}
@@ -1623,6 +1638,10 @@ impl BlockChainClient for Client {
 		self.chain.read().block_receipts(hash).map(|receipts| ::rlp::encode(&receipts).into_vec())
 	}
 
+	fn verify_header_chain(&self, headers: &[Header]) -> Result<(), EthcoreError> {
+		verification::verify_header_chain(headers, &*self.engine)
+	}
+
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError> {
 		use verification::queue::kind::BlockLike;
 		use verification::queue::kind::blocks::Unverified;
@@ -1766,6 +1785,11 @@ impl BlockChainClient for Client {
 		}
 	}
 
+	fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction> {
+		let number = self.chain.read().best_block_number();
+		self.miner.transaction(number, hash)
+	}
+
 	fn ready_transactions(&self) -> Vec<PendingTransaction> {
 		let (number, timestamp) = {
 			let chain = self.chain.read();
@@ -1774,6 +1798,10 @@ impl BlockChainClient for Client {
 		self.miner.ready_transactions(number, timestamp)
 	}
 
+	fn local_transactions(&self) -> HashSet<H256> {
+		self.miner.local_transactions().keys().cloned().collect()
+	}
+
 	fn queue_consensus_message(&self, message: Bytes) {
 		let channel = self.io_channel.lock().clone();
 		if let Err(e) = channel.send(ClientIoMessage::NewMessage(message)) {
@@ -1812,7 +1840,7 @@ impl BlockChainClient for Client {
 			})
 	}
 
-	fn transact_contract(&self, address: Address, data: Bytes) -> Result<transaction::ImportResult, EthcoreError> {
+	fn transact_contract(&self, address: Address, data: Bytes) -> Result<H256, EthcoreError> {
 		let transaction = Transaction {
 			nonce: self.latest_nonce(&self.miner.author()),
 			action: Action::Call(address),
@@ -1824,7 +1852,33 @@ impl BlockChainClient for Client {
 		let chain_id = self.engine.signing_chain_id(&self.latest_env_info());
 		let signature = self.engine.sign(transaction.hash(chain_id))?;
 		let signed = SignedTransaction::new(transaction.with_signature(signature, chain_id))?;
-		self.miner.import_own_transaction(self, signed.into())
+		let hash = signed.hash();
+		self.miner.import_own_transaction(self, signed.into()).map(|_| hash)
+	}
+
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> {
+		self.engine.misbehaviour_evidence()
+	}
+
+	fn add_notify(&self, target: Arc<ChainNotify>) {
+		Client::add_notify(self, target)
+	}
+
+	fn local_data(&self, key: &[u8]) -> Option<Bytes> {
+		self.db.read().get(::db::COL_NODE_INFO, key)
+			.unwrap_or_else(|e| {
+				warn!(target: "client", "Unable to read local data: {}", e);
+				None
+			})
+			.map(|value| value.into_vec())
+	}
+
+	fn set_local_data(&self, key: &[u8], value: Bytes) {
+		let mut batch = DBTransaction::new();
+		batch.put_vec(::db::COL_NODE_INFO, key, value);
+		if let Err(e) = self.db.read().write(batch) {
+			warn!(target: "client", "Unable to write local data: {}", e);
+		}
 	}
 
 	fn registrar_address(&self) -> Option<Address> {
@@ -18,11 +18,21 @@
 
 use std::fmt;
 use std::sync::Arc;
-use ethereum_types::{H256, U256};
+use std::collections::{BTreeMap, BTreeSet};
+use ethereum_types::{H256, U256, Address};
 use journaldb;
-use {trie, kvdb_memorydb, bytes};
+use {trie, kvdb_memorydb, bytes, rlp};
+use hash::keccak;
 use kvdb::{self, KeyValueDB};
-use {state, state_db, client, executive, trace, transaction, db, spec, pod_state};
+use {state, state_db, client, executive, trace, transaction, db, spec, pod_state, ethjson};
+use block::Block;
+use blockchain::{BlockChain, BlockProvider};
+use client::BlockChainClient;
+use types::blockchain_info::BlockChainInfo;
+use header::{BlockNumber, Header};
+use io::IoChannel;
+use log_entry::LogEntry;
+use miner::Miner;
 use factory::Factories;
 use evm::{self, VMType, FinalizationResult};
 use vm::{self, ActionParams};
@@ -40,6 +50,8 @@ pub enum EvmTestError {
 	Database(kvdb::Error),
 	/// Post-condition failure,
 	PostCondition(String),
+	/// Missing or inconsistent chain data.
+	Chain(String),
 }
 
 impl<E: Into<::error::Error>> From<E> for EvmTestError {
@@ -58,6 +70,7 @@ impl fmt::Display for EvmTestError {
 			ClientError(ref err) => write!(fmt, "{}", err),
 			Database(ref err) => write!(fmt, "DB: {}", err),
 			PostCondition(ref err) => write!(fmt, "{}", err),
+			Chain(ref err) => write!(fmt, "Chain: {}", err),
 		}
 	}
 }
@@ -74,6 +87,16 @@ lazy_static! {
 	pub static ref BYZANTIUM_TRANSITION: spec::Spec = ethereum::new_transition_test();
 }
 
+/// Block read from an existing client database.
+pub struct ChainBlock {
+	/// Block header.
+	pub header: Header,
+	/// Environment the block transactions are executed in.
+	pub env_info: client::EnvInfo,
+	/// Block transactions.
+	pub transactions: Vec<transaction::SignedTransaction>,
+}
+
 /// Simplified, single-block EVM test client.
 pub struct EvmTestClient<'a> {
 	state: state::State<state_db::StateDB>,
@@ -108,7 +131,13 @@ impl<'a> EvmTestClient<'a> {
 
 	/// Creates new EVM test client with in-memory DB initialized with given PodState.
 	pub fn from_pod_state(spec: &'a spec::Spec, pod_state: pod_state::PodState) -> Result<Self, EvmTestError> {
-		let factories = Self::factories();
+		Self::from_pod_state_with_vm(spec, pod_state, Self::vm_factory())
+	}
+
+	/// Creates new EVM test client with in-memory DB initialized with given PodState,
+	/// executing code with VMs created by `vm`.
+	pub fn from_pod_state_with_vm(spec: &'a spec::Spec, pod_state: pod_state::PodState, vm: evm::Factory) -> Result<Self, EvmTestError> {
+		let factories = Self::factories_with_vm(vm);
 		let state =	Self::state_from_pod(spec, &factories, pod_state)?;
 
 		Ok(EvmTestClient {
@@ -117,11 +146,116 @@ impl<'a> EvmTestClient<'a> {
 		})
 	}
 
+	/// Creates new EVM test client on top of the state stored in an existing client database.
+	///
+	/// Returns the client along with block `number`. The state is the one the block starts from
+	/// if `at_beginning` is set and the one resulting from the block otherwise.
+	/// Nothing is ever written to the database.
+	pub fn from_database(
+		spec: &'a spec::Spec,
+		db: Arc<KeyValueDB>,
+		pruning: journaldb::Algorithm,
+		number: BlockNumber,
+		at_beginning: bool,
+	) -> Result<(Self, ChainBlock), EvmTestError> {
+		// `BlockChain` would insert the genesis block into an empty database.
+		if db.get(db::COL_EXTRA, b"best").map_err(EvmTestError::Database)?.is_none() {
+			return Err(EvmTestError::Chain("No blocks found in the database".into()));
+		}
+
+		let chain = BlockChain::new(Default::default(), &spec.genesis_block(), db.clone());
+		if chain.block_hash(0) != Some(spec.genesis_header().hash()) {
+			return Err(EvmTestError::Chain("Database belongs to a different chain".into()));
+		}
+
+		let missing = |what: &str| EvmTestError::Chain(format!("Missing {} of block #{}", what, number));
+		let hash = chain.block_hash(number).ok_or_else(|| missing("hash"))?;
+		let header = chain.block_header_data(&hash).ok_or_else(|| missing("header"))?.decode();
+		let body = chain.block_body(&hash).ok_or_else(|| missing("body"))?;
+
+		let state_root = if at_beginning {
+			*chain.block_header_data(header.parent_hash()).ok_or_else(|| missing("parent"))?.decode().state_root()
+		} else {
+			*header.state_root()
+		};
+
+		let mut last_hashes = vec![H256::default(); 256];
+		last_hashes[0] = *header.parent_hash();
+		for i in 0..255 {
+			match chain.block_details(&last_hashes[i]) {
+				Some(details) => last_hashes[i + 1] = details.parent,
+				None => break,
+			}
+		}
+
+		let env_info = client::EnvInfo {
+			number: header.number(),
+			author: *header.author(),
+			timestamp: header.timestamp(),
+			difficulty: *header.difficulty(),
+			last_hashes: Arc::new(last_hashes),
+			gas_used: 0.into(),
+			gas_limit: *header.gas_limit(),
+		};
+
+		let transactions = body.transactions().into_iter()
+			.map(transaction::SignedTransaction::new)
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|err| EvmTestError::Chain(format!("Invalid transaction in block #{}: {}", number, err)))?;
+
+		let journal_db = journaldb::new(db, pruning, db::COL_STATE);
+		let state_db = state_db::StateDB::new(journal_db, 5 * 1024 * 1024);
+		let state = state::State::from_existing(
+			state_db,
+			state_root,
+			spec.engine.account_start_nonce(header.number()),
+			Self::factories(),
+		).map_err(EvmTestError::Trie)?;
+
+		let block = ChainBlock {
+			header,
+			env_info,
+			transactions,
+		};
+
+		Ok((EvmTestClient { state, spec }, block))
+	}
+
+	/// Creates a client over a copy of the current state, which is not affected by further execution.
+	/// Changes which are not committed yet are carried over, the record of touched accounts is not.
+	pub fn fork(&self) -> Self {
+		EvmTestClient {
+			state: self.state.clone(),
+			spec: self.spec,
+		}
+	}
+
+	/// Returns the accounts and storage items touched by the executions on this client,
+	/// with the values they have in the state of `pre`.
+	pub fn touched_pod_state(&self, pre: &EvmTestClient) -> Result<pod_state::PodState, EvmTestError> {
+		pre.state.to_pod_subset(&self.state.touched()).map_err(EvmTestError::Trie)
+	}
+
+	/// Returns given accounts and storage items as they are in the current state.
+	/// Accounts which do not exist and zero storage values are left out.
+	pub fn pod_state_subset(&self, accounts: &BTreeMap<Address, BTreeSet<H256>>) -> Result<pod_state::PodState, EvmTestError> {
+		self.state.to_pod_subset(accounts).map_err(EvmTestError::Trie)
+	}
+
 	fn factories() -> Factories {
+		Self::factories_with_vm(Self::vm_factory())
+	}
+
+	fn vm_factory() -> evm::Factory {
+		evm::Factory::new(VMType::Interpreter, 5 * 1024)
+	}
+
+	fn factories_with_vm(vm: evm::Factory) -> Factories {
 		Factories {
-			vm: evm::Factory::new(VMType::Interpreter, 5 * 1024),
+			vm: vm,
 			trie: trie::TrieFactory::new(trie::TrieSpec::Secure),
 			accountdb: Default::default(),
+			wasm: Default::default(),
 		}
 	}
 
@@ -176,10 +310,18 @@ impl<'a> EvmTestClient<'a> {
 			gas_used: 0.into(),
 			gas_limit: *genesis.gas_limit(),
 		};
+		self.call_with_env_info(&info, params, vm_tracer)
+	}
+
+	/// Execute the VM given `EnvInfo`, ActionParams and tracer.
+	/// Returns amount of gas left and the output.
+	pub fn call_with_env_info<T: trace::VMTracer>(&mut self, info: &client::EnvInfo, params: ActionParams, vm_tracer: &mut T)
+		-> Result<FinalizationResult, EvmTestError>
+	{
 		let mut substate = state::Substate::new();
 		let mut tracer = trace::NoopTracer;
 		let mut output = vec![];
-		let mut executive = executive::Executive::new(&mut self.state, &info, self.spec.engine.machine());
+		let mut executive = executive::Executive::new(&mut self.state, info, self.spec.engine.machine());
 		executive.call(
 			params,
 			&mut substate,
@@ -218,6 +360,7 @@ impl<'a> EvmTestClient<'a> {
 					state_root: *self.state.root(),
 					gas_left: initial_gas - result.receipt.gas_used,
 					output: result.output,
+					logs: result.receipt.logs,
 					vm_trace: result.vm_trace,
 				}
 			},
@@ -239,6 +382,8 @@ pub enum TransactResult<T> {
 		gas_left: U256,
 		/// Output
 		output: Vec<u8>,
+		/// Logs
+		logs: Vec<LogEntry>,
 		/// VM Traces
 		vm_trace: Option<T>,
 	},
@@ -250,3 +395,70 @@ pub enum TransactResult<T> {
 		error: ::error::Error,
 	},
 }
+
+/// Returns the hash state tests use to check the logs of a transaction.
+pub fn logs_hash(logs: &[LogEntry]) -> H256 {
+	keccak(&*rlp::encode_list::<LogEntry, _>(logs))
+}
+
+/// Imports the blocks of a blockchain test into a fresh client and returns the best block hash.
+///
+/// The genesis block and state of `spec` are replaced by the ones of the test. Blocks which can't
+/// be decoded are skipped and invalid ones are rejected by the client, like they would be on import.
+pub fn import_chain_test(spec: &spec::Spec, blockchain: &ethjson::blockchain::BlockChain) -> Result<H256, EvmTestError> {
+	let spec = chain_test_spec(spec, blockchain)?;
+	let db = Arc::new(kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0)));
+	import_chain_test_blocks(&spec, blockchain, db, client::ClientConfig::default().pruning)
+		.map(|info| info.best_block_hash)
+}
+
+/// Imports a blockchain test like `import_chain_test`, into an in-memory archive database.
+/// Returns the spec with the test genesis and the database, which can be used with
+/// `EvmTestClient::from_database` to load the state of every block, along with the chain info.
+pub fn import_archived_chain_test(
+	spec: &spec::Spec,
+	blockchain: &ethjson::blockchain::BlockChain,
+) -> Result<(spec::Spec, Arc<KeyValueDB>, BlockChainInfo), EvmTestError> {
+	let spec = chain_test_spec(spec, blockchain)?;
+	let db: Arc<KeyValueDB> = Arc::new(kvdb_memorydb::create(db::NUM_COLUMNS.unwrap_or(0)));
+	let info = import_chain_test_blocks(&spec, blockchain, db.clone(), journaldb::Algorithm::Archive)?;
+	Ok((spec, db, info))
+}
+
+fn chain_test_spec(spec: &spec::Spec, blockchain: &ethjson::blockchain::BlockChain) -> Result<spec::Spec, EvmTestError> {
+	let mut spec = spec.clone();
+	spec.set_genesis_state(blockchain.pre_state.clone().into())?;
+	spec.overwrite_genesis_params(spec::Genesis::from(blockchain.genesis()));
+	if !spec.is_state_root_valid() {
+		return Err(EvmTestError::Chain("Genesis state root does not match the pre state".into()));
+	}
+	Ok(spec)
+}
+
+fn import_chain_test_blocks(
+	spec: &spec::Spec,
+	blockchain: &ethjson::blockchain::BlockChain,
+	db: Arc<KeyValueDB>,
+	pruning: journaldb::Algorithm,
+) -> Result<BlockChainInfo, EvmTestError> {
+	let mut config = client::ClientConfig::default();
+	config.history = 8;
+	config.pruning = pruning;
+	let client = client::Client::new(
+		config,
+		spec,
+		db,
+		Arc::new(Miner::with_spec(spec)),
+		IoChannel::disconnected(),
+	)?;
+
+	for block in &blockchain.blocks_rlp() {
+		if Block::is_good(block) {
+			let _ = client.import_block(block.clone());
+			client.flush_queue();
+			client.import_verified_blocks();
+		}
+	}
+
+	Ok(client.chain_info())
+}
@@ -18,7 +18,7 @@
 
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrder};
 use std::sync::Arc;
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::mem;
 use itertools::Itertools;
 use rustc_hex::FromHex;
@@ -37,7 +37,7 @@ use blockchain::TreeRoute;
 use client::{
 	BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockId,
 	TransactionId, UncleId, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError,
-	ProvingBlockChainClient,
+	ProvingBlockChainClient, ChainNotify,
 };
 use db::{NUM_COLUMNS, COL_STATE};
 use header::{Header as BlockHeader, BlockNumber};
@@ -45,7 +45,8 @@ use filter::Filter;
 use log_entry::LocalizedLogEntry;
 use receipt::{Receipt, LocalizedReceipt, TransactionOutcome};
 use blockchain::extras::BlockReceipts;
-use error::{ImportResult, Error as EthcoreError};
+use error::{ImportResult, BlockError, Error as EthcoreError};
+use unexpected::Mismatch;
 use evm::{Factory as EvmFactory, VMType};
 use vm::Schedule;
 use miner::{Miner, MinerService};
@@ -61,6 +62,7 @@ use error::CallError;
 use trace::LocalizedTrace;
 use state_db::StateDB;
 use encoded;
+use engines::evidence::Evidence;
 
 /// Test client.
 pub struct TestBlockChainClient {
@@ -108,6 +110,10 @@ pub struct TestBlockChainClient {
 	pub traces: RwLock<Option<Vec<LocalizedTrace>>>,
 	/// Pruning history size to report.
 	pub history: RwLock<Option<u64>>,
+	/// Misbehaviour evidence to return.
+	pub misbehaviour_evidence: RwLock<Vec<Evidence>>,
+	/// Data stored locally.
+	pub local_data: RwLock<HashMap<Bytes, Bytes>>,
 }
 
 /// Used for generating test client blocks.
@@ -174,6 +180,8 @@ impl TestBlockChainClient {
 			first_block: RwLock::new(None),
 			traces: RwLock::new(None),
 			history: RwLock::new(None),
+			misbehaviour_evidence: RwLock::new(Vec::new()),
+			local_data: RwLock::new(HashMap::new()),
 		};
 
 		// insert genesis hash.
@@ -346,6 +354,25 @@ impl TestBlockChainClient {
 		self.insert_transaction_with_gas_price_to_queue(U256::from(20_000_000_000u64))
 	}
 
+	/// Inserts a locally submitted transaction to miners transactions queue.
+	pub fn insert_local_transaction_to_queue(&self) -> H256 {
+		let keypair = Random.generate().unwrap();
+		let tx = Transaction {
+			action: Action::Create,
+			value: U256::from(100),
+			data: "3331600055".from_hex().unwrap(),
+			gas: U256::from(100_000),
+			gas_price: U256::from(20_000_000_000u64),
+			nonce: U256::zero()
+		};
+		let signed_tx = tx.sign(keypair.secret(), None);
+		self.set_balance(signed_tx.sender(), 10_000_000_000_000_000_000u64.into());
+		let hash = signed_tx.hash();
+		let res = self.miner.import_own_transaction(self, signed_tx.into()).expect("Successful import");
+		assert_eq!(res, transaction::ImportResult::Current);
+		hash
+	}
+
 	/// Set reported history size.
 	pub fn set_history(&self, h: Option<u64>) {
 		*self.history.write() = h;
@@ -632,6 +659,15 @@ impl BlockChainClient for TestBlockChainClient {
 		None
 	}
 
+	fn verify_header_chain(&self, headers: &[BlockHeader]) -> Result<(), EthcoreError> {
+		for (header, parent) in headers.iter().zip(headers.iter().skip(1)) {
+			if *header.parent_hash() != parent.hash() || header.number() != parent.number() + 1 {
+				return Err(BlockError::InvalidParentHash(Mismatch { expected: parent.hash(), found: *header.parent_hash() }).into());
+			}
+		}
+		Ok(())
+	}
+
 	fn import_block(&self, b: Bytes) -> Result<H256, BlockImportError> {
 		let header = Rlp::new(&b).val_at::<BlockHeader>(0);
 		let h = header.hash();
@@ -741,11 +777,20 @@ impl BlockChainClient for TestBlockChainClient {
 		self.spec.engine.handle_message(&message).unwrap();
 	}
 
+	fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction> {
+		let info = self.chain_info();
+		self.miner.transaction(info.best_block_number, hash)
+	}
+
 	fn ready_transactions(&self) -> Vec<PendingTransaction> {
 		let info = self.chain_info();
 		self.miner.ready_transactions(info.best_block_number, info.best_block_timestamp)
 	}
 
+	fn local_transactions(&self) -> HashSet<H256> {
+		self.miner.local_transactions().keys().cloned().collect()
+	}
+
 	fn signing_chain_id(&self) -> Option<u64> { None }
 
 	fn mode(&self) -> Mode { Mode::Active }
@@ -768,7 +813,7 @@ impl BlockChainClient for TestBlockChainClient {
 
 	fn call_contract(&self, _id: BlockId, _address: Address, _data: Bytes) -> Result<Bytes, String> { Ok(vec![]) }
 
-	fn transact_contract(&self, address: Address, data: Bytes) -> Result<transaction::ImportResult, EthcoreError> {
+	fn transact_contract(&self, address: Address, data: Bytes) -> Result<H256, EthcoreError> {
 		let transaction = Transaction {
 			nonce: self.latest_nonce(&self.miner.author()),
 			action: Action::Call(address),
@@ -780,7 +825,22 @@ impl BlockChainClient for TestBlockChainClient {
 		let chain_id = Some(self.spec.chain_id());
 		let sig = self.spec.engine.sign(transaction.hash(chain_id)).unwrap();
 		let signed = SignedTransaction::new(transaction.with_signature(sig, chain_id)).unwrap();
-		self.miner.import_own_transaction(self, signed.into())
+		let hash = signed.hash();
+		self.miner.import_own_transaction(self, signed.into()).map(|_| hash)
+	}
+
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> {
+		self.misbehaviour_evidence.read().clone()
+	}
+
+	fn add_notify(&self, _target: Arc<ChainNotify>) {}
+
+	fn local_data(&self, key: &[u8]) -> Option<Bytes> {
+		self.local_data.read().get(key).cloned()
+	}
+
+	fn set_local_data(&self, key: &[u8], value: Bytes) {
+		self.local_data.write().insert(key.to_vec(), value);
 	}
 
 	fn registrar_address(&self) -> Option<Address> { None }
@@ -14,22 +14,25 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
 use itertools::Itertools;
 
 use block::{OpenBlock, SealedBlock, ClosedBlock};
 use blockchain::TreeRoute;
+use client::ChainNotify;
 use encoded;
+use engines::evidence::Evidence;
 use vm::LastHashes;
 use error::{ImportResult, CallError, Error as EthcoreError, BlockImportError};
 use evm::{Factory as EvmFactory, Schedule};
 use executive::Executed;
 use filter::Filter;
-use header::{BlockNumber};
+use header::{BlockNumber, Header};
 use log_entry::LocalizedLogEntry;
 use receipt::LocalizedReceipt;
 use trace::LocalizedTrace;
-use transaction::{LocalizedTransaction, PendingTransaction, SignedTransaction, ImportResult as TransactionImportResult};
+use transaction::{LocalizedTransaction, PendingTransaction, SignedTransaction};
 use verification::queue::QueueInfo as BlockQueueInfo;
 
 use ethereum_types::{H256, U256, Address};
@@ -156,6 +159,10 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get raw block receipts data by block header hash.
 	fn block_receipts(&self, hash: &H256) -> Option<Bytes>;
 
+	/// Verify a chain of block headers ordered from child to parent, which need not connect
+	/// to the local chain, as far as possible without their state.
+	fn verify_header_chain(&self, headers: &[Header]) -> Result<(), EthcoreError>;
+
 	/// Import a block into the blockchain.
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError>;
 
@@ -220,6 +227,12 @@ pub trait BlockChainClient : Sync + Send {
 	/// List all transactions that are allowed into the next block.
 	fn ready_transactions(&self) -> Vec<PendingTransaction>;
 
+	/// Look up a transaction pending in the queue by its hash.
+	fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction>;
+
+	/// Hashes of transactions submitted through this node.
+	fn local_transactions(&self) -> HashSet<H256>;
+
 	/// Sorted list of transaction gas prices from at least last sample_size blocks.
 	fn gas_price_corpus(&self, sample_size: usize) -> ::stats::Corpus<U256> {
 		let mut h = self.chain_info().best_block_hash;
@@ -273,7 +286,20 @@ pub trait BlockChainClient : Sync + Send {
 	fn call_contract(&self, id: BlockId, address: Address, data: Bytes) -> Result<Bytes, String>;
 
 	/// Import a transaction: used for misbehaviour reporting.
-	fn transact_contract(&self, address: Address, data: Bytes) -> Result<TransactionImportResult, EthcoreError>;
+	/// Returns the hash of the imported transaction.
+	fn transact_contract(&self, address: Address, data: Bytes) -> Result<H256, EthcoreError>;
+
+	/// Misbehaviour of validators reported by the consensus engine, oldest first.
+	fn misbehaviour_evidence(&self) -> Vec<Evidence>;
+
+	/// Add a handler for blockchain events.
+	fn add_notify(&self, target: Arc<ChainNotify>);
+
+	/// Get data the node keeps locally, outside of the chain.
+	fn local_data(&self, key: &[u8]) -> Option<Bytes>;
+
+	/// Store data locally, outside of the chain.
+	fn set_local_data(&self, key: &[u8], value: Bytes);
 
 	/// Get the address of the registry itself.
 	fn registrar_address(&self) -> Option<Address>;
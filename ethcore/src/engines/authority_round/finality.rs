@@ -29,8 +29,10 @@ pub struct UnknownValidator;
 
 /// Rolling finality checker for authority round consensus.
 /// Stores a chain of unfinalized hashes that can be pushed onto.
+/// Each hash is signed by the block author and, with empty steps, by the
+/// authors of the empty steps included in the block.
 pub struct RollingFinality {
-	headers: VecDeque<(H256, Address)>,
+	headers: VecDeque<(H256, Vec<Address>)>,
 	signers: SimpleList,
 	sign_count: HashMap<Address, usize>,
 	last_pushed: Option<H256>,
@@ -52,28 +54,32 @@ impl RollingFinality {
 	///
 	/// Fails if any provided signature isn't part of the signers set.
 	pub fn build_ancestry_subchain<I>(&mut self, iterable: I) -> Result<(), UnknownValidator>
-		where I: IntoIterator<Item=(H256, Address)>
+		where I: IntoIterator<Item=(H256, Vec<Address>)>
 	{
 		self.clear();
-		for (hash, signer) in iterable {
-			if !self.signers.contains(&signer) { return Err(UnknownValidator) }
+		for (hash, signers) in iterable {
+			if signers.iter().any(|signer| !self.signers.contains(signer)) { return Err(UnknownValidator) }
 			if self.last_pushed.is_none() { self.last_pushed = Some(hash) }
 
 			// break when we've got our first finalized block.
 			{
-				let current_signed = self.sign_count.len();
-				let would_be_finalized = (current_signed + 1) * 2 > self.signers.len();
+				let mut new_signers = signers.clone();
+				new_signers.sort();
+				new_signers.dedup();
+				new_signers.retain(|signer| !self.sign_count.contains_key(signer));
 
-				let entry = self.sign_count.entry(signer);
-				if let (true, &Entry::Vacant(_)) = (would_be_finalized, &entry) {
+				let would_be_finalized = (self.sign_count.len() + new_signers.len()) * 2 > self.signers.len();
+				if would_be_finalized {
 					trace!(target: "finality", "Encountered already finalized block {}", hash);
 					break
 				}
 
-				*entry.or_insert(0) += 1;
+				for signer in &signers {
+					*self.sign_count.entry(*signer).or_insert(0) += 1;
+				}
 			}
 
-			self.headers.push_front((hash, signer));
+			self.headers.push_front((hash, signers));
 		}
 
 		trace!(target: "finality", "Rolling finality state: {:?}", self.headers);
@@ -100,33 +106,37 @@ impl RollingFinality {
 
 	/// Push a hash onto the rolling finality checker (implying `subchain_head` == head.parent)
 	///
-	/// Fails if `signer` isn't a member of the active validator set.
+	/// Fails if any of the `signers` isn't a member of the active validator set.
 	/// Returns a list of all newly finalized headers.
 	// TODO: optimize with smallvec.
-	pub fn push_hash(&mut self, head: H256, signer: Address) -> Result<Vec<H256>, UnknownValidator> {
-		if !self.signers.contains(&signer) { return Err(UnknownValidator) }
+	pub fn push_hash(&mut self, head: H256, signers: Vec<Address>) -> Result<Vec<H256>, UnknownValidator> {
+		if signers.iter().any(|signer| !self.signers.contains(signer)) { return Err(UnknownValidator) }
 
-		self.headers.push_back((head, signer));
-		*self.sign_count.entry(signer).or_insert(0) += 1;
+		for signer in &signers {
+			*self.sign_count.entry(*signer).or_insert(0) += 1;
+		}
+		self.headers.push_back((head, signers));
 
 		let mut newly_finalized = Vec::new();
 
 		while self.sign_count.len() * 2 > self.signers.len() {
-			let (hash, signer) = self.headers.pop_front()
+			let (hash, signers) = self.headers.pop_front()
 				.expect("headers length always greater than sign count length; qed");
 
 			newly_finalized.push(hash);
 
-			match self.sign_count.entry(signer) {
-				Entry::Occupied(mut entry) => {
-					// decrement count for this signer and purge on zero.
-					*entry.get_mut() -= 1;
+			for signer in signers {
+				match self.sign_count.entry(signer) {
+					Entry::Occupied(mut entry) => {
+						// decrement count for this signer and purge on zero.
+						*entry.get_mut() -= 1;
 
-					if *entry.get() == 0 {
-						entry.remove();
+						if *entry.get() == 0 {
+							entry.remove();
+						}
 					}
+					Entry::Vacant(_) => panic!("all hashes in `header` should have entries in `sign_count` for their signers; qed"),
 				}
-				Entry::Vacant(_) => panic!("all hashes in `header` should have an entry in `sign_count` for their signer; qed"),
 			}
 		}
 
@@ -137,7 +147,7 @@ impl RollingFinality {
 	}
 }
 
-pub struct Iter<'a>(::std::collections::vec_deque::Iter<'a, (H256, Address)>);
+pub struct Iter<'a>(::std::collections::vec_deque::Iter<'a, (H256, Vec<Address>)>);
 
 impl<'a> Iterator for Iter<'a> {
 	type Item = H256;
@@ -156,7 +166,7 @@ mod tests {
 	fn rejects_unknown_signer() {
 		let signers = (0..3).map(|_| Address::random()).collect();
 		let mut finality = RollingFinality::blank(signers);
-		assert!(finality.push_hash(H256::random(), Address::random()).is_err());
+		assert!(finality.push_hash(H256::random(), vec![Address::random()]).is_err());
 	}
 
 	#[test]
@@ -169,19 +179,19 @@ mod tests {
 		// 3 / 6 signers is < 51% so no finality.
 		for (i, hash) in hashes.iter().take(6).cloned().enumerate() {
 			let i = i % 3;
-			assert!(finality.push_hash(hash, signers[i]).unwrap().len() == 0);
+			assert!(finality.push_hash(hash, vec![signers[i]]).unwrap().len() == 0);
 		}
 
 		// after pushing a block signed by a fourth validator, the first four
 		// blocks of the unverified chain become verified.
-		assert_eq!(finality.push_hash(hashes[6], signers[4]).unwrap(),
+		assert_eq!(finality.push_hash(hashes[6], vec![signers[4]]).unwrap(),
 			vec![hashes[0], hashes[1], hashes[2], hashes[3]]);
 	}
 
 	#[test]
 	fn from_ancestry() {
 		let signers: Vec<_> = (0..6).map(|_| Address::random()).collect();
-		let hashes: Vec<_> = (0..12).map(|i| (H256::random(), signers[i % 6])).collect();
+		let hashes: Vec<_> = (0..12).map(|i| (H256::random(), vec![signers[i % 6]])).collect();
 
 		let mut finality = RollingFinality::blank(signers.clone());
 		finality.build_ancestry_subchain(hashes.iter().rev().cloned()).unwrap();
@@ -189,4 +199,30 @@ mod tests {
 		assert_eq!(finality.unfinalized_hashes().count(), 3);
 		assert_eq!(finality.subchain_head(), Some(hashes[11].0));
 	}
+
+	#[test]
+	fn finalize_multiple_signers() {
+		let signers: Vec<_> = (0..6).map(|_| Address::random()).collect();
+		let mut finality = RollingFinality::blank(signers.clone());
+		let hash = H256::random();
+
+		// after pushing a block signed by four validators, it becomes verified right away.
+		assert_eq!(finality.push_hash(hash, signers[0..4].to_vec()).unwrap(), vec![hash]);
+	}
+
+	#[test]
+	fn from_ancestry_multiple_signers() {
+		let signers: Vec<_> = (0..6).map(|_| Address::random()).collect();
+		let hashes: Vec<_> = (0..12).map(|i| {
+			(H256::random(), vec![signers[i % 6], signers[(i + 1) % 6], signers[(i + 2) % 6]])
+		}).collect();
+
+		let mut finality = RollingFinality::blank(signers.clone());
+		finality.build_ancestry_subchain(hashes.iter().rev().cloned()).unwrap();
+
+		// only the last hash has < 51% of authorities' signatures
+		assert_eq!(finality.unfinalized_hashes().count(), 1);
+		assert_eq!(finality.unfinalized_hashes().next(), Some(hashes[11].0));
+		assert_eq!(finality.subchain_head(), Some(hashes[11].0));
+	}
 }
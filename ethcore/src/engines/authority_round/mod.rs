@@ -19,7 +19,9 @@
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
 use std::sync::{Weak, Arc};
 use std::time::{UNIX_EPOCH, Duration};
-use std::collections::{BTreeMap, HashSet};
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
 
 use account_provider::AccountProvider;
 use block::*;
@@ -29,16 +31,22 @@ use error::{Error, BlockError};
 use ethjson;
 use machine::{AuxiliaryData, Call, EthereumMachine};
 use header::{Header, BlockNumber};
+use ids::BlockId;
+use trace::RewardType;
 
+use super::block_reward::{self, BlockRewardContract, RewardKind};
+use super::evidence::Evidence;
 use super::signer::EngineSigner;
 use super::validator_set::{ValidatorSet, SimpleList, new_validator_set};
 
 use self::finality::RollingFinality;
 
-use ethkey::{verify_address, Signature};
+use bytes::Bytes;
+use ethkey::{public_to_address, recover, verify_address, Signature};
+use hash::keccak;
 use io::{IoContext, IoHandler, TimerToken, IoService};
 use itertools::{self, Itertools};
-use rlp::{UntrustedRlp, encode};
+use rlp::{UntrustedRlp, RlpStream, Encodable, Decodable, DecoderError, encode};
 use ethereum_types::{H256, H520, Address, U128, U256};
 use semantic_version::SemanticVersion;
 use parking_lot::{Mutex, RwLock};
@@ -49,11 +57,11 @@ mod finality;
 /// `AuthorityRound` params.
 pub struct AuthorityRoundParams {
 	/// Time to wait before next block or authority switching,
-	/// in seconds.
+	/// in seconds, indexed by the block number it applies from.
 	///
 	/// Deliberately typed as u16 as too high of a value leads
 	/// to slow block issuance.
-	pub step_duration: u16,
+	pub step_durations: BTreeMap<BlockNumber, u16>,
 	/// Starting step,
 	pub start_step: Option<u64>,
 	/// Valid validators.
@@ -70,19 +78,47 @@ pub struct AuthorityRoundParams {
 	pub maximum_uncle_count_transition: u64,
 	/// Number of accepted uncles.
 	pub maximum_uncle_count: usize,
+	/// Block from which the block reward contract is used.
+	pub block_reward_contract_transition: u64,
+	/// Block reward contract.
+	pub block_reward_contract: Option<BlockRewardContract>,
+	/// Gas provided to the block reward contract call.
+	pub block_reward_contract_gas: U256,
+	/// Block from which empty steps are broadcast instead of sealing empty blocks.
+	pub empty_steps_transition: u64,
+	/// Maximum number of empty steps included in a single block.
+	pub maximum_empty_steps: usize,
+	/// First block sealed by this engine. Its parent was sealed by the engine of the previous
+	/// stage of a multi-stage chain, unless this is the genesis stage.
+	pub stage_start: BlockNumber,
 }
 
 const U16_MAX: usize = ::std::u16::MAX as usize;
 
+/// Gas provided to the block reward contract call unless the spec sets a limit.
+const DEFAULT_BLOCK_REWARD_CONTRACT_GAS: u64 = 50_000_000;
+
+fn clamp_step_duration(step_duration: ethjson::uint::Uint) -> u16 {
+	let step_duration_usize: usize = step_duration.into();
+	if step_duration_usize > U16_MAX {
+		warn!(target: "engine", "step_duration is too high ({}), setting it to {}", step_duration_usize, U16_MAX);
+		U16_MAX as u16
+	} else {
+		step_duration_usize as u16
+	}
+}
+
 impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
 	fn from(p: ethjson::spec::AuthorityRoundParams) -> Self {
-		let mut step_duration_usize: usize = p.step_duration.into();
-		if step_duration_usize > U16_MAX {
-			step_duration_usize = U16_MAX;
-			warn!(target: "engine", "step_duration is too high ({}), setting it to {}", step_duration_usize, U16_MAX);
-		}
+		let step_durations: BTreeMap<BlockNumber, u16> = match p.step_duration {
+			ethjson::spec::StepDuration::Single(duration) =>
+				vec![(0, clamp_step_duration(duration))].into_iter().collect(),
+			ethjson::spec::StepDuration::Transitions(durations) => durations.into_iter()
+				.map(|(block, duration)| (block.into(), clamp_step_duration(duration)))
+				.collect(),
+		};
 		AuthorityRoundParams {
-			step_duration: step_duration_usize as u16,
+			step_durations: step_durations,
 			validators: new_validator_set(p.validators),
 			start_step: p.start_step.map(Into::into),
 			validate_score_transition: p.validate_score_transition.map_or(0, Into::into),
@@ -91,25 +127,144 @@ impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
 			block_reward: p.block_reward.map_or_else(Default::default, Into::into),
 			maximum_uncle_count_transition: p.maximum_uncle_count_transition.map_or(0, Into::into),
 			maximum_uncle_count: p.maximum_uncle_count.map_or(0, Into::into),
+			block_reward_contract_transition: p.block_reward_contract_transition.map_or(0, Into::into),
+			block_reward_contract: p.block_reward_contract_address.map(|address| BlockRewardContract::new(address.into())),
+			block_reward_contract_gas: p.block_reward_contract_gas_limit.map_or(DEFAULT_BLOCK_REWARD_CONTRACT_GAS.into(), Into::into),
+			// the genesis seal never contains empty steps.
+			empty_steps_transition: p.empty_steps_transition.map_or(u64::max_value(), |n| {
+				let n: u64 = n.into();
+				cmp::max(n, 1)
+			}),
+			maximum_empty_steps: p.maximum_empty_steps.map_or(usize::max_value(), Into::into),
+			stage_start: 0,
 		}
 	}
 }
 
+/// Step duration starting at a known step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StepDurationInfo {
+	/// Block number from which the duration applies.
+	transition_number: BlockNumber,
+	/// First step with this duration.
+	transition_step: usize,
+	/// Unix time (in seconds) at which `transition_step` starts.
+	transition_timestamp: u64,
+	/// Step duration, in seconds.
+	duration: u64,
+}
+
 // Helper for managing the step.
+//
+// Step duration transitions are configured by block number. Since steps are
+// derived from time, a transition at block `n` is anchored once block `n - 1`
+// is part of the canonical chain: steps with the new duration begin right after
+// that block's step. Side forks never move the anchors.
 #[derive(Debug)]
 struct Step {
 	calibrate: bool, // whether calibration is enabled.
 	inner: AtomicUsize,
-	duration: u16,
+	durations: BTreeMap<BlockNumber, u16>,
+	anchored: RwLock<Vec<StepDurationInfo>>,
 }
 
 impl Step {
+	fn new(durations: BTreeMap<BlockNumber, u16>, start_step: Option<u64>) -> Self {
+		let first = StepDurationInfo {
+			transition_number: 0,
+			transition_step: 0,
+			transition_timestamp: 0,
+			duration: *durations.get(&0).expect("step duration for block 0 is checked in AuthorityRound::new; qed") as u64,
+		};
+		let step = Step {
+			calibrate: start_step.is_none(),
+			inner: AtomicUsize::new(start_step.unwrap_or(0) as usize),
+			durations: durations,
+			anchored: RwLock::new(vec![first]),
+		};
+		step.calibrate();
+		step
+	}
+
 	fn load(&self) -> usize { self.inner.load(AtomicOrdering::SeqCst) }
+
+	/// Unix time (in seconds) at which the given step starts.
+	fn step_start(&self, step: usize) -> Option<u64> {
+		let anchored = self.anchored.read();
+		let info = anchored.iter().rev().find(|info| info.transition_step <= step)
+			.expect("first duration starts at step 0; qed");
+		((step - info.transition_step) as u64)
+			.checked_mul(info.duration)
+			.and_then(|offset| offset.checked_add(info.transition_timestamp))
+	}
+
+	/// Step at the given unix time (in seconds).
+	fn step_at(&self, time: u64) -> usize {
+		let anchored = self.anchored.read();
+		let info = anchored.iter().rev().find(|info| info.transition_timestamp <= time)
+			.expect("first duration starts at time 0; qed");
+		info.transition_step + ((time - info.transition_timestamp) / info.duration) as usize
+	}
+
+	/// Highest step the network could have reached if the given step is current,
+	/// taking into account transitions which are not anchored yet.
+	fn max_possible_step(&self, current: usize) -> usize {
+		let anchored = self.anchored.read();
+		let last = anchored.last().expect("first duration is always anchored; qed");
+		let pending: Vec<u64> = self.durations.range((last.transition_number + 1)..)
+			.map(|(_, duration)| *duration as u64)
+			.collect();
+
+		match pending.iter().min() {
+			Some(fastest) if *fastest < last.duration => {
+				let elapsed = unix_now().as_secs().saturating_sub(last.transition_timestamp);
+				// every transition may start a step early.
+				let possible = last.transition_step + (elapsed / fastest) as usize + pending.len();
+				cmp::max(current, possible)
+			},
+			_ => current,
+		}
+	}
+
+	/// Anchor the step duration transitions given the steps of the canonical blocks
+	/// preceding them, in transition order. Transitions without a known parent step
+	/// stay unanchored.
+	fn anchor(&self, parent_steps: &[usize]) {
+		let mut anchored = vec![self.anchored.read()[0]];
+		for ((number, duration), parent_step) in self.durations.iter().skip(1).zip(parent_steps) {
+			let transition_step = parent_step + 1;
+			let transition_timestamp = {
+				let previous = anchored.last().expect("first duration is always anchored; qed");
+				previous.transition_timestamp + (transition_step.saturating_sub(previous.transition_step) as u64) * previous.duration
+			};
+
+			anchored.push(StepDurationInfo {
+				transition_number: *number,
+				transition_step: transition_step,
+				transition_timestamp: transition_timestamp,
+				duration: *duration as u64,
+			});
+		}
+
+		{
+			let mut current = self.anchored.write();
+			if *current == anchored {
+				return;
+			}
+			if let Some(last) = anchored.last() {
+				debug!(target: "engine", "Step duration is {}s from step {} (block {})", last.duration, last.transition_step, last.transition_number);
+			}
+			*current = anchored;
+		}
+
+		self.calibrate();
+	}
+
 	fn duration_remaining(&self) -> Duration {
 		let now = unix_now();
-		let expected_seconds = (self.load() as u64)
+		let expected_seconds = self.load()
 			.checked_add(1)
-			.and_then(|ctr| ctr.checked_mul(self.duration as u64))
+			.and_then(|next| self.step_start(next))
 			.map(Duration::from_secs);
 
 		match expected_seconds {
@@ -138,8 +293,8 @@ impl Step {
 
 	fn calibrate(&self) {
 		if self.calibrate {
-			let new_step = unix_now().as_secs() / (self.duration as u64);
-			self.inner.store(new_step as usize, AtomicOrdering::SeqCst);
+			let new_step = self.step_at(unix_now().as_secs());
+			self.inner.store(new_step, AtomicOrdering::SeqCst);
 		}
 	}
 
@@ -154,17 +309,17 @@ impl Step {
 		// Make absolutely sure that the given step is incorrect.
 		self.calibrate();
 		let current = self.load();
+		let possible = self.max_possible_step(current);
 
 		// reject blocks too far in the future
-		if given > current + REJECTED_STEP_DRIFT {
+		if given > possible + REJECTED_STEP_DRIFT {
 			Err(None)
 		// wait a bit for blocks in near future
-		} else if given > current {
-			let d = self.duration as u64;
+		} else if given > possible {
 			Err(Some(OutOfBounds {
 				min: None,
-				max: Some(d * current as u64),
-				found: d * given as u64,
+				max: Some(self.step_start(current).unwrap_or(u64::max_value())),
+				found: self.step_start(given).unwrap_or(u64::max_value()),
 			}))
 		} else {
 			Ok(())
@@ -172,9 +327,110 @@ impl Step {
 	}
 }
 
-// Chain scoring: total weight is sqrt(U256::max_value())*height - step
-fn calculate_score(parent_step: U256, current_step: U256) -> U256 {
-	U256::from(U128::max_value()) + parent_step - current_step
+// Chain scoring: total weight is sqrt(U256::max_value())*height - step + empty steps
+fn calculate_score(parent_step: U256, current_step: U256, current_empty_steps: U256) -> U256 {
+	U256::from(U128::max_value()) + parent_step - current_step + current_empty_steps
+}
+
+/// A message broadcast by an authority when it's their turn to seal a block but there are no
+/// transactions. Other authorities collect these messages and include them in the seal of the
+/// next block as proof.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct EmptyStep {
+	step: usize,
+	parent_hash: H256,
+	signature: H520,
+}
+
+impl EmptyStep {
+	fn from_sealed(sealed_empty_step: SealedEmptyStep, parent_hash: &H256) -> EmptyStep {
+		EmptyStep {
+			step: sealed_empty_step.step,
+			parent_hash: *parent_hash,
+			signature: sealed_empty_step.signature,
+		}
+	}
+
+	fn message_hash(&self) -> H256 {
+		keccak(empty_step_rlp(self.step, &self.parent_hash))
+	}
+
+	/// Whether the empty step was signed by the proposer of its step.
+	fn verify(&self, validators: &ValidatorSet) -> Result<bool, Error> {
+		let correct_proposer = step_proposer(validators, &self.parent_hash, self.step);
+		verify_address(&correct_proposer, &self.signature.into(), &self.message_hash())
+			.map_err(Into::into)
+	}
+
+	fn author(&self) -> Result<Address, Error> {
+		let public = recover(&self.signature.into(), &self.message_hash())?;
+		Ok(public_to_address(&public))
+	}
+
+	fn sealed(&self) -> SealedEmptyStep {
+		SealedEmptyStep {
+			signature: self.signature,
+			step: self.step,
+		}
+	}
+}
+
+impl fmt::Display for EmptyStep {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "({}, {}, {})", self.signature, self.step, self.parent_hash)
+	}
+}
+
+impl Encodable for EmptyStep {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let empty_step_rlp = empty_step_rlp(self.step, &self.parent_hash);
+		s.begin_list(2)
+			.append(&self.signature)
+			.append_raw(&empty_step_rlp, 1);
+	}
+}
+
+impl Decodable for EmptyStep {
+	fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+		let signature = rlp.val_at(0)?;
+		let empty_step_rlp = rlp.at(1)?;
+
+		Ok(EmptyStep {
+			step: empty_step_rlp.val_at(0)?,
+			parent_hash: empty_step_rlp.val_at(1)?,
+			signature: signature,
+		})
+	}
+}
+
+/// The signed part of an empty step message.
+fn empty_step_rlp(step: usize, parent_hash: &H256) -> Vec<u8> {
+	let mut s = RlpStream::new_list(2);
+	s.append(&step).append(parent_hash);
+	s.out()
+}
+
+/// An empty step as included in the seal of a block. The parent hash is implied by the block.
+struct SealedEmptyStep {
+	signature: H520,
+	step: usize,
+}
+
+impl Encodable for SealedEmptyStep {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2)
+			.append(&self.signature)
+			.append(&self.step);
+	}
+}
+
+impl Decodable for SealedEmptyStep {
+	fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+		Ok(SealedEmptyStep {
+			signature: rlp.val_at(0)?,
+			step: rlp.val_at(1)?,
+		})
+	}
 }
 
 struct EpochManager {
@@ -275,8 +531,15 @@ pub struct AuthorityRound {
 	epoch_manager: Mutex<EpochManager>,
 	immediate_transitions: bool,
 	block_reward: U256,
+	block_reward_contract_transition: u64,
+	block_reward_contract: Option<BlockRewardContract>,
+	block_reward_contract_gas: U256,
 	maximum_uncle_count_transition: u64,
 	maximum_uncle_count: usize,
+	empty_steps: Mutex<BTreeSet<EmptyStep>>,
+	empty_steps_transition: u64,
+	maximum_empty_steps: usize,
+	stage_start: BlockNumber,
 	machine: EthereumMachine,
 }
 
@@ -284,6 +547,7 @@ pub struct AuthorityRound {
 struct EpochVerifier {
 	step: Arc<Step>,
 	subchain_validators: SimpleList,
+	empty_steps_transition: u64,
 }
 
 impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
@@ -292,7 +556,7 @@ impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
 		verify_timestamp(&*self.step, header_step(header)?)?;
 		// always check the seal since it's fast.
 		// nothing heavier to do.
-		verify_external(header, &self.subchain_validators)
+		verify_external(header, &self.subchain_validators, self.empty_steps_transition)
 	}
 
 	fn check_finality_proof(&self, proof: &[u8]) -> Option<Vec<H256>> {
@@ -315,10 +579,13 @@ impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
 			// without panic.
 			//
 			// `verify_external` checks that signature is correct and author == signer.
-			if header.seal().len() != 2 { return None }
-			otry!(verify_external(header, &self.subchain_validators).ok());
+			if header.seal().len() != header_expected_seal_fields(header, self.empty_steps_transition) { return None }
+			otry!(verify_external(header, &self.subchain_validators, self.empty_steps_transition).ok());
+
+			let mut signers = otry!(header_empty_steps_signers(header, self.empty_steps_transition).ok());
+			signers.push(header.author().clone());
 
-			let newly_finalized = otry!(finality_checker.push_hash(header.hash(), header.author().clone()).ok());
+			let newly_finalized = otry!(finality_checker.push_hash(header.hash(), signers).ok());
 			finalized.extend(newly_finalized);
 		}
 
@@ -334,6 +601,56 @@ fn header_signature(header: &Header) -> Result<Signature, ::rlp::DecoderError> {
 	UntrustedRlp::new(&header.seal().get(1).expect("was checked with verify_block_basic; has 2 fields; qed")).as_val::<H520>().map(Into::into)
 }
 
+// Seal of a block sealed at the given step, with empty steps past the empty steps transition.
+fn seal_fields(step: usize, signature: &H520, empty_steps: Option<&[EmptyStep]>) -> Vec<Bytes> {
+	let mut fields = vec![
+		encode(&step).into_vec(),
+		encode(&(signature as &[u8])).into_vec(),
+	];
+
+	if let Some(empty_steps) = empty_steps {
+		let sealed_empty_steps: Vec<_> = empty_steps.iter().map(EmptyStep::sealed).collect();
+		fields.push(::rlp::encode_list(&sealed_empty_steps).into_vec());
+	}
+
+	fields
+}
+
+// Step and empty steps chosen for a new block when it was closed.
+fn closed_block_empty_steps(header: &Header) -> Option<(usize, Vec<EmptyStep>)> {
+	if header.seal().len() != 3 {
+		return None;
+	}
+
+	match (header_step(header), header_empty_steps(header)) {
+		(Ok(step), Ok(empty_steps)) => Some((step, empty_steps)),
+		_ => None,
+	}
+}
+
+fn header_expected_seal_fields(header: &Header, empty_steps_transition: u64) -> usize {
+	if header.number() >= empty_steps_transition { 3 } else { 2 }
+}
+
+fn header_empty_steps(header: &Header) -> Result<Vec<EmptyStep>, ::rlp::DecoderError> {
+	let empty_steps = UntrustedRlp::new(&header.seal().get(2).expect("was checked with verify_block_basic; has 3 fields; qed")).as_list::<SealedEmptyStep>()?;
+	Ok(empty_steps.into_iter().map(|sealed| EmptyStep::from_sealed(sealed, header.parent_hash())).collect())
+}
+
+// gets the signers of empty step messages for the given header, does not include repeated signers
+fn header_empty_steps_signers(header: &Header, empty_steps_transition: u64) -> Result<Vec<Address>, Error> {
+	if header.number() >= empty_steps_transition {
+		let mut signers = HashSet::new();
+		for empty_step in header_empty_steps(header)? {
+			signers.insert(empty_step.author()?);
+		}
+
+		Ok(signers.into_iter().collect())
+	} else {
+		Ok(Vec::new())
+	}
+}
+
 fn step_proposer(validators: &ValidatorSet, bh: &H256, step: usize) -> Address {
 	let proposer = validators.get(bh, step);
 	trace!(target: "engine", "Fetched proposer for step {}: {}", step, proposer);
@@ -360,9 +677,19 @@ fn verify_timestamp(step: &Step, header_step: usize) -> Result<(), BlockError> {
 	}
 }
 
-fn verify_external(header: &Header, validators: &ValidatorSet) -> Result<(), Error> {
+fn verify_external(header: &Header, validators: &ValidatorSet, empty_steps_transition: u64) -> Result<(), Error> {
 	let header_step = header_step(header)?;
 
+	// the included empty steps must be signed by the proposers of their steps.
+	if header.number() >= empty_steps_transition {
+		for empty_step in header_empty_steps(header)? {
+			if !empty_step.verify(validators)? {
+				trace!(target: "engine", "verify_block_external: invalid empty step proof: {}", empty_step);
+				Err(EngineError::InsufficientProof(format!("invalid empty step proof: {}", empty_step)))?;
+			}
+		}
+	}
+
 	let proposer_signature = header_signature(header)?;
 	let correct_proposer = validators.get(header.parent_hash(), header_step);
 	let is_invalid_proposer = *header.author() != correct_proposer ||
@@ -404,20 +731,17 @@ impl AsMillis for Duration {
 impl AuthorityRound {
 	/// Create a new instance of AuthorityRound engine.
 	pub fn new(our_params: AuthorityRoundParams, machine: EthereumMachine) -> Result<Arc<Self>, Error> {
-		if our_params.step_duration == 0 {
-			error!(target: "engine", "Authority Round step duration can't be zero, aborting");
-			panic!("authority_round: step duration can't be zero")
+		if our_params.step_durations.values().any(|duration| *duration == 0) {
+			return Err(EngineError::ZeroStepDuration.into());
+		}
+		if !our_params.step_durations.contains_key(&0) {
+			return Err(EngineError::MissingFirstStepDuration.into());
 		}
 		let should_timeout = our_params.start_step.is_none();
-		let initial_step = our_params.start_step.unwrap_or_else(|| (unix_now().as_secs() / (our_params.step_duration as u64))) as usize;
 		let engine = Arc::new(
 			AuthorityRound {
 				transition_service: IoService::<()>::start()?,
-				step: Arc::new(Step {
-					inner: AtomicUsize::new(initial_step),
-					calibrate: our_params.start_step.is_none(),
-					duration: our_params.step_duration,
-				}),
+				step: Arc::new(Step::new(our_params.step_durations, our_params.start_step)),
 				can_propose: AtomicBool::new(true),
 				client: RwLock::new(None),
 				signer: Default::default(),
@@ -427,8 +751,15 @@ impl AuthorityRound {
 				epoch_manager: Mutex::new(EpochManager::blank()),
 				immediate_transitions: our_params.immediate_transitions,
 				block_reward: our_params.block_reward,
+				block_reward_contract_transition: our_params.block_reward_contract_transition,
+				block_reward_contract: our_params.block_reward_contract,
+				block_reward_contract_gas: our_params.block_reward_contract_gas,
 				maximum_uncle_count_transition: our_params.maximum_uncle_count_transition,
 				maximum_uncle_count: our_params.maximum_uncle_count,
+				empty_steps: Mutex::new(BTreeSet::new()),
+				empty_steps_transition: our_params.empty_steps_transition,
+				maximum_empty_steps: our_params.maximum_empty_steps,
+				stage_start: our_params.stage_start,
 				machine: machine,
 			});
 
@@ -439,6 +770,116 @@ impl AuthorityRound {
 		}
 		Ok(engine)
 	}
+
+	// collected empty steps for the given parent, strictly between the given steps.
+	// only the most recent `maximum_empty_steps` are returned.
+	fn empty_steps(&self, from_step: usize, to_step: usize, parent_hash: &H256) -> Vec<EmptyStep> {
+		let mut empty_steps: Vec<_> = self.empty_steps.lock().iter()
+			.filter(|e| e.step > from_step && e.step < to_step && e.parent_hash == *parent_hash)
+			.cloned()
+			.collect();
+
+		let excess = empty_steps.len().saturating_sub(self.maximum_empty_steps);
+		empty_steps.drain(..excess);
+		empty_steps
+	}
+
+	// drop collected empty steps which can no longer be included in a block.
+	fn clear_empty_steps(&self, step: usize) {
+		self.empty_steps.lock().retain(|e| e.step > step);
+	}
+
+	// returns whether the empty step was not known yet.
+	fn handle_empty_step_message(&self, empty_step: EmptyStep) -> bool {
+		let mut empty_steps = self.empty_steps.lock();
+		// there's a single proposer per step and parent.
+		if empty_steps.iter().any(|e| e.step == empty_step.step && e.parent_hash == empty_step.parent_hash) {
+			return false;
+		}
+		empty_steps.insert(empty_step)
+	}
+
+	fn broadcast_message(&self, message: Vec<u8>) {
+		if let Some(ref weak) = *self.client.read() {
+			if let Some(c) = weak.upgrade() {
+				c.broadcast_consensus_message(message);
+			}
+		}
+	}
+
+	fn parent_step(&self, parent_hash: &H256) -> Result<usize, Error> {
+		let client = self.client.read().as_ref().and_then(|weak| weak.upgrade())
+			.ok_or(EngineError::RequiresClient)?;
+		let parent = client.as_full_client()
+			.ok_or(EngineError::RequiresClient)?
+			.block_header(BlockId::Hash(*parent_hash))
+			.ok_or(BlockError::UnknownParent(*parent_hash))?;
+
+		Ok(self.parent_header_step(&parent.decode())?.0)
+	}
+
+	// Step of the parent block and whether it was sealed by this engine. The first block
+	// of a later stage follows a parent sealed by another engine, which is treated as
+	// sealed in the step before the one of its timestamp.
+	fn parent_header_step(&self, parent: &Header) -> Result<(usize, bool), ::rlp::DecoderError> {
+		if self.stage_start != 0 && parent.number() + 1 == self.stage_start {
+			return Ok((self.step.step_at(parent.timestamp()).saturating_sub(1), false));
+		}
+
+		header_step(parent).map(|step| (step, true))
+	}
+
+	fn generate_empty_step(&self, parent_hash: &H256) {
+		let step = self.step.load();
+		let empty_step_rlp = empty_step_rlp(step, parent_hash);
+
+		match self.sign(keccak(&empty_step_rlp)) {
+			Ok(signature) => {
+				let empty_step = EmptyStep {
+					step: step,
+					parent_hash: *parent_hash,
+					signature: signature.into(),
+				};
+
+				trace!(target: "engine", "generate_empty_step: broadcasting empty step {}", empty_step);
+				self.broadcast_message(encode(&empty_step).into_vec());
+				self.handle_empty_step_message(empty_step);
+			},
+			Err(_) => warn!(target: "engine", "generate_empty_step: FAIL: accounts secret key unavailable."),
+		}
+	}
+
+	// anchor the step duration transitions the canonical chain has already passed.
+	fn anchor_step_durations(&self) {
+		if self.step.durations.len() < 2 {
+			return;
+		}
+		let client = match self.client.read().as_ref().and_then(|weak| weak.upgrade()) {
+			Some(client) => client,
+			None => return,
+		};
+		let full_client = match client.as_full_client() {
+			Some(full_client) => full_client,
+			None => return,
+		};
+
+		let mut parent_steps = Vec::new();
+		for number in self.step.durations.keys().skip(1) {
+			let parent = match full_client.block_header(BlockId::Number(number - 1)) {
+				Some(parent) => parent.decode(),
+				None => break,
+			};
+			match header_step(&parent) {
+				Ok(step) => parent_steps.push(step),
+				Err(e) => {
+					warn!(target: "engine", "Unable to anchor step duration transition at block {}: {}", number, e);
+					break;
+				},
+			}
+		}
+
+		self.step.anchor(&parent_steps);
+	}
 }
 
 fn unix_now() -> Duration {
@@ -485,11 +926,15 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 	fn machine(&self) -> &EthereumMachine { &self.machine }
 
-	/// Two fields - consensus step and the corresponding proposer signature.
-	fn seal_fields(&self) -> usize { 2 }
+	/// Two fields - consensus step and the corresponding proposer signature, followed by the
+	/// included empty steps after `empty_steps_transition`.
+	fn seal_fields(&self, header: &Header) -> usize {
+		header_expected_seal_fields(header, self.empty_steps_transition)
+	}
 
 	fn step(&self) {
 		self.step.increment();
+		self.anchor_step_durations();
 		self.can_propose.store(true, AtomicOrdering::SeqCst);
 		if let Some(ref weak) = *self.client.read() {
 			if let Some(c) = weak.upgrade() {
@@ -500,10 +945,19 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
-		map![
+		let mut info = map![
 			"step".into() => header_step(header).as_ref().map(ToString::to_string).unwrap_or("".into()),
 			"signature".into() => header_signature(header).as_ref().map(ToString::to_string).unwrap_or("".into())
-		]
+		];
+
+		if header.number() >= self.empty_steps_transition {
+			let empty_steps = header_empty_steps(header).as_ref()
+				.map(|empty_steps| format!("[{}]", empty_steps.iter().map(ToString::to_string).join(",")))
+				.unwrap_or("".into());
+			info.insert("emptySteps".into(), empty_steps);
+		}
+
+		info
 	}
 
 	fn maximum_uncle_count(&self, block: BlockNumber) -> usize {
@@ -516,8 +970,16 @@ impl Engine<EthereumMachine> for AuthorityRound {
 	}
 
 	fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
-		let parent_step = header_step(parent).expect("Header has been verified; qed");
-		let score = calculate_score(parent_step.into(), self.step.load().into());
+		let (parent_step, _) = self.parent_header_step(parent).expect("Header has been verified; qed");
+		let current_step = self.step.load();
+
+		let current_empty_steps_len = if header.number() >= self.empty_steps_transition {
+			self.empty_steps(parent_step, current_step, &parent.hash()).len()
+		} else {
+			0
+		};
+
+		let score = calculate_score(parent_step.into(), current_step.into(), current_empty_steps_len.into());
 		header.set_difficulty(score);
 	}
 
@@ -536,20 +998,40 @@ impl Engine<EthereumMachine> for AuthorityRound {
 		if !self.can_propose.load(AtomicOrdering::SeqCst) { return Seal::None; }
 
 		let header = block.header();
-		let parent_step: U256 = header_step(parent)
-			.expect("Header has been verified; qed").into();
+		let (parent_step, _) = self.parent_header_step(parent)
+			.expect("Header has been verified; qed");
 
 		let step = self.step.load();
 
-		let expected_diff = calculate_score(parent_step, step.into());
+		// the empty steps were chosen (and rewarded) when the block was closed.
+		let empty_steps = if header.number() >= self.empty_steps_transition {
+			match closed_block_empty_steps(header) {
+				Some((closed_step, empty_steps)) => {
+					if closed_step != step {
+						debug!(target: "engine", "Aborting seal generation. The step has changed since the block was closed: {} != {}",
+							closed_step, step);
+						return Seal::None;
+					}
+					empty_steps
+				},
+				None => {
+					warn!(target: "engine", "Aborting seal generation. The block was not closed with its empty steps.");
+					return Seal::None;
+				},
+			}
+		} else {
+			Vec::new()
+		};
+
+		let expected_diff = calculate_score(parent_step.into(), step.into(), empty_steps.len().into());
 
 		if header.difficulty() != &expected_diff {
-			debug!(target: "engine", "Aborting seal generation. The step has changed in the meantime. {:?} != {:?}",
+			debug!(target: "engine", "Aborting seal generation. The step or empty steps have changed in the meantime. {:?} != {:?}",
 				   header.difficulty(), expected_diff);
 			return Seal::None;
 		}
 
-		if parent_step > step.into() {
+		if parent_step > step {
 			warn!(target: "engine", "Aborting seal generation for invalid step: {} > {}", parent_step, step);
 			return Seal::None;
 		}
@@ -582,17 +1064,40 @@ impl Engine<EthereumMachine> for AuthorityRound {
 		if is_step_proposer(validators, header.parent_hash(), step, header.author()) {
 			// this is guarded against by `can_propose` unless the block was signed
 			// on the same step (implies same key) and on a different node.
-			if parent_step == step.into() {
+			if parent_step == step {
 				warn!("Attempted to seal block on the same step as parent. Is this authority sealing with more than one node?");
 				return Seal::None;
 			}
 
+			// if there are no transactions to include in the block, broadcast a signed empty step
+			// message instead of sealing. Once the maximum number of empty steps is reached the
+			// block is sealed anyway.
+			if header.number() >= self.empty_steps_transition &&
+				block.transactions().is_empty() &&
+				empty_steps.len() < self.maximum_empty_steps {
+
+				if self.can_propose.compare_and_swap(true, false, AtomicOrdering::SeqCst) {
+					self.generate_empty_step(header.parent_hash());
+				}
+
+				return Seal::None;
+			}
+
 			if let Ok(signature) = self.sign(header.bare_hash()) {
 				trace!(target: "engine", "generate_seal: Issuing a block for step {}.", step);
 
 				// only issue the seal if we were the first to reach the compare_and_swap.
 				if self.can_propose.compare_and_swap(true, false, AtomicOrdering::SeqCst) {
-					return Seal::Regular(vec![encode(&step).into_vec(), encode(&(&H520::from(signature) as &[u8])).into_vec()]);
+					// empty steps up to the parent step can't be included anymore.
+					self.clear_empty_steps(parent_step);
+
+					let empty_steps = if header.number() >= self.empty_steps_transition {
+						Some(&empty_steps[..])
+					} else {
+						None
+					};
+
+					return Seal::Regular(seal_fields(step, &H520::from(signature), empty_steps));
 				}
 			} else {
 				warn!(target: "engine", "generate_seal: FAIL: Accounts secret key unavailable.");
@@ -638,8 +1143,89 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 	/// Apply the block reward on finalisation of the block.
 	fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
-		// TODO: move to "machine::WithBalances" trait.
-		::engines::common::bestow_block_reward(block, self.block_reward)
+		let number = block.fields().header.number();
+
+		let mut benefactors = Vec::new();
+		if number >= self.empty_steps_transition {
+			if block.fields().header.seal().is_empty() {
+				// this is a new block, choose the empty steps to include in the seal and keep them
+				// on the block, so that `generate_seal` includes the same ones that are rewarded.
+				let parent_hash = *block.fields().header.parent_hash();
+				let parent_step = self.parent_step(&parent_hash)?;
+				let step = self.step.load();
+				let empty_steps = self.empty_steps(parent_step, step, &parent_hash);
+				block.fields_mut().header.set_seal(seal_fields(step, &H520::default(), Some(&empty_steps)));
+			}
+
+			// reward the empty steps included in the seal.
+			for empty_step in header_empty_steps(&block.fields().header)? {
+				benefactors.push((empty_step.author()?, RewardKind::EmptyStep));
+			}
+		}
+
+		match self.block_reward_contract {
+			Some(ref contract) if number >= self.block_reward_contract_transition => {
+				benefactors.push((*block.fields().header.author(), RewardKind::Author));
+				benefactors.extend(block.fields().uncles.iter().map(|uncle| (*uncle.author(), RewardKind::Uncle)));
+
+				let rewards = {
+					let mut call = |to, data| {
+						let result = self.machine.execute_as_system(
+							block,
+							to,
+							self.block_reward_contract_gas,
+							Some(data),
+						);
+						result.map_err(|e| format!("{}", e))
+					};
+					contract.reward(&benefactors, &mut call)?
+				};
+
+				block_reward::apply_block_rewards(&rewards, RewardType::External, block)
+			},
+			_ => {
+				// TODO: move to "machine::WithBalances" trait.
+				::engines::common::bestow_block_reward(block, self.block_reward)?;
+
+				// empty step authors are rewarded as if they had sealed a block.
+				let empty_step_rewards: Vec<_> = benefactors.into_iter()
+					.map(|(address, _)| (address, self.block_reward))
+					.collect();
+
+				if empty_step_rewards.is_empty() {
+					Ok(())
+				} else {
+					block_reward::apply_block_rewards(&empty_step_rewards, RewardType::EmptyStep, block)
+				}
+			},
+		}
+	}
+
+	fn handle_message(&self, rlp: &[u8]) -> Result<(), EngineError> {
+		fn fmt_err<T: ::std::fmt::Debug>(x: T) -> EngineError {
+			EngineError::MalformedMessage(format!("{:?}", x))
+		}
+
+		let rlp = UntrustedRlp::new(rlp);
+		let empty_step: EmptyStep = rlp.as_val().map_err(fmt_err)?;
+
+		if !empty_step.verify(&*self.validators).unwrap_or(false) {
+			trace!(target: "engine", "handle_message: received invalid empty step {}", empty_step);
+			return Err(EngineError::NotAuthorized(empty_step.author().unwrap_or_default()));
+		}
+
+		if self.step.check_future(empty_step.step).is_err() {
+			trace!(target: "engine", "handle_message: empty step from the future {}", empty_step);
+			return Ok(());
+		}
+
+		trace!(target: "engine", "handle_message: received empty step {}", empty_step);
+		if self.handle_empty_step_message(empty_step) {
+			// relay newly seen empty steps so they reach all authorities.
+			self.broadcast_message(rlp.as_raw().to_vec());
+		}
+
+		Ok(())
 	}
 
 	/// Check the number of seal fields.
@@ -656,7 +1242,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 		match verify_timestamp(&*self.step, header_step(header)?) {
 			Err(BlockError::InvalidSeal) => {
-				self.validators.report_benign(header.author(), set_number, header.number());
+				self.validators.report_benign(header.author(), set_number, header.number(), "block from the future");
 				Err(BlockError::InvalidSeal.into())
 			}
 			Err(e) => Err(e.into()),
@@ -667,7 +1253,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
 	/// Do the step and gas limit validation.
 	fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
 		let step = header_step(header)?;
-		let parent_step = header_step(parent)?;
+		let (parent_step, own_parent) = self.parent_header_step(parent)?;
 		// TODO [ToDr] Should this go from epoch manager?
 		let set_number = header.number();
 
@@ -676,26 +1262,61 @@ impl Engine<EthereumMachine> for AuthorityRound {
 			|| (header.number() >= self.validate_step_transition && step <= parent_step) {
 			trace!(target: "engine", "Multiple blocks proposed for step {}.", parent_step);
 
-			self.validators.report_malicious(header.author(), set_number, header.number(), Default::default());
+			self.validators.report_malicious(header.author(), set_number, header.number(), Default::default(), "multiple blocks in one step");
 			Err(EngineError::DoubleVote(header.author().clone()))?;
 		}
 
-		// Report skipped primaries.
-		if let (true, Some(me)) = (step > parent_step + 1, self.signer.read().address()) {
+		// Ensure the included empty steps are ordered, belong to the parent and fit between the
+		// parent step and the block step. Their signatures are checked with the validator set.
+		let empty_steps = if header.number() >= self.empty_steps_transition {
+			let empty_steps = header_empty_steps(header)?;
+
+			if empty_steps.len() > self.maximum_empty_steps {
+				Err(EngineError::InsufficientProof(
+					format!("too many empty steps: {} > {}", empty_steps.len(), self.maximum_empty_steps)))?;
+			}
+
+			let mut previous_step = parent_step;
+			for empty_step in &empty_steps {
+				if empty_step.step <= previous_step || empty_step.step >= step {
+					Err(EngineError::InsufficientProof(
+						format!("empty step proof for invalid step: {}", empty_step.step)))?;
+				}
+				previous_step = empty_step.step;
+			}
+
+			let expected_difficulty = calculate_score(parent_step.into(), step.into(), empty_steps.len().into());
+			if header.difficulty() != &expected_difficulty {
+				Err(BlockError::InvalidDifficulty(Mismatch { expected: expected_difficulty, found: *header.difficulty() }))?;
+			}
+
+			empty_steps
+		} else {
+			Vec::new()
+		};
+
+		// Report skipped primaries, unless the parent was sealed by another engine.
+		if let (true, Some(me)) = (own_parent && step > parent_step + 1, self.signer.read().address()) {
 			debug!(target: "engine", "Author {} built block with step gap. current step: {}, parent step: {}",
 				header.author(), step, parent_step);
 			let mut reported = HashSet::new();
 			for s in parent_step + 1..step {
+				// Primaries which broadcast an empty step didn't skip their turn.
+				if empty_steps.iter().any(|e| e.step == s) { continue; }
+
 				let skipped_primary = step_proposer(&*self.validators, &parent.hash(), s);
 				// Do not report this signer.
 				if skipped_primary != me {
-					self.validators.report_benign(&skipped_primary, set_number, header.number());
+					self.validators.report_benign(&skipped_primary, set_number, header.number(), "skipped step");
 				}
 				// Stop reporting once validators start repeating.
 				if !reported.insert(skipped_primary) { break; }
 			}
 		}
 
+		// empty steps up to the parent step can't be included anymore.
+		self.clear_empty_steps(parent_step);
+
 		Ok(())
 	}
 
@@ -728,7 +1349,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 		// verify signature against fixed list, but reports should go to the
 		// contract itself.
-		verify_external(header, validators)
+		verify_external(header, validators, self.empty_steps_transition)
 	}
 
 	fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
@@ -784,6 +1405,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 			let mut hash = chain_head.parent_hash().clone();
 			let epoch_transition_hash = epoch_manager.epoch_transition_hash;
+			let empty_steps_transition = self.empty_steps_transition;
 
 			// walk the chain within current epoch backwards.
 			// author == ec_recover(sig) known since
@@ -792,7 +1414,16 @@ impl Engine<EthereumMachine> for AuthorityRound {
 				chain(hash).and_then(|header| {
 					if header.number() == 0 { return None }
 
-					let res = (hash, header.author().clone());
+					let mut signers = match header_empty_steps_signers(&header, empty_steps_transition) {
+						Ok(signers) => signers,
+						Err(_) => {
+							warn!(target: "finality", "Failed to get empty step signatures from block {}", hash);
+							return None;
+						}
+					};
+					signers.push(header.author().clone());
+
+					let res = (hash, signers);
 					trace!(target: "finality", "Ancestry iteration: yielding {:?}", res);
 
 					hash = header.parent_hash().clone();
@@ -809,7 +1440,16 @@ impl Engine<EthereumMachine> for AuthorityRound {
 		}
 
 		{
-			if let Ok(finalized) = epoch_manager.finality_checker.push_hash(chain_head.hash(), *chain_head.author()) {
+			let mut signers = match header_empty_steps_signers(chain_head, self.empty_steps_transition) {
+				Ok(signers) => signers,
+				Err(_) => {
+					warn!(target: "finality", "Failed to get empty step signatures from block {}", chain_head.hash());
+					return None;
+				}
+			};
+			signers.push(*chain_head.author());
+
+			if let Ok(finalized) = epoch_manager.finality_checker.push_hash(chain_head.hash(), signers) {
 				let mut finalized = finalized.into_iter();
 				while let Some(finalized_hash) = finalized.next() {
 					if let Some(pending) = transition_store(finalized_hash) {
@@ -863,6 +1503,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
 				let verifier = Box::new(EpochVerifier {
 					step: self.step.clone(),
 					subchain_validators: list,
+					empty_steps_transition: self.empty_steps_transition,
 				});
 
 				match finalize {
@@ -876,9 +1517,18 @@ impl Engine<EthereumMachine> for AuthorityRound {
 
 	fn register_client(&self, client: Weak<EngineClient>) {
 		*self.client.write() = Some(client.clone());
+		self.anchor_step_durations();
 		self.validators.register_client(client);
 	}
 
+	fn stop(&self) {
+		self.transition_service.stop()
+	}
+
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> {
+		self.validators.evidence()
+	}
+
 	fn set_signer(&self, ap: Arc<AccountProvider>, address: Address, password: String) {
 		self.signer.write().set(ap, address, password);
 	}
@@ -901,16 +1551,82 @@ mod tests {
 	use std::sync::Arc;
 	use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 	use hash::keccak;
-	use ethereum_types::{H520, U256};
+	use ethereum_types::{H256, H520, Address, U256};
 	use header::Header;
 	use rlp::encode;
 	use block::*;
 	use tests::helpers::*;
 	use account_provider::AccountProvider;
+	use client::Client;
 	use spec::Spec;
-	use engines::{Seal, Engine};
-	use engines::validator_set::TestSet;
-	use super::{AuthorityRoundParams, AuthorityRound};
+	use engines::{Seal, Engine, EngineError};
+	use error::Error;
+	use engines::validator_set::{TestSet, SimpleList};
+	use super::{AuthorityRoundParams, AuthorityRound, EmptyStep, calculate_score, empty_step_rlp, header_empty_steps, DEFAULT_BLOCK_REWARD_CONTRACT_GAS};
+
+	fn aura<F>(f: F) -> Arc<AuthorityRound> where F: FnOnce(&mut AuthorityRoundParams) {
+		try_aura(f).unwrap()
+	}
+
+	fn try_aura<F>(f: F) -> Result<Arc<AuthorityRound>, Error> where F: FnOnce(&mut AuthorityRoundParams) {
+		let mut params = AuthorityRoundParams {
+			step_durations: [(0, 1)].iter().cloned().collect(),
+			start_step: Some(1),
+			validators: Box::new(TestSet::new(Default::default(), Default::default())),
+			validate_score_transition: 0,
+			validate_step_transition: 0,
+			immediate_transitions: true,
+			maximum_uncle_count_transition: 0,
+			maximum_uncle_count: 0,
+			block_reward: Default::default(),
+			block_reward_contract_transition: 0,
+			block_reward_contract: Default::default(),
+			block_reward_contract_gas: DEFAULT_BLOCK_REWARD_CONTRACT_GAS.into(),
+			empty_steps_transition: u64::max_value(),
+			maximum_empty_steps: 0,
+			stage_start: 0,
+		};
+
+		// mutate aura params
+		f(&mut params);
+
+		// create engine
+		let mut c_params = ::spec::CommonParams::default();
+		c_params.gas_limit_bound_divisor = 5.into();
+		let machine = ::machine::EthereumMachine::regular(c_params, Default::default());
+		AuthorityRound::new(params, machine)
+	}
+
+	// two validators, `keccak("1")` proposes on even steps and `keccak("0")` on odd steps.
+	fn setup_empty_steps() -> (Spec, Arc<AccountProvider>, Vec<Address>, Arc<AuthorityRound>, Arc<Client>) {
+		let spec = Spec::new_test_round();
+		let tap = Arc::new(AccountProvider::transient_provider());
+		let addr1 = tap.insert_account(keccak("1").into(), "1").unwrap();
+		let addr2 = tap.insert_account(keccak("0").into(), "0").unwrap();
+
+		let engine = aura(|p| {
+			p.start_step = Some(2);
+			p.validators = Box::new(SimpleList::new(vec![addr1, addr2]));
+			p.block_reward = 10.into();
+			p.empty_steps_transition = 1;
+			p.maximum_empty_steps = 1;
+		});
+
+		// the engine only holds a weak reference to the client.
+		let client = generate_dummy_client_with_spec_and_accounts(Spec::new_test_round, None);
+		engine.register_client(Arc::downgrade(&client) as _);
+
+		(spec, tap, vec![addr1, addr2], engine, client)
+	}
+
+	fn sealed_empty_step(tap: &AccountProvider, signer: Address, password: &str, step: usize, parent_hash: &H256) -> EmptyStep {
+		let signature = tap.sign(signer, Some(password.into()), keccak(empty_step_rlp(step, parent_hash))).unwrap();
+		EmptyStep {
+			step: step,
+			parent_hash: *parent_hash,
+			signature: signature.into(),
+		}
+	}
 
 	#[test]
 	fn has_valid_metadata() {
@@ -1080,24 +1796,9 @@ mod tests {
 	#[test]
 	fn reports_skipped() {
 		let last_benign = Arc::new(AtomicUsize::new(0));
-		let params = AuthorityRoundParams {
-			step_duration: 1,
-			start_step: Some(1),
-			validators: Box::new(TestSet::new(Default::default(), last_benign.clone())),
-			validate_score_transition: 0,
-			validate_step_transition: 0,
-			immediate_transitions: true,
-			maximum_uncle_count_transition: 0,
-			maximum_uncle_count: 0,
-			block_reward: Default::default(),
-		};
-
-		let aura = {
-			let mut c_params = ::spec::CommonParams::default();
-			c_params.gas_limit_bound_divisor = 5.into();
-			let machine = ::machine::EthereumMachine::regular(c_params, Default::default());
-			AuthorityRound::new(params, machine).unwrap()
-		};
+		let aura = aura(|p| {
+			p.validators = Box::new(TestSet::new(Default::default(), last_benign.clone()));
+		});
 
 		let mut parent_header: Header = Header::default();
 		parent_header.set_seal(vec![encode(&1usize).into_vec()]);
@@ -1119,75 +1820,268 @@ mod tests {
 
 	#[test]
 	fn test_uncles_transition() {
-		let last_benign = Arc::new(AtomicUsize::new(0));
-		let params = AuthorityRoundParams {
-			step_duration: 1,
-			start_step: Some(1),
-			validators: Box::new(TestSet::new(Default::default(), last_benign.clone())),
-			validate_score_transition: 0,
-			validate_step_transition: 0,
-			immediate_transitions: true,
-			maximum_uncle_count_transition: 1,
-			maximum_uncle_count: 0,
-			block_reward: Default::default(),
-		};
-
-		let aura = {
-			let mut c_params = ::spec::CommonParams::default();
-			c_params.gas_limit_bound_divisor = 5.into();
-			let machine = ::machine::EthereumMachine::regular(c_params, Default::default());
-			AuthorityRound::new(params, machine).unwrap()
-		};
+		let aura = aura(|p| {
+			p.maximum_uncle_count_transition = 1;
+		});
 
 		assert_eq!(aura.maximum_uncle_count(0), 2);
 		assert_eq!(aura.maximum_uncle_count(1), 0);
 		assert_eq!(aura.maximum_uncle_count(100), 0);
 	}
 
-    #[test]
-    #[should_panic(expected="counter is too high")]
-    fn test_counter_increment_too_high() {
-        use super::Step;
-        let step = Step {
-            calibrate: false,
-            inner: AtomicUsize::new(::std::usize::MAX),
-            duration: 1,
-        };
-        step.increment();
+	#[test]
+	#[should_panic(expected="counter is too high")]
+	fn test_counter_increment_too_high() {
+		use super::Step;
+		let step = Step::new([(0, 1)].iter().cloned().collect(), Some(::std::usize::MAX as u64));
+		step.increment();
 	}
 
 	#[test]
 	#[should_panic(expected="counter is too high")]
 	fn test_counter_duration_remaining_too_high() {
 		use super::Step;
-		let step = Step {
-			calibrate: false,
-			inner: AtomicUsize::new(::std::usize::MAX),
-			duration: 1,
-		};
+		let step = Step::new([(0, 1)].iter().cloned().collect(), Some(::std::usize::MAX as u64));
 		step.duration_remaining();
 	}
 
 	#[test]
-	#[should_panic(expected="authority_round: step duration can't be zero")]
-	fn test_step_duration_zero() {
-		let last_benign = Arc::new(AtomicUsize::new(0));
-		let params = AuthorityRoundParams {
-			step_duration: 0,
-			start_step: Some(1),
-			validators: Box::new(TestSet::new(Default::default(), last_benign.clone())),
-			validate_score_transition: 0,
-			validate_step_transition: 0,
-			immediate_transitions: true,
-			maximum_uncle_count_transition: 0,
-			maximum_uncle_count: 0,
-			block_reward: Default::default(),
+	fn step_duration_transitions() {
+		use super::Step;
+		let step = Step::new([(0, 5), (10, 2)].iter().cloned().collect(), Some(1));
+		assert_eq!(step.step_start(4), Some(20));
+		assert_eq!(step.step_at(23), 4);
+
+		// canonical block 9 sealed at step 7 anchors the transition at step 8 (time 40).
+		step.anchor(&[7]);
+		assert_eq!(step.step_start(7), Some(35));
+		assert_eq!(step.step_start(8), Some(40));
+		assert_eq!(step.step_start(10), Some(44));
+		assert_eq!(step.step_at(39), 7);
+		assert_eq!(step.step_at(45), 10);
+
+		// anchoring is idempotent.
+		step.anchor(&[7]);
+		assert_eq!(step.step_start(10), Some(44));
+
+		// a reorganisation of the canonical chain moves the anchor.
+		step.anchor(&[9]);
+		assert_eq!(step.step_start(10), Some(50));
+		assert_eq!(step.step_start(11), Some(52));
+
+		// and reverting it unanchors the transition.
+		step.anchor(&[]);
+		assert_eq!(step.step_start(10), Some(50));
+		assert_eq!(step.step_start(12), Some(60));
+	}
+
+	#[test]
+	fn side_fork_does_not_anchor_step_durations() {
+		let engine = aura(|p| {
+			p.step_durations = [(0, 5), (10, 2)].iter().cloned().collect();
+			p.validate_step_transition = 0;
+		});
+
+		let mut parent_header: Header = Header::default();
+		parent_header.set_number(9);
+		parent_header.set_seal(vec![encode(&7usize).into_vec()]);
+		parent_header.set_gas_limit("222222".parse::<U256>().unwrap());
+		let mut header: Header = Header::default();
+		header.set_number(10);
+		header.set_parent_hash(parent_header.hash());
+		header.set_gas_limit("222222".parse::<U256>().unwrap());
+		header.set_seal(vec![encode(&8usize).into_vec()]);
+
+		assert!(engine.verify_block_family(&header, &parent_header).is_ok());
+		// without a canonical block 9 the transition stays unanchored.
+		assert_eq!(engine.step.step_start(10), Some(50));
+	}
+
+	#[test]
+	fn broadcast_empty_step_instead_of_sealing_empty_block() {
+		let (spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+
+		let b1 = OpenBlock::new(&*engine, Default::default(), false, db, &genesis_header, last_hashes, accounts[0], (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b1 = b1.close_and_lock();
+
+		engine.set_signer(tap.clone(), accounts[0], "1".into());
+		assert!(engine.generate_seal(b1.block(), &genesis_header) == Seal::None);
+
+		let empty_steps = engine.empty_steps(0, 3, &genesis_header.hash());
+		assert_eq!(empty_steps.len(), 1);
+		assert_eq!(empty_steps[0].step, 2);
+		assert_eq!(empty_steps[0].author().unwrap(), accounts[0]);
+	}
+
+	#[test]
+	fn seals_and_rewards_empty_steps() {
+		let (spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+
+		// step 2: the first validator broadcasts an empty step.
+		let empty_step = sealed_empty_step(&tap, accounts[0], "1", 2, &genesis_header.hash());
+		engine.handle_message(&encode(&empty_step)).unwrap();
+		engine.step();
+
+		// step 3: the second validator reaches the maximum number of empty steps and seals.
+		let b2 = OpenBlock::new(&*engine, Default::default(), false, db, &genesis_header, last_hashes, accounts[1], (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b2 = b2.close_and_lock();
+		assert_eq!(b2.block().header().difficulty(), &calculate_score(0.into(), 3.into(), 1.into()));
+
+		engine.set_signer(tap.clone(), accounts[1], "0".into());
+		let seal = match engine.generate_seal(b2.block(), &genesis_header) {
+			Seal::Regular(seal) => seal,
+			_ => panic!("block should be sealed"),
 		};
+		assert_eq!(seal.len(), 3);
 
-		let mut c_params = ::spec::CommonParams::default();
-		c_params.gas_limit_bound_divisor = 5.into();
-		let machine = ::machine::EthereumMachine::regular(c_params, Default::default());
-		AuthorityRound::new(params, machine).unwrap();
+		let b2 = b2.try_seal(&*engine, seal).ok().expect("valid seal");
+		let header = b2.header();
+		assert_eq!(header_empty_steps(header).unwrap(), vec![empty_step]);
+		assert!(engine.verify_block_family(header, &genesis_header).is_ok());
+		assert!(engine.verify_block_external(header).is_ok());
+
+		// both the author and the empty step signer are rewarded.
+		assert_eq!(b2.state().balance(&accounts[0]).unwrap(), 10.into());
+		assert_eq!(b2.state().balance(&accounts[1]).unwrap(), 10.into());
+	}
+
+	#[test]
+	fn seals_empty_steps_chosen_on_close() {
+		let (spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+
+		let empty_step = sealed_empty_step(&tap, accounts[0], "1", 2, &genesis_header.hash());
+		engine.handle_message(&encode(&empty_step)).unwrap();
+		engine.step();
+
+		let b2 = OpenBlock::new(&*engine, Default::default(), false, db, &genesis_header, last_hashes, accounts[1], (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b2 = b2.close_and_lock();
+
+		// the empty step message is dropped after the block was closed.
+		engine.clear_empty_steps(2);
+		assert!(engine.empty_steps(0, 3, &genesis_header.hash()).is_empty());
+
+		engine.set_signer(tap.clone(), accounts[1], "0".into());
+		let seal = match engine.generate_seal(b2.block(), &genesis_header) {
+			Seal::Regular(seal) => seal,
+			_ => panic!("block should be sealed"),
+		};
+
+		// the rewarded empty step is still included in the seal.
+		let b2 = b2.try_seal(&*engine, seal).ok().expect("valid seal");
+		assert_eq!(header_empty_steps(b2.header()).unwrap(), vec![empty_step]);
+		assert_eq!(b2.state().balance(&accounts[0]).unwrap(), 10.into());
+	}
+
+	#[test]
+	fn aborts_seal_when_step_changed_since_close() {
+		let (spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+
+		let b1 = OpenBlock::new(&*engine, Default::default(), false, db, &genesis_header, last_hashes, accounts[1], (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b1 = b1.close_and_lock();
+		engine.step();
+
+		engine.set_signer(tap.clone(), accounts[1], "0".into());
+		assert!(engine.generate_seal(b1.block(), &genesis_header) == Seal::None);
+	}
+
+	#[test]
+	fn rejects_invalid_empty_steps() {
+		let (spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let mut parent_header = spec.genesis_header();
+		parent_header.set_gas_limit("222222".parse::<U256>().unwrap());
+		let parent_hash = parent_header.hash();
+
+		let header_with = |empty_steps: &[EmptyStep], difficulty: U256| {
+			let mut header: Header = Header::default();
+			header.set_number(1);
+			header.set_parent_hash(parent_hash);
+			header.set_gas_limit("222222".parse::<U256>().unwrap());
+			header.set_author(accounts[1]);
+			header.set_difficulty(difficulty);
+			let sealed: Vec<_> = empty_steps.iter().map(EmptyStep::sealed).collect();
+			let signature = tap.sign(accounts[1], Some("0".into()), header.bare_hash()).unwrap();
+			header.set_seal(vec![
+				encode(&3usize).into_vec(),
+				encode(&(&*signature as &[u8])).into_vec(),
+				::rlp::encode_list(&sealed).into_vec(),
+			]);
+			header
+		};
+
+		let valid = sealed_empty_step(&tap, accounts[0], "1", 2, &parent_hash);
+		let header = header_with(&[valid.clone()], calculate_score(0.into(), 3.into(), 1.into()));
+		assert!(engine.verify_block_family(&header, &parent_header).is_ok());
+		assert!(engine.verify_block_external(&header).is_ok());
+
+		// the empty steps are accounted for in the difficulty.
+		let header = header_with(&[valid.clone()], calculate_score(0.into(), 3.into(), 0.into()));
+		assert!(engine.verify_block_family(&header, &parent_header).is_err());
+
+		// empty steps must be between the parent step and the block step.
+		let future = sealed_empty_step(&tap, accounts[0], "1", 4, &parent_hash);
+		let header = header_with(&[future], calculate_score(0.into(), 3.into(), 1.into()));
+		assert!(engine.verify_block_family(&header, &parent_header).is_err());
+
+		// empty steps must be signed by the proposer of their step.
+		let wrong_signer = sealed_empty_step(&tap, accounts[1], "0", 2, &parent_hash);
+		let header = header_with(&[wrong_signer], calculate_score(0.into(), 3.into(), 1.into()));
+		assert!(engine.verify_block_family(&header, &parent_header).is_ok());
+		assert!(engine.verify_block_external(&header).is_err());
+	}
+
+	#[test]
+	fn handles_empty_step_messages() {
+		let (_spec, tap, accounts, engine, _client) = setup_empty_steps();
+		let parent_hash = H256::from(1);
+
+		let empty_step = sealed_empty_step(&tap, accounts[1], "0", 2, &parent_hash);
+		match engine.handle_message(&encode(&empty_step)) {
+			Err(EngineError::NotAuthorized(address)) => assert_eq!(address, accounts[1]),
+			_ => panic!("empty step from the wrong proposer should be rejected"),
+		}
+
+		let empty_step = sealed_empty_step(&tap, accounts[0], "1", 2, &parent_hash);
+		assert!(engine.handle_message(&encode(&empty_step)).is_ok());
+		assert_eq!(engine.empty_steps(0, 3, &parent_hash), vec![empty_step]);
+		assert!(engine.empty_steps(0, 3, &H256::from(2)).is_empty());
+
+		// steps up to the parent step are dropped once a block is imported.
+		engine.clear_empty_steps(2);
+		assert!(engine.empty_steps(0, 3, &parent_hash).is_empty());
+	}
+
+	#[test]
+	fn test_step_duration_first_missing() {
+		let result = try_aura(|p| {
+			p.step_durations = [(10, 1)].iter().cloned().collect();
+		});
+		match result {
+			Err(Error::Engine(EngineError::MissingFirstStepDuration)) => {},
+			_ => panic!("step durations without block 0 should be rejected"),
+		}
+	}
+
+	#[test]
+	fn test_step_duration_zero() {
+		let result = try_aura(|p| {
+			p.step_durations = [(0, 5), (10, 0)].iter().cloned().collect();
+		});
+		match result {
+			Err(Error::Engine(EngineError::ZeroStepDuration)) => {},
+			_ => panic!("zero step duration should be rejected"),
+		}
 	}
 }
 
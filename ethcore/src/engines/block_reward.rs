@@ -0,0 +1,146 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block rewards determined by a system contract.
+
+use futures::Future;
+use native_contracts::BlockReward as Provider;
+use ethereum_types::{Address, U256};
+
+use block::ExecutedBlock;
+use error::Error;
+use state::CleanupMode;
+use trace::{Tracer, ExecutiveTracer, RewardType};
+use super::EngineError;
+use super::validator_set::SystemCall;
+
+/// The kind of block reward.
+/// Depending on the consensus engine the allocated block reward might have
+/// different semantics which could lead e.g. to different reward values.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardKind {
+	/// Reward attributed to the block author.
+	Author = 0,
+	/// Reward attributed to the block uncle(s).
+	Uncle = 1,
+	/// Reward attributed to the author(s) of empty step(s) included in the block (AuthorityRound engine).
+	EmptyStep = 2,
+}
+
+/// A client for the block reward contract.
+///
+/// The contract should have the following interface:
+/// `function reward(address[] benefactors, uint16[] kind) returns (address[], uint256[])`
+#[derive(Clone)]
+pub struct BlockRewardContract {
+	provider: Provider,
+}
+
+impl BlockRewardContract {
+	/// Create a new block reward contract client targeting the given address.
+	pub fn new(address: Address) -> BlockRewardContract {
+		BlockRewardContract {
+			provider: Provider::new(address),
+		}
+	}
+
+	/// Calls the block reward contract with the given benefactors list (and associated reward kind)
+	/// and returns the reward allocation (address - value). The block reward contract *must* be
+	/// called by the system address so the `caller` must ensure that (e.g. using
+	/// `machine.execute_as_system`).
+	pub fn reward(
+		&self,
+		benefactors: &[(Address, RewardKind)],
+		caller: &mut SystemCall,
+	) -> Result<Vec<(Address, U256)>, Error> {
+		let addresses = benefactors.iter().map(|&(address, _)| address).collect();
+		let kinds = benefactors.iter().map(|&(_, kind)| kind as u16).collect();
+
+		let (addresses, rewards) = self.provider.reward(caller, addresses, kinds)
+			.wait()
+			.map_err(EngineError::FailedSystemCall)?;
+
+		if addresses.len() != rewards.len() {
+			return Err(EngineError::FailedSystemCall(
+				"invalid data returned by reward contract: both arrays must have the same size".into()
+			).into());
+		}
+
+		Ok(addresses.into_iter().zip(rewards.into_iter()).collect())
+	}
+}
+
+/// Applies the given block rewards, i.e. adds the given balance to each beneficiary's address.
+/// If tracing is enabled the operations are recorded with the given reward type.
+pub fn apply_block_rewards(rewards: &[(Address, U256)], reward_type: RewardType, block: &mut ExecutedBlock) -> Result<(), Error> {
+	let fields = block.fields_mut();
+	for &(ref address, ref amount) in rewards {
+		fields.state.add_balance(address, amount, CleanupMode::NoEmpty)?;
+	}
+
+	// Commit state so that we can actually figure out the state root.
+	fields.state.commit()?;
+
+	if let Some(ref mut traces) = *fields.traces {
+		let mut tracer = ExecutiveTracer::default();
+		for &(address, amount) in rewards {
+			tracer.trace_reward(address, amount, reward_type.clone());
+		}
+		traces.push(tracer.drain());
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use ethereum_types::{Address, U256};
+	use block::*;
+	use spec::Spec;
+	use tests::helpers::*;
+	use super::{BlockRewardContract, RewardKind};
+
+	#[test]
+	fn block_reward_contract() {
+		let spec = Spec::new_test_round_block_reward_contract();
+		let engine = &*spec.engine;
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = ::std::sync::Arc::new(vec![genesis_header.hash()]);
+		let author = Address::from(0x10);
+		let beneficiary = Address::from(0x20);
+
+		let mut b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, author, (3141562.into(), 31415620.into()), vec![], false).unwrap();
+
+		// the test contract ignores its input and always rewards 0x20 with 5 wei.
+		let contract = BlockRewardContract::new(Address::from(0x42));
+		let rewards = {
+			let machine = engine.machine();
+			let block = b.block_mut();
+			let mut call = |to, data| {
+				machine.execute_as_system(block, to, U256::max_value(), Some(data)).map_err(|e| format!("{}", e))
+			};
+			contract.reward(&[(author, RewardKind::Author)], &mut call).unwrap()
+		};
+		assert_eq!(rewards, vec![(beneficiary, U256::from(5))]);
+
+		// the engine applies the contract reward on close instead of the fixed one.
+		let b = b.close_and_lock();
+		assert_eq!(b.state().balance(&author).unwrap(), U256::zero());
+		assert_eq!(b.state().balance(&beneficiary).unwrap(), U256::from(5));
+	}
+}
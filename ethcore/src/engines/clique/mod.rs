@@ -0,0 +1,572 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Clique proof-of-authority consensus engine (EIP-225).
+//!
+//! Blocks are signed by a set of authorized signers. The signature is kept in the last
+//! 65 bytes of the extra data, checkpoint blocks list the signers in between the 32-byte
+//! vanity prefix and the signature. Signers vote on adding or removing an address by setting
+//! it as the block author along with an "authorize" or "drop" nonce.
+
+mod signers;
+
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Weak, Arc};
+use std::time::UNIX_EPOCH;
+
+use account_provider::AccountProvider;
+use block::*;
+use client::EngineClient;
+use engines::{Engine, Seal, EngineError};
+use error::{Error, BlockError};
+use ethjson;
+use header::{Header, Seal as HeaderSeal};
+use ids::BlockId;
+use io::{IoContext, IoHandler, TimerToken, IoService};
+use machine::EthereumMachine;
+use semantic_version::SemanticVersion;
+use super::signer::EngineSigner;
+use self::signers::{SignerSnapshot, ApplyError};
+
+use ethkey::{recover, public_to_address, Signature};
+use ethereum_types::{H64, H256, H520, Address, U256};
+use lru_cache::LruCache;
+use parking_lot::{Mutex, RwLock};
+use rlp::{UntrustedRlp, encode};
+use unexpected::{Mismatch, OutOfBounds};
+
+/// Length of the vanity prefix of the extra data.
+const EXTRA_VANITY: usize = 32;
+/// Length of the signature suffix of the extra data.
+const EXTRA_SEAL: usize = 65;
+/// Nonce voting to authorize the block author.
+const NONCE_AUTH_VOTE: [u8; 8] = [0xff; 8];
+/// Nonce voting to drop the block author.
+const NONCE_DROP_VOTE: [u8; 8] = [0; 8];
+/// Difficulty of blocks sealed by the in-turn signer.
+const DIFF_INTURN: u64 = 2;
+/// Difficulty of blocks sealed by an out-of-turn signer.
+const DIFF_NOTURN: u64 = 1;
+/// Delay per position out-of-turn signers wait before sealing, in milliseconds.
+const OUT_OF_TURN_DELAY_MS: u64 = 500;
+/// Number of signer snapshots kept in memory.
+const SNAPSHOT_CACHE_ITEMS: usize = 128;
+/// Interval at which sealing is retried, in milliseconds.
+const SEALING_TICK_MS: u64 = 500;
+
+/// `Clique` params.
+#[derive(Debug, PartialEq)]
+pub struct CliqueParams {
+	/// Minimal number of seconds between blocks.
+	pub period: u64,
+	/// Number of blocks after which votes are reset and the signer set is checkpointed.
+	pub epoch: u64,
+}
+
+impl From<ethjson::spec::CliqueParams> for CliqueParams {
+	fn from(p: ethjson::spec::CliqueParams) -> Self {
+		CliqueParams {
+			period: p.period.map_or(15, Into::into),
+			epoch: p.epoch.map_or(30000, Into::into),
+		}
+	}
+}
+
+/// Engine using `Clique` proof-of-authority consensus.
+pub struct Clique {
+	transition_service: IoService<()>,
+	machine: EthereumMachine,
+	period: u64,
+	epoch: u64,
+	client: RwLock<Option<Weak<EngineClient>>>,
+	signer: RwLock<EngineSigner>,
+	proposals: RwLock<HashMap<Address, bool>>,
+	snapshots: Mutex<LruCache<H256, SignerSnapshot>>,
+}
+
+impl Clique {
+	/// Create a new instance of Clique engine.
+	pub fn new(our_params: CliqueParams, machine: EthereumMachine) -> Result<Arc<Self>, Error> {
+		if our_params.epoch == 0 {
+			error!(target: "engine", "Clique epoch length can't be zero, aborting");
+			panic!("clique: epoch length can't be zero")
+		}
+		let engine = Arc::new(
+			Clique {
+				transition_service: IoService::<()>::start()?,
+				machine: machine,
+				period: our_params.period,
+				epoch: our_params.epoch,
+				client: RwLock::new(None),
+				signer: Default::default(),
+				proposals: RwLock::new(HashMap::new()),
+				snapshots: Mutex::new(LruCache::new(SNAPSHOT_CACHE_ITEMS)),
+			});
+
+		// Blocks are only sealed on demand when there is no minimal period.
+		if our_params.period > 0 {
+			let handler = TransitionHandler { engine: Arc::downgrade(&engine) };
+			engine.transition_service.register_handler(Arc::new(handler))?;
+		}
+		Ok(engine)
+	}
+
+	/// Vote for adding (or removing, if `authorize` is false) the given address
+	/// in the blocks sealed by this node.
+	pub fn propose(&self, address: Address, authorize: bool) {
+		self.proposals.write().insert(address, authorize);
+	}
+
+	/// Stop voting on the given address.
+	pub fn discard(&self, address: &Address) {
+		self.proposals.write().remove(address);
+	}
+
+	fn is_checkpoint(&self, number: u64) -> bool {
+		number % self.epoch == 0
+	}
+
+	fn header_by_hash(&self, hash: H256) -> Result<Header, Error> {
+		let client = self.client.read().as_ref().and_then(|weak| weak.upgrade()).ok_or(EngineError::RequiresClient)?;
+		let full_client = client.as_full_client().ok_or(EngineError::RequiresClient)?;
+		match full_client.block_header(BlockId::Hash(hash)) {
+			Some(header) => Ok(header.decode()),
+			None => Err(BlockError::UnknownParent(hash).into()),
+		}
+	}
+
+	/// Signer set after the block with the given hash.
+	fn snapshot_at(&self, hash: H256) -> Result<SignerSnapshot, Error> {
+		if let Some(snapshot) = self.snapshots.lock().get_mut(&hash) {
+			return Ok(snapshot.clone());
+		}
+		let header = self.header_by_hash(hash)?;
+		self.snapshot(&header)
+	}
+
+	/// Signer set after the given block. Walks back to the closest cached snapshot
+	/// or checkpoint and applies the blocks in between.
+	fn snapshot(&self, header: &Header) -> Result<SignerSnapshot, Error> {
+		let mut pending = Vec::new();
+		let mut current = header.clone();
+		let mut snapshot = loop {
+			if let Some(snapshot) = self.snapshots.lock().get_mut(&current.hash()) {
+				break snapshot.clone();
+			}
+			if self.is_checkpoint(current.number()) {
+				break SignerSnapshot::new(current.number(), current.hash(), checkpoint_signers(&current)?);
+			}
+			let parent = self.header_by_hash(*current.parent_hash())?;
+			pending.push(current);
+			current = parent;
+		};
+
+		for header in pending.iter().rev() {
+			let signer = recover_signer(header)?;
+			self.apply(&mut snapshot, header, signer)?;
+		}
+
+		self.snapshots.lock().insert(snapshot.hash, snapshot.clone());
+		Ok(snapshot)
+	}
+
+	fn apply(&self, snapshot: &mut SignerSnapshot, header: &Header, signer: Address) -> Result<(), Error> {
+		let vote = (*header.author(), header_vote(header)?);
+		snapshot.apply(header.number(), header.hash(), signer, vote, self.epoch).map_err(|e| match e {
+			ApplyError::OutOfOrder => Error::from(BlockError::UnknownParent(*header.parent_hash())),
+			ApplyError::Unauthorized(address) => Error::from(EngineError::NotAuthorized(address)),
+			ApplyError::Recent(address) => Error::from(EngineError::CliqueTooRecentlySigned(address)),
+		})
+	}
+}
+
+fn unix_now_ms() -> u64 {
+	let now = UNIX_EPOCH.elapsed().expect("Valid time has to be set in your system.");
+	now.as_secs() * 1_000 + (now.subsec_nanos() / 1_000_000) as u64
+}
+
+fn header_nonce(header: &Header) -> Result<H64, Error> {
+	let seal = header.seal();
+	let nonce = seal.get(1).ok_or(BlockError::InvalidSealArity(Mismatch { expected: 2, found: seal.len() }))?;
+	Ok(UntrustedRlp::new(nonce).as_val::<H64>()?)
+}
+
+/// Whether the header votes to authorize (`true`) or drop (`false`) its author.
+fn header_vote(header: &Header) -> Result<bool, Error> {
+	let nonce = header_nonce(header)?;
+	if nonce == H64::from(NONCE_AUTH_VOTE) {
+		Ok(true)
+	} else if nonce == H64::from(NONCE_DROP_VOTE) {
+		Ok(false)
+	} else {
+		Err(EngineError::CliqueInvalidNonce(nonce).into())
+	}
+}
+
+/// Signers listed in the extra data of a checkpoint block.
+fn checkpoint_signers(header: &Header) -> Result<Vec<Address>, Error> {
+	let extra = header.extra_data();
+	if extra.len() < EXTRA_VANITY {
+		return Err(EngineError::CliqueMissingVanity.into());
+	}
+	if extra.len() < EXTRA_VANITY + EXTRA_SEAL {
+		return Err(EngineError::CliqueMissingSignature.into());
+	}
+	let signers = &extra[EXTRA_VANITY..extra.len() - EXTRA_SEAL];
+	if signers.len() % 20 != 0 {
+		return Err(EngineError::CliqueInvalidSigners(signers.len()).into());
+	}
+	Ok(signers.chunks(20).map(Address::from_slice).collect())
+}
+
+/// Hash signed by the block sealer: the full header without the signature.
+fn signature_hash(header: &Header) -> Result<H256, Error> {
+	let extra = header.extra_data();
+	if extra.len() < EXTRA_VANITY + EXTRA_SEAL {
+		return Err(EngineError::CliqueMissingSignature.into());
+	}
+	let mut unsigned = header.clone();
+	unsigned.set_extra_data(extra[..extra.len() - EXTRA_SEAL].to_vec());
+	Ok(unsigned.rlp_keccak(HeaderSeal::With))
+}
+
+fn recover_signer(header: &Header) -> Result<Address, Error> {
+	let hash = signature_hash(header)?;
+	let extra = header.extra_data();
+	let signature = H520::from_slice(&extra[extra.len() - EXTRA_SEAL..]);
+	Ok(public_to_address(&recover(&signature.into(), &hash)?))
+}
+
+struct TransitionHandler {
+	engine: Weak<Clique>,
+}
+
+const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
+
+impl IoHandler<()> for TransitionHandler {
+	fn initialize(&self, io: &IoContext<()>) {
+		io.register_timer(ENGINE_TIMEOUT_TOKEN, SEALING_TICK_MS)
+			.unwrap_or_else(|e| warn!(target: "engine", "Failed to start sealing timer: {}.", e))
+	}
+
+	fn timeout(&self, _io: &IoContext<()>, timer: TimerToken) {
+		if timer == ENGINE_TIMEOUT_TOKEN {
+			if let Some(engine) = self.engine.upgrade() {
+				engine.step();
+			}
+		}
+	}
+}
+
+impl Engine<EthereumMachine> for Clique {
+	fn name(&self) -> &str { "Clique" }
+	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
+
+	fn machine(&self) -> &EthereumMachine { &self.machine }
+
+	// Two fields - mix hash (always zero) and nonce (vote).
+	fn seal_fields(&self, _header: &Header) -> usize { 2 }
+
+	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
+		map![
+			"signer".into() => recover_signer(header).as_ref().map(ToString::to_string).unwrap_or("".into())
+		]
+	}
+
+	fn step(&self) {
+		if let Some(ref weak) = *self.client.read() {
+			if let Some(c) = weak.upgrade() {
+				c.update_sealing();
+			}
+		}
+	}
+
+	fn seals_internally(&self) -> Option<bool> {
+		Some(self.signer.read().is_some())
+	}
+
+	fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
+		let snapshot = match self.snapshot(parent) {
+			Ok(snapshot) => snapshot,
+			Err(e) => {
+				warn!(target: "engine", "Unable to compute Clique signers at block #{}: {}", parent.number(), e);
+				return;
+			}
+		};
+		let number = header.number();
+
+		let timestamp = cmp::max(header.timestamp(), parent.timestamp() + self.period);
+		header.set_timestamp(timestamp);
+
+		let inturn = self.signer.read().address().map_or(false, |signer| snapshot.is_inturn(number, &signer));
+		header.set_difficulty(U256::from(if inturn { DIFF_INTURN } else { DIFF_NOTURN }));
+
+		// checkpoints never carry votes.
+		let checkpoint = self.is_checkpoint(number);
+		let vote = match checkpoint {
+			true => Address::zero(),
+			false => self.proposals.read().iter()
+				.find(|&(address, authorize)| snapshot.is_valid_vote(address, *authorize))
+				.map_or_else(Address::zero, |(address, _)| *address),
+		};
+		header.set_author(vote);
+
+		let mut extra = header.extra_data().clone();
+		extra.resize(EXTRA_VANITY, 0);
+		if checkpoint {
+			for signer in snapshot.signers() {
+				extra.extend_from_slice(&*signer);
+			}
+		}
+		extra.extend_from_slice(&[0u8; EXTRA_SEAL]);
+		header.set_extra_data(extra);
+	}
+
+	/// Attempt to seal the block internally. The signature itself is added in `on_seal_block`.
+	fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
+		let header = block.header();
+		let signer = match self.signer.read().address() {
+			Some(signer) => signer,
+			None => return Seal::None,
+		};
+		let snapshot = match self.snapshot(parent) {
+			Ok(snapshot) => snapshot,
+			Err(e) => {
+				warn!(target: "engine", "generate_seal: unable to compute signers: {}", e);
+				return Seal::None;
+			}
+		};
+
+		let number = header.number();
+		let distance = match snapshot.turn_distance(number, &signer) {
+			Some(distance) => distance,
+			None => {
+				trace!(target: "engine", "generate_seal: {} is not an authorized signer", signer);
+				return Seal::None;
+			}
+		};
+		if snapshot.is_recent(number, &signer) {
+			trace!(target: "engine", "generate_seal: {} signed recently, must wait for others", signer);
+			return Seal::None;
+		}
+
+		let expected_difficulty = U256::from(if distance == 0 { DIFF_INTURN } else { DIFF_NOTURN });
+		if *header.difficulty() != expected_difficulty {
+			trace!(target: "engine", "generate_seal: block was prepared with a different turn, waiting for a fresh one");
+			return Seal::None;
+		}
+
+		// out-of-turn signers give the in-turn one a head start.
+		let delay = OUT_OF_TURN_DELAY_MS * distance as u64;
+		if unix_now_ms() < header.timestamp() * 1_000 + delay {
+			return Seal::None;
+		}
+
+		let nonce = match self.proposals.read().get(header.author()) {
+			Some(&true) => NONCE_AUTH_VOTE,
+			_ => NONCE_DROP_VOTE,
+		};
+		Seal::Regular(vec![encode(&H256::zero()).into_vec(), encode(&H64::from(nonce)).into_vec()])
+	}
+
+	fn on_seal_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+		let header = block.fields_mut().header;
+		let signature = self.sign(signature_hash(header)?)?;
+
+		let mut extra = header.extra_data().clone();
+		let offset = extra.len() - EXTRA_SEAL;
+		extra[offset..].copy_from_slice(&*H520::from(signature));
+		header.set_extra_data(extra);
+		Ok(())
+	}
+
+	fn verify_local_seal(&self, _header: &Header) -> Result<(), Error> {
+		Ok(())
+	}
+
+	fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
+		let extra = header.extra_data();
+		if extra.len() < EXTRA_VANITY {
+			return Err(EngineError::CliqueMissingVanity.into());
+		}
+		if extra.len() < EXTRA_VANITY + EXTRA_SEAL {
+			return Err(EngineError::CliqueMissingSignature.into());
+		}
+
+		let checkpoint = self.is_checkpoint(header.number());
+		let signers_len = extra.len() - EXTRA_VANITY - EXTRA_SEAL;
+		if (checkpoint && signers_len % 20 != 0) || (!checkpoint && signers_len != 0) {
+			return Err(EngineError::CliqueInvalidSigners(signers_len).into());
+		}
+
+		let authorize = header_vote(header)?;
+		if checkpoint && authorize {
+			return Err(EngineError::CliqueInvalidNonce(header_nonce(header)?).into());
+		}
+		if checkpoint && !header.author().is_zero() {
+			return Err(EngineError::CliqueWrongAuthorCheckpoint(*header.author()).into());
+		}
+
+		let mix_hash = UntrustedRlp::new(&header.seal()[0]).as_val::<H256>()?;
+		if !mix_hash.is_zero() {
+			return Err(BlockError::MismatchedH256SealElement(Mismatch { expected: H256::zero(), found: mix_hash }).into());
+		}
+		Ok(())
+	}
+
+	fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+		let min = parent.timestamp() + self.period;
+		if header.timestamp() < min {
+			return Err(BlockError::InvalidTimestamp(OutOfBounds { min: Some(min), max: None, found: header.timestamp() }).into());
+		}
+		Ok(())
+	}
+
+	fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
+		let mut snapshot = self.snapshot_at(*header.parent_hash())?;
+		let number = header.number();
+
+		let signer = recover_signer(header)?;
+		if !snapshot.is_signer(&signer) {
+			return Err(EngineError::NotAuthorized(signer).into());
+		}
+		if snapshot.is_recent(number, &signer) {
+			return Err(EngineError::CliqueTooRecentlySigned(signer).into());
+		}
+
+		let expected = U256::from(if snapshot.is_inturn(number, &signer) { DIFF_INTURN } else { DIFF_NOTURN });
+		if *header.difficulty() != expected {
+			return Err(BlockError::InvalidDifficulty(Mismatch { expected: expected, found: *header.difficulty() }).into());
+		}
+
+		if self.is_checkpoint(number) && checkpoint_signers(header)? != snapshot.signers() {
+			return Err(EngineError::CliqueCheckpointMismatch.into());
+		}
+
+		self.apply(&mut snapshot, header, signer)?;
+		self.snapshots.lock().insert(snapshot.hash, snapshot);
+		Ok(())
+	}
+
+	fn register_client(&self, client: Weak<EngineClient>) {
+		*self.client.write() = Some(client);
+	}
+
+	fn stop(&self) {
+		self.transition_service.stop()
+	}
+
+	fn set_signer(&self, ap: Arc<AccountProvider>, address: Address, password: String) {
+		self.signer.write().set(ap, address, password);
+	}
+
+	fn sign(&self, hash: H256) -> Result<Signature, Error> {
+		self.signer.read().sign(hash).map_err(Into::into)
+	}
+
+	fn snapshot_components(&self) -> Option<Box<::snapshot::SnapshotComponents>> {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use hash::keccak;
+	use block::*;
+	use tests::helpers::*;
+	use account_provider::AccountProvider;
+	use header::Header;
+	use spec::Spec;
+	use engines::Seal;
+
+	#[test]
+	fn has_valid_metadata() {
+		let engine = Spec::new_test_clique().engine;
+		assert!(!engine.name().is_empty());
+		assert!(engine.version().major >= 1);
+		assert_eq!(engine.seal_fields(&Header::default()), 2);
+	}
+
+	#[test]
+	fn rejects_header_without_signature() {
+		let engine = Spec::new_test_clique().engine;
+		let mut header = Header::default();
+		header.set_number(1);
+		header.set_seal(vec![::rlp::encode(&::ethereum_types::H256::zero()).into_vec(), ::rlp::encode(&::ethereum_types::H64::zero()).into_vec()]);
+
+		header.set_extra_data(vec![0; 10]);
+		assert!(engine.verify_block_basic(&header).is_err());
+		header.set_extra_data(vec![0; 32]);
+		assert!(engine.verify_block_basic(&header).is_err());
+		header.set_extra_data(vec![0; 97]);
+		assert!(engine.verify_block_basic(&header).is_ok());
+	}
+
+	#[test]
+	fn can_seal_and_verify_block() {
+		let tap = AccountProvider::transient_provider();
+		let addr = tap.insert_account(keccak("").into(), "").unwrap();
+
+		let spec = Spec::new_test_clique();
+		let engine = &*spec.engine;
+		engine.set_signer(Arc::new(tap), addr, "".into());
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, addr, (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b = b.close_and_lock();
+		assert_eq!(*b.header().difficulty(), 2.into());
+		match engine.generate_seal(b.block(), &genesis_header) {
+			Seal::Regular(seal) => {
+				let sealed = b.seal(engine, seal).unwrap();
+				assert_eq!(sealed.header().extra_data().len(), 32 + 65);
+				assert!(engine.verify_block_basic(sealed.header()).is_ok());
+				assert!(engine.verify_block_family(sealed.header(), &genesis_header).is_ok());
+				assert!(engine.verify_block_external(sealed.header()).is_ok());
+			},
+			_ => panic!("authorized signer should seal"),
+		}
+	}
+
+	#[test]
+	fn does_not_seal_when_not_authorized() {
+		let tap = AccountProvider::transient_provider();
+		let addr = tap.insert_account(keccak("1").into(), "1").unwrap();
+
+		let spec = Spec::new_test_clique();
+		let engine = &*spec.engine;
+		engine.set_signer(Arc::new(tap), addr, "1".into());
+		let genesis_header = spec.genesis_header();
+		let db = spec.ensure_db_good(get_temp_state_db(), &Default::default()).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, addr, (3141562.into(), 31415620.into()), vec![], false).unwrap();
+		let b = b.close_and_lock();
+		assert_eq!(engine.generate_seal(b.block(), &genesis_header), Seal::None);
+	}
+
+	#[test]
+	fn seals_internally() {
+		let tap = AccountProvider::transient_provider();
+		let authority = tap.insert_account(keccak("").into(), "").unwrap();
+
+		let engine = Spec::new_test_clique().engine;
+		assert!(!engine.seals_internally().unwrap());
+		engine.set_signer(Arc::new(tap), authority, "".into());
+		assert!(engine.seals_internally().unwrap());
+	}
+}
@@ -0,0 +1,304 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Clique signer set at a given block along with the votes cast so far.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use ethereum_types::{H256, Address};
+use header::BlockNumber;
+
+/// A single vote cast by a signer.
+#[derive(Debug, Clone, PartialEq)]
+struct Vote {
+	signer: Address,
+	address: Address,
+	authorize: bool,
+}
+
+/// Running count of votes for a single address.
+#[derive(Debug, Clone, PartialEq)]
+struct Tally {
+	authorize: bool,
+	votes: usize,
+}
+
+/// Reasons a header cannot be applied to the signer set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyError {
+	/// Header does not directly follow the snapshot.
+	OutOfOrder,
+	/// Header was sealed by an address outside of the signer set.
+	Unauthorized(Address),
+	/// Header was sealed by a signer of one of the recent blocks.
+	Recent(Address),
+}
+
+/// State of the signer set after a given block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignerSnapshot {
+	/// Number of the block the snapshot was taken at.
+	pub number: BlockNumber,
+	/// Hash of the block the snapshot was taken at.
+	pub hash: H256,
+	signers: BTreeSet<Address>,
+	recents: BTreeMap<BlockNumber, Address>,
+	votes: Vec<Vote>,
+	tally: HashMap<Address, Tally>,
+}
+
+impl SignerSnapshot {
+	/// Create a snapshot at a checkpoint block with the given signers.
+	pub fn new(number: BlockNumber, hash: H256, signers: Vec<Address>) -> Self {
+		SignerSnapshot {
+			number: number,
+			hash: hash,
+			signers: signers.into_iter().collect(),
+			recents: BTreeMap::new(),
+			votes: Vec::new(),
+			tally: HashMap::new(),
+		}
+	}
+
+	/// Signers in ascending order.
+	pub fn signers(&self) -> Vec<Address> {
+		self.signers.iter().cloned().collect()
+	}
+
+	/// Whether the address is currently a signer.
+	pub fn is_signer(&self, address: &Address) -> bool {
+		self.signers.contains(address)
+	}
+
+	/// Whether casting the given vote would have any effect.
+	pub fn is_valid_vote(&self, address: &Address, authorize: bool) -> bool {
+		self.is_signer(address) != authorize
+	}
+
+	/// Whether `signer` is due to seal the block with the given number.
+	pub fn is_inturn(&self, number: BlockNumber, signer: &Address) -> bool {
+		self.turn_distance(number, signer) == Some(0)
+	}
+
+	/// How many positions after the in-turn signer `signer` comes for the block with the given number.
+	pub fn turn_distance(&self, number: BlockNumber, signer: &Address) -> Option<usize> {
+		let len = self.signers.len();
+		self.signers.iter().position(|s| s == signer).map(|index| {
+			let inturn = (number % len as u64) as usize;
+			(index + len - inturn) % len
+		})
+	}
+
+	/// Whether `signer` sealed one of the recent blocks and may not seal the block with the given number.
+	pub fn is_recent(&self, number: BlockNumber, signer: &Address) -> bool {
+		let limit = self.recent_limit();
+		self.recents.iter().any(|(seen, s)| s == signer && (number < limit || *seen > number - limit))
+	}
+
+	/// Apply a header sealed by `signer` on top of this snapshot.
+	/// `vote` is the voted address along with whether it should be authorized,
+	/// `epoch` is the checkpoint interval.
+	pub fn apply(
+		&mut self,
+		number: BlockNumber,
+		hash: H256,
+		signer: Address,
+		vote: (Address, bool),
+		epoch: u64,
+	) -> Result<(), ApplyError> {
+		if number != self.number + 1 {
+			return Err(ApplyError::OutOfOrder);
+		}
+
+		// votes are reset at checkpoints.
+		if number % epoch == 0 {
+			self.votes.clear();
+			self.tally.clear();
+		}
+
+		// allow the oldest recent signer to seal again.
+		let limit = self.recent_limit();
+		if number >= limit {
+			self.recents.remove(&(number - limit));
+		}
+
+		if !self.is_signer(&signer) {
+			return Err(ApplyError::Unauthorized(signer));
+		}
+		if self.recents.values().any(|s| *s == signer) {
+			return Err(ApplyError::Recent(signer));
+		}
+		self.recents.insert(number, signer);
+
+		// a signer may only hold a single vote per address.
+		let (address, authorize) = vote;
+		if let Some(index) = self.votes.iter().position(|v| v.signer == signer && v.address == address) {
+			let previous = self.votes.remove(index);
+			self.uncast(&previous.address, previous.authorize);
+		}
+
+		if self.cast(&address, authorize) {
+			self.votes.push(Vote {
+				signer: signer,
+				address: address,
+				authorize: authorize,
+			});
+		}
+
+		let passed = self.tally.get(&address).map_or(false, |t| t.votes > self.signers.len() / 2);
+		if passed {
+			if authorize {
+				self.signers.insert(address);
+			} else {
+				self.signers.remove(&address);
+
+				// the signer set shrunk, release the oldest recent signer.
+				let limit = self.recent_limit();
+				if number >= limit {
+					self.recents.remove(&(number - limit));
+				}
+
+				// discard the votes of the removed signer.
+				let (removed, kept): (Vec<_>, Vec<_>) = self.votes.drain(..).partition(|v| v.signer == address);
+				self.votes = kept;
+				for v in removed {
+					self.uncast(&v.address, v.authorize);
+				}
+			}
+
+			self.votes.retain(|v| v.address != address);
+			self.tally.remove(&address);
+		}
+
+		self.number = number;
+		self.hash = hash;
+		Ok(())
+	}
+
+	fn recent_limit(&self) -> u64 {
+		self.signers.len() as u64 / 2 + 1
+	}
+
+	fn cast(&mut self, address: &Address, authorize: bool) -> bool {
+		if !self.is_valid_vote(address, authorize) {
+			return false;
+		}
+		self.tally.entry(*address).or_insert(Tally { authorize: authorize, votes: 0 }).votes += 1;
+		true
+	}
+
+	fn uncast(&mut self, address: &Address, authorize: bool) {
+		let remove = match self.tally.get_mut(address) {
+			Some(tally) => {
+				if tally.authorize != authorize {
+					return;
+				}
+				tally.votes -= 1;
+				tally.votes == 0
+			},
+			None => false,
+		};
+		if remove {
+			self.tally.remove(address);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethereum_types::{H256, Address};
+	use super::{SignerSnapshot, ApplyError};
+
+	const EPOCH: u64 = 30000;
+
+	fn addr(n: u64) -> Address {
+		Address::from(n)
+	}
+
+	fn snapshot(signers: &[u64]) -> SignerSnapshot {
+		SignerSnapshot::new(0, H256::zero(), signers.iter().cloned().map(addr).collect())
+	}
+
+	fn apply(snap: &mut SignerSnapshot, signer: u64, vote: (u64, bool)) -> Result<(), ApplyError> {
+		let number = snap.number + 1;
+		snap.apply(number, H256::from(number), addr(signer), (addr(vote.0), vote.1), EPOCH)
+	}
+
+	#[test]
+	fn single_signer_adds_signer() {
+		let mut snap = snapshot(&[1]);
+		apply(&mut snap, 1, (2, true)).unwrap();
+		assert_eq!(snap.signers(), vec![addr(1), addr(2)]);
+	}
+
+	#[test]
+	fn majority_required_to_add_signer() {
+		let mut snap = snapshot(&[1, 2]);
+		apply(&mut snap, 1, (3, true)).unwrap();
+		assert!(!snap.is_signer(&addr(3)));
+		apply(&mut snap, 2, (3, true)).unwrap();
+		assert!(snap.is_signer(&addr(3)));
+	}
+
+	#[test]
+	fn removed_signer_votes_are_discarded() {
+		let mut snap = snapshot(&[1, 2, 3]);
+		apply(&mut snap, 3, (4, true)).unwrap();
+		apply(&mut snap, 1, (3, false)).unwrap();
+		apply(&mut snap, 2, (3, false)).unwrap();
+		assert_eq!(snap.signers(), vec![addr(1), addr(2)]);
+
+		// the vote of the removed signer no longer counts towards adding 4.
+		apply(&mut snap, 1, (4, true)).unwrap();
+		assert!(!snap.is_signer(&addr(4)));
+		apply(&mut snap, 2, (4, true)).unwrap();
+		assert!(snap.is_signer(&addr(4)));
+	}
+
+	#[test]
+	fn recent_signer_is_rejected() {
+		let mut snap = snapshot(&[1, 2, 3]);
+		apply(&mut snap, 1, (0, false)).unwrap();
+		assert!(snap.is_recent(2, &addr(1)));
+		assert_eq!(apply(&mut snap, 1, (0, false)), Err(ApplyError::Recent(addr(1))));
+		apply(&mut snap, 2, (0, false)).unwrap();
+		assert!(!snap.is_recent(3, &addr(1)));
+		apply(&mut snap, 1, (0, false)).unwrap();
+	}
+
+	#[test]
+	fn unauthorized_signer_is_rejected() {
+		let mut snap = snapshot(&[1]);
+		assert_eq!(apply(&mut snap, 2, (0, false)), Err(ApplyError::Unauthorized(addr(2))));
+	}
+
+	#[test]
+	fn votes_are_reset_at_checkpoint() {
+		let mut snap = SignerSnapshot::new(EPOCH - 2, H256::zero(), vec![addr(1), addr(2)]);
+		apply(&mut snap, 1, (3, true)).unwrap();
+		apply(&mut snap, 2, (0, false)).unwrap();
+		apply(&mut snap, 1, (3, true)).unwrap();
+		assert!(!snap.is_signer(&addr(3)));
+	}
+
+	#[test]
+	fn turns_follow_address_order() {
+		let snap = snapshot(&[3, 1, 2]);
+		assert!(snap.is_inturn(3, &addr(1)));
+		assert!(snap.is_inturn(4, &addr(2)));
+		assert_eq!(snap.turn_distance(4, &addr(1)), Some(2));
+		assert_eq!(snap.turn_distance(4, &addr(4)), None);
+	}
+}
@@ -0,0 +1,349 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local record of validator misbehaviour reports.
+
+use std::collections::VecDeque;
+use ethereum_types::{H256, Address};
+use parking_lot::RwLock;
+use hash::keccak;
+use rlp::{RlpStream, UntrustedRlp, Encodable, Decodable, DecoderError};
+use bytes::Bytes;
+use header::BlockNumber;
+
+/// Number of times submitting a report is attempted before giving up on it.
+pub const MAX_REPORT_ATTEMPTS: usize = 10;
+
+/// Maximum number of reports kept in the store.
+const MAX_EVIDENCE: usize = 1024;
+
+/// Severity of the reported misbehaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehaviour {
+	/// Benign misbehaviour, e.g. a missed turn.
+	Benign,
+	/// Malicious misbehaviour, e.g. a double vote.
+	Malicious,
+}
+
+/// Submission status of a report.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportStatus {
+	/// Not submitted yet.
+	Pending,
+	/// The report transaction was accepted by the local queue, but is not included in the chain yet.
+	Submitted,
+	/// The report transaction is included in the canonical chain.
+	Included,
+	/// The last submission failed with the given error and will be retried.
+	Failed(String),
+	/// Submission failed `MAX_REPORT_ATTEMPTS` times with the last given error.
+	Abandoned(String),
+}
+
+/// Evidence of a reported misbehaviour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evidence {
+	/// Identifier of the report.
+	pub hash: H256,
+	/// Reported validator.
+	pub validator: Address,
+	/// Block number of the validator set the validator belongs to.
+	pub set_block: BlockNumber,
+	/// Block at which the misbehaviour happened.
+	pub block: BlockNumber,
+	/// Severity of the misbehaviour.
+	pub kind: Misbehaviour,
+	/// Proof of the misbehaviour, empty when none is available.
+	pub proof: Bytes,
+	/// Human readable reason of the report.
+	pub reason: String,
+	/// Submission status.
+	pub status: ReportStatus,
+	/// Number of submissions attempted so far.
+	pub attempts: usize,
+	/// Hash of the last report transaction accepted by the queue.
+	pub transaction: Option<H256>,
+}
+
+impl Evidence {
+	/// Create new pending evidence.
+	pub fn new(validator: Address, set_block: BlockNumber, block: BlockNumber, kind: Misbehaviour, proof: Bytes, reason: &str) -> Self {
+		let mut s = RlpStream::new_list(4);
+		s.append(&validator).append(&block).append(&(kind as u8)).append(&proof);
+
+		Evidence {
+			hash: keccak(s.out()),
+			validator: validator,
+			set_block: set_block,
+			block: block,
+			kind: kind,
+			proof: proof,
+			reason: reason.into(),
+			status: ReportStatus::Pending,
+			attempts: 0,
+			transaction: None,
+		}
+	}
+
+	/// Whether the report should be (re)submitted.
+	pub fn needs_submission(&self) -> bool {
+		match self.status {
+			ReportStatus::Pending | ReportStatus::Failed(_) => true,
+			ReportStatus::Submitted | ReportStatus::Included | ReportStatus::Abandoned(_) => false,
+		}
+	}
+}
+
+impl Encodable for ReportStatus {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let (id, error) = match *self {
+			ReportStatus::Pending => (0u8, ""),
+			ReportStatus::Submitted => (1, ""),
+			ReportStatus::Included => (2, ""),
+			ReportStatus::Failed(ref e) => (3, &e[..]),
+			ReportStatus::Abandoned(ref e) => (4, &e[..]),
+		};
+		s.begin_list(2).append(&id).append(&error);
+	}
+}
+
+impl Decodable for ReportStatus {
+	fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+		let error: String = rlp.val_at(1)?;
+		match rlp.val_at::<u8>(0)? {
+			0 => Ok(ReportStatus::Pending),
+			1 => Ok(ReportStatus::Submitted),
+			2 => Ok(ReportStatus::Included),
+			3 => Ok(ReportStatus::Failed(error)),
+			4 => Ok(ReportStatus::Abandoned(error)),
+			_ => Err(DecoderError::Custom("Unknown report status.")),
+		}
+	}
+}
+
+impl Encodable for Evidence {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(9)
+			.append(&self.validator)
+			.append(&self.set_block)
+			.append(&self.block)
+			.append(&(self.kind as u8))
+			.append(&self.proof)
+			.append(&self.reason)
+			.append(&self.status)
+			.append(&self.attempts)
+			.append(&self.transaction);
+	}
+}
+
+impl Decodable for Evidence {
+	fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+		let kind = match rlp.val_at::<u8>(3)? {
+			0 => Misbehaviour::Benign,
+			1 => Misbehaviour::Malicious,
+			_ => return Err(DecoderError::Custom("Unknown misbehaviour.")),
+		};
+		let reason: String = rlp.val_at(5)?;
+		let mut evidence = Evidence::new(rlp.val_at(0)?, rlp.val_at(1)?, rlp.val_at(2)?, kind, rlp.val_at(4)?, &reason);
+		evidence.status = rlp.val_at(6)?;
+		evidence.attempts = rlp.val_at(7)?;
+		evidence.transaction = rlp.val_at(8)?;
+		Ok(evidence)
+	}
+}
+
+/// Bounded store of misbehaviour evidence, oldest first.
+#[derive(Default)]
+pub struct EvidenceStore {
+	entries: RwLock<VecDeque<Evidence>>,
+}
+
+impl EvidenceStore {
+	/// Record new evidence. Returns `false` if the same misbehaviour was already recorded.
+	pub fn insert(&self, evidence: Evidence) -> bool {
+		let mut entries = self.entries.write();
+		if entries.iter().any(|e| e.hash == evidence.hash) {
+			return false;
+		}
+
+		if entries.len() == MAX_EVIDENCE {
+			entries.pop_front();
+		}
+		entries.push_back(evidence);
+		true
+	}
+
+	/// Note the outcome of a submission attempt, given the hash of the report transaction.
+	pub fn note_attempt(&self, hash: &H256, result: Result<H256, String>) {
+		if let Some(evidence) = self.entries.write().iter_mut().find(|e| e.hash == *hash) {
+			evidence.attempts += 1;
+			evidence.status = match result {
+				Ok(transaction) => {
+					evidence.transaction = Some(transaction);
+					ReportStatus::Submitted
+				},
+				Err(e) => if evidence.attempts >= MAX_REPORT_ATTEMPTS {
+					ReportStatus::Abandoned(e)
+				} else {
+					ReportStatus::Failed(e)
+				},
+			};
+		}
+	}
+
+	/// Update the status of reports whose transaction was submitted: `is_included` tells
+	/// whether a transaction is in the canonical chain and `is_queued` whether it is still
+	/// waiting in the queue. Reports whose transaction was dropped are submitted again.
+	/// Returns `true` if any status changed.
+	pub fn check_inclusion<F, G>(&self, is_included: F, is_queued: G) -> bool where
+		F: Fn(&H256) -> bool,
+		G: Fn(&H256) -> bool,
+	{
+		let mut changed = false;
+		for evidence in self.entries.write().iter_mut() {
+			let transaction = match (&evidence.status, evidence.transaction) {
+				(&ReportStatus::Submitted, Some(transaction)) | (&ReportStatus::Included, Some(transaction)) => transaction,
+				_ => continue,
+			};
+
+			let status = if is_included(&transaction) {
+				ReportStatus::Included
+			} else if is_queued(&transaction) {
+				ReportStatus::Submitted
+			} else if evidence.attempts >= MAX_REPORT_ATTEMPTS {
+				ReportStatus::Abandoned("report transaction was never included".into())
+			} else {
+				ReportStatus::Failed("report transaction was never included".into())
+			};
+
+			if status != evidence.status {
+				evidence.status = status;
+				changed = true;
+			}
+		}
+		changed
+	}
+
+	/// Evidence which still has to be submitted.
+	pub fn pending(&self) -> Vec<Evidence> {
+		self.entries.read().iter().filter(|e| e.needs_submission()).cloned().collect()
+	}
+
+	/// Serialize all recorded evidence.
+	pub fn to_rlp(&self) -> Bytes {
+		let entries = self.entries.read();
+		let mut s = RlpStream::new_list(entries.len());
+		for evidence in entries.iter() {
+			s.append(evidence);
+		}
+		s.out()
+	}
+
+	/// Restore evidence serialized with `to_rlp`, keeping the already recorded entries.
+	pub fn restore(&self, data: &[u8]) -> Result<(), DecoderError> {
+		let restored = UntrustedRlp::new(data).as_list::<Evidence>()?;
+		for evidence in restored {
+			self.insert(evidence);
+		}
+		Ok(())
+	}
+
+	/// All recorded evidence.
+	pub fn list(&self) -> Vec<Evidence> {
+		self.entries.read().iter().cloned().collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethereum_types::{H256, Address};
+	use super::*;
+
+	fn evidence(block: BlockNumber) -> Evidence {
+		Evidence::new(Address::from(1), 0, block, Misbehaviour::Benign, Vec::new(), "skipped step")
+	}
+
+	#[test]
+	fn ignores_duplicate_evidence() {
+		let store = EvidenceStore::default();
+		assert!(store.insert(evidence(1)));
+		assert!(!store.insert(evidence(1)));
+		assert!(store.insert(evidence(2)));
+		assert_eq!(store.list().len(), 2);
+	}
+
+	#[test]
+	fn retries_until_abandoned() {
+		let store = EvidenceStore::default();
+		let e = evidence(1);
+		store.insert(e.clone());
+		store.insert(evidence(2));
+		store.note_attempt(&evidence(2).hash, Ok(H256::from(2)));
+
+		for _ in 0..MAX_REPORT_ATTEMPTS - 1 {
+			store.note_attempt(&e.hash, Err("No client!".into()));
+			assert_eq!(store.pending().len(), 1);
+		}
+		store.note_attempt(&e.hash, Err("No client!".into()));
+
+		assert!(store.pending().is_empty());
+		let list = store.list();
+		assert_eq!(list[0].status, ReportStatus::Abandoned("No client!".into()));
+		assert_eq!(list[0].attempts, MAX_REPORT_ATTEMPTS);
+		assert_eq!(list[1].status, ReportStatus::Submitted);
+	}
+
+	#[test]
+	fn resubmits_reports_never_included() {
+		let store = EvidenceStore::default();
+		let (e1, e2) = (evidence(1), evidence(2));
+		store.insert(e1.clone());
+		store.insert(e2.clone());
+		store.note_attempt(&e1.hash, Ok(H256::from(1)));
+		store.note_attempt(&e2.hash, Ok(H256::from(2)));
+
+		// both are still queued.
+		assert!(!store.check_inclusion(|_| false, |_| true));
+		assert!(store.pending().is_empty());
+
+		// the first one is included, the second one was dropped from the queue.
+		assert!(store.check_inclusion(|tx| *tx == H256::from(1), |_| false));
+		let list = store.list();
+		assert_eq!(list[0].status, ReportStatus::Included);
+		assert_eq!(list[1].status, ReportStatus::Failed("report transaction was never included".into()));
+		assert_eq!(store.pending(), vec![list[1].clone()]);
+
+		// a reorganisation retracts the first one.
+		assert!(store.check_inclusion(|_| false, |_| true));
+		assert_eq!(store.list()[0].status, ReportStatus::Submitted);
+	}
+
+	#[test]
+	fn restores_evidence() {
+		let store = EvidenceStore::default();
+		let mut malicious = Evidence::new(Address::from(2), 0, 3, Misbehaviour::Malicious, vec![1, 2, 3], "double vote");
+		malicious.status = ReportStatus::Failed("No client!".into());
+		malicious.attempts = 1;
+		store.insert(evidence(1));
+		store.insert(malicious);
+		store.note_attempt(&evidence(1).hash, Ok(H256::from(1)));
+
+		let restored = EvidenceStore::default();
+		restored.restore(&store.to_rlp()).unwrap();
+		assert_eq!(restored.list(), store.list());
+	}
+}
@@ -18,7 +18,10 @@
 
 mod authority_round;
 mod basic_authority;
+mod block_reward;
+mod clique;
 mod instant_seal;
+mod multi;
 mod null_engine;
 mod signer;
 mod tendermint;
@@ -27,11 +30,14 @@ mod validator_set;
 mod vote_collector;
 
 pub mod epoch;
+pub mod evidence;
 
-pub use self::authority_round::AuthorityRound;
+pub use self::authority_round::{AuthorityRound, AuthorityRoundParams};
 pub use self::basic_authority::BasicAuthority;
+pub use self::clique::Clique;
 pub use self::epoch::{EpochVerifier, Transition as EpochTransition};
 pub use self::instant_seal::InstantSeal;
+pub use self::multi::MultiEngine;
 pub use self::null_engine::NullEngine;
 pub use self::tendermint::Tendermint;
 
@@ -40,6 +46,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 use self::epoch::PendingTransition;
+use self::evidence::Evidence;
 
 use account_provider::AccountProvider;
 use builtin::Builtin;
@@ -52,7 +59,7 @@ use transaction::{UnverifiedTransaction, SignedTransaction};
 
 use ethkey::Signature;
 use parity_machine::{Machine, LocalizedMachine as Localized};
-use ethereum_types::{H256, U256, Address};
+use ethereum_types::{H256, H64, U256, Address};
 use semantic_version::SemanticVersion;
 use unexpected::{Mismatch, OutOfBounds};
 use bytes::Bytes;
@@ -82,6 +89,24 @@ pub enum EngineError {
 	MalformedMessage(String),
 	/// Requires client ref, but none registered.
 	RequiresClient,
+	/// Clique header extra data is missing the 32-byte vanity prefix.
+	CliqueMissingVanity,
+	/// Clique header extra data is missing the 65-byte signature suffix.
+	CliqueMissingSignature,
+	/// Clique header extra data contains a signer list of invalid length.
+	CliqueInvalidSigners(usize),
+	/// Clique checkpoint block votes for a non-zero address.
+	CliqueWrongAuthorCheckpoint(Address),
+	/// Clique checkpoint block lists different signers than the current set.
+	CliqueCheckpointMismatch,
+	/// Clique nonce is neither an authorize nor a drop vote.
+	CliqueInvalidNonce(H64),
+	/// Clique signer has sealed one of the recent blocks.
+	CliqueTooRecentlySigned(Address),
+	/// Authority Round step duration is zero.
+	ZeroStepDuration,
+	/// Authority Round step durations do not start at block 0.
+	MissingFirstStepDuration,
 }
 
 impl fmt::Display for EngineError {
@@ -97,6 +122,15 @@ impl fmt::Display for EngineError {
 			FailedSystemCall(ref msg) => format!("Failed to make system call: {}", msg),
 			MalformedMessage(ref msg) => format!("Received malformed consensus message: {}", msg),
 			RequiresClient => format!("Call requires client but none registered"),
+			CliqueMissingVanity => format!("Extra data is missing vanity data"),
+			CliqueMissingSignature => format!("Extra data is missing signature"),
+			CliqueInvalidSigners(len) => format!("Extra data contains {} bytes of signers which is not a valid list", len),
+			CliqueWrongAuthorCheckpoint(ref address) => format!("Checkpoint block votes for non-zero address {}", address),
+			CliqueCheckpointMismatch => format!("Checkpoint block signers do not match the current signer set"),
+			CliqueInvalidNonce(ref nonce) => format!("Nonce {} is not a valid vote", nonce),
+			CliqueTooRecentlySigned(ref address) => format!("Signer {} has signed a block too recently", address),
+			ZeroStepDuration => format!("Step duration can't be zero"),
+			MissingFirstStepDuration => format!("Step duration for block 0 is missing"),
 		};
 
 		f.write_fmt(format_args!("Engine error ({})", msg))
@@ -184,7 +218,7 @@ pub trait Engine<M: Machine>: Sync + Send {
 	fn machine(&self) -> &M;
 
 	/// The number of additional header fields required for this engine.
-	fn seal_fields(&self) -> usize { 0 }
+	fn seal_fields(&self, _header: &M::Header) -> usize { 0 }
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, _header: &M::Header) -> BTreeMap<String, String> { BTreeMap::new() }
@@ -210,6 +244,12 @@ pub trait Engine<M: Machine>: Sync + Send {
 		Ok(())
 	}
 
+	/// Block transformation functions, after the seal has been set on a locally sealed block.
+	/// Allows engines which sign over the seal fields to finalize the header.
+	fn on_seal_block(&self, _block: &mut M::LiveBlock) -> Result<(), M::Error> {
+		Ok(())
+	}
+
 	/// None means that it requires external input (e.g. PoW) to seal a block.
 	/// Some(true) means the engine is currently prime for seal generation (i.e. node is the current validator).
 	/// Some(false) means that the node might seal internally but is not qualified now.
@@ -318,6 +358,9 @@ pub trait Engine<M: Machine>: Sync + Send {
 	/// Stops any services that the may hold the Engine and makes it safe to drop.
 	fn stop(&self) {}
 
+	/// Misbehaviour of other validators reported by this engine, oldest first.
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> { Vec::new() }
+
 	/// Create a factory for building snapshot chunks and restoring from them.
 	/// Returning `None` indicates that this engine doesn't support snapshot creation.
 	fn snapshot_components(&self) -> Option<Box<SnapshotComponents>> {
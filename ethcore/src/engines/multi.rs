@@ -0,0 +1,473 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Engine for multi-stage chains: every stage is sealed by its own engine, active from
+//! a given block on.
+//!
+//! The block before a new stage begins ends an epoch: its transition proof is the genesis
+//! epoch data of the next engine, so that its epoch logic starts from a known state.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::sync::{Weak, Arc};
+use ethereum_types::{H256, Address};
+use ethkey::Signature;
+use parking_lot::RwLock;
+use account_provider::AccountProvider;
+use block::ExecutedBlock;
+use client::EngineClient;
+use engines::{Engine, EthEngine, Seal, EngineError, ConstructedVerifier, EpochChange, Proof, StateDependentProof};
+use engines::evidence::Evidence;
+use error::Error;
+use header::{Header, BlockNumber};
+use machine::{AuxiliaryData, Call, EthereumMachine};
+use semantic_version::SemanticVersion;
+use snapshot::SnapshotComponents;
+
+/// Proof of the transition to a new stage: the genesis epoch data of its engine.
+struct StageTransition {
+	engine: Arc<EthEngine>,
+	header: Header,
+}
+
+impl StateDependentProof<EthereumMachine> for StageTransition {
+	fn generate_proof(&self, caller: &Call) -> Result<Vec<u8>, String> {
+		self.engine.genesis_epoch_data(&self.header, caller)
+	}
+
+	fn check_proof(&self, _machine: &EthereumMachine, proof: &[u8]) -> Result<(), String> {
+		match self.engine.epoch_verifier(&self.header, proof) {
+			ConstructedVerifier::Err(e) => Err(format!("invalid stage transition proof: {}", e)),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// An engine which delegates to a different engine depending on the block number.
+pub struct MultiEngine {
+	engines: BTreeMap<BlockNumber, Arc<EthEngine>>,
+	machine: EthereumMachine,
+	client: RwLock<Option<Weak<EngineClient>>>,
+}
+
+impl MultiEngine {
+	/// Create a new multi-stage engine from engines keyed by their first block.
+	pub fn new(engines: BTreeMap<BlockNumber, Arc<EthEngine>>, machine: EthereumMachine) -> Self {
+		assert!(engines.contains_key(&0), "Multi engine has to specify an engine from block 0.");
+		MultiEngine {
+			engines: engines,
+			machine: machine,
+			client: RwLock::new(None),
+		}
+	}
+
+	fn engine_arc(&self, number: BlockNumber) -> &Arc<EthEngine> {
+		self.engines.iter()
+			.rev()
+			.find(|&(block, _)| *block <= number)
+			.map(|(_, engine)| engine)
+			.expect("constructor validation ensures that there is an engine for block 0;
+					 block 0 is less than any block number;
+					 qed")
+	}
+
+	/// Engine in charge of the block with the given number.
+	fn engine_for(&self, number: BlockNumber) -> &EthEngine {
+		&**self.engine_arc(number)
+	}
+
+	/// Engine of the last stage.
+	fn last_engine(&self) -> &EthEngine {
+		&**self.engines.values().next_back().expect("constructor validation ensures that there is an engine for block 0; qed")
+	}
+
+	/// Whether a new stage begins at the given block.
+	fn is_stage_start(&self, number: BlockNumber) -> bool {
+		number != 0 && self.engines.contains_key(&number)
+	}
+
+	/// Number of the next block on top of the best block.
+	fn next_block(&self) -> BlockNumber {
+		self.client.read().as_ref()
+			.and_then(Weak::upgrade)
+			.map_or(0, |client| client.chain_info().best_block_number + 1)
+	}
+
+	/// Engine in charge of the next block on top of the best block.
+	fn active_engine(&self) -> &EthEngine {
+		self.engine_for(self.next_block())
+	}
+
+	/// Engines of the stage of the next block and of all later stages, in stage order.
+	/// While syncing, peers may already be sealing a later stage than the best block.
+	fn current_and_later_engines(&self) -> Vec<&EthEngine> {
+		let next = self.next_block();
+		let current = self.engines.keys()
+			.rev()
+			.find(|block| **block <= next)
+			.cloned()
+			.unwrap_or(0);
+
+		self.engines.range(current..).map(|(_, engine)| &**engine).collect()
+	}
+}
+
+impl Engine<EthereumMachine> for MultiEngine {
+	fn name(&self) -> &str { "Multi" }
+
+	fn version(&self) -> SemanticVersion { self.active_engine().version() }
+
+	fn machine(&self) -> &EthereumMachine { &self.machine }
+
+	fn seal_fields(&self, header: &Header) -> usize {
+		self.engine_for(header.number()).seal_fields(header)
+	}
+
+	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
+		self.engine_for(header.number()).extra_info(header)
+	}
+
+	fn maximum_uncle_count(&self, block: BlockNumber) -> usize {
+		self.engine_for(block).maximum_uncle_count(block)
+	}
+
+	fn maximum_uncle_age(&self) -> usize {
+		self.engines.values().fold(0, |age, engine| cmp::max(age, engine.maximum_uncle_age()))
+	}
+
+	fn on_new_block(&self, block: &mut ExecutedBlock, epoch_begin: bool) -> Result<(), Error> {
+		let number = block.fields().header.number();
+		self.engine_for(number).on_new_block(block, epoch_begin)
+	}
+
+	fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+		let number = block.fields().header.number();
+		self.engine_for(number).on_close_block(block)
+	}
+
+	fn on_seal_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+		let number = block.fields().header.number();
+		self.engine_for(number).on_seal_block(block)
+	}
+
+	fn seals_internally(&self) -> Option<bool> {
+		self.active_engine().seals_internally()
+	}
+
+	fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
+		self.engine_for(block.fields().header.number()).generate_seal(block, parent)
+	}
+
+	fn verify_local_seal(&self, header: &Header) -> Result<(), Error> {
+		self.engine_for(header.number()).verify_local_seal(header)
+	}
+
+	fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
+		self.engine_for(header.number()).verify_block_basic(header)
+	}
+
+	fn verify_block_unordered(&self, header: &Header) -> Result<(), Error> {
+		self.engine_for(header.number()).verify_block_unordered(header)
+	}
+
+	fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+		self.engine_for(header.number()).verify_block_family(header, parent)
+	}
+
+	fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
+		self.engine_for(header.number()).verify_block_external(header)
+	}
+
+	// epoch data and verifiers belong to the engine of the blocks following the
+	// transition block.
+	fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
+		self.engine_for(header.number() + 1).genesis_epoch_data(header, call)
+	}
+
+	fn signals_epoch_end(&self, header: &Header, aux: AuxiliaryData)
+		-> EpochChange<EthereumMachine>
+	{
+		let next = header.number() + 1;
+		if self.is_stage_start(next) {
+			trace!(target: "engine", "Stage transition signalled at block {}", header.number());
+			return EpochChange::Yes(Proof::WithState(Arc::new(StageTransition {
+				engine: self.engine_arc(next).clone(),
+				header: header.clone(),
+			})));
+		}
+
+		self.engine_for(header.number()).signals_epoch_end(header, aux)
+	}
+
+	fn is_epoch_end(
+		&self,
+		chain_head: &Header,
+		chain: &super::Headers<Header>,
+		transition_store: &super::PendingTransitionStore,
+	) -> Option<Vec<u8>> {
+		// stage transitions are final right away.
+		if self.is_stage_start(chain_head.number() + 1) {
+			return transition_store(chain_head.hash()).map(|pending| pending.proof);
+		}
+
+		self.engine_for(chain_head.number()).is_epoch_end(chain_head, chain, transition_store)
+	}
+
+	fn epoch_verifier<'a>(&self, header: &Header, proof: &'a [u8]) -> ConstructedVerifier<'a, EthereumMachine> {
+		self.engine_for(header.number() + 1).epoch_verifier(header, proof)
+	}
+
+	fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
+		self.engine_for(header.number()).populate_from_parent(header, parent)
+	}
+
+	// a message is handled by the first engine accepting it, otherwise the error of the
+	// current stage is returned.
+	fn handle_message(&self, message: &[u8]) -> Result<(), EngineError> {
+		let mut error = None;
+		for engine in self.current_and_later_engines() {
+			match engine.handle_message(message) {
+				Ok(()) => return Ok(()),
+				Err(e) => if error.is_none() { error = Some(e) },
+			}
+		}
+
+		Err(error.unwrap_or(EngineError::UnexpectedMessage))
+	}
+
+	fn is_proposal(&self, header: &Header) -> bool {
+		self.engine_for(header.number()).is_proposal(header)
+	}
+
+	fn set_signer(&self, ap: Arc<AccountProvider>, address: Address, password: String) {
+		for engine in self.engines.values() {
+			engine.set_signer(ap.clone(), address, password.clone());
+		}
+	}
+
+	// all engines share the signer: sign with the first current or later engine sealing
+	// with it, so that consensus messages and reports can be signed ahead of its stage.
+	fn sign(&self, hash: H256) -> Result<Signature, Error> {
+		let engines = self.current_and_later_engines();
+		match engines.iter().find(|engine| engine.seals_internally().is_some()) {
+			Some(engine) => engine.sign(hash),
+			None => self.active_engine().sign(hash),
+		}
+	}
+
+	fn register_client(&self, client: Weak<EngineClient>) {
+		for engine in self.engines.values() {
+			engine.register_client(client.clone());
+		}
+		*self.client.write() = Some(client);
+	}
+
+	// later engines keep stepping, so that their step is current when their stage begins.
+	fn step(&self) {
+		for engine in self.current_and_later_engines() {
+			engine.step();
+		}
+	}
+
+	fn stop(&self) {
+		for engine in self.engines.values() {
+			engine.stop();
+		}
+	}
+
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> {
+		self.engines.values().flat_map(|engine| engine.misbehaviour_evidence()).collect()
+	}
+
+	// snapshots use the format of the last stage, so that all nodes agree on it
+	// regardless of how far they are synced.
+	fn snapshot_components(&self) -> Option<Box<SnapshotComponents>> {
+		self.last_engine().snapshot_components()
+	}
+
+	fn supports_warp(&self) -> bool {
+		self.last_engine().supports_warp()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use bytes::Bytes;
+	use ethash::{EthashManager, OptimizeFor};
+	use ethkey::{KeyPair, sign};
+	use hash::keccak;
+	use ethereum_types::{Address, H64, H256, H520};
+	use rlp::encode;
+	use tempdir::TempDir;
+	use account_provider::AccountProvider;
+	use block::IsBlock;
+	use client::{BlockChainClient, Client, MiningBlockChainClient};
+	use engines::{EpochChange, Proof, ConstructedVerifier};
+	use engines::epoch::PendingTransition;
+	use header::Header;
+	use spec::Spec;
+	use tests::helpers::generate_dummy_client_with_spec_and_accounts;
+
+	// the test spec switches from BasicAuthority to AuthorityRound at block 3.
+	fn aura_header(number: u64, step: usize) -> Header {
+		let mut header = Header::default();
+		header.set_number(number);
+		header.set_seal(vec![encode(&step).into_vec(), encode(&(&H520::default() as &[u8])).into_vec()]);
+		header
+	}
+
+	fn aura_seal(step: usize, proposer: &KeyPair, header: &Header) -> Vec<Bytes> {
+		let signature = sign(proposer.secret(), &header.bare_hash()).unwrap();
+		vec![encode(&step).into_vec(), encode(&(&*signature as &[u8])).into_vec()]
+	}
+
+	// builds the next block on top of the best block, seals it with `seal` and imports it.
+	fn import_next<F>(client: &Client, author: Address, timestamp: u64, seal: F)
+		where F: FnOnce(&Header) -> Vec<Bytes>
+	{
+		let mut open = client.prepare_open_block(author, (3141562.into(), 31415620.into()), vec![]);
+		open.set_timestamp(timestamp);
+		let locked = open.close_and_lock();
+		let seal = seal(locked.header());
+		let sealed = locked.seal(client.engine(), seal).unwrap();
+
+		client.import_block(sealed.rlp_bytes()).unwrap();
+		client.flush_queue();
+		client.import_verified_blocks();
+	}
+
+	#[test]
+	fn dispatches_by_block_number() {
+		let spec = Spec::new_test_multi_engine();
+		let engine = &*spec.engine;
+
+		let mut header = Header::default();
+		header.set_number(2);
+		assert_eq!(engine.seal_fields(&header), 1);
+		header.set_number(3);
+		assert_eq!(engine.seal_fields(&header), 2);
+	}
+
+	#[test]
+	fn signals_stage_transition() {
+		let spec = Spec::new_test_multi_engine();
+		let engine = &*spec.engine;
+
+		let mut header = Header::default();
+		header.set_number(1);
+		match engine.signals_epoch_end(&header, Default::default()) {
+			EpochChange::No => {},
+			_ => panic!("no transition within a stage"),
+		}
+
+		// the last BasicAuthority block hands over to AuthorityRound.
+		header.set_number(2);
+		let proof = match engine.signals_epoch_end(&header, Default::default()) {
+			EpochChange::Yes(Proof::WithState(proof)) => proof,
+			_ => panic!("stage transition must be signalled"),
+		};
+		let proof = proof.generate_proof(&|_, _| Err("no state".into())).unwrap();
+
+		let hash = header.hash();
+		let store = |h: H256| if h == hash { Some(PendingTransition { proof: proof.clone() }) } else { None };
+		assert_eq!(engine.is_epoch_end(&header, &|_| None, &store), Some(proof.clone()));
+
+		// AuthorityRound verifies the blocks of the new stage with its own validators.
+		let verifier = match engine.epoch_verifier(&header, &proof) {
+			ConstructedVerifier::Trusted(verifier) => verifier,
+			_ => panic!("validator list transitions are trusted"),
+		};
+
+		let tap = AccountProvider::transient_provider();
+		let validator = tap.insert_account(keccak("1").into(), "").unwrap();
+		let mut aura = aura_header(3, 3);
+		aura.set_author(validator);
+		let signature = tap.sign(validator, Some("".into()), aura.bare_hash()).unwrap();
+		aura.set_seal(vec![encode(&3usize).into_vec(), encode(&(&*signature as &[u8])).into_vec()]);
+		assert!(verifier.verify_light(&aura).is_ok());
+	}
+
+	#[test]
+	fn first_block_of_stage_follows_foreign_parent() {
+		let spec = Spec::new_test_multi_engine();
+		let engine = &*spec.engine;
+
+		let mut parent = Header::default();
+		parent.set_number(2);
+		parent.set_timestamp(10);
+		parent.set_seal(vec![encode(&(&H520::default() as &[u8])).into_vec()]);
+
+		let mut header = aura_header(3, 11);
+		header.set_parent_hash(parent.hash());
+		assert!(engine.verify_block_family(&header, &parent).is_ok());
+
+		// the first step can't precede the parent's timestamp.
+		let mut header = aura_header(3, 8);
+		header.set_parent_hash(parent.hash());
+		assert!(engine.verify_block_family(&header, &parent).is_err());
+	}
+
+	#[test]
+	fn imports_blocks_across_stages() {
+		let client = generate_dummy_client_with_spec_and_accounts(Spec::new_test_multi_engine, None);
+		client.engine().register_client(Arc::downgrade(&client) as _);
+
+		let authority = KeyPair::from_secret_slice(&keccak("")).unwrap();
+		for timestamp in &[10, 20] {
+			import_next(&client, authority.address(), *timestamp, |header| {
+				let signature = sign(authority.secret(), &header.bare_hash()).unwrap();
+				vec![encode(&(&*signature as &[u8])).into_vec()]
+			});
+		}
+		assert_eq!(client.chain_info().best_block_number, 2);
+
+		// the first AuthorityRound block follows the BasicAuthority block at step 20.
+		let even = KeyPair::from_secret_slice(&keccak("1")).unwrap();
+		import_next(&client, even.address(), 30, |header| aura_seal(30, &even, header));
+		assert_eq!(client.chain_info().best_block_number, 3);
+
+		// the next one follows the step of its AuthorityRound parent.
+		client.engine().step();
+		let odd = KeyPair::from_secret_slice(&keccak("0")).unwrap();
+		import_next(&client, odd.address(), 31, |header| aura_seal(31, &odd, header));
+		assert_eq!(client.chain_info().best_block_number, 4);
+	}
+
+	#[test]
+	fn imports_blocks_from_ethash_to_authority_round() {
+		let client = generate_dummy_client_with_spec_and_accounts(Spec::new_test_multi_engine_ethash, None);
+		client.engine().register_client(Arc::downgrade(&client) as _);
+
+		let cache_dir = TempDir::new("").unwrap();
+		let pow = EthashManager::new(cache_dir.path(), OptimizeFor::Memory);
+		for timestamp in &[10, 20] {
+			import_next(&client, Address::default(), *timestamp, |header| {
+				let result = pow.compute_light(header.number(), &header.bare_hash().0, 0);
+				vec![encode(&H256(result.mix_hash)).into_vec(), encode(&H64::zero()).into_vec()]
+			});
+		}
+		assert_eq!(client.chain_info().best_block_number, 2);
+
+		// the mined parent has no step, it counts as sealed in the step before its timestamp.
+		let even = KeyPair::from_secret_slice(&keccak("1")).unwrap();
+		import_next(&client, even.address(), 30, |header| aura_seal(30, &even, header));
+		assert_eq!(client.chain_info().best_block_number, 3);
+
+		// blocks of the last stage are snapshotted with its engine.
+		assert!(client.engine().supports_warp());
+	}
+}
@@ -30,7 +30,7 @@ use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::collections::{HashSet, BTreeMap};
 use hash::keccak;
 use ethereum_types::{H256, H520, U128, U256, Address};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use unexpected::{OutOfBounds, Mismatch};
 use client::EngineClient;
 use bytes::Bytes;
@@ -42,6 +42,7 @@ use account_provider::AccountProvider;
 use block::*;
 use engines::{Engine, Seal, EngineError, ConstructedVerifier};
 use io::IoService;
+use super::evidence::Evidence;
 use super::signer::EngineSigner;
 use super::validator_set::{ValidatorSet, SimpleList};
 use super::transition::TransitionHandler;
@@ -49,7 +50,7 @@ use super::vote_collector::VoteCollector;
 use self::message::*;
 use self::params::TendermintParams;
 use semantic_version::SemanticVersion;
-use machine::{AuxiliaryData, EthereumMachine};
+use machine::{AuxiliaryData, Call, EthereumMachine};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Step {
@@ -98,6 +99,8 @@ pub struct Tendermint {
 	last_proposed: RwLock<H256>,
 	/// Set used to determine the current validators.
 	validators: Box<ValidatorSet>,
+	/// Validator set of the current epoch, used when the validator set can't be queried directly.
+	epoch_manager: Mutex<EpochManager>,
 	/// Reward per block, in base units.
 	block_reward: U256,
 	/// ethereum machine descriptor
@@ -115,10 +118,20 @@ impl <F> super::EpochVerifier<EthereumMachine> for EpochVerifier<F>
 	where F: Fn(&Signature, &Message) -> Result<Address, Error> + Send + Sync
 {
 	fn verify_light(&self, header: &Header) -> Result<(), Error> {
-		let message = header.bare_hash();
+		if header.seal().len() != 3 {
+			return Err(BlockError::InvalidSealArity(Mismatch { expected: 3, found: header.seal().len() }).into());
+		}
+
+		// only commits carry the precommit signatures which finalize the block.
+		if ConsensusMessage::new_proposal(header).is_ok() {
+			return Err(BlockError::InvalidSeal.into());
+		}
+
+		let vote_step = VoteStep::new(header.number() as usize, consensus_view(header)?, Step::Precommit);
+		let message = message_hash(vote_step, header.bare_hash());
 
 		let mut addresses = HashSet::new();
-		let ref header_signatures_field = header.seal().get(2).ok_or(BlockError::InvalidSeal)?;
+		let ref header_signatures_field = header.seal()[2];
 		for rlp in UntrustedRlp::new(header_signatures_field).iter() {
 			let signature: H520 = rlp.as_val()?;
 			let address = (self.recover)(&signature.into(), &message)?;
@@ -126,7 +139,9 @@ impl <F> super::EpochVerifier<EthereumMachine> for EpochVerifier<F>
 			if !self.subchain_validators.contains(header.parent_hash(), &address) {
 				return Err(EngineError::NotAuthorized(address.to_owned()).into());
 			}
-			addresses.insert(address);
+			if !addresses.insert(address) {
+				return Err(BlockError::InvalidSeal.into());
+			}
 		}
 
 		let n = addresses.len();
@@ -143,11 +158,70 @@ impl <F> super::EpochVerifier<EthereumMachine> for EpochVerifier<F>
 	}
 
 	fn check_finality_proof(&self, proof: &[u8]) -> Option<Vec<H256>> {
-		let header: Header = ::rlp::decode(proof);
+		let header: Header = UntrustedRlp::new(proof).as_val().ok()?;
 		self.verify_light(&header).ok().map(|_| vec![header.hash()])
 	}
 }
 
+struct EpochManager {
+	epoch_transition_hash: H256,
+	validators: SimpleList,
+}
+
+impl EpochManager {
+	fn blank() -> Self {
+		EpochManager {
+			epoch_transition_hash: H256::default(),
+			validators: SimpleList::new(Vec::new()),
+		}
+	}
+
+	// zoom to epoch for given header. returns true if succeeded, false otherwise.
+	// transitions are final as soon as they are committed, so the epoch set only
+	// changes when the last transition before the header's parent changes.
+	fn zoom_to(&mut self, client: &EngineClient, machine: &EthereumMachine, validators: &ValidatorSet, header: &Header) -> bool {
+		let last_transition = match client.epoch_transition_for(*header.parent_hash()) {
+			Some(t) => t,
+			None => {
+				debug!(target: "engine", "No epoch transition found for parent of {}.", header.hash());
+				return false;
+			}
+		};
+
+		if last_transition.block_hash == self.epoch_transition_hash {
+			return true;
+		}
+
+		let (signal_number, set_proof, _) = match destructure_proofs(&last_transition.proof) {
+			Ok(x) => x,
+			Err(e) => {
+				debug!(target: "engine", "Invalid epoch transition proof at {}: {}", last_transition.block_hash, e);
+				return false;
+			}
+		};
+
+		trace!(target: "engine", "extracting epoch set for epoch ({}, {}) signalled at #{}",
+			last_transition.block_number, last_transition.block_hash, signal_number);
+
+		let first = signal_number == 0;
+		match validators.epoch_set(first, machine, signal_number, set_proof) {
+			Ok((list, _)) => {
+				self.validators = list;
+				self.epoch_transition_hash = last_transition.block_hash;
+				true
+			}
+			Err(e) => {
+				debug!(target: "engine", "Unable to extract epoch set at {}: {}", last_transition.block_hash, e);
+				false
+			}
+		}
+	}
+
+	fn validators(&self) -> &SimpleList {
+		&self.validators
+	}
+}
+
 fn combine_proofs(signal_number: BlockNumber, set_proof: &[u8], finality_proof: &[u8]) -> Vec<u8> {
 	let mut stream = ::rlp::RlpStream::new_list(3);
 	stream.append(&signal_number).append(&set_proof).append(&finality_proof);
@@ -163,6 +237,76 @@ fn destructure_proofs(combined: &[u8]) -> Result<(BlockNumber, &[u8], &[u8]), Er
 	))
 }
 
+/// Find the designated proposer for the given view.
+fn view_proposer(validators: &ValidatorSet, bh: &H256, height: Height, view: View) -> Address {
+	let proposer_nonce = height + view;
+	trace!(target: "engine", "Proposer nonce: {}", proposer_nonce);
+	validators.get(bh, proposer_nonce)
+}
+
+/// Check if address is a proposer for given view.
+fn check_view_proposer(validators: &ValidatorSet, bh: &H256, height: Height, view: View, address: &Address) -> Result<(), EngineError> {
+	let proposer = view_proposer(validators, bh, height, view);
+	if proposer == *address {
+		Ok(())
+	} else {
+		Err(EngineError::NotProposer(Mismatch { expected: proposer, found: address.clone() }))
+	}
+}
+
+/// Verify the seal of a header against the given validator set, as of the header's parent.
+fn verify_external(header: &Header, validators: &ValidatorSet, votes: &VoteCollector<ConsensusMessage>) -> Result<(), Error> {
+	if let Ok(proposal) = ConsensusMessage::new_proposal(header) {
+		let proposer = proposal.verify()?;
+		if !validators.contains(header.parent_hash(), &proposer) {
+			return Err(EngineError::NotAuthorized(proposer).into());
+		}
+		check_view_proposer(
+			validators,
+			header.parent_hash(),
+			proposal.vote_step.height,
+			proposal.vote_step.view,
+			&proposer
+		).map_err(Into::into)
+	} else {
+		let vote_step = VoteStep::new(header.number() as usize, consensus_view(header)?, Step::Precommit);
+		let precommit_hash = message_hash(vote_step.clone(), header.bare_hash());
+		let ref signatures_field = header.seal().get(2).expect("block went through verify_block_basic; block has .seal_fields() fields; qed");
+		let mut origins = HashSet::new();
+		for rlp in UntrustedRlp::new(signatures_field).iter() {
+			let precommit = ConsensusMessage {
+				signature: rlp.as_val()?,
+				block_hash: Some(header.bare_hash()),
+				vote_step: vote_step.clone(),
+			};
+			let address = match votes.get(&precommit) {
+				Some(a) => a,
+				None => public_to_address(&recover(&precommit.signature.into(), &precommit_hash)?),
+			};
+			if !validators.contains(header.parent_hash(), &address) {
+				return Err(EngineError::NotAuthorized(address.to_owned()).into());
+			}
+
+			if !origins.insert(address) {
+				warn!(target: "engine", "verify_block_unordered: Duplicate signature from {} on the seal.", address);
+				return Err(BlockError::InvalidSeal.into());
+			}
+		}
+
+		let n = origins.len();
+		let threshold = validators.count(header.parent_hash()) * 2/3;
+		if n > threshold {
+			Ok(())
+		} else {
+			Err(EngineError::BadSealFieldSize(OutOfBounds {
+				min: Some(threshold),
+				max: None,
+				found: n
+			}).into())
+		}
+	}
+}
+
 impl Tendermint {
 	/// Create a new instance of Tendermint engine
 	pub fn new(our_params: TendermintParams, machine: EthereumMachine) -> Result<Arc<Self>, Error> {
@@ -181,6 +325,7 @@ impl Tendermint {
 				proposal_parent: Default::default(),
 				last_proposed: Default::default(),
 				validators: our_params.validators,
+				epoch_manager: Mutex::new(EpochManager::blank()),
 				block_reward: our_params.block_reward,
 				machine: machine,
 			});
@@ -318,19 +463,7 @@ impl Tendermint {
 
 	/// Find the designated for the given view.
 	fn view_proposer(&self, bh: &H256, height: Height, view: View) -> Address {
-		let proposer_nonce = height + view;
-		trace!(target: "engine", "Proposer nonce: {}", proposer_nonce);
-		self.validators.get(bh, proposer_nonce)
-	}
-
-	/// Check if address is a proposer for given view.
-	fn check_view_proposer(&self, bh: &H256, height: Height, view: View, address: &Address) -> Result<(), EngineError> {
-		let proposer = self.view_proposer(bh, height, view);
-		if proposer == *address {
-			Ok(())
-		} else {
-			Err(EngineError::NotProposer(Mismatch { expected: proposer, found: address.clone() }))
-		}
+		view_proposer(&*self.validators, bh, height, view)
 	}
 
 	/// Check if current signer is the current proposer.
@@ -444,7 +577,7 @@ impl Engine<EthereumMachine> for Tendermint {
 	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
 
 	/// (consensus view, proposal signature, authority signatures)
-	fn seal_fields(&self) -> usize { 3 }
+	fn seal_fields(&self, _header: &Header) -> usize { 3 }
 
 	fn machine(&self) -> &EthereumMachine { &self.machine }
 
@@ -532,7 +665,7 @@ impl Engine<EthereumMachine> for Tendermint {
 			self.broadcast_message(rlp.as_raw().to_vec());
 			if let Some(double) = self.votes.vote(message.clone(), &sender) {
 				let height = message.vote_step.height as BlockNumber;
-				self.validators.report_malicious(&sender, height, height, ::rlp::encode(&double).into_vec());
+				self.validators.report_malicious(&sender, height, height, ::rlp::encode(&double).into_vec(), "double vote");
 				return Err(EngineError::DoubleVote(sender));
 			}
 			trace!(target: "engine", "Handling a valid {:?} from {}.", message, sender);
@@ -573,7 +706,7 @@ impl Engine<EthereumMachine> for Tendermint {
 
 	fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
 		let seal_length = header.seal().len();
-		if seal_length == self.seal_fields() {
+		if seal_length == self.seal_fields(header) {
 			// Either proposal or commit.
 			if (header.seal()[1] == ::rlp::NULL_RLP)
 				!= (header.seal()[2] == ::rlp::EMPTY_LIST_RLP) {
@@ -584,52 +717,35 @@ impl Engine<EthereumMachine> for Tendermint {
 			}
 		} else {
 			Err(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: seal_length }
+				Mismatch { expected: self.seal_fields(header), found: seal_length }
 			).into())
 		}
 	}
 
 	fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
-		if let Ok(proposal) = ConsensusMessage::new_proposal(header) {
-			let proposer = proposal.verify()?;
-			if !self.is_authority(&proposer) {
-				return Err(EngineError::NotAuthorized(proposer).into());
-			}
-			self.check_view_proposer(
-				header.parent_hash(),
-				proposal.vote_step.height,
-				proposal.vote_step.view,
-				&proposer
-			).map_err(Into::into)
-		} else {
-			let vote_step = VoteStep::new(header.number() as usize, consensus_view(header)?, Step::Precommit);
-			let precommit_hash = message_hash(vote_step.clone(), header.bare_hash());
-			let ref signatures_field = header.seal().get(2).expect("block went through verify_block_basic; block has .seal_fields() fields; qed");
-			let mut origins = HashSet::new();
-			for rlp in UntrustedRlp::new(signatures_field).iter() {
-				let precommit = ConsensusMessage {
-					signature: rlp.as_val()?,
-					block_hash: Some(header.bare_hash()),
-					vote_step: vote_step.clone(),
-				};
-				let address = match self.votes.get(&precommit) {
-					Some(a) => a,
-					None => public_to_address(&recover(&precommit.signature.into(), &precommit_hash)?),
-				};
-				if !self.validators.contains(header.parent_hash(), &address) {
-					return Err(EngineError::NotAuthorized(address.to_owned()).into());
+		let client = self.client.read().as_ref().and_then(|weak| weak.upgrade());
+
+		// light clients can't query the validator set directly, so they check
+		// against the set proven by the last epoch transition.
+		match client {
+			Some(ref client) if client.as_full_client().is_none() => {
+				let mut epoch_manager = self.epoch_manager.lock();
+				if !epoch_manager.zoom_to(&**client, &self.machine, &*self.validators, header) {
+					debug!(target: "engine", "Unable to zoom to epoch.");
+					return Err(EngineError::RequiresClient.into())
 				}
 
-				if !origins.insert(address) {
-					warn!(target: "engine", "verify_block_unordered: Duplicate signature from {} on the seal.", address);
-					return Err(BlockError::InvalidSeal.into());
-				}
+				verify_external(header, epoch_manager.validators(), &self.votes)
 			}
-
-			self.check_above_threshold(origins.len()).map_err(Into::into)
+			_ => verify_external(header, &*self.validators, &self.votes),
 		}
 	}
 
+	fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
+		self.validators.genesis_epoch_data(header, call)
+			.map(|set_proof| combine_proofs(0, &set_proof, &[]))
+	}
+
 	fn signals_epoch_end(&self, header: &Header, aux: AuxiliaryData)
 		-> super::EpochChange<EthereumMachine>
 	{
@@ -731,7 +847,7 @@ impl Engine<EthereumMachine> for Tendermint {
 					// Report the proposer if no proposal was received.
 					let height = self.height.load(AtomicOrdering::SeqCst);
 					let current_proposer = self.view_proposer(&*self.proposal_parent.read(), height, self.view.load(AtomicOrdering::SeqCst));
-					self.validators.report_benign(&current_proposer, height as BlockNumber, height as BlockNumber);
+					self.validators.report_benign(&current_proposer, height as BlockNumber, height as BlockNumber, "no proposal");
 				}
 				Step::Prevote
 			},
@@ -769,6 +885,10 @@ impl Engine<EthereumMachine> for Tendermint {
 		*self.client.write() = Some(client.clone());
 		self.validators.register_client(client);
 	}
+
+	fn misbehaviour_evidence(&self) -> Vec<Evidence> {
+		self.validators.evidence()
+	}
 }
 
 #[cfg(test)]
@@ -1142,4 +1262,163 @@ mod tests {
 
 		engine.stop();
 	}
+
+	#[test]
+	fn epoch_verifier_checks_precommit_signatures() {
+		let (spec, tap) = setup();
+		let engine = spec.engine;
+
+		let mut header = Header::default();
+		header.set_number(2);
+		header.set_gas_limit(U256::from_str("222222").unwrap());
+		let proposer = insert_and_unlock(&tap, "1");
+		header.set_author(proposer);
+		let mut seal = proposal_seal(&tap, &header, 0);
+		seal[1] = ::rlp::NULL_RLP.to_vec();
+
+		let voter = insert_and_unlock(&tap, "0");
+
+		// signatures over the bare hash do not prove finality.
+		let wrong0 = tap.sign(voter, None, header.bare_hash()).unwrap();
+		let wrong1 = tap.sign(proposer, None, header.bare_hash()).unwrap();
+		seal[2] = ::rlp::encode_list(&vec![H520::from(wrong0), H520::from(wrong1)]).into_vec();
+		header.set_seal(seal.clone());
+
+		let proof = combine_proofs(0, &[], &[]);
+		let verifier = match engine.epoch_verifier(&header, &proof) {
+			ConstructedVerifier::Trusted(v) => v,
+			_ => panic!("simple list epochs are trusted"),
+		};
+		assert!(verifier.verify_light(&header).is_err());
+
+		let vote_info = message_info_rlp(&VoteStep::new(2, 0, Step::Precommit), Some(header.bare_hash()));
+		let signature0 = tap.sign(voter, None, keccak(&vote_info)).unwrap();
+		let signature1 = tap.sign(proposer, None, keccak(&vote_info)).unwrap();
+		seal[2] = ::rlp::encode_list(&vec![H520::from(signature0), H520::from(signature1)]).into_vec();
+		header.set_seal(seal);
+
+		assert!(verifier.verify_light(&header).is_ok());
+		assert_eq!(verifier.check_finality_proof(&::rlp::encode(&header)), Some(vec![header.hash()]));
+		assert_eq!(verifier.check_finality_proof(&[0xc1, 0x80, 0x01]), None);
+
+		engine.stop();
+	}
+
+	#[test]
+	fn epoch_verifier_rejects_proposal_seal() {
+		let (spec, tap) = setup();
+		let engine = spec.engine;
+
+		let mut header = Header::default();
+		header.set_number(2);
+		let proposer = insert_and_unlock(&tap, "1");
+		header.set_author(proposer);
+		header.set_seal(proposal_seal(&tap, &header, 0));
+
+		let proof = combine_proofs(0, &[], &[]);
+		let verifier = match engine.epoch_verifier(&header, &proof) {
+			ConstructedVerifier::Trusted(v) => v,
+			_ => panic!("simple list epochs are trusted"),
+		};
+
+		match verifier.verify_light(&header) {
+			Err(Error::Block(BlockError::InvalidSeal)) => {},
+			_ => panic!(),
+		}
+
+		engine.stop();
+	}
+
+	// a client without a full chain, which knows the epoch transitions of the given parents.
+	struct LightClient {
+		chain: ::client::TestBlockChainClient,
+		transitions: ::std::collections::HashMap<H256, ::engines::EpochTransition>,
+	}
+
+	impl EngineClient for LightClient {
+		fn update_sealing(&self) {}
+
+		fn submit_seal(&self, _block_hash: H256, _seal: Vec<Bytes>) {}
+
+		fn broadcast_consensus_message(&self, _message: Bytes) {}
+
+		fn epoch_transition_for(&self, parent_hash: H256) -> Option<::engines::EpochTransition> {
+			self.transitions.get(&parent_hash).cloned()
+		}
+
+		fn chain_info(&self) -> ::client::BlockChainInfo {
+			::client::BlockChainClient::chain_info(&self.chain)
+		}
+
+		fn as_full_client(&self) -> Option<&::client::BlockChainClient> { None }
+
+		fn block_number(&self, _id: ::ids::BlockId) -> Option<BlockNumber> { None }
+	}
+
+	#[test]
+	fn light_client_verifies_against_epoch_set() {
+		let tap = Arc::new(AccountProvider::transient_provider());
+		let v0 = insert_and_unlock(&tap, "0");
+		let v1 = insert_and_unlock(&tap, "1");
+
+		// the validator set changes at block 5.
+		let validators = ::engines::validator_set::new_validator_set(::ethjson::spec::ValidatorSet::Multi(vec![
+			(::ethjson::uint::Uint(0.into()), ::ethjson::spec::ValidatorSet::List(vec![v0.into()])),
+			(::ethjson::uint::Uint(5.into()), ::ethjson::spec::ValidatorSet::List(vec![v1.into()])),
+		].into_iter().collect()));
+		let params = TendermintParams {
+			validators: validators,
+			timeouts: Default::default(),
+			block_reward: 0.into(),
+		};
+		let machine = ::machine::EthereumMachine::regular(::spec::CommonParams::default(), Default::default());
+		let engine = Tendermint::new(params, machine).unwrap();
+
+		let (genesis_child, epoch_child) = (H256::from(1), H256::from(6));
+		let transition = |number: BlockNumber, hash: H256| ::engines::EpochTransition {
+			block_hash: hash,
+			block_number: number,
+			proof: combine_proofs(number, &[], &[]),
+		};
+		let client = Arc::new(LightClient {
+			chain: ::client::TestBlockChainClient::new(),
+			transitions: vec![
+				(genesis_child, transition(0, H256::from(100))),
+				(epoch_child, transition(5, H256::from(105))),
+			].into_iter().collect(),
+		});
+		engine.register_client(Arc::downgrade(&client) as _);
+
+		let proposed = |number: BlockNumber, parent_hash: H256, author: Address| {
+			let mut header = Header::default();
+			header.set_number(number);
+			header.set_parent_hash(parent_hash);
+			header.set_author(author);
+			let seal = proposal_seal(&tap, &header, 0);
+			header.set_seal(seal);
+			header
+		};
+
+		// the first epoch is proven by the genesis transition.
+		assert!(engine.verify_block_external(&proposed(2, genesis_child, v0)).is_ok());
+		match engine.verify_block_external(&proposed(2, genesis_child, v1)) {
+			Err(Error::Engine(EngineError::NotAuthorized(address))) => assert_eq!(address, v1),
+			_ => panic!("the second validator is not in the first epoch"),
+		}
+
+		// zooming to the next epoch switches the set.
+		assert!(engine.verify_block_external(&proposed(7, epoch_child, v1)).is_ok());
+		assert!(engine.verify_block_external(&proposed(7, epoch_child, v0)).is_err());
+
+		// and back again.
+		assert!(engine.verify_block_external(&proposed(2, genesis_child, v0)).is_ok());
+
+		// blocks without a known epoch can't be verified.
+		match engine.verify_block_external(&proposed(2, H256::from(2), v0)) {
+			Err(Error::Engine(EngineError::RequiresClient)) => {},
+			_ => panic!("unknown epochs require a client"),
+		}
+
+		engine.stop();
+	}
 }
@@ -16,16 +16,19 @@
 
 /// Validator set maintained in a contract, updated using `getValidators` method.
 /// It can also report validators for misbehaviour with two levels: `reportMalicious` and `reportBenign`.
+/// Reports are recorded locally and persisted. On every new chain head, submissions which failed
+/// or whose transaction never got included are retried.
 
-use std::sync::Weak;
+use std::sync::{Weak, Arc};
 use ethereum_types::{H256, Address};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use bytes::Bytes;
 
 use futures::Future;
 use native_contracts::ValidatorReport as Provider;
 
-use client::EngineClient;
+use client::{EngineClient, ChainNotify, TransactionId};
+use engines::evidence::{Evidence, EvidenceStore, Misbehaviour};
 use header::{Header, BlockNumber};
 use machine::{AuxiliaryData, Call, EthereumMachine};
 
@@ -35,25 +38,36 @@ use super::safe_contract::ValidatorSafeContract;
 /// A validator contract with reporting.
 pub struct ValidatorContract {
 	validators: ValidatorSafeContract,
-	provider: Provider,
-	client: RwLock<Option<Weak<EngineClient>>>, // TODO [keorn]: remove
+	reporter: Arc<Reporter>,
 }
 
 impl ValidatorContract {
 	pub fn new(contract_address: Address) -> Self {
 		ValidatorContract {
 			validators: ValidatorSafeContract::new(contract_address),
-			provider: Provider::new(contract_address),
-			client: RwLock::new(None),
+			reporter: Arc::new(Reporter {
+				contract_address: contract_address,
+				provider: Provider::new(contract_address),
+				evidence: EvidenceStore::default(),
+				client: RwLock::new(None),
+			}),
 		}
 	}
 }
 
-impl ValidatorContract {
+// Submits misbehaviour reports to the contract and follows them until they are included.
+struct Reporter {
+	contract_address: Address,
+	provider: Provider,
+	evidence: EvidenceStore,
+	client: RwLock<Option<Weak<EngineClient>>>, // TODO [keorn]: remove
+}
+
+impl Reporter {
 	// could be `impl Trait`.
 	// note: dispatches transactions to network as well as execute.
 	// TODO [keorn]: Make more general.
-	fn transact(&self) -> Box<Fn(Address, Bytes) -> Result<Bytes, String>> {
+	fn transact(&self, submitted: Arc<Mutex<Option<H256>>>) -> Box<Fn(Address, Bytes) -> Result<Bytes, String>> {
 		let client = self.client.read().clone();
 		Box::new(move |a, d| client.as_ref()
 			.and_then(Weak::upgrade)
@@ -65,7 +79,104 @@ impl ValidatorContract {
 					None => Err("No full client!".into()),
 				}
 			})
-			.map(|_| Default::default()))
+			.map(|hash| {
+				*submitted.lock() = Some(hash);
+				Default::default()
+			}))
+	}
+
+	fn report(&self, evidence: Evidence) {
+		if self.evidence.insert(evidence.clone()) {
+			self.submit(&evidence);
+			self.persist();
+		}
+	}
+
+	fn submit(&self, evidence: &Evidence) {
+		let address = evidence.validator;
+		let submitted = Arc::new(Mutex::new(None));
+		let transact = self.transact(submitted.clone());
+		let result = match evidence.kind {
+			Misbehaviour::Malicious => self.provider.report_malicious(&*transact, address, evidence.block.into(), evidence.proof.clone()).wait(),
+			Misbehaviour::Benign => self.provider.report_benign(&*transact, address, evidence.block.into()).wait(),
+		};
+
+		let result = result.and_then(|_| submitted.lock().take().ok_or_else(|| "No report transaction".into()));
+		match result {
+			Ok(ref hash) => warn!(target: "engine", "Reported {:?} misbehaviour of validator {} in transaction {}: {}", evidence.kind, address, hash, evidence.reason),
+			Err(ref s) => warn!(target: "engine", "Validator {} could not be reported {}", address, s),
+		}
+		self.evidence.note_attempt(&evidence.hash, result);
+	}
+
+	// check whether submitted reports got included and resubmit the ones which failed or were dropped.
+	fn retry_reports(&self) {
+		let client = match self.client.read().as_ref().and_then(Weak::upgrade) {
+			Some(client) => client,
+			None => return,
+		};
+		let client = match client.as_full_client() {
+			Some(client) => client,
+			None => return,
+		};
+
+		let mut changed = self.evidence.check_inclusion(
+			|hash| client.transaction(TransactionId::Hash(*hash)).is_some(),
+			|hash| client.queued_transaction(hash).is_some(),
+		);
+
+		for evidence in self.evidence.pending() {
+			trace!(target: "engine", "Retrying report of validator {} at block {}", evidence.validator, evidence.block);
+			self.submit(&evidence);
+			changed = true;
+		}
+
+		if changed {
+			self.persist();
+		}
+	}
+
+	fn storage_key(&self) -> Vec<u8> {
+		let mut key = b"validator_reports".to_vec();
+		key.extend_from_slice(&self.contract_address);
+		key
+	}
+
+	fn persist(&self) {
+		if let Some(client) = self.client.read().as_ref().and_then(Weak::upgrade) {
+			if let Some(client) = client.as_full_client() {
+				client.set_local_data(&self.storage_key(), self.evidence.to_rlp());
+			}
+		}
+	}
+
+	fn restore(&self, client: &EngineClient) {
+		let data = match client.as_full_client().and_then(|c| c.local_data(&self.storage_key())) {
+			Some(data) => data,
+			None => return,
+		};
+
+		if let Err(e) = self.evidence.restore(&data) {
+			warn!(target: "engine", "Unable to restore validator reports: {}", e);
+		}
+	}
+}
+
+impl ChainNotify for Reporter {
+	fn new_blocks(
+		&self,
+		_imported: Vec<H256>,
+		_invalid: Vec<H256>,
+		enacted: Vec<H256>,
+		retracted: Vec<H256>,
+		_sealed: Vec<H256>,
+		_proposed: Vec<Bytes>,
+		_duration: u64,
+	) {
+		// called once for every new chain head.
+		if !enacted.is_empty() || !retracted.is_empty() {
+			self.retry_reports();
+		}
 	}
 }
 
@@ -111,23 +222,27 @@ impl ValidatorSet for ValidatorContract {
 		self.validators.count_with_caller(bh, caller)
 	}
 
-	fn report_malicious(&self, address: &Address, _set_block: BlockNumber, block: BlockNumber, proof: Bytes) {
-		match self.provider.report_malicious(&*self.transact(), *address, block.into(), proof).wait() {
-			Ok(_) => warn!(target: "engine", "Reported malicious validator {}", address),
-			Err(s) => warn!(target: "engine", "Validator {} could not be reported {}", address, s),
-		}
+	fn report_malicious(&self, address: &Address, set_block: BlockNumber, block: BlockNumber, proof: Bytes, reason: &str) {
+		self.reporter.report(Evidence::new(*address, set_block, block, Misbehaviour::Malicious, proof, reason));
 	}
 
-	fn report_benign(&self, address: &Address, _set_block: BlockNumber, block: BlockNumber) {
-		match self.provider.report_benign(&*self.transact(), *address, block.into()).wait() {
-			Ok(_) => warn!(target: "engine", "Reported benign validator misbehaviour {}", address),
-			Err(s) => warn!(target: "engine", "Validator {} could not be reported {}", address, s),
-		}
+	fn report_benign(&self, address: &Address, set_block: BlockNumber, block: BlockNumber, reason: &str) {
+		self.reporter.report(Evidence::new(*address, set_block, block, Misbehaviour::Benign, Vec::new(), reason));
+	}
+
+	fn evidence(&self) -> Vec<Evidence> {
+		self.reporter.evidence.list()
 	}
 
 	fn register_client(&self, client: Weak<EngineClient>) {
 		self.validators.register_client(client.clone());
-		*self.client.write() = Some(client);
+		if let Some(c) = client.upgrade() {
+			self.reporter.restore(&*c);
+			if let Some(full_client) = c.as_full_client() {
+				full_client.add_notify(self.reporter.clone());
+			}
+		}
+		*self.reporter.client.write() = Some(client);
 	}
 }
 
@@ -145,6 +260,7 @@ mod tests {
 	use miner::MinerService;
 	use types::ids::BlockId;
 	use client::BlockChainClient;
+	use engines::evidence::{Misbehaviour, ReportStatus};
 	use tests::helpers::generate_dummy_client_with_spec_and_accounts;
 	use super::super::ValidatorSet;
 	use super::ValidatorContract;
@@ -212,5 +328,27 @@ mod tests {
 		client.engine().step();
 		client.engine().step();
 		assert_eq!(client.chain_info().best_block_number, 2);
+
+		// Both reports were recorded.
+		let evidence = client.engine().misbehaviour_evidence();
+		assert!(evidence.iter().any(|e| e.validator == v1 && e.kind == Misbehaviour::Benign));
+		assert!(evidence.iter().any(|e| e.validator == v1 && e.kind == Misbehaviour::Malicious));
+		// and included in the chain.
+		assert!(evidence.iter().all(|e| e.status == ReportStatus::Included));
+	}
+
+	#[test]
+	fn persists_reports() {
+		let client = generate_dummy_client_with_spec_and_accounts(Spec::new_validator_contract, None);
+		let validator_contract = "0000000000000000000000000000000000000005".parse::<Address>().unwrap();
+		let vc = ValidatorContract::new(validator_contract);
+		vc.register_client(Arc::downgrade(&client) as _);
+		vc.report_benign(&Address::from(1), 0, 1, "skipped step");
+		assert_eq!(vc.evidence().len(), 1);
+
+		// reports are restored after a restart.
+		let restarted = ValidatorContract::new(validator_contract);
+		restarted.register_client(Arc::downgrade(&client) as _);
+		assert_eq!(restarted.evidence(), vc.evidence());
 	}
 }
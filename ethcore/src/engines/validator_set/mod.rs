@@ -31,6 +31,7 @@ use ethjson::spec::ValidatorSet as ValidatorSpec;
 use client::EngineClient;
 use header::{Header, BlockNumber};
 use machine::{AuxiliaryData, Call, EthereumMachine};
+use engines::evidence::Evidence;
 
 #[cfg(test)]
 pub use self::test::TestSet;
@@ -136,9 +137,11 @@ pub trait ValidatorSet: Send + Sync {
 	fn count_with_caller(&self, parent_block_hash: &H256, caller: &Call) -> usize;
 
 	/// Notifies about malicious behaviour.
-	fn report_malicious(&self, _validator: &Address, _set_block: BlockNumber, _block: BlockNumber, _proof: Bytes) {}
+	fn report_malicious(&self, _validator: &Address, _set_block: BlockNumber, _block: BlockNumber, _proof: Bytes, _reason: &str) {}
 	/// Notifies about benign misbehaviour.
-	fn report_benign(&self, _validator: &Address, _set_block: BlockNumber, _block: BlockNumber) {}
+	fn report_benign(&self, _validator: &Address, _set_block: BlockNumber, _block: BlockNumber, _reason: &str) {}
+	/// Misbehaviour reported through this set, oldest first.
+	fn evidence(&self) -> Vec<Evidence> { Vec::new() }
 	/// Allows blockchain state access.
 	fn register_client(&self, _client: Weak<EngineClient>) {}
 }
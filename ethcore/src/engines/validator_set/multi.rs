@@ -25,6 +25,7 @@ use ids::BlockId;
 use header::{BlockNumber, Header};
 use client::EngineClient;
 use machine::{AuxiliaryData, Call, EthereumMachine};
+use engines::evidence::Evidence;
 use super::{SystemCall, ValidatorSet};
 
 type BlockNumberLookup = Box<Fn(BlockId) -> Result<BlockNumber, String> + Send + Sync + 'static>;
@@ -123,12 +124,16 @@ impl ValidatorSet for Multi {
 			.map_or_else(usize::max_value, |set| set.count_with_caller(bh, caller))
 	}
 
-	fn report_malicious(&self, validator: &Address, set_block: BlockNumber, block: BlockNumber, proof: Bytes) {
-		self.correct_set_by_number(set_block).1.report_malicious(validator, set_block, block, proof);
+	fn report_malicious(&self, validator: &Address, set_block: BlockNumber, block: BlockNumber, proof: Bytes, reason: &str) {
+		self.correct_set_by_number(set_block).1.report_malicious(validator, set_block, block, proof, reason);
 	}
 
-	fn report_benign(&self, validator: &Address, set_block: BlockNumber, block: BlockNumber) {
-		self.correct_set_by_number(set_block).1.report_benign(validator, set_block, block);
+	fn report_benign(&self, validator: &Address, set_block: BlockNumber, block: BlockNumber, reason: &str) {
+		self.correct_set_by_number(set_block).1.report_benign(validator, set_block, block, reason);
+	}
+
+	fn evidence(&self) -> Vec<Evidence> {
+		self.sets.values().flat_map(|set| set.evidence()).collect()
 	}
 
 	fn register_client(&self, client: Weak<EngineClient>) {
@@ -218,6 +218,8 @@ pub enum Error {
 	Block(BlockError),
 	/// Unknown engine given.
 	UnknownEngineName(String),
+	/// Unknown builtin contract given.
+	UnknownBuiltinName(String),
 	/// Error concerning EVM code execution.
 	Execution(ExecutionError),
 	/// Error concerning transaction processing.
@@ -259,6 +261,8 @@ impl fmt::Display for Error {
 			Error::Import(ref err) => err.fmt(f),
 			Error::UnknownEngineName(ref name) =>
 				f.write_fmt(format_args!("Unknown engine name ({})", name)),
+			Error::UnknownBuiltinName(ref name) =>
+				f.write_fmt(format_args!("Unknown builtin name ({})", name)),
 			Error::PowHashInvalid => f.write_str("Invalid or out of date PoW hash."),
 			Error::PowInvalid => f.write_str("Invalid nonce or mishash"),
 			Error::Trie(ref err) => err.fmt(f),
@@ -37,6 +37,7 @@ const SNAPSHOT_BLOCKS: u64 = 5000;
 /// Maximum number of blocks allowed in an ethash snapshot.
 const MAX_SNAPSHOT_BLOCKS: u64 = 30000;
 
+/// Difficulty bomb delay of the deprecated `eip649Transition` without an `eip649Delay`.
 const DEFAULT_EIP649_DELAY: u64 = 3_000_000;
 
 /// Ethash params.
@@ -80,14 +81,10 @@ pub struct EthashParams {
 	pub mcip3_dev_reward: U256,
 	/// MCIP-3 contract address for the developer funds.
 	pub mcip3_dev_contract: Address,
-	/// Block reward in base units.
-	pub block_reward: U256,
-	/// EIP-649 transition block.
-	pub eip649_transition: u64,
-	/// EIP-649 bomb delay.
-	pub eip649_delay: u64,
-	/// EIP-649 base reward.
-	pub eip649_reward: Option<U256>,
+	/// Block reward in base units, indexed by the block it applies from.
+	pub block_reward: BTreeMap<BlockNumber, U256>,
+	/// Delays of the difficulty bomb in blocks, indexed by the block they apply from.
+	pub difficulty_bomb_delays: BTreeMap<BlockNumber, BlockNumber>,
 	/// EXPIP-2 block height
 	pub expip2_transition: u64,
 	/// EXPIP-2 duration limit
@@ -96,6 +93,26 @@ pub struct EthashParams {
 
 impl From<ethjson::spec::EthashParams> for EthashParams {
 	fn from(p: ethjson::spec::EthashParams) -> Self {
+		let mut block_reward: BTreeMap<BlockNumber, U256> = match p.block_reward {
+			Some(ethjson::spec::BlockReward::Single(reward)) => vec![(0, reward.into())].into_iter().collect(),
+			Some(ethjson::spec::BlockReward::Multi(rewards)) => rewards.into_iter().map(|(block, reward)| (block.into(), reward.into())).collect(),
+			None => vec![(0, U256::zero())].into_iter().collect(),
+		};
+		let mut difficulty_bomb_delays: BTreeMap<BlockNumber, BlockNumber> = p.difficulty_bomb_delays.map_or_else(BTreeMap::new, |delays| {
+			delays.into_iter().map(|(block, delay)| (block.into(), delay.into())).collect()
+		});
+
+		// the deprecated EIP-649 params are a bomb delay and a reward transition, unless
+		// the new params already give one at the same block.
+		if let Some(transition) = p.eip649_transition {
+			let transition: BlockNumber = transition.into();
+			let delay = p.eip649_delay.map_or(DEFAULT_EIP649_DELAY, Into::into);
+			difficulty_bomb_delays.entry(transition).or_insert(delay);
+			if let Some(reward) = p.eip649_reward {
+				block_reward.entry(transition).or_insert(reward.into());
+			}
+		}
+
 		EthashParams {
 			minimum_difficulty: p.minimum_difficulty.into(),
 			difficulty_bound_divisor: p.difficulty_bound_divisor.into(),
@@ -116,10 +133,8 @@ impl From<ethjson::spec::EthashParams> for EthashParams {
 			mcip3_ubi_contract: p.mcip3_ubi_contract.map_or_else(Address::new, Into::into),
 			mcip3_dev_reward: p.mcip3_dev_reward.map_or(U256::from(0), Into::into),
 			mcip3_dev_contract: p.mcip3_dev_contract.map_or_else(Address::new, Into::into),
-			block_reward: p.block_reward.map_or_else(Default::default, Into::into),
-			eip649_transition: p.eip649_transition.map_or(u64::max_value(), Into::into),
-			eip649_delay: p.eip649_delay.map_or(DEFAULT_EIP649_DELAY, Into::into),
-			eip649_reward: p.eip649_reward.map(Into::into),
+			block_reward: block_reward,
+			difficulty_bomb_delays: difficulty_bomb_delays,
 			expip2_transition: p.expip2_transition.map_or(u64::max_value(), Into::into),
 			expip2_duration_limit: p.expip2_duration_limit.map_or(30, Into::into),
 		}
@@ -171,11 +186,11 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
 	fn machine(&self) -> &EthereumMachine { &self.machine }
 
 	// Two fields - nonce and mix.
-	fn seal_fields(&self) -> usize { 2 }
+	fn seal_fields(&self, _header: &Header) -> usize { 2 }
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
-		if header.seal().len() == self.seal_fields() {
+		if header.seal().len() == self.seal_fields(header) {
 			map![
 				"nonce".to_owned() => format!("0x{}", header.nonce().hex()),
 				"mixHash".to_owned() => format!("0x{}", header.mix_hash().hex())
@@ -209,12 +224,10 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
 		let author = *LiveBlock::header(&*block).author();
 		let number = LiveBlock::header(&*block).number();
 
-		// Applies EIP-649 reward.
-		let reward = if number >= self.ethash_params.eip649_transition {
-			self.ethash_params.eip649_reward.unwrap_or(self.ethash_params.block_reward)
-		} else {
-			self.ethash_params.block_reward
-		};
+		// Applies the latest reward transition, no reward before the first one.
+		let reward = self.ethash_params.block_reward.iter().rev()
+			.find(|&(block, _)| *block <= number)
+			.map_or_else(U256::zero, |(_, reward)| *reward);
 
 		// Applies ECIP-1017 eras.
 		let eras_rounds = self.ethash_params.ecip1017_era_rounds;
@@ -267,9 +280,9 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
 
 	fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
 		// check the seal fields.
-		if header.seal().len() != self.seal_fields() {
+		if header.seal().len() != self.seal_fields(header) {
 			return Err(From::from(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
+				Mismatch { expected: self.seal_fields(header), found: header.seal().len() }
 			)));
 		}
 		UntrustedRlp::new(&header.seal()[0]).as_val::<H256>()?;
@@ -298,9 +311,9 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
 	}
 
 	fn verify_block_unordered(&self, header: &Header) -> Result<(), Error> {
-		if header.seal().len() != self.seal_fields() {
+		if header.seal().len() != self.seal_fields(header) {
 			return Err(From::from(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
+				Mismatch { expected: self.seal_fields(header), found: header.seal().len() }
 			)));
 		}
 		let result = self.pow.compute_light(header.number() as u64, &header.bare_hash().0, header.nonce().low_u64());
@@ -397,10 +410,9 @@ impl Ethash {
 		target = cmp::max(min_difficulty, target);
 		if header.number() < self.ethash_params.bomb_defuse_transition {
 			if header.number() < self.ethash_params.ecip1010_pause_transition {
-				let mut number = header.number();
-				if number >= self.ethash_params.eip649_transition {
-					number = number.saturating_sub(self.ethash_params.eip649_delay);
-				}
+				let number = self.ethash_params.difficulty_bomb_delays.iter()
+					.filter(|&(block, _)| *block <= header.number())
+					.fold(header.number(), |number, (_, delay)| number.saturating_sub(*delay));
 				let period = (number / EXP_DIFF_PERIOD) as usize;
 				if period > 1 {
 					target = cmp::max(min_difficulty, target + (U256::from(1) << (period - 2)));
@@ -785,6 +797,59 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn converts_deprecated_eip649_params() {
+		let params: ::ethjson::spec::EthashParams = ::serde_json::from_str(r#"{
+			"minimumDifficulty": "0x020000",
+			"difficultyBoundDivisor": "0x0800",
+			"blockReward": "0x4563918244F40000",
+			"eip649Transition": "0x42ae50",
+			"eip649Reward": "0x29A2241AF62C0000"
+		}"#).unwrap();
+		let params = EthashParams::from(params);
+
+		assert_eq!(params.block_reward, vec![
+			(0, U256::from_str("4563918244F40000").unwrap()),
+			(4370000, U256::from_str("29A2241AF62C0000").unwrap()),
+		].into_iter().collect());
+		assert_eq!(params.difficulty_bomb_delays, vec![(4370000, 3000000)].into_iter().collect());
+
+		let params: ::ethjson::spec::EthashParams = ::serde_json::from_str(r#"{
+			"minimumDifficulty": "0x020000",
+			"difficultyBoundDivisor": "0x0800"
+		}"#).unwrap();
+		assert_eq!(EthashParams::from(params).block_reward, vec![(0, U256::zero())].into_iter().collect());
+	}
+
+	#[test]
+	fn difficulty_bomb_delays_accumulate() {
+		let delayed = |delays: Vec<(u64, u64)>| {
+			let ethparams = EthashParams {
+				difficulty_bomb_delays: delays.into_iter().collect(),
+				..get_default_ethash_params()
+			};
+			Ethash::new(&::std::env::temp_dir(), ethparams, new_homestead_test_machine(), None)
+		};
+
+		let mut parent_header = Header::default();
+		parent_header.set_number(5000000);
+		parent_header.set_difficulty(U256::from_str("2000000000000000").unwrap());
+		parent_header.set_timestamp(1500000000);
+		let mut header = Header::default();
+		header.set_number(parent_header.number() + 1);
+		header.set_timestamp(parent_header.timestamp() + 15);
+
+		let undelayed = delayed(vec![]).calculate_difficulty(&header, &parent_header);
+		let future = delayed(vec![(6000000, 3000000)]).calculate_difficulty(&header, &parent_header);
+		let single = delayed(vec![(4000000, 3000000)]).calculate_difficulty(&header, &parent_header);
+		let stacked = delayed(vec![(4000000, 3000000), (4500000, 1000000)]).calculate_difficulty(&header, &parent_header);
+
+		// Bomb periods are 50 without delay, 20 after 3M blocks and 10 after 4M blocks.
+		assert_eq!(undelayed, future);
+		assert_eq!(undelayed - single, (U256::one() << 48) - (U256::one() << 18));
+		assert_eq!(undelayed - stacked, (U256::one() << 48) - (U256::one() << 8));
+	}
+
 	#[test]
 	fn test_difficulty_bomb_continue() {
 		let machine = new_homestead_test_machine();
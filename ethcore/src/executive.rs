@@ -38,8 +38,6 @@ pub use executed::{Executed, ExecutionResult};
 /// Maybe something like here: `https://github.com/ethereum/libethereum/blob/4db169b8504f2b87f7d5a481819cfb959fc65f6c/libethereum/ExtVM.cpp`
 const STACK_SIZE_PER_DEPTH: usize = 24*1024;
 
-const WASM_MAGIC_NUMBER: &'static [u8; 4] = b"\0asm";
-
 /// Returns new address created from address, nonce, and code hash
 pub fn contract_address(address_scheme: CreateContractAddress, sender: &Address, nonce: &U256, code: &[u8]) -> (Address, Option<H256>) {
 	use rlp::RlpStream;
@@ -140,6 +138,30 @@ impl TransactOptions<trace::NoopTracer, trace::ExecutiveVMTracer> {
 	}
 }
 
+impl TransactOptions<trace::ExecutiveTracer, trace::DebugLogVMTracer> {
+	/// Creates new `TransactOptions` with default tracing and collecting contract debug messages.
+	pub fn with_tracing_and_debug_logging() -> Self {
+		TransactOptions {
+			tracer: trace::ExecutiveTracer::default(),
+			vm_tracer: trace::DebugLogVMTracer::toplevel(),
+			check_nonce: true,
+			output_from_init_contract: false,
+		}
+	}
+}
+
+impl TransactOptions<trace::NoopTracer, trace::DebugLogVMTracer> {
+	/// Creates new `TransactOptions` with no tracing, collecting contract debug messages.
+	pub fn with_debug_logging() -> Self {
+		TransactOptions {
+			tracer: trace::NoopTracer,
+			vm_tracer: trace::DebugLogVMTracer::toplevel(),
+			check_nonce: true,
+			output_from_init_contract: false,
+		}
+	}
+}
+
 impl TransactOptions<trace::NoopTracer, trace::NoopVMTracer> {
 	/// Creates new `TransactOptions` without any tracing.
 	pub fn with_no_tracing() -> Self {
@@ -152,10 +174,10 @@ impl TransactOptions<trace::NoopTracer, trace::NoopVMTracer> {
 	}
 }
 
-pub fn executor(machine: &Machine, vm_factory: &Factory, params: &ActionParams) -> Box<vm::Vm> {
-	if machine.supports_wasm() && params.code.as_ref().map_or(false, |code| code.len() > 4 && &code[0..4] == WASM_MAGIC_NUMBER) {
+pub fn executor(machine: &Machine, vm_factory: &Factory, wasm_cache: &Arc<wasm::SharedCache>, params: &ActionParams) -> Box<vm::Vm> {
+	if machine.supports_wasm() && params.code.as_ref().map_or(false, |code| code.len() > 4 && &code[0..4] == wasm::WASM_MAGIC_NUMBER) {
 		Box::new(
-			wasm::WasmInterpreter::new()
+			wasm::WasmInterpreter::with_cache(wasm_cache.clone())
 				// prefer to fail fast
 				.expect("Failed to create wasm runtime")
 		)
@@ -351,9 +373,10 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 		// Ordinary execution - keep VM in same thread
 		if (self.depth + 1) % depth_threshold != 0 {
 			let vm_factory = self.state.vm_factory();
+			let wasm_cache = self.state.wasm_cache();
 			let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer, static_call);
 			trace!(target: "executive", "ext.schedule.have_delegate_call: {}", ext.schedule().have_delegate_call);
-			return executor(self.machine, &vm_factory, &params).exec(params, &mut ext).finalize(ext);
+			return executor(self.machine, &vm_factory, &wasm_cache, &params).exec(params, &mut ext).finalize(ext);
 		}
 
 		// Start in new thread to reset stack
@@ -362,10 +385,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 		crossbeam::scope(|scope| {
 			let machine = self.machine;
 			let vm_factory = self.state.vm_factory();
+			let wasm_cache = self.state.wasm_cache();
 			let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer, static_call);
 
 			scope.spawn(move || {
-				executor(machine, &vm_factory, &params).exec(params, &mut ext).finalize(ext)
+				executor(machine, &vm_factory, &wasm_cache, &params).exec(params, &mut ext).finalize(ext)
 			})
 		}).join()
 	}
@@ -677,6 +701,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 				| Err(vm::Error::StackUnderflow {..})
 				| Err(vm::Error::BuiltIn {..})
 				| Err(vm::Error::Wasm {..})
+				| Err(vm::Error::WasmPanic(_))
 				| Err(vm::Error::OutOfStack {..})
 				| Err(vm::Error::MutableCallInStaticContext)
 				| Err(vm::Error::OutOfBounds)
@@ -927,9 +952,11 @@ mod tests {
 						VMOperation { pc: 8, instruction: 96, gas_cost: 3.into(), executed: Some(VMExecutedOperation { gas_used: 67955.into(), stack_push: vec_into![0], mem_diff: None, store_diff: None }) },
 						VMOperation { pc: 10, instruction: 243, gas_cost: 0.into(), executed: Some(VMExecutedOperation { gas_used: 67955.into(), stack_push: vec_into![], mem_diff: None, store_diff: None }) }
 					],
-					subs: vec![]
+					subs: vec![],
+					debug_log: vec![],
 				}
-			]
+			],
+			debug_log: vec![],
 		};
 		assert_eq!(vm_tracer.drain().unwrap(), expected_vm_trace);
 	}
@@ -1084,7 +1111,8 @@ mod tests {
 				VMOperation { pc: 8, instruction: 96, gas_cost: 3.into(), executed: Some(VMExecutedOperation { gas_used: 99976.into(), stack_push: vec_into![0], mem_diff: None, store_diff: None }) },
 				VMOperation { pc: 10, instruction: 243, gas_cost: 0.into(), executed: Some(VMExecutedOperation { gas_used: 99976.into(), stack_push: vec_into![], mem_diff: None, store_diff: None }) }
 			],
-			subs: vec![]
+			subs: vec![],
+			debug_log: vec![],
 		};
 		assert_eq!(vm_tracer.drain().unwrap(), expected_vm_trace);
 	}
@@ -1501,4 +1529,194 @@ mod tests {
 		assert_eq!(output[..], returns[..]);
 		assert_eq!(state.storage_at(&contract_address, &H256::from(&U256::zero())).unwrap(), H256::from(&U256::from(0)));
 	}
+
+	fn wasm_logging_transaction(state: &mut State<::state_db::StateDB>) -> SignedTransaction {
+		let code = include_bytes!("../res/wasm-tests/compiled/events.wasm").to_vec();
+		let contract = Address::from_str("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+		state.init_code(&contract, code).unwrap();
+
+		let keypair = Random.generate().unwrap();
+		let t = Transaction {
+			action: Action::Call(contract),
+			value: U256::zero(),
+			data: b"something".to_vec(),
+			gas: U256::from(1_000_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret(), None);
+		state.add_balance(&t.sender(), &U256::from(1), CleanupMode::NoEmpty).unwrap();
+		t
+	}
+
+	#[test]
+	fn wasm_log_replay_vm_trace() {
+		let spec = ::spec::Spec::new_wasm_test();
+		let mut state = get_temp_state();
+		let t = wasm_logging_transaction(&mut state);
+		let mut info = EnvInfo::default();
+		info.gas_limit = U256::from(1_000_000);
+
+		// `trace_replayTransaction` replays the transaction with both tracers enabled.
+		let executed = {
+			let mut ex = Executive::new(&mut state, &info, spec.engine.machine());
+			ex.transact(&t, TransactOptions::with_tracing_and_vm_tracing()).unwrap()
+		};
+
+		assert_eq!(executed.logs.len(), 1);
+		assert_eq!(executed.logs[0].topics.len(), 2);
+
+		let vm_trace = executed.vm_trace.expect("vm tracing is enabled");
+		let logs: Vec<_> = vm_trace.operations.iter().filter(|op| op.instruction >= 0xa0 && op.instruction <= 0xa4).collect();
+		assert_eq!(logs.len(), 1);
+		// LOG2
+		assert_eq!(logs[0].instruction, 0xa2);
+		assert!(logs[0].executed.is_some());
+	}
+
+	/// Records the instructions reported at each tracing stage.
+	struct InstructionTracer {
+		next: Vec<u8>,
+		prepared: Vec<u8>,
+	}
+
+	impl VMTracer for InstructionTracer {
+		type Output = (Vec<u8>, Vec<u8>);
+
+		fn trace_next_instruction(&mut self, _pc: usize, instruction: u8, _current_gas: U256) -> bool {
+			self.next.push(instruction);
+			true
+		}
+
+		fn trace_prepare_execute(&mut self, _pc: usize, instruction: u8, _gas_cost: U256) {
+			self.prepared.push(instruction);
+		}
+
+		fn prepare_subtrace(&self, _code: &[u8]) -> Self {
+			InstructionTracer { next: Vec::new(), prepared: Vec::new() }
+		}
+
+		fn done_subtrace(&mut self, _sub: Self) {}
+
+		fn drain(self) -> Option<Self::Output> {
+			Some((self.next, self.prepared))
+		}
+	}
+
+	#[test]
+	fn wasm_log_traced_with_same_instruction() {
+		let spec = ::spec::Spec::new_wasm_test();
+		let mut state = get_temp_state();
+		let t = wasm_logging_transaction(&mut state);
+		let mut info = EnvInfo::default();
+		info.gas_limit = U256::from(1_000_000);
+
+		let vm_tracer = InstructionTracer { next: Vec::new(), prepared: Vec::new() };
+		let executed = {
+			let mut ex = Executive::new(&mut state, &info, spec.engine.machine());
+			ex.transact(&t, TransactOptions::new(NoopTracer, vm_tracer)).unwrap()
+		};
+
+		let (next, prepared) = executed.vm_trace.expect("vm tracing is enabled");
+		assert_eq!(next, prepared);
+		assert!(next.contains(&0xa2));
+	}
+
+	#[test]
+	fn wasm_builtin_externals() {
+		// Module importing `_sha256`, `_ecrecover`, `_bn128_add`, `_bn128_mul` and `_bn128_pairing`.
+		// Its `_call` hashes "abc", recovers the signer of a known signature, adds the alt_bn128
+		// generator to itself, multiplies it by 2 and checks an empty pairing, then stores the
+		// results as words 0 to 6.
+		let code = FromHex::from_hex("\
+903
+			0061736d01000000011d0560037f7f7f0060027f7f017f60027f7f0060037f7f7f017f60017f00027a0703656e76066d\
+			656d6f727902000103656e76075f736861323536000003656e760a5f65637265636f766572000103656e760a5f626e31\
+			32385f616464000103656e760a5f626e3132385f6d756c000103656e760e5f626e3132385f70616972696e6700030365\
+			6e760e5f73746f726167655f7772697465000203020104070901055f63616c6c00060a6b01690041a00b410341801210\
+			0041800841ac1210011a41800941c01210021a41800a41801310031a4100410041c01310041a418010418012100541a0\
+			1041a012100541c01041c012100541e01041e0121005418011418013100541a01141a013100541c01141c01310050b0b\
+			e50405004180080b800147173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000\
+			000000000000000000000000000000000000000000000000001b650acf9d3f5f0a2c799776a1254355d5f4061762a237\
+			396a99a0e0e3fc2bcd6729514a0dacb2e623ac4abd157cb18163ff942280db4d5caad66ddf941ba12e03004180090b80\
+			010000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000\
+			000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000\
+			0100000000000000000000000000000000000000000000000000000000000000020041800a0b60000000000000000000\
+			000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000\
+			0000000000000200000000000000000000000000000000000000000000000000000000000000020041a00b0b03616263\
+			004180100be0010000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+			000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000\
+			000000000000020000000000000000000000000000000000000000000000000000000000000003000000000000000000\
+			000000000000000000000000000000000000000000000400000000000000000000000000000000000000000000000000\
+			000000000000050000000000000000000000000000000000000000000000000000000000000006"
+		).unwrap();
+		let contract = Address::from_str("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+
+		let spec = ::spec::Spec::new_wasm_test();
+		let mut state = get_temp_state();
+		state.init_code(&contract, code).unwrap();
+		let keypair = Random.generate().unwrap();
+		let t = Transaction {
+			action: Action::Call(contract),
+			value: U256::zero(),
+			data: vec![],
+			gas: U256::from(1_000_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret(), None);
+		let mut info = EnvInfo::default();
+		info.gas_limit = U256::from(1_000_000);
+
+		let executed = {
+			let mut ex = Executive::new(&mut state, &info, spec.engine.machine());
+			ex.transact(&t, TransactOptions::with_no_tracing()).unwrap()
+		};
+		assert!(executed.exception.is_none());
+
+		let word = |i: u64| state.storage_at(&contract, &H256::from(&U256::from(i))).unwrap();
+		// sha256("abc")
+		assert_eq!(word(0), H256::from("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+		assert_eq!(word(1), H256::from("000000000000000000000000c08b5542d177ac6686946920409741463a15dddb"));
+		// 2 * G1, both from addition and multiplication
+		let double_x = H256::from("030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3");
+		let double_y = H256::from("15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4");
+		assert_eq!((word(2), word(3)), (double_x, double_y));
+		assert_eq!((word(4), word(5)), (double_x, double_y));
+		assert_eq!(word(6), H256::from(&U256::one()));
+	}
+
+	#[test]
+	fn wasm_debug_log_without_vm_tracing() {
+		// Module whose `_call` logs "hello" with `_debug`.
+		let code = FromHex::from_hex("\
+			0061736d01000000010a0260027f7f0060017f00021c0203656e76066d656d6f727902000103656e76065f6465627567\
+			000003020101070901055f63616c6c00010a0b010900418008410510000b0b0c01004180080b0568656c6c6f"
+		).unwrap();
+		let contract = Address::from_str("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+
+		let spec = ::spec::Spec::new_wasm_test();
+		let mut state = get_temp_state();
+		state.init_code(&contract, code).unwrap();
+		let keypair = Random.generate().unwrap();
+		let t = Transaction {
+			action: Action::Call(contract),
+			value: U256::zero(),
+			data: vec![],
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret(), None);
+		let mut info = EnvInfo::default();
+		info.gas_limit = U256::from(100_000);
+
+		// `trace_call` with the `trace` and `debugLog` options.
+		let executed = {
+			let mut ex = Executive::new(&mut state, &info, spec.engine.machine());
+			ex.transact(&t, TransactOptions::with_tracing_and_debug_logging()).unwrap()
+		};
+
+		assert_eq!(executed.trace.len(), 1);
+		let vm_trace = executed.vm_trace.expect("debug messages are collected");
+		assert_eq!(vm_trace.debug_log, vec!["hello".to_owned()]);
+		assert!(vm_trace.operations.is_empty());
+	}
 }
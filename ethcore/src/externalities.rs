@@ -28,6 +28,7 @@ use vm::{
 	ReturnData
 };
 use evm::FinalizationResult;
+use wasm;
 use transaction::UNSIGNED_SENDER;
 use trace::{Tracer, VMTracer};
 
@@ -299,6 +300,20 @@ impl<'a, T: 'a, V: 'a, B: 'a> Ext for Externalities<'a, T, V, B>
 		Ok(self.state.code_size(address)?.unwrap_or(0))
 	}
 
+	fn builtin_cost(&self, name: &str, input: &[u8]) -> Option<U256> {
+		self.machine.builtin_by_name(name, self.env_info.number).map(|builtin| builtin.cost(input))
+	}
+
+	fn builtin_execute(&self, name: &str, input: &[u8]) -> vm::Result<Bytes> {
+		let builtin = self.machine.builtin_by_name(name, self.env_info.number)
+			.ok_or(vm::Error::BuiltIn("No builtin contract with given name"))?;
+
+		let mut output = Vec::new();
+		builtin.execute(input, &mut BytesRef::Flexible(&mut output))
+			.map_err(|e| vm::Error::BuiltIn(e.0))?;
+		Ok(output)
+	}
+
 	fn ret(mut self, gas: &U256, data: &ReturnData, apply_state: bool) -> vm::Result<U256>
 		where Self: Sized {
 		let handle_copy = |to: &mut Option<&mut Bytes>| {
@@ -327,6 +342,10 @@ impl<'a, T: 'a, V: 'a, B: 'a> Ext for Externalities<'a, T, V, B>
 						false => Ok(*gas)
 					}
 				}
+				if self.schedule.wasm_validation && data.len() > 4 && &data[0..4] == wasm::WASM_MAGIC_NUMBER {
+					wasm::validate(&*data, &self.schedule.wasm)
+						.map_err(|e| vm::Error::Wasm(format!("Invalid contract code: {}", e)))?;
+				}
 				handle_copy(copy);
 				self.state.init_code(&self.origin_info.address, data.to_vec())?;
 				Ok(*gas - return_cost)
@@ -407,6 +426,18 @@ impl<'a, T: 'a, V: 'a, B: 'a> Ext for Externalities<'a, T, V, B>
 	fn trace_executed(&mut self, gas_used: U256, stack_push: &[U256], mem_diff: Option<(usize, &[u8])>, store_diff: Option<(U256, U256)>) {
 		self.vm_tracer.trace_executed(gas_used, stack_push, mem_diff, store_diff)
 	}
+
+	fn trace_debug_log(&mut self, message: &str) {
+		self.vm_tracer.trace_debug_log(message)
+	}
+
+	fn trace_memory_usage(&mut self, bytes: usize) {
+		self.vm_tracer.trace_memory_usage(bytes)
+	}
+
+	fn trace_host_call(&mut self, name: &str, gas_cost: U256) {
+		self.vm_tracer.trace_host_call(name, gas_cost)
+	}
 }
 
 #[cfg(test)]
@@ -572,4 +603,22 @@ mod tests {
 
 		assert_eq!(setup.sub_state.suicides.len(), 1);
 	}
+
+	#[test]
+	fn can_run_builtins() {
+		let sha256 = "sha256";
+		let unknown = "unknown";
+
+		let mut setup = TestSetup::new();
+		let state = &mut setup.state;
+		let mut tracer = NoopTracer;
+		let mut vm_tracer = NoopVMTracer;
+
+		let ext = Externalities::new(state, &setup.env_info, &setup.machine, 0, get_test_origin(), &mut setup.sub_state, OutputPolicy::InitContract(None), &mut tracer, &mut vm_tracer, false);
+
+		assert_eq!(ext.builtin_cost(sha256, &[]), Some(60.into()));
+		assert_eq!(ext.builtin_execute(sha256, &[]).unwrap(), H256::from("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").to_vec());
+		assert_eq!(ext.builtin_cost(unknown, &[]), None);
+		assert!(ext.builtin_execute(unknown, &[]).is_err());
+	}
 }
@@ -14,9 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
 use trie::TrieFactory;
 use evm::Factory as EvmFactory;
 use account_db::Factory as AccountFactory;
+use wasm::SharedCache as WasmCache;
 
 /// Collection of factories.
 #[derive(Default, Clone)]
@@ -27,4 +29,6 @@ pub struct Factories {
 	pub trie: TrieFactory,
 	/// factory for account databases.
 	pub accountdb: AccountFactory,
+	/// cache of instrumented wasm modules.
+	pub wasm: Arc<WasmCache>,
 }
@@ -14,13 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
-use client::{EvmTestClient, BlockChainClient, Client, ClientConfig};
-use block::Block;
-use spec::Genesis;
+use client::{EvmTestClient, import_chain_test};
 use ethjson;
-use miner::Miner;
-use io::IoChannel;
 
 pub fn json_chain_test(json_data: &[u8]) -> Vec<String> {
 	::ethcore_logger::init_log();
@@ -39,42 +34,22 @@ pub fn json_chain_test(json_data: &[u8]) -> Vec<String> {
 
 			flush!("   - {}...", name);
 
-			let spec = {
-				let mut spec = match EvmTestClient::spec_from_json(&blockchain.network) {
-					Some(spec) => (*spec).clone(),
-					None => {
-						println!("   - {} | {:?} Ignoring tests because of missing spec", name, blockchain.network);
-						continue;
-					}
-				};
-
-				let genesis = Genesis::from(blockchain.genesis());
-				let state = From::from(blockchain.pre_state.clone());
-				spec.set_genesis_state(state).expect("Failed to overwrite genesis state");
-				spec.overwrite_genesis_params(genesis);
-				assert!(spec.is_state_root_valid());
-				spec
+			let spec = match EvmTestClient::spec_from_json(&blockchain.network) {
+				Some(spec) => spec,
+				None => {
+					println!("   - {} | {:?} Ignoring tests because of missing spec", name, blockchain.network);
+					continue;
+				}
 			};
 
-			{
-				let db = Arc::new(::kvdb_memorydb::create(::db::NUM_COLUMNS.unwrap_or(0)));
-				let mut config = ClientConfig::default();
-				config.history = 8;
-				let client = Client::new(
-					config,
-					&spec,
-					db,
-					Arc::new(Miner::with_spec(&spec)),
-					IoChannel::disconnected(),
-				).unwrap();
-				for b in &blockchain.blocks_rlp() {
-					if Block::is_good(&b) {
-						let _ = client.import_block(b.clone());
-						client.flush_queue();
-						client.import_verified_blocks();
-					}
-				}
-				fail_unless(client.chain_info().best_block_hash == blockchain.best_block.into());
+			match import_chain_test(spec, &blockchain) {
+				Ok(best_block) => {
+					fail_unless(best_block == blockchain.best_block.into());
+				},
+				Err(err) => {
+					println!("   - {} | Unable to import: {}", name, err);
+					fail_unless(false);
+				},
 			}
 		}
 
@@ -160,6 +160,14 @@ impl<'a, T: 'a, V: 'a, B: 'a> Ext for TestExt<'a, T, V, B>
 		self.ext.extcodesize(address)
 	}
 
+	fn builtin_cost(&self, name: &str, input: &[u8]) -> Option<U256> {
+		self.ext.builtin_cost(name, input)
+	}
+
+	fn builtin_execute(&self, name: &str, input: &[u8]) -> vm::Result<Bytes> {
+		self.ext.builtin_execute(name, input)
+	}
+
 	fn log(&mut self, topics: Vec<H256>, data: &[u8]) -> vm::Result<()> {
 		self.ext.log(topics, data)
 	}
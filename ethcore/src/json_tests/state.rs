@@ -17,7 +17,7 @@
 use super::test_common::*;
 use pod_state::PodState;
 use trace;
-use client::{EvmTestClient, EvmTestError, TransactResult};
+use client::{EvmTestClient, EvmTestError, TransactResult, logs_hash};
 use ethjson;
 use transaction::SignedTransaction;
 use vm::EnvInfo;
@@ -47,6 +47,7 @@ pub fn json_chain_test(json_data: &[u8]) -> Vec<String> {
 					let info = format!("   - {} | {:?} ({}/{}) ...", name, spec_name, i + 1, total);
 
 					let post_root: H256 = state.hash.into();
+					let post_logs: Option<H256> = state.logs.map(Into::into);
 					let transaction: SignedTransaction = multitransaction.select(&state.indexes).into();
 
 					let result = || -> Result<_, EvmTestError> {
@@ -64,6 +65,11 @@ pub fn json_chain_test(json_data: &[u8]) -> Vec<String> {
 							flushln!("{} fail", info);
 							failed.push(name.clone());
 						},
+						Ok(TransactResult::Ok { ref logs, .. }) if post_logs.map_or(false, |post_logs| logs_hash(logs) != post_logs) => {
+							println!("{} !!! Logs mismatch (got: {}, expect: {}", info, logs_hash(logs), post_logs.unwrap());
+							flushln!("{} fail", info);
+							failed.push(name.clone());
+						},
 						Ok(TransactResult::Err { state_root, ref error }) if state_root != post_root => {
 							println!("{} !!! State mismatch (got: {}, expect: {}", info, state_root, post_root);
 							println!("{} !!! Execution error: {:?}", info, error);
@@ -92,6 +92,9 @@ extern crate patricia_trie as trie;
 extern crate triehash;
 extern crate ansi_term;
 extern crate semantic_version;
+extern crate serde_ignored;
+extern crate serde_path_to_error;
+extern crate serde_json;
 extern crate unexpected;
 extern crate kvdb;
 extern crate kvdb_rocksdb;
@@ -131,6 +134,7 @@ pub extern crate ethstore;
 
 pub mod account_provider;
 pub mod block;
+pub mod builtin;
 pub mod client;
 pub mod db;
 pub mod encoded;
@@ -156,7 +160,6 @@ mod cache_manager;
 mod blooms;
 mod pod_account;
 mod account_db;
-mod builtin;
 mod executive;
 mod externalities;
 mod blockchain;
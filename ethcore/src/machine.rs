@@ -305,6 +305,14 @@ impl EthereumMachine {
 			.and_then(|b| if b.is_active(block_number) { Some(b) } else { None })
 	}
 
+	/// Attempt to get a handle to a built-in contract by the name it was given in the spec.
+	/// Only returns references to activated built-ins.
+	pub fn builtin_by_name(&self, name: &str, block_number: BlockNumber) -> Option<&Builtin> {
+		self.builtins()
+			.values()
+			.find(|b| b.name() == name && b.is_active(block_number))
+	}
+
 	/// Some intrinsic operation parameters; by default they take their value from the `spec()`'s `engine_params`.
 	pub fn maximum_extra_data_size(&self) -> usize { self.params().maximum_extra_data_size }
 
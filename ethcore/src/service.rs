@@ -18,7 +18,7 @@
 
 use std::sync::Arc;
 use std::path::Path;
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use kvdb::KeyValueDB;
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use bytes::Bytes;
@@ -48,6 +48,12 @@ pub enum ClientIoMessage {
 	FeedStateChunk(H256, Bytes),
 	/// Feed a block chunk to the snapshot service
 	FeedBlockChunk(H256, Bytes),
+	/// Begin restoring the state of a checkpoint block, given with its receipts and total difficulty
+	BeginNodeRestoration(Bytes, Bytes, U256),
+	/// Move the state restoration to another checkpoint block
+	MoveNodeRestoration(Bytes, Bytes, U256),
+	/// Feed state trie nodes to the snapshot service
+	FeedStateNodes(Vec<Bytes>),
 	/// Take a snapshot for the block with given number.
 	TakeSnapshot(u64),
 	/// New consensus message received.
@@ -197,6 +203,11 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 			}
 			ClientIoMessage::FeedStateChunk(ref hash, ref chunk) => self.snapshot.feed_state_chunk(*hash, chunk),
 			ClientIoMessage::FeedBlockChunk(ref hash, ref chunk) => self.snapshot.feed_block_chunk(*hash, chunk),
+			ClientIoMessage::BeginNodeRestoration(ref block, ref receipts, td) =>
+				self.snapshot.begin_node_restore_sync(block.clone(), receipts, td),
+			ClientIoMessage::MoveNodeRestoration(ref block, ref receipts, td) =>
+				self.snapshot.move_node_restore_sync(block.clone(), receipts, td),
+			ClientIoMessage::FeedStateNodes(ref nodes) => self.snapshot.feed_state_nodes(nodes),
 			ClientIoMessage::TakeSnapshot(num) => {
 				let client = self.client.clone();
 				let snapshot = self.snapshot.clone();
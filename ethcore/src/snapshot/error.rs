@@ -63,6 +63,10 @@ pub enum Error {
 	BadEpochProof(u64),
 	/// Wrong chunk format.
 	WrongChunkFormat(String),
+	/// State trie nodes still missing.
+	MissingStateNodes(usize),
+	/// Checkpoint block can't start a chain.
+	BadCheckpoint(String),
 }
 
 impl fmt::Display for Error {
@@ -88,6 +92,8 @@ impl fmt::Display for Error {
 			Error::SnapshotsUnsupported => write!(f, "Snapshots unsupported by consensus engine."),
 			Error::BadEpochProof(i) => write!(f, "Bad epoch proof for transition to epoch {}", i),
 			Error::WrongChunkFormat(ref msg) => write!(f, "Wrong chunk format: {}", msg),
+			Error::MissingStateNodes(missing) => write!(f, "Incomplete state: {} trie nodes not found.", missing),
+			Error::BadCheckpoint(ref msg) => write!(f, "Bad checkpoint block: {}", msg),
 		}
 	}
 }
@@ -54,6 +54,7 @@ pub use self::error::Error;
 
 pub use self::consensus::*;
 pub use self::service::{Service, DatabaseRestore};
+pub use self::state_nodes::StateNodeRebuilder;
 pub use self::traits::SnapshotService;
 pub use self::watcher::Watcher;
 pub use types::snapshot_manifest::ManifestData;
@@ -67,6 +68,7 @@ mod account;
 mod block;
 mod consensus;
 mod error;
+mod state_nodes;
 mod watcher;
 
 #[cfg(test)]
@@ -23,24 +23,28 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use super::{ManifestData, StateRebuilder, Rebuilder, RestorationStatus, SnapshotService};
+use super::{ManifestData, StateRebuilder, StateNodeRebuilder, Rebuilder, RestorationStatus, SnapshotService};
 use super::io::{SnapshotReader, LooseReader, SnapshotWriter, LooseWriter};
 
 use blockchain::BlockChain;
 use client::{BlockChainClient, Client};
-use engines::EthEngine;
+use engines::{EthEngine, EpochTransition};
 use error::Error;
+use header::Header;
 use ids::BlockId;
+use receipt::Receipt;
 use service::ClientIoMessage;
 
 use io::IoChannel;
 
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
+use hash::keccak;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard};
 use util_error::UtilError;
 use bytes::Bytes;
 use journaldb::Algorithm;
 use kvdb_rocksdb::{Database, DatabaseConfig};
+use rlp::UntrustedRlp;
 use snappy;
 
 /// Helper for removing directories in case of error.
@@ -193,6 +197,133 @@ impl Restoration {
 	}
 }
 
+/// Restoration of the state at a trusted checkpoint block from individual trie nodes.
+struct NodeRestoration {
+	block: Bytes,
+	receipts: Vec<Receipt>,
+	header: Header,
+	total_difficulty: U256,
+	state: StateNodeRebuilder,
+	pruning: Algorithm,
+	guard: Guard,
+	db: Arc<Database>,
+}
+
+impl NodeRestoration {
+	// make a new node restoration of the state of the given block.
+	fn new(block: Bytes, receipts: &[u8], total_difficulty: U256, pruning: Algorithm, db_path: PathBuf, db_config: &DatabaseConfig, guard: Guard) -> Result<Self, Error> {
+		let header: Header = UntrustedRlp::new(&block).val_at(0)?;
+		let receipts = UntrustedRlp::new(receipts).as_list()?;
+
+		let raw_db = Arc::new(Database::open(db_config, &*db_path.to_string_lossy())
+			.map_err(UtilError::from)?);
+
+		Ok(NodeRestoration {
+			state: StateNodeRebuilder::new(raw_db.clone(), pruning, *header.state_root())?,
+			block: block,
+			receipts: receipts,
+			header: header,
+			total_difficulty: total_difficulty,
+			pruning: pruning,
+			guard: guard,
+			db: raw_db,
+		})
+	}
+
+	// move the restoration to the state of another block, keeping the nodes restored so far.
+	fn retarget(&mut self, block: Bytes, receipts: &[u8], total_difficulty: U256) -> Result<(), Error> {
+		let header: Header = UntrustedRlp::new(&block).val_at(0)?;
+		let receipts = UntrustedRlp::new(receipts).as_list()?;
+
+		self.state.retarget(*header.state_root())?;
+		self.block = block;
+		self.receipts = receipts;
+		self.header = header;
+		self.total_difficulty = total_difficulty;
+		Ok(())
+	}
+
+	// prove the validator set at the checkpoint from the restored state, as is done for the genesis.
+	fn epoch_proof(&self, engine: &EthEngine) -> Result<Vec<u8>, String> {
+		use journaldb;
+		use transaction::{Action, Transaction};
+
+		let header = &self.header;
+		let db = journaldb::new(self.db.clone(), self.pruning, ::db::COL_STATE);
+		let factories = Default::default();
+
+		let call = |a, d| {
+			let mut db = db.boxed_clone();
+			let env_info = ::evm::EnvInfo {
+				number: header.number(),
+				author: *header.author(),
+				timestamp: header.timestamp(),
+				difficulty: *header.difficulty(),
+				gas_limit: *header.gas_limit(),
+				last_hashes: Arc::new(Vec::new()),
+				gas_used: 0.into(),
+			};
+
+			let tx = Transaction {
+				nonce: engine.account_start_nonce(header.number()),
+				action: Action::Call(a),
+				gas: U256::from(50_000_000),
+				gas_price: U256::default(),
+				value: U256::default(),
+				data: d,
+			}.fake_sign(Default::default());
+
+			let res = ::state::prove_transaction(
+				db.as_hashdb_mut(),
+				*header.state_root(),
+				&tx,
+				engine.machine(),
+				&env_info,
+				factories.clone(),
+				true,
+			);
+
+			res.map(|(out, proof)| {
+				(out, proof.into_iter().map(|x| x.into_vec()).collect())
+			}).ok_or_else(|| "Failed to prove call: insufficient state".into())
+		};
+
+		engine.genesis_epoch_data(header, &call)
+	}
+
+	// finish up restoration: the checkpoint block becomes the best block of a chain
+	// otherwise consisting of the genesis only.
+	fn finalize(self, genesis: &[u8], engine: &EthEngine) -> Result<(), Error> {
+		if !self.state.is_done() { return Ok(()) }
+
+		self.state.finalize(self.header.number(), self.header.hash())?;
+
+		let parent_td = self.total_difficulty.checked_sub(*self.header.difficulty())
+			.ok_or_else(|| ::snapshot::Error::BadCheckpoint(format!("total difficulty {} is below the block difficulty", self.total_difficulty)))?;
+
+		// blocks after the checkpoint are verified in the epoch it starts.
+		let proof = self.epoch_proof(engine)
+			.map_err(|e| ::snapshot::Error::BadCheckpoint(format!("unable to prove the validator set: {}", e)))?;
+
+		let chain = BlockChain::new(Default::default(), genesis, self.db.clone());
+		let mut batch = self.db.transaction();
+
+		chain.insert_unordered_block(&mut batch, &self.block, self.receipts, Some(parent_td), true, false);
+		chain.insert_epoch_transition(&mut batch, self.header.number(), EpochTransition {
+			block_number: self.header.number(),
+			block_hash: self.header.hash(),
+			proof: proof,
+		});
+
+		self.db.write_buffered(batch);
+		chain.commit();
+		self.db.flush().map_err(UtilError::from)?;
+
+		self.guard.disarm();
+		Ok(())
+	}
+}
+
 /// Type alias for client io channel.
 pub type Channel = IoChannel<ClientIoMessage>;
 
@@ -219,6 +350,8 @@ pub struct ServiceParams {
 /// This controls taking snapshots and restoring from them.
 pub struct Service {
 	restoration: Mutex<Option<Restoration>>,
+	node_restoration: Mutex<Option<NodeRestoration>>,
+	pending_nodes: Mutex<HashSet<H256>>,
 	snapshot_root: PathBuf,
 	db_config: DatabaseConfig,
 	io_channel: Mutex<Channel>,
@@ -240,6 +373,8 @@ impl Service {
 	pub fn new(params: ServiceParams) -> Result<Self, Error> {
 		let mut service = Service {
 			restoration: Mutex::new(None),
+			node_restoration: Mutex::new(None),
+			pending_nodes: Mutex::new(HashSet::new()),
 			snapshot_root: params.snapshot_root,
 			db_config: params.db_config,
 			io_channel: Mutex::new(params.channel),
@@ -406,8 +541,9 @@ impl Service {
 		self.state_chunks.store(0, Ordering::SeqCst);
 		self.block_chunks.store(0, Ordering::SeqCst);
 
-		// tear down existing restoration.
+		// tear down existing restorations.
 		*res = None;
+		*self.node_restoration.lock() = None;
 
 		// delete and restore the restoration dir.
 		if let Err(e) = fs::remove_dir_all(&rest_dir) {
@@ -549,6 +685,167 @@ impl Service {
 		}
 	}
 
+	/// Initialize restoration of the state at a trusted checkpoint block from individual
+	/// trie nodes. Takes the RLP of the block and of its receipts, and the total difficulty
+	/// of the chain up to and including the block.
+	pub fn init_node_restore(&self, block: Bytes, receipts: &[u8], total_difficulty: U256) -> Result<(), Error> {
+		let rest_dir = self.restoration_dir();
+
+		let mut res = self.node_restoration.lock();
+
+		self.state_chunks.store(0, Ordering::SeqCst);
+		self.block_chunks.store(0, Ordering::SeqCst);
+
+		// tear down existing restorations.
+		*res = None;
+		*self.restoration.lock() = None;
+
+		// delete and restore the restoration dir.
+		if let Err(e) = fs::remove_dir_all(&rest_dir) {
+			match e.kind() {
+				ErrorKind::NotFound => {},
+				_ => return Err(e.into()),
+			}
+		}
+
+		fs::create_dir_all(&rest_dir)?;
+
+		*res = Some(NodeRestoration::new(block, receipts, total_difficulty, self.pruning, self.restoration_db(), &self.db_config, Guard::new(rest_dir))?);
+
+		// progress is reported in trie nodes rather than chunks.
+		*self.status.lock() = RestorationStatus::Ongoing {
+			state_chunks: 1,
+			block_chunks: 0,
+			state_chunks_done: 0,
+			block_chunks_done: 0,
+		};
+
+		self.restoring_snapshot.store(true, Ordering::SeqCst);
+		Ok(())
+	}
+
+	/// Move the ongoing node restoration to the state of another block, given the RLP of
+	/// the block and of its receipts and its total difficulty. State restored so far is kept
+	/// and only the differences are left to download. Begins a new node restoration if none
+	/// is ongoing.
+	pub fn retarget_node_restore(&self, block: Bytes, receipts: &[u8], total_difficulty: U256) -> Result<(), Error> {
+		let mut restoration = self.node_restoration.lock();
+		if restoration.is_none() {
+			drop(restoration);
+			return self.init_node_restore(block, receipts, total_difficulty);
+		}
+
+		if let Some(ref mut rest) = *restoration {
+			rest.retarget(block, receipts, total_difficulty)?;
+		}
+		self.finalize_nodes(&mut *restoration)
+	}
+
+	// feed trie nodes to the node restoration, finalizing it once the state is complete.
+	fn feed_nodes(&self, nodes: &[Bytes]) -> Result<(), Error> {
+		let mut restoration = self.node_restoration.lock();
+		if let Some(ref mut rest) = *restoration {
+			rest.state.feed(nodes, &self.restoring_snapshot)?;
+		}
+
+		{
+			let mut pending = self.pending_nodes.lock();
+			for node in nodes {
+				pending.remove(&keccak(node));
+			}
+		}
+
+		self.finalize_nodes(&mut *restoration)
+	}
+
+	// update the progress of the node restoration and finalize it if the state is complete.
+	fn finalize_nodes(&self, restoration: &mut Option<NodeRestoration>) -> Result<(), Error> {
+		let is_done = match *restoration {
+			Some(ref rest) => {
+				let (done, missing) = rest.state.progress();
+				self.state_chunks.store(done, Ordering::SeqCst);
+				if let RestorationStatus::Ongoing { ref mut state_chunks, .. } = *self.status.lock() {
+					*state_chunks = (done + missing) as u32;
+				}
+
+				rest.state.is_done()
+			}
+			None => return Ok(()),
+		};
+
+		if is_done {
+			trace!(target: "snapshot", "finalizing node restoration");
+
+			restoration.take()
+				.map(|r| r.finalize(&self.genesis_block, &*self.engine))
+				.unwrap_or(Ok(()))?;
+
+			self.replace_client_db()?;
+
+			let _ = fs::remove_dir_all(self.restoration_dir());
+			*self.status.lock() = RestorationStatus::Inactive;
+		}
+
+		Ok(())
+	}
+
+	/// Feed state trie nodes or code to be processed synchronously.
+	pub fn feed_state_nodes(&self, nodes: &[Bytes]) {
+		match self.feed_nodes(nodes) {
+			Ok(()) => (),
+			Err(e) => {
+				warn!("Encountered error during state node restoration: {}", e);
+				self.fail_node_restore();
+			}
+		}
+	}
+
+	/// Begin a node restoration synchronously, see `init_node_restore`.
+	pub fn begin_node_restore_sync(&self, block: Bytes, receipts: &[u8], total_difficulty: U256) {
+		if let Err(e) = self.init_node_restore(block, receipts, total_difficulty) {
+			warn!("Failed to initialize state node restoration: {}", e);
+			self.fail_node_restore();
+		}
+	}
+
+	/// Move a node restoration synchronously, see `retarget_node_restore`.
+	pub fn move_node_restore_sync(&self, block: Bytes, receipts: &[u8], total_difficulty: U256) {
+		if let Err(e) = self.retarget_node_restore(block, receipts, total_difficulty) {
+			warn!("Failed to move state node restoration: {}", e);
+			self.fail_node_restore();
+		}
+	}
+
+	// tear down a node restoration after an error.
+	fn fail_node_restore(&self) {
+		*self.node_restoration.lock() = None;
+		self.pending_nodes.lock().clear();
+		*self.status.lock() = RestorationStatus::Failed;
+		let _ = fs::remove_dir_all(self.restoration_dir());
+	}
+
+	// mark a node restoration as ongoing until the queued message starting it is handled.
+	fn queue_node_restore(&self, message: ClientIoMessage) {
+		self.pending_nodes.lock().clear();
+		self.restoring_snapshot.store(true, Ordering::SeqCst);
+		{
+			let mut status = self.status.lock();
+			match *status {
+				RestorationStatus::Ongoing { .. } => {},
+				_ => *status = RestorationStatus::Ongoing {
+					state_chunks: 1,
+					block_chunks: 0,
+					state_chunks_done: 0,
+					block_chunks_done: 0,
+				},
+			}
+		}
+
+		if let Err(e) = self.io_channel.lock().send(message) {
+			trace!("Error sending snapshot service message: {:?}", e);
+		}
+	}
+
 	/// Feed a block chunk to be processed synchronously.
 	pub fn feed_block_chunk(&self, hash: H256, chunk: &[u8]) {
 		match self.feed_chunk(hash, chunk, false) {
@@ -596,6 +893,8 @@ impl SnapshotService for Service {
 	fn abort_restore(&self) {
 		self.restoring_snapshot.store(false, Ordering::SeqCst);
 		*self.restoration.lock() = None;
+		*self.node_restoration.lock() = None;
+		self.pending_nodes.lock().clear();
 		*self.status.lock() = RestorationStatus::Inactive;
 	}
 
@@ -610,6 +909,32 @@ impl SnapshotService for Service {
 			trace!("Error sending snapshot service message: {:?}", e);
 		}
 	}
+
+	fn begin_node_restore(&self, block: Bytes, receipts: Bytes, total_difficulty: U256) {
+		self.queue_node_restore(ClientIoMessage::BeginNodeRestoration(block, receipts, total_difficulty));
+	}
+
+	fn move_node_restore(&self, block: Bytes, receipts: Bytes, total_difficulty: U256) {
+		self.queue_node_restore(ClientIoMessage::MoveNodeRestoration(block, receipts, total_difficulty));
+	}
+
+	fn missing_state_nodes(&self, count: usize) -> Vec<H256> {
+		let restoration = self.node_restoration.lock();
+		let pending = self.pending_nodes.lock();
+		restoration.as_ref().map_or_else(Vec::new, |r| {
+			r.state.missing(count + pending.len()).into_iter()
+				.filter(|h| !pending.contains(h))
+				.take(count)
+				.collect()
+		})
+	}
+
+	fn restore_state_nodes(&self, nodes: Vec<Bytes>) {
+		self.pending_nodes.lock().extend(nodes.iter().map(|n| keccak(n)));
+		if let Err(e) = self.io_channel.lock().send(ClientIoMessage::FeedStateNodes(nodes)) {
+			trace!("Error sending snapshot service message: {:?}", e);
+		}
+	}
 }
 
 impl Drop for Service {
@@ -0,0 +1,401 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State restoration from individual trie nodes, as served in response to `GetNodeData`.
+//!
+//! Restoration starts from a trusted state root and only accepts data whose hash
+//! has been requested, so once nothing is missing the database holds the complete
+//! state under that root.
+//!
+//! The root may be moved to a more recent block while restoring. Downloaded trie nodes
+//! are only written to the database once their whole subtrie is there, so anything in
+//! the database is complete and is never walked again. Moving the root only walks the
+//! nodes held back in memory, and only the differences are left to download. Nodes only
+//! referenced by the old root are left behind.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use account_db::{AccountDB, AccountDBMut};
+use bloom_journal::Bloom;
+use bytes::Bytes;
+use ethereum_types::H256;
+use hash::{keccak, KECCAK_NULL_RLP, KECCAK_EMPTY};
+use hashdb::HashDB;
+use journaldb::{self, Algorithm, JournalDB};
+use kvdb::{DBValue, KeyValueDB};
+use rlp::{UntrustedRlp, DecoderError};
+use state_db::StateDB;
+
+use super::{BasicAccount, Error};
+
+// What a requested hash refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeKind {
+	// account trie node found at the given nibble path.
+	Account(Vec<u8>),
+	// storage trie node of the account with the given address hash.
+	Storage(H256),
+	// code of the account with the given address hash.
+	Code(H256),
+}
+
+impl NodeKind {
+	// the key an item with the given hash is stored under, as storage and code
+	// are kept apart per account.
+	fn key(&self, hash: &H256) -> H256 {
+		match *self {
+			NodeKind::Account(_) => *hash,
+			NodeKind::Storage(ref address_hash) | NodeKind::Code(ref address_hash) => {
+				let mut key = *hash;
+				for (k, a) in key[12..].iter_mut().zip(&address_hash[12..]) {
+					*k ^= *a;
+				}
+				key
+			}
+		}
+	}
+}
+
+// A downloaded item held back until everything it references is in the database.
+struct PendingNode {
+	hash: H256,
+	data: Bytes,
+	kind: NodeKind,
+	// number of references to items not in the database yet.
+	deps: usize,
+	// keys of the pending nodes referencing this one, once per reference.
+	parents: Vec<H256>,
+	// whether the node is part of the state being restored.
+	linked: bool,
+}
+
+/// Used to rebuild the state trie from individual trie nodes and code.
+pub struct StateNodeRebuilder {
+	db: Box<JournalDB>,
+	state_root: H256,
+	missing: HashMap<H256, Vec<(NodeKind, Option<H256>)>>,
+	pending: HashMap<H256, PendingNode>,
+	bloom: Bloom,
+	nodes_done: usize,
+}
+
+impl StateNodeRebuilder {
+	/// Create a new rebuilder for the given state root, writing into the given backing DB.
+	pub fn new(db: Arc<KeyValueDB>, pruning: Algorithm, state_root: H256) -> Result<Self, ::error::Error> {
+		let mut rebuilder = StateNodeRebuilder {
+			db: journaldb::new(db.clone(), pruning, ::db::COL_STATE),
+			state_root: state_root,
+			missing: HashMap::new(),
+			pending: HashMap::new(),
+			bloom: StateDB::load_bloom(&*db),
+			nodes_done: 0,
+		};
+		rebuilder.require(state_root, NodeKind::Account(Vec::new()), None)?;
+		rebuilder.commit()?;
+		Ok(rebuilder)
+	}
+
+	/// Move the restoration to a new state root, keeping everything fed so far.
+	/// Only the parts of the new state which are not in the database yet become missing.
+	pub fn retarget(&mut self, state_root: H256) -> Result<(), ::error::Error> {
+		trace!(target: "snapshot", "moving state node restoration from {} to {}", self.state_root, state_root);
+		self.state_root = state_root;
+		self.missing.clear();
+
+		// nodes held back are linked again as they are reached from the new root.
+		for node in self.pending.values_mut() {
+			node.deps = 0;
+			node.parents.clear();
+			node.linked = false;
+		}
+
+		self.require(state_root, NodeKind::Account(Vec::new()), None)?;
+		self.pending.retain(|_, node| node.linked);
+		self.commit()
+	}
+
+	/// Get the state root being restored.
+	pub fn state_root(&self) -> H256 { self.state_root }
+
+	/// Get up to `count` hashes of trie nodes and code which have not been fed yet.
+	pub fn missing(&self, count: usize) -> Vec<H256> {
+		self.missing.keys().take(count).cloned().collect()
+	}
+
+	/// Get the number of items fed so far and the number of items known to be missing.
+	pub fn progress(&self) -> (usize, usize) { (self.nodes_done, self.missing.len()) }
+
+	/// Whether the whole state is present.
+	pub fn is_done(&self) -> bool { self.missing.is_empty() }
+
+	/// Feed raw trie nodes or code into the rebuilder. Items which were not requested
+	/// are ignored. Returns the number of useful items.
+	pub fn feed(&mut self, nodes: &[Bytes], flag: &AtomicBool) -> Result<usize, ::error::Error> {
+		let mut useful = 0;
+		for node in nodes {
+			if !flag.load(Ordering::SeqCst) { return Err(Error::RestorationAborted.into()) }
+
+			let hash = keccak(node);
+			let requests = match self.missing.remove(&hash) {
+				Some(requests) => requests,
+				None => continue,
+			};
+
+			for (kind, parent) in requests {
+				self.insert(hash, node, kind, parent)?;
+			}
+			useful += 1;
+			self.nodes_done += 1;
+		}
+
+		self.commit()?;
+		trace!(target: "snapshot", "fed {} state nodes, {} missing, {} held back", useful, self.missing.len(), self.pending.len());
+		Ok(useful)
+	}
+
+	/// Finalize the restoration. Fails if any node is still missing, otherwise makes a
+	/// dummy journal entry.
+	pub fn finalize(mut self, era: u64, id: H256) -> Result<Box<JournalDB>, ::error::Error> {
+		if !self.missing.is_empty() { return Err(Error::MissingStateNodes(self.missing.len()).into()) }
+
+		let mut batch = self.db.backing().transaction();
+		self.db.journal_under(&mut batch, era, &id)?;
+		self.db.backing().write_buffered(batch);
+
+		Ok(self.db)
+	}
+
+	// write out the completed items and the account bloom.
+	fn commit(&mut self) -> Result<(), ::error::Error> {
+		let backing = self.db.backing().clone();
+		let mut batch = backing.transaction();
+		StateDB::commit_bloom(&mut batch, self.bloom.drain_journal())?;
+		self.db.inject(&mut batch)?;
+		backing.write_buffered(batch);
+		Ok(())
+	}
+
+	// hold back an item whose hash matched a request and require whatever it references.
+	fn insert(&mut self, hash: H256, data: &[u8], kind: NodeKind, parent: Option<H256>) -> Result<(), ::error::Error> {
+		let key = kind.key(&hash);
+		if let Some(node) = self.pending.get_mut(&key) {
+			// requested through several references.
+			node.parents.extend(parent);
+			return Ok(());
+		}
+
+		self.pending.insert(key, PendingNode {
+			hash: hash,
+			data: data.to_vec(),
+			kind: kind.clone(),
+			deps: 0,
+			parents: parent.into_iter().collect(),
+			linked: true,
+		});
+		self.walk(key, data, kind)
+	}
+
+	// require the items referenced by a pending node, completing it if they are all there.
+	fn walk(&mut self, key: H256, data: &[u8], kind: NodeKind) -> Result<(), ::error::Error> {
+		// hold the node back while walking it, as references to nodes held back can
+		// complete meanwhile.
+		self.add_dep(Some(key));
+		match kind {
+			NodeKind::Account(path) => self.walk_node(key, data, path, None)?,
+			NodeKind::Storage(address_hash) => self.walk_node(key, data, Vec::new(), Some(address_hash))?,
+			NodeKind::Code(_) => {},
+		}
+
+		let done = match self.pending.get_mut(&key) {
+			Some(node) => {
+				node.deps -= 1;
+				node.deps == 0
+			}
+			None => false,
+		};
+		if done {
+			self.complete(key);
+		}
+		Ok(())
+	}
+
+	// write out a pending node whose references are all in the database, and then any
+	// parents this completes.
+	fn complete(&mut self, key: H256) {
+		let mut queue = vec![key];
+		while let Some(key) = queue.pop() {
+			let node = match self.pending.remove(&key) {
+				Some(node) => node,
+				None => continue,
+			};
+
+			match node.kind {
+				NodeKind::Account(_) =>
+					self.db.as_hashdb_mut().emplace(node.hash, DBValue::from_slice(&node.data)),
+				NodeKind::Storage(address_hash) | NodeKind::Code(address_hash) =>
+					AccountDBMut::from_hash(self.db.as_hashdb_mut(), address_hash).emplace(node.hash, DBValue::from_slice(&node.data)),
+			}
+
+			for parent in node.parents {
+				if let Some(parent_node) = self.pending.get_mut(&parent) {
+					parent_node.deps -= 1;
+					if parent_node.deps == 0 {
+						queue.push(parent);
+					}
+				}
+			}
+		}
+	}
+
+	// require the children of a trie node. `storage` is the address hash of the owning
+	// account for storage tries and `None` for the account trie.
+	fn walk_node(&mut self, key: H256, node: &[u8], path: Vec<u8>, storage: Option<H256>) -> Result<(), ::error::Error> {
+		let rlp = UntrustedRlp::new(node);
+		match rlp.item_count()? {
+			2 => {
+				let (partial, is_leaf) = decode_partial_path(rlp.at(0)?.data()?)?;
+				let mut path = path;
+				path.extend(partial);
+				match (is_leaf, storage) {
+					(true, None) => self.walk_account(key, &path, rlp.at(1)?.data()?),
+					(true, Some(_)) => Ok(()),
+					(false, _) => self.walk_child(key, rlp.at(1)?, path, storage),
+				}
+			}
+			17 => {
+				for i in 0..16 {
+					let mut child_path = path.clone();
+					child_path.push(i as u8);
+					self.walk_child(key, rlp.at(i)?, child_path, storage)?;
+				}
+				Ok(())
+			}
+			_ => Err(DecoderError::RlpIncorrectListLen.into()),
+		}
+	}
+
+	fn walk_child(&mut self, key: H256, child: UntrustedRlp, path: Vec<u8>, storage: Option<H256>) -> Result<(), ::error::Error> {
+		if child.is_empty() {
+			return Ok(());
+		}
+
+		// small nodes are inlined into their parent.
+		if child.is_list() {
+			return self.walk_node(key, child.as_raw(), path, storage);
+		}
+
+		let hash: H256 = child.as_val()?;
+		let kind = match storage {
+			Some(address_hash) => NodeKind::Storage(address_hash),
+			None => NodeKind::Account(path),
+		};
+		self.require(hash, kind, Some(key))
+	}
+
+	fn walk_account(&mut self, key: H256, path: &[u8], value: &[u8]) -> Result<(), ::error::Error> {
+		if path.len() != 64 {
+			return Err(DecoderError::Custom("Invalid account key length.").into());
+		}
+
+		let address_hash = H256::from_slice(&pack_nibbles(path));
+		let account: BasicAccount = UntrustedRlp::new(value).as_val()?;
+
+		self.bloom.set(&*address_hash);
+		self.require(account.storage_root, NodeKind::Storage(address_hash), Some(key))?;
+		if account.code_hash != KECCAK_EMPTY {
+			self.require(account.code_hash, NodeKind::Code(address_hash), Some(key))?;
+		}
+		Ok(())
+	}
+
+	// make the pending node `parent` wait for an item unless it's in the database already,
+	// requesting it if it's not held back either.
+	fn require(&mut self, hash: H256, kind: NodeKind, parent: Option<H256>) -> Result<(), ::error::Error> {
+		if hash == KECCAK_NULL_RLP {
+			return Ok(());
+		}
+
+		let key = kind.key(&hash);
+		let relink = match self.pending.get_mut(&key) {
+			Some(node) => {
+				node.parents.extend(parent);
+				let relink = !node.linked;
+				node.linked = true;
+				Some(relink)
+			}
+			None => None,
+		};
+
+		match relink {
+			Some(relink) => {
+				self.add_dep(parent);
+				// held back since before the root moved, its references are required again.
+				if relink {
+					let (data, kind) = {
+						let node = &self.pending[&key];
+						(node.data.clone(), node.kind.clone())
+					};
+					self.walk(key, &data, kind)?;
+				}
+			}
+			None => {
+				let present = match kind {
+					NodeKind::Account(_) => self.db.as_hashdb().contains(&hash),
+					NodeKind::Storage(ref address_hash) | NodeKind::Code(ref address_hash) =>
+						AccountDB::from_hash(self.db.as_hashdb(), *address_hash).contains(&hash),
+				};
+				if !present {
+					self.add_dep(parent);
+					let requests = self.missing.entry(hash).or_insert_with(Vec::new);
+					requests.push((kind, parent));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn add_dep(&mut self, parent: Option<H256>) {
+		if let Some(parent) = parent {
+			if let Some(node) = self.pending.get_mut(&parent) {
+				node.deps += 1;
+			}
+		}
+	}
+}
+
+// decode a hex-prefix encoded partial path into nibbles and the leaf flag.
+fn decode_partial_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecoderError> {
+	let first = match encoded.first() {
+		Some(first) => *first,
+		None => return Err(DecoderError::Custom("Empty trie node path.")),
+	};
+
+	let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+	if first & 0x10 != 0 {
+		nibbles.push(first & 0x0f);
+	}
+	for byte in &encoded[1..] {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+	Ok((nibbles, first & 0x20 != 0))
+}
+
+fn pack_nibbles(nibbles: &[u8]) -> Vec<u8> {
+	nibbles.chunks(2).map(|pair| (pair[0] << 4) | pair.get(1).cloned().unwrap_or(0)).collect()
+}
@@ -138,3 +138,66 @@ fn guards_delete_folders() {
 	drop(service);
 	assert!(!path.exists());
 }
+
+#[test]
+fn node_restoration_starts_chain_at_checkpoint() {
+	use blockchain::BlockChain;
+	use snapshot::RestorationStatus;
+
+	const NUM_BLOCKS: u32 = 20;
+
+	let gas_prices = vec![1.into(), 2.into()];
+	let client = generate_dummy_client_with_spec_and_data(Spec::new_null, NUM_BLOCKS, 2, &gas_prices);
+
+	let tempdir = TempDir::new("").unwrap();
+	let client_db = tempdir.path().join("client_db");
+	let path = tempdir.path().join("snapshot");
+
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client_db = Arc::new(Database::open(&db_config, client_db.to_str().unwrap()).unwrap());
+
+	let spec = Spec::new_null();
+	let client2 = Client::new(
+		Default::default(),
+		&spec,
+		client_db.clone(),
+		Arc::new(::miner::Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+
+	let service_params = ServiceParams {
+		engine: spec.engine.clone(),
+		genesis_block: spec.genesis_block(),
+		db_config: db_config,
+		pruning: ::journaldb::Algorithm::Archive,
+		channel: IoChannel::disconnected(),
+		snapshot_root: path,
+		db_restore: client2.clone(),
+	};
+
+	let service = Service::new(service_params).unwrap();
+
+	let number = NUM_BLOCKS as u64;
+	let hash = client.block_hash(BlockId::Number(number)).unwrap();
+	let block = client.block(BlockId::Hash(hash)).unwrap().into_inner();
+	let receipts = client.block_receipts(&hash).unwrap();
+	let total_difficulty = client.block_total_difficulty(BlockId::Hash(hash)).unwrap();
+
+	service.init_node_restore(block, &receipts, total_difficulty).unwrap();
+	loop {
+		let missing = service.missing_state_nodes(256);
+		if missing.is_empty() { break }
+		let nodes: Vec<_> = missing.iter().map(|h| client.state_data(h).unwrap()).collect();
+		service.feed_state_nodes(&nodes);
+	}
+
+	assert_eq!(service.status(), RestorationStatus::Inactive);
+
+	let info = client2.chain_info();
+	assert_eq!(info.best_block_hash, hash);
+	assert_eq!(info.total_difficulty, total_difficulty);
+
+	let chain = BlockChain::new(Default::default(), &spec.genesis_block(), client_db);
+	assert!(chain.epoch_transition(number, hash).is_some());
+	assert!(chain.epoch_transition(0, spec.genesis_header().hash()).is_some());
+}
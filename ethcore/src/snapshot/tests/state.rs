@@ -22,7 +22,7 @@ use hash::{KECCAK_NULL_RLP, keccak};
 
 use basic_account::BasicAccount;
 use snapshot::account;
-use snapshot::{chunk_state, Error as SnapshotError, Progress, StateRebuilder};
+use snapshot::{chunk_state, Error as SnapshotError, Progress, StateRebuilder, StateNodeRebuilder};
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 use super::helpers::{compare_dbs, StateProducer};
 
@@ -91,6 +91,175 @@ fn snap_and_restore() {
 	compare_dbs(&old_db, new_db.as_hashdb());
 }
 
+#[test]
+fn restore_from_trie_nodes() {
+	use std::collections::HashMap;
+	use hashdb::HashDB;
+
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..150 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	// serve items by the hash of their contents, like `GetNodeData` does.
+	let served: HashMap<_, _> = old_db.keys().keys()
+		.map(|key| old_db.get(key).unwrap().into_vec())
+		.map(|value| (keccak(&value), value))
+		.collect();
+
+	let tempdir = TempDir::new("").unwrap();
+	let db_path = tempdir.path().join("db");
+	let state_root = producer.state_root();
+	let db = {
+		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+		let mut rebuilder = StateNodeRebuilder::new(new_db.clone(), Algorithm::OverlayRecent, state_root).unwrap();
+		let flag = AtomicBool::new(true);
+
+		while !rebuilder.is_done() {
+			let nodes: Vec<_> = rebuilder.missing(384).iter().map(|hash| served[hash].clone()).collect();
+			assert_eq!(rebuilder.feed(&nodes, &flag).unwrap(), nodes.len());
+		}
+
+		// data that was not asked for is ignored.
+		assert_eq!(rebuilder.feed(&[b"unrequested".to_vec()], &flag).unwrap(), 0);
+		rebuilder.finalize(1000, H256::default()).unwrap();
+
+		new_db
+	};
+
+	let new_db = journaldb::new(db, Algorithm::OverlayRecent, ::db::COL_STATE);
+	assert_eq!(new_db.earliest_era(), Some(1000));
+
+	compare_dbs(&old_db, new_db.as_hashdb());
+}
+
+#[test]
+fn trie_node_restoration_incomplete() {
+	let tempdir = TempDir::new("").unwrap();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let new_db = Arc::new(Database::open(&db_cfg, tempdir.path().to_str().unwrap()).unwrap());
+
+	let rebuilder = StateNodeRebuilder::new(new_db, Algorithm::OverlayRecent, H256::random()).unwrap();
+	assert_eq!(rebuilder.progress(), (0, 1));
+
+	match rebuilder.finalize(1000, H256::default()) {
+		Err(Error::Snapshot(SnapshotError::MissingStateNodes(1))) => {},
+		_ => panic!("incomplete state finalized"),
+	}
+}
+
+#[test]
+fn retarget_trie_node_restoration() {
+	use std::collections::HashMap;
+	use account_db::AccountDB;
+	use hash::KECCAK_EMPTY;
+	use hashdb::HashDB;
+	use trie::{Trie, TrieDB};
+
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..100 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+	let old_root = producer.state_root();
+	for _ in 0..20 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+	let new_root = producer.state_root();
+	assert!(old_root != new_root);
+
+	// serves the nodes of both states.
+	let served: HashMap<_, _> = old_db.keys().keys()
+		.map(|key| old_db.get(key).unwrap().into_vec())
+		.map(|value| (keccak(&value), value))
+		.collect();
+
+	let tempdir = TempDir::new("").unwrap();
+	let db = Arc::new(Database::open(&db_cfg, tempdir.path().to_str().unwrap()).unwrap());
+	let mut rebuilder = StateNodeRebuilder::new(db.clone(), Algorithm::OverlayRecent, old_root).unwrap();
+	let flag = AtomicBool::new(true);
+
+	// restore part of the old state, then move on to the new one.
+	for _ in 0..3 {
+		let nodes: Vec<_> = rebuilder.missing(384).iter().map(|hash| served[hash].clone()).collect();
+		rebuilder.feed(&nodes, &flag).unwrap();
+	}
+	rebuilder.retarget(new_root).unwrap();
+	assert_eq!(rebuilder.state_root(), new_root);
+
+	while !rebuilder.is_done() {
+		let nodes: Vec<_> = rebuilder.missing(384).iter().map(|hash| served[hash].clone()).collect();
+		assert_eq!(rebuilder.feed(&nodes, &flag).unwrap(), nodes.len());
+	}
+	rebuilder.finalize(1000, H256::default()).unwrap();
+
+	// the whole new state is reachable.
+	let new_db = journaldb::new(db, Algorithm::OverlayRecent, ::db::COL_STATE);
+	let trie = TrieDB::new(new_db.as_hashdb(), &new_root).unwrap();
+	for item in trie.iter().unwrap() {
+		let (key, value) = item.unwrap();
+		let account: BasicAccount = ::rlp::decode(&value);
+		let account_db = AccountDB::from_hash(new_db.as_hashdb(), H256::from_slice(&key));
+		let storage = TrieDB::new(&account_db, &account.storage_root).unwrap();
+		assert!(storage.iter().unwrap().all(|item| item.is_ok()));
+		assert!(account.code_hash == KECCAK_EMPTY || account_db.contains(&account.code_hash));
+	}
+}
+
+#[test]
+fn retarget_complete_trie_node_restoration() {
+	use std::collections::HashMap;
+	use hashdb::HashDB;
+
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..100 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+	let old_root = producer.state_root();
+	for _ in 0..3 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+	let new_root = producer.state_root();
+
+	let served: HashMap<_, _> = old_db.keys().keys()
+		.map(|key| old_db.get(key).unwrap().into_vec())
+		.map(|value| (keccak(&value), value))
+		.collect();
+
+	let tempdir = TempDir::new("").unwrap();
+	let db = Arc::new(Database::open(&db_cfg, tempdir.path().to_str().unwrap()).unwrap());
+	let mut rebuilder = StateNodeRebuilder::new(db, Algorithm::OverlayRecent, old_root).unwrap();
+	let flag = AtomicBool::new(true);
+
+	while !rebuilder.is_done() {
+		let nodes: Vec<_> = rebuilder.missing(384).iter().map(|hash| served[hash].clone()).collect();
+		rebuilder.feed(&nodes, &flag).unwrap();
+	}
+	let (old_nodes, _) = rebuilder.progress();
+
+	rebuilder.retarget(new_root).unwrap();
+	while !rebuilder.is_done() {
+		let nodes: Vec<_> = rebuilder.missing(384).iter().map(|hash| served[hash].clone()).collect();
+		rebuilder.feed(&nodes, &flag).unwrap();
+	}
+	let (all_nodes, _) = rebuilder.progress();
+
+	// only the changed part of the state is downloaded again.
+	assert!(all_nodes - old_nodes < old_nodes);
+	rebuilder.finalize(1000, H256::default()).unwrap();
+}
+
 #[test]
 fn get_code_from_prev_chunk() {
 	use std::collections::HashSet;
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{ManifestData, RestorationStatus};
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use bytes::Bytes;
 
 /// The interface for a snapshot network service.
@@ -51,4 +51,22 @@ pub trait SnapshotService : Sync + Send {
 	/// Feed a raw block chunk to the service to be processed asynchronously.
 	/// no-op if currently restoring.
 	fn restore_block_chunk(&self, hash: H256, chunk: Bytes);
+
+	/// Begin restoring the state of a trusted checkpoint block from individual trie nodes,
+	/// given the RLP of the block and of its receipts, and the total difficulty of the chain
+	/// up to and including the block. Processed asynchronously.
+	/// If restoration in-progress, this will reset it.
+	fn begin_node_restore(&self, block: Bytes, receipts: Bytes, total_difficulty: U256);
+
+	/// Move the node restoration to the state of a more recent block, given as for
+	/// `begin_node_restore`. Everything restored so far is kept.
+	/// Begins a new node restoration if none is in progress.
+	fn move_node_restore(&self, block: Bytes, receipts: Bytes, total_difficulty: U256);
+
+	/// Get up to `count` hashes of trie nodes or code still missing from the node restoration.
+	fn missing_state_nodes(&self, count: usize) -> Vec<H256>;
+
+	/// Feed trie nodes or code to the node restoration, to be processed asynchronously.
+	/// no-op if not currently restoring from nodes.
+	fn restore_state_nodes(&self, nodes: Vec<Bytes>);
 }
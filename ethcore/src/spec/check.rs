@@ -0,0 +1,463 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain spec validation.
+//!
+//! Checks a JSON chain spec for mistakes which would otherwise surface as a panic at startup
+//! or as a silently misconfigured chain, and reports all of them with their JSON paths.
+//! Settings which are valid but most likely unintended are reported as warnings.
+
+use std::{env, fmt};
+use ethereum_types::{H160, H256, U256};
+use ethjson;
+use ethjson::spec::{BlockReward, Engine, Pricing, StepDuration, ValidatorSet};
+use ethjson::uint::Uint;
+use rustc_hex::ToHex;
+use serde_ignored;
+use serde_json;
+use serde_path_to_error;
+use builtin::is_builtin_name;
+use super::Spec;
+
+/// How severe a problem found in a chain spec is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+	/// The spec can't be used as it is.
+	Error,
+	/// The spec can be used, but most likely doesn't do what was intended.
+	Warning,
+}
+
+/// A problem found in a chain spec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecProblem {
+	/// JSON path of the offending value, e.g. `engine.Ethash.params.eip150Transition`.
+	/// Empty if the problem concerns the spec as a whole.
+	pub path: String,
+	/// Description of the problem.
+	pub message: String,
+	/// Whether the problem prevents using the spec.
+	pub severity: Severity,
+}
+
+impl SpecProblem {
+	fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+		SpecProblem {
+			path: path.into(),
+			message: message.into(),
+			severity: Severity::Error,
+		}
+	}
+
+	fn warning<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+		SpecProblem {
+			severity: Severity::Warning,
+			..SpecProblem::new(path, message)
+		}
+	}
+
+	/// Whether the problem prevents using the spec.
+	pub fn is_error(&self) -> bool {
+		self.severity == Severity::Error
+	}
+}
+
+impl fmt::Display for SpecProblem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.severity == Severity::Warning {
+			write!(f, "warning: ")?;
+		}
+
+		if self.path.is_empty() {
+			write!(f, "{}", self.message)
+		} else {
+			write!(f, "{}: {}", self.path, self.message)
+		}
+	}
+}
+
+/// Check a JSON chain spec and return all problems found in it.
+///
+/// The spec is only built once its JSON is free of errors which would make building it
+/// panic. Building it runs the genesis constructors, whose state root is compared with
+/// the one given in the spec; the services started by its engine are stopped afterwards.
+pub fn check_spec(json: &[u8]) -> Vec<SpecProblem> {
+	let mut problems = Vec::new();
+
+	let mut track = serde_path_to_error::Track::new();
+	let spec: Result<ethjson::spec::Spec, _> = {
+		let mut deserializer = serde_json::Deserializer::from_slice(json);
+		let deserializer = serde_path_to_error::Deserializer::new(&mut deserializer, &mut track);
+		serde_ignored::deserialize(deserializer, |field| {
+			problems.push(SpecProblem::new(field.to_string(), "unknown field"));
+		})
+	};
+
+	let spec = match spec {
+		Ok(spec) => spec,
+		Err(e) => {
+			// the root of the document is displayed as `.`.
+			let path = track.path().to_string();
+			let path = if path == "." { String::new() } else { path };
+			problems.push(SpecProblem::new(path, e.to_string()));
+			return problems;
+		}
+	};
+
+	check_engine(&spec.engine, "engine", true, &mut problems);
+	check_params(&spec.params, &spec.engine, &mut problems);
+	check_builtins(&spec.accounts, &mut problems);
+
+	if problems.iter().any(SpecProblem::is_error) {
+		return problems;
+	}
+
+	let expected_root: Option<H256> = spec.genesis.state_root.clone().map(Into::into);
+	let spec = match Spec::load(&env::temp_dir(), json) {
+		Ok(spec) => spec,
+		Err(e) => {
+			problems.push(SpecProblem::new("", format!("failed to build the spec: {}", e)));
+			return problems;
+		}
+	};
+	spec.engine.stop();
+
+	if let Some(expected_root) = expected_root {
+		match spec.recompute_state_root() {
+			Ok(ref root) if *root == expected_root => {},
+			Ok(root) => problems.push(SpecProblem::new("genesis.stateRoot", format!(
+				"genesis state root mismatch: expected {:?}, the accounts and constructors give {:?}", expected_root, root
+			))),
+			Err(e) => problems.push(SpecProblem::new("accounts", format!("failed to build the genesis state: {}", e))),
+		}
+	}
+
+	problems
+}
+
+fn is_zero(value: &Uint) -> bool {
+	value.0.is_zero()
+}
+
+fn check_nonzero(value: Option<&Uint>, path: &str, problems: &mut Vec<SpecProblem>) {
+	if value.map_or(false, is_zero) {
+		problems.push(SpecProblem::new(path, "must not be zero"));
+	}
+}
+
+fn check_engine(engine: &Engine, path: &str, first: bool, problems: &mut Vec<SpecProblem>) {
+	match *engine {
+		Engine::Null(_) | Engine::InstantSeal => {},
+		Engine::Ethash(ref ethash) => {
+			let path = format!("{}.Ethash.params", path);
+			if !first {
+				problems.push(SpecProblem::new(path.clone(), "ethash can only seal from the genesis block"));
+			}
+
+			let p = &ethash.params;
+			check_nonzero(Some(&p.difficulty_bound_divisor), &format!("{}.difficultyBoundDivisor", path), problems);
+			check_nonzero(p.difficulty_increment_divisor.as_ref(), &format!("{}.difficultyIncrementDivisor", path), problems);
+			check_nonzero(p.metropolis_difficulty_increment_divisor.as_ref(), &format!("{}.metropolisDifficultyIncrementDivisor", path), problems);
+			check_nonzero(p.difficulty_hardfork_bound_divisor.as_ref(), &format!("{}.difficultyHardforkBoundDivisor", path), problems);
+			check_nonzero(p.ecip1017_era_rounds.as_ref(), &format!("{}.ecip1017EraRounds", path), problems);
+
+			if let (Some(pause), Some(cont)) = (p.ecip1010_pause_transition.as_ref(), p.ecip1010_continue_transition.as_ref()) {
+				if cont.0 < pause.0 {
+					problems.push(SpecProblem::new(
+						format!("{}.ecip1010ContinueTransition", path),
+						format!("must not precede ecip1010PauseTransition ({})", pause.0),
+					));
+				}
+			}
+
+			if let Some(BlockReward::Multi(ref rewards)) = p.block_reward {
+				if !rewards.contains_key(&Uint(U256::zero())) {
+					problems.push(SpecProblem::new(format!("{}.blockReward", path), "no block reward for block 0"));
+				}
+			}
+
+			if p.eip649_transition.is_some() {
+				problems.push(SpecProblem::warning(
+					format!("{}.eip649Transition", path),
+					"deprecated, use blockReward and difficultyBombDelays transitions instead",
+				));
+			} else {
+				for &(name, value) in &[("eip649Delay", &p.eip649_delay), ("eip649Reward", &p.eip649_reward)] {
+					if value.is_some() {
+						problems.push(SpecProblem::warning(format!("{}.{}", path, name), "ignored without eip649Transition"));
+					}
+				}
+			}
+		},
+		Engine::BasicAuthority(ref basic) => {
+			let path = format!("{}.basicAuthority.params", path);
+			check_validators(&basic.params.validators, &format!("{}.validators", path), problems);
+		},
+		Engine::AuthorityRound(ref aura) => {
+			let path = format!("{}.authorityRound.params", path);
+			let p = &aura.params;
+			let durations_path = format!("{}.stepDuration", path);
+			let durations: Vec<(String, &Uint)> = match p.step_duration {
+				StepDuration::Single(ref duration) => vec![(durations_path, duration)],
+				StepDuration::Transitions(ref durations) => {
+					if !durations.contains_key(&Uint(U256::zero())) {
+						problems.push(SpecProblem::new(durations_path.clone(), "no step duration for block 0"));
+					}
+					durations.iter().map(|(block, duration)| (format!("{}.{}", durations_path, block.0), duration)).collect()
+				},
+			};
+			for (path, duration) in durations {
+				if is_zero(duration) {
+					problems.push(SpecProblem::new(path, "step duration must not be zero"));
+				} else if duration.0 > U256::from(u16::max_value()) {
+					problems.push(SpecProblem::new(path, format!("step duration exceeds {} seconds", u16::max_value())));
+				}
+			}
+
+			if p.block_reward_contract_transition.is_some() && p.block_reward_contract_address.is_none() {
+				problems.push(SpecProblem::new(
+					format!("{}.blockRewardContractTransition", path),
+					"given without blockRewardContractAddress",
+				));
+			}
+			check_validators(&p.validators, &format!("{}.validators", path), problems);
+		},
+		Engine::Tendermint(ref tendermint) => {
+			let path = format!("{}.tendermint.params", path);
+			let p = &tendermint.params;
+			check_nonzero(p.timeout_propose.as_ref(), &format!("{}.timeoutPropose", path), problems);
+			check_nonzero(p.timeout_prevote.as_ref(), &format!("{}.timeoutPrevote", path), problems);
+			check_nonzero(p.timeout_precommit.as_ref(), &format!("{}.timeoutPrecommit", path), problems);
+			check_nonzero(p.timeout_commit.as_ref(), &format!("{}.timeoutCommit", path), problems);
+			check_validators(&p.validators, &format!("{}.validators", path), problems);
+		},
+		Engine::Clique(ref clique) => {
+			check_nonzero(clique.params.epoch.as_ref(), &format!("{}.clique.params.epoch", path), problems);
+		},
+		Engine::Multi(ref engines) => {
+			let path = format!("{}.multi", path);
+			if !engines.contains_key(&Uint(U256::zero())) {
+				problems.push(SpecProblem::new(path.clone(), "no engine for block 0"));
+			}
+			for (block, engine) in engines {
+				check_engine(engine, &format!("{}.{}", path, block.0), first && block.0.is_zero(), problems);
+			}
+		},
+	}
+}
+
+fn check_validators(validators: &ValidatorSet, path: &str, problems: &mut Vec<SpecProblem>) {
+	match *validators {
+		ValidatorSet::List(ref list) if list.is_empty() => {
+			problems.push(SpecProblem::new(format!("{}.list", path), "no validators"));
+		},
+		ValidatorSet::Multi(ref sets) => {
+			let path = format!("{}.multi", path);
+			if !sets.contains_key(&Uint(U256::zero())) {
+				problems.push(SpecProblem::new(path.clone(), "no validator set for block 0"));
+			}
+			for (block, set) in sets {
+				check_validators(set, &format!("{}.{}", path, block.0), problems);
+			}
+		},
+		_ => {},
+	}
+}
+
+// Fork transitions, grouped by hard fork. A transition preceding one of an earlier fork is
+// unusual, but valid.
+const FORKS: &'static [&'static [&'static str]] = &[
+	&["homesteadTransition"],
+	&["eip150Transition"],
+	&["eip155Transition", "eip160Transition", "eip161abcTransition", "eip161dTransition"],
+	&["eip100bTransition", "eip140Transition", "eip211Transition", "eip214Transition", "eip658Transition"],
+];
+
+fn transition<'a>(path: &str, name: &'static str, value: &'a Option<Uint>) -> Option<(String, &'static str, &'a Uint)> {
+	value.as_ref().map(|value| (format!("{}.{}", path, name), name, value))
+}
+
+fn check_params(params: &ethjson::spec::Params, engine: &Engine, problems: &mut Vec<SpecProblem>) {
+	check_nonzero(Some(&params.gas_limit_bound_divisor), "params.gasLimitBoundDivisor", problems);
+	check_nonzero(params.wasm_max_memory_pages.as_ref(), "params.wasmMaxMemoryPages", problems);
+	if params.wasm != Some(true) {
+		let wasm_params = [
+			("wasmValidationTransition", &params.wasm_validation_transition),
+			("wasmMaxMemoryPages", &params.wasm_max_memory_pages),
+			("wasmBuiltinsTransition", &params.wasm_builtins_transition),
+		];
+		for &(name, value) in &wasm_params {
+			if value.is_some() {
+				problems.push(SpecProblem::warning(format!("params.{}", name), "ignored without wasm"));
+			}
+		}
+	}
+
+	let mut transitions: Vec<_> = vec![
+		transition("params", "eip155Transition", &params.eip155_transition),
+		transition("params", "eip140Transition", &params.eip140_transition),
+		transition("params", "eip211Transition", &params.eip211_transition),
+		transition("params", "eip214Transition", &params.eip214_transition),
+		transition("params", "eip658Transition", &params.eip658_transition),
+	];
+
+	let ethash = match *engine {
+		Engine::Ethash(ref ethash) => Some(("engine.Ethash.params", ethash)),
+		Engine::Multi(ref engines) => match engines.get(&Uint(U256::zero())) {
+			Some(&Engine::Ethash(ref ethash)) => Some(("engine.multi.0.Ethash.params", ethash)),
+			_ => None,
+		},
+		_ => None,
+	};
+	if let Some((path, ethash)) = ethash {
+		let p = &ethash.params;
+		transitions.extend(vec![
+			transition(path, "homesteadTransition", &p.homestead_transition),
+			transition(path, "eip150Transition", &p.eip150_transition),
+			transition(path, "eip160Transition", &p.eip160_transition),
+			transition(path, "eip161abcTransition", &p.eip161abc_transition),
+			transition(path, "eip161dTransition", &p.eip161d_transition),
+			transition(path, "eip100bTransition", &p.eip100b_transition),
+		]);
+	}
+	let transitions: Vec<_> = transitions.into_iter().filter_map(|t| t).collect();
+
+	let fork_of = |name: &'static str| FORKS.iter().position(|fork| fork.contains(&name)).expect("all noted transitions are listed in FORKS; qed");
+	for &(ref path, name, value) in &transitions {
+		let earlier = transitions.iter()
+			.filter(|&&(_, other, other_value)| fork_of(other) < fork_of(name) && other_value.0 > value.0)
+			.max_by_key(|&&(_, _, other_value)| other_value.0);
+
+		if let Some(&(_, other, other_value)) = earlier {
+			problems.push(SpecProblem::warning(path.clone(), format!(
+				"transition at block {} precedes {} at block {}", value.0, other, other_value.0
+			)));
+		}
+	}
+}
+
+fn check_builtins(accounts: &ethjson::spec::State, problems: &mut Vec<SpecProblem>) {
+	for (address, builtin) in accounts.builtins() {
+		let address: H160 = address.into();
+		let path = format!("accounts.0x{}.builtin", address.to_hex());
+
+		if !is_builtin_name(&builtin.name) {
+			problems.push(SpecProblem::new(format!("{}.name", path), format!("unknown builtin `{}`", builtin.name)));
+		}
+
+		match builtin.pricing {
+			Pricing::Modexp(ref modexp) if modexp.divisor == 0 =>
+				problems.push(SpecProblem::new(format!("{}.pricing.modexp.divisor", path), "must not be zero")),
+			Pricing::Blake2F(ref blake2_f) if blake2_f.gas_per_round == 0 =>
+				problems.push(SpecProblem::new(format!("{}.pricing.blake2_f.gas_per_round", path), "must not be zero")),
+			_ => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_spec, Severity};
+
+	#[test]
+	fn bundled_specs_are_valid() {
+		let specs: &[&[u8]] = &[
+			include_bytes!("../../res/ethereum/foundation.json"),
+			include_bytes!("../../res/basic_authority.json"),
+			include_bytes!("../../res/authority_round.json"),
+			include_bytes!("../../res/tendermint.json"),
+			include_bytes!("../../res/multi_engine.json"),
+		];
+
+		for spec in specs {
+			assert_eq!(check_spec(spec), vec![]);
+		}
+	}
+
+	#[test]
+	fn reports_problems_with_paths() {
+		let spec = include_str!("../../res/basic_authority.json")
+			.replace("\"ecrecover\"", "\"ecrecovr\"")
+			.replace("\"eip140Transition\": \"0x0\"", "\"eip140Transition\": \"0x0\", \"eip155Transition\": \"0x10\"")
+			.replace("\"list\": [\"0x9cce34f7ab185c7aba1b7c8140d620b4bda941d6\"]", "\"list\": []")
+			.replace("\"minGasLimit\"", "\"minGasLimt\": \"0x1388\", \"minGasLimit\"");
+
+		let problems: Vec<_> = check_spec(spec.as_bytes()).into_iter().map(|problem| (problem.path, problem.severity)).collect();
+		assert_eq!(problems, vec![
+			("params.minGasLimt".to_owned(), Severity::Error),
+			("engine.basicAuthority.params.validators.list".to_owned(), Severity::Error),
+			("params.eip140Transition".to_owned(), Severity::Warning),
+			("params.eip211Transition".to_owned(), Severity::Warning),
+			("params.eip214Transition".to_owned(), Severity::Warning),
+			("params.eip658Transition".to_owned(), Severity::Warning),
+			("accounts.0x0000000000000000000000000000000000000001.builtin.name".to_owned(), Severity::Error),
+		]);
+	}
+
+	#[test]
+	fn reports_invalid_values_with_paths() {
+		let spec = include_str!("../../res/basic_authority.json")
+			.replace("\"durationLimit\": \"0x0d\"", "\"durationLimit\": [13]");
+
+		let problems = check_spec(spec.as_bytes());
+		assert_eq!(problems.len(), 1);
+		assert_eq!(problems[0].path, "engine.basicAuthority.params.durationLimit");
+	}
+
+	#[test]
+	fn reports_zero_builtin_pricing() {
+		let spec = include_str!("../../res/basic_authority.json")
+			.replace("\"divisor\": 20", "\"divisor\": 0")
+			.replace(
+				"\"0000000000000000000000000000000000000008\"",
+				"\"0000000000000000000000000000000000000009\": { \"balance\": \"1\", \"builtin\": { \"name\": \"blake2_f\", \"activate_at\": 0, \"pricing\": { \"blake2_f\": { \"gas_per_round\": 0 } } } },\n\t\t\"0000000000000000000000000000000000000008\"",
+			);
+
+		let problems: Vec<_> = check_spec(spec.as_bytes()).into_iter().map(|problem| (problem.path, problem.severity)).collect();
+		assert_eq!(problems, vec![
+			("accounts.0x0000000000000000000000000000000000000005.builtin.pricing.modexp.divisor".to_owned(), Severity::Error),
+			("accounts.0x0000000000000000000000000000000000000009.builtin.pricing.blake2_f.gas_per_round".to_owned(), Severity::Error),
+		]);
+	}
+
+	#[test]
+	fn builds_specs_with_warnings_only() {
+		let spec = include_str!("../../res/basic_authority.json")
+			.replace("\"eip140Transition\": \"0x0\"", "\"eip140Transition\": \"0x0\", \"eip155Transition\": \"0x10\"")
+			.replace(
+				"\"gasLimit\": \"0x2fefd8\"",
+				"\"gasLimit\": \"0x2fefd8\", \"stateRoot\": \"0x0000000000000000000000000000000000000000000000000000000000000001\"",
+			);
+
+		let problems = check_spec(spec.as_bytes());
+		assert!(problems.iter().any(|problem| problem.severity == Severity::Warning));
+		assert_eq!(problems.iter().filter(|problem| problem.is_error()).map(|problem| problem.path.clone()).collect::<Vec<_>>(), vec![
+			"genesis.stateRoot".to_owned(),
+		]);
+	}
+
+	#[test]
+	fn reports_state_root_mismatch() {
+		let spec = include_str!("../../res/basic_authority.json").replace(
+			"\"gasLimit\": \"0x2fefd8\"",
+			"\"gasLimit\": \"0x2fefd8\", \"stateRoot\": \"0x0000000000000000000000000000000000000000000000000000000000000001\"",
+		);
+
+		let problems = check_spec(spec.as_bytes());
+		assert_eq!(problems.len(), 1);
+		assert_eq!(problems[0].path, "genesis.stateRoot");
+	}
+}
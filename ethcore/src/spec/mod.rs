@@ -16,9 +16,11 @@
 
 //! Blockchain params.
 
+mod check;
 mod genesis;
 mod seal;
 pub mod spec;
 
+pub use self::check::{check_spec, Severity, SpecProblem};
 pub use self::spec::*;
 pub use self::genesis::Genesis;
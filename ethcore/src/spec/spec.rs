@@ -34,8 +34,8 @@ use vm::{EnvInfo, CallType, ActionValue, ActionParams, ParamsType};
 use super::genesis::Genesis;
 use super::seal::Generic as GenericSeal;
 
-use builtin::Builtin;
-use engines::{EthEngine, NullEngine, InstantSeal, BasicAuthority, AuthorityRound, Tendermint, DEFAULT_BLOCKHASH_CONTRACT};
+use builtin::{Builtin, is_builtin_name};
+use engines::{EthEngine, NullEngine, InstantSeal, BasicAuthority, AuthorityRound, AuthorityRoundParams, Tendermint, Clique, MultiEngine, DEFAULT_BLOCKHASH_CONTRACT};
 use error::Error;
 use executive::Executive;
 use factory::Factories;
@@ -59,8 +59,7 @@ fn fmt_err<F: ::std::fmt::Display>(f: F) -> String {
 ///
 /// we define a "bugfix" hard fork as any hard fork which
 /// you would put on-by-default in a new chain.
-#[derive(Debug, PartialEq, Default)]
-#[cfg_attr(test, derive(Clone))]
+#[derive(Debug, PartialEq, Default, Clone)]
 pub struct CommonParams {
 	/// Account start nonce.
 	pub account_start_nonce: U256,
@@ -111,6 +110,12 @@ pub struct CommonParams {
 	pub remove_dust_contracts: bool,
 	/// Wasm support
 	pub wasm: bool,
+	/// Number of first block where wasm contract code is validated at deployment.
+	pub wasm_validation_transition: BlockNumber,
+	/// Maximum number of initial 64KiB linear memory pages a wasm contract may declare.
+	pub wasm_max_memory_pages: Option<u32>,
+	/// Number of first block where wasm externals backed by builtin contracts are available.
+	pub wasm_builtins_transition: BlockNumber,
 	/// Gas limit bound divisor (how much gas limit can change per block)
 	pub gas_limit_bound_divisor: U256,
 	/// Registrar contract address.
@@ -137,6 +142,11 @@ impl CommonParams {
 		schedule.have_revert = block_number >= self.eip140_transition;
 		schedule.have_static_call = block_number >= self.eip214_transition;
 		schedule.have_return_data = block_number >= self.eip211_transition;
+		schedule.wasm_validation = block_number >= self.wasm_validation_transition;
+		if let Some(pages) = self.wasm_max_memory_pages {
+			schedule.wasm.max_memory_pages = pages;
+		}
+		schedule.wasm.builtins = block_number >= self.wasm_builtins_transition;
 		if block_number >= self.eip210_transition {
 			schedule.blockhash_gas = 800;
 		}
@@ -221,6 +231,18 @@ impl From<ethjson::spec::Params> for CommonParams {
 			nonce_cap_increment: p.nonce_cap_increment.map_or(64, Into::into),
 			remove_dust_contracts: p.remove_dust_contracts.unwrap_or(false),
 			wasm: p.wasm.unwrap_or(false),
+			wasm_validation_transition: p.wasm_validation_transition.map_or(
+				BlockNumber::max_value(),
+				Into::into,
+			),
+			wasm_max_memory_pages: p.wasm_max_memory_pages.map(|pages| {
+				let pages: u64 = pages.into();
+				pages as u32
+			}),
+			wasm_builtins_transition: p.wasm_builtins_transition.map_or(
+				BlockNumber::max_value(),
+				Into::into,
+			),
 			gas_limit_bound_divisor: p.gas_limit_bound_divisor.into(),
 			registrar: p.registrar.map_or_else(Address::new, Into::into),
 			node_permission_contract: p.node_permission_contract.map(Into::into),
@@ -337,27 +359,43 @@ impl Clone for Spec {
 	}
 }
 
-fn load_machine_from(s: ethjson::spec::Spec) -> EthereumMachine {
-	let builtins = s.accounts.builtins().into_iter().map(|p| (p.0.into(), From::from(p.1))).collect();
+/// Make sure every builtin the spec refers to is known, either as an Ethereum builtin or
+/// one registered with `builtin::register_builtin`.
+fn check_builtins(s: ethjson::spec::Spec) -> Result<ethjson::spec::Spec, String> {
+	for (address, builtin) in s.accounts.builtins() {
+		if !is_builtin_name(&builtin.name) {
+			let address: Address = address.into();
+			return Err(fmt_err(format!("unknown builtin `{}` at {:?}", builtin.name, address)));
+		}
+	}
+	Ok(s)
+}
+
+fn load_machine_from(s: ethjson::spec::Spec) -> Result<EthereumMachine, Error> {
+	let builtins = load_builtins(&s.accounts.builtins())?;
 	let params = CommonParams::from(s.params);
 
-	Spec::machine(&s.engine, params, builtins)
+	Ok(Spec::machine(&s.engine, params, builtins))
+}
+
+fn load_builtins(builtins: &BTreeMap<ethjson::hash::Address, ethjson::spec::Builtin>) -> Result<BTreeMap<Address, Builtin>, Error> {
+	builtins.iter().map(|(address, builtin)| {
+		Builtin::from_json(builtin.clone())
+			.map(|b| (address.clone().into(), b))
+			.map_err(|_| Error::UnknownBuiltinName(builtin.name.clone()))
+	}).collect()
 }
 
 /// Load from JSON object.
 fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec) -> Result<Spec, Error> {
-	let builtins = s.accounts
-		.builtins()
-		.into_iter()
-		.map(|p| (p.0.into(), From::from(p.1)))
-		.collect();
+	let builtins = s.accounts.builtins();
 	let g = Genesis::from(s.genesis);
 	let GenericSeal(seal_rlp) = g.seal.into();
 	let params = CommonParams::from(s.params);
 
 	let mut s = Spec {
 		name: s.name.clone().into(),
-		engine: Spec::engine(spec_params, s.engine, params, builtins),
+		engine: Spec::engine(spec_params, s.engine, 0, params, &builtins)?,
 		data_dir: s.data_dir.unwrap_or(s.name).into(),
 		nodes: s.nodes.unwrap_or_else(Vec::new),
 		parent_hash: g.parent_hash,
@@ -417,33 +455,54 @@ impl Spec {
 		params: CommonParams,
 		builtins: BTreeMap<Address, Builtin>,
 	) -> EthereumMachine {
-		if let ethjson::spec::Engine::Ethash(ref ethash) = *engine_spec {
-			EthereumMachine::with_ethash_extensions(params, builtins, ethash.params.clone().into())
-		} else {
-			EthereumMachine::regular(params, builtins)
+		match *engine_spec {
+			ethjson::spec::Engine::Ethash(ref ethash) =>
+				EthereumMachine::with_ethash_extensions(params, builtins, ethash.params.clone().into()),
+			// the state machine of a multi-stage chain is the one of its genesis engine.
+			ethjson::spec::Engine::Multi(ref engines) => match engines.values().next() {
+				Some(first) => Self::machine(first, params, builtins),
+				None => EthereumMachine::regular(params, builtins),
+			},
+			_ => EthereumMachine::regular(params, builtins),
 		}
 	}
 
-	/// Convert engine spec into a arc'd Engine of the right underlying type.
+	/// Convert engine spec into a arc'd Engine of the right underlying type, sealing blocks
+	/// from `stage_start` on.
 	/// TODO avoid this hard-coded nastiness - use dynamic-linked plugin framework instead.
 	fn engine(
 		spec_params: SpecParams,
 		engine_spec: ethjson::spec::Engine,
+		stage_start: BlockNumber,
 		params: CommonParams,
-		builtins: BTreeMap<Address, Builtin>,
-	) -> Arc<EthEngine> {
-		let machine = Self::machine(&engine_spec, params, builtins);
+		builtins: &BTreeMap<ethjson::hash::Address, ethjson::spec::Builtin>,
+	) -> Result<Arc<EthEngine>, Error> {
+		let machine = Self::machine(&engine_spec, params.clone(), load_builtins(builtins)?);
 
-		match engine_spec {
+		let engine: Arc<EthEngine> = match engine_spec {
 			ethjson::spec::Engine::Null(null) => Arc::new(NullEngine::new(null.params.into(), machine)),
 			ethjson::spec::Engine::Ethash(ethash) => Arc::new(::ethereum::Ethash::new(spec_params.cache_dir, ethash.params.into(), machine, spec_params.optimization_setting)),
 			ethjson::spec::Engine::InstantSeal => Arc::new(InstantSeal::new(machine)),
 			ethjson::spec::Engine::BasicAuthority(basic_authority) => Arc::new(BasicAuthority::new(basic_authority.params.into(), machine)),
-			ethjson::spec::Engine::AuthorityRound(authority_round) => AuthorityRound::new(authority_round.params.into(), machine)
-				.expect("Failed to start AuthorityRound consensus engine."),
-			ethjson::spec::Engine::Tendermint(tendermint) => Tendermint::new(tendermint.params.into(), machine)
-				.expect("Failed to start the Tendermint consensus engine."),
-		}
+			ethjson::spec::Engine::AuthorityRound(authority_round) => {
+				let mut params: AuthorityRoundParams = authority_round.params.into();
+				params.stage_start = stage_start;
+				AuthorityRound::new(params, machine)?
+			},
+			ethjson::spec::Engine::Tendermint(tendermint) => Tendermint::new(tendermint.params.into(), machine)?,
+			ethjson::spec::Engine::Clique(clique) => Clique::new(clique.params.into(), machine)?,
+			ethjson::spec::Engine::Multi(engines) => {
+				let engines = engines.into_iter()
+					.map(|(block, engine_spec)| {
+						let block: BlockNumber = block.into();
+						Ok((block, Self::engine(spec_params, engine_spec, block, params.clone(), builtins)?))
+					})
+					.collect::<Result<_, Error>>()?;
+				Arc::new(MultiEngine::new(engines, machine))
+			},
+		};
+
+		Ok(engine)
 	}
 
 	// given a pre-constructor state, run all the given constructors and produce a new state and
@@ -633,6 +692,13 @@ impl Spec {
 		*self.state_root_memo.read() == self.genesis_state.root()
 	}
 
+	/// Compute the genesis state root from the genesis accounts and constructors, ignoring
+	/// a state root given in the chain spec. The result replaces the memoized state root.
+	pub fn recompute_state_root(&self) -> Result<H256, Error> {
+		let _ = self.run_constructors(&Default::default(), BasicBackend(MemoryDB::new()))?;
+		Ok(self.state_root())
+	}
+
 	/// Ensure that the given state DB has the trie nodes in for the genesis state.
 	pub fn ensure_db_good<T: Backend>(&self, db: T, factories: &Factories) -> Result<T, Error> {
 		if db.as_hashdb().contains(&self.state_root()) {
@@ -649,7 +715,8 @@ impl Spec {
 	pub fn load_machine<R: Read>(reader: R) -> Result<EthereumMachine, String> {
 		ethjson::spec::Spec::load(reader)
 			.map_err(fmt_err)
-			.map(load_machine_from)
+			.and_then(check_builtins)
+			.and_then(|s| load_machine_from(s).map_err(fmt_err))
 
 	}
 
@@ -659,7 +726,7 @@ impl Spec {
 	where
 		R: Read,
 	{
-		ethjson::spec::Spec::load(reader).map_err(fmt_err).and_then(
+		ethjson::spec::Spec::load(reader).map_err(fmt_err).and_then(check_builtins).and_then(
 			|x| {
 				load_from(params.into(), x).map_err(fmt_err)
 			},
@@ -744,6 +811,11 @@ impl Spec {
 		load_bundled!("null")
 	}
 
+	/// Create a new Spec which is a NullEngine consensus with Byzantium rules and wasm contracts enabled.
+	pub fn new_wasm_test() -> Spec {
+		load_bundled!("wasm_test")
+	}
+
 	/// Create a new Spec which constructs a contract at address 5 with storage at 0 equal to 1.
 	pub fn new_test_constructor() -> Spec {
 		load_bundled!("constructor")
@@ -755,6 +827,12 @@ impl Spec {
 		load_bundled!("instant_seal")
 	}
 
+	/// Create a new Spec with Ethash consensus and a minimum difficulty of 1, so that any
+	/// nonce is a valid proof of work.
+	pub fn new_test_ethash() -> Self {
+		load_bundled!("ethash_trivial")
+	}
+
 	/// Create a new Spec with AuthorityRound consensus which does internal sealing (not
 	/// requiring work).
 	/// Accounts with secrets keccak("0") and keccak("1") are the validators.
@@ -762,6 +840,12 @@ impl Spec {
 		load_bundled!("authority_round")
 	}
 
+	/// Create a new Spec with AuthorityRound consensus whose block
+	/// rewards are determined by a contract at address 0x42, which always rewards 0x20 with 5 wei.
+	pub fn new_test_round_block_reward_contract() -> Self {
+		load_bundled!("authority_round_block_reward_contract")
+	}
+
 	/// Create a new Spec with Tendermint consensus which does internal sealing (not requiring
 	/// work).
 	/// Account keccak("0") and keccak("1") are a authorities.
@@ -769,6 +853,27 @@ impl Spec {
 		load_bundled!("tendermint")
 	}
 
+	/// Create a new Spec which switches consensus engines at block 3.
+	/// BasicAuthority with account keccak("") as the only authority seals blocks 1 and 2,
+	/// AuthorityRound with validators keccak("0") and keccak("1") the blocks from 3 on.
+	pub fn new_test_multi_engine() -> Self {
+		load_bundled!("multi_engine")
+	}
+
+	/// Create a new Spec which switches from Ethash to AuthorityRound at block 3.
+	/// Blocks 1 and 2 are mined with the minimum difficulty of 1, AuthorityRound with
+	/// validators keccak("0") and keccak("1") seals the blocks from 3 on.
+	pub fn new_test_multi_engine_ethash() -> Self {
+		load_bundled!("multi_engine_ethash")
+	}
+
+	/// Create a new Spec with Clique consensus which does internal sealing (not requiring
+	/// work).
+	/// Account keccak("") is the only signer.
+	pub fn new_test_clique() -> Self {
+		load_bundled!("clique")
+	}
+
 	/// TestList.sol used in both specs: https://github.com/paritytech/contracts/pull/30/files
 	/// Accounts with secrets keccak("0") and keccak("1") are initially the validators.
 	/// Create a new Spec with BasicAuthority which uses a contract at address 5 to determine
@@ -816,6 +921,14 @@ mod tests {
 		assert!(Spec::load(&::std::env::temp_dir(), &[] as &[u8]).is_err());
 	}
 
+	#[test]
+	fn test_load_unknown_builtin() {
+		let json = include_str!("../../res/null.json").replace("\"identity\"", "\"no_such_builtin\"");
+		let err = Spec::load(&::std::env::temp_dir(), json.as_bytes()).err().unwrap();
+		assert!(err.contains("unknown builtin `no_such_builtin`"));
+		assert!(Spec::load_machine(json.as_bytes()).is_err());
+	}
+
 	#[test]
 	fn test_chain() {
 		let test_spec = Spec::new_test();
@@ -855,4 +968,21 @@ mod tests {
 		assert_eq!(state.storage_at(&address, &H256::zero()).unwrap(), expected);
 		assert_eq!(state.balance(&address).unwrap(), 1.into());
 	}
+
+	#[test]
+	fn wasm_validation_params() {
+		let spec = Spec::new_wasm_test();
+		let schedule = spec.engine.schedule(0);
+		assert!(schedule.wasm_validation);
+		assert!(schedule.wasm.builtins);
+		assert_eq!(schedule.wasm.max_memory_pages, ::vm::WasmCosts::default().max_memory_pages);
+
+		let mut params = spec.params().clone();
+		params.wasm_validation_transition = 10;
+		params.wasm_max_memory_pages = Some(16);
+		assert!(!params.schedule(9).wasm_validation);
+		assert!(params.schedule(10).wasm_validation);
+		assert_eq!(params.schedule(10).wasm.max_memory_pages, 16);
+		assert!(!params.schedule(10).wasm.builtins);
+	}
 }
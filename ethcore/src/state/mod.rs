@@ -21,7 +21,7 @@
 
 use std::cell::{RefCell, RefMut};
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, BTreeMap, HashSet};
+use std::collections::{HashMap, BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 use std::sync::Arc;
 use hash::{KECCAK_NULL_RLP, KECCAK_EMPTY};
@@ -41,6 +41,7 @@ use types::state_diff::StateDiff;
 use transaction::SignedTransaction;
 use state_db::StateDB;
 use evm::{Factory as EvmFactory};
+use wasm::SharedCache as WasmCache;
 
 use ethereum_types::{H256, U256, Address};
 use hashdb::{HashDB, AsHashDB};
@@ -312,6 +313,8 @@ pub struct State<B: Backend> {
 	cache: RefCell<HashMap<Address, AccountEntry>>,
 	// The original account is preserved in
 	checkpoints: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
+	// Storage keys read or written on each account, kept independently of the caches.
+	accessed_storage: RefCell<HashMap<Address, HashSet<H256>>>,
 	account_start_nonce: U256,
 	factories: Factories,
 }
@@ -352,6 +355,7 @@ impl<B: Backend> State<B> {
 			root: root,
 			cache: RefCell::new(HashMap::new()),
 			checkpoints: RefCell::new(Vec::new()),
+			accessed_storage: RefCell::new(HashMap::new()),
 			account_start_nonce: account_start_nonce,
 			factories: factories,
 		}
@@ -368,6 +372,7 @@ impl<B: Backend> State<B> {
 			root: root,
 			cache: RefCell::new(HashMap::new()),
 			checkpoints: RefCell::new(Vec::new()),
+			accessed_storage: RefCell::new(HashMap::new()),
 			account_start_nonce: account_start_nonce,
 			factories: factories
 		};
@@ -380,6 +385,11 @@ impl<B: Backend> State<B> {
 		self.factories.vm.clone()
 	}
 
+	/// Get the cache of instrumented wasm modules used on this state.
+	pub fn wasm_cache(&self) -> Arc<WasmCache> {
+		self.factories.wasm.clone()
+	}
+
 	/// Swap the current backend for another.
 	// TODO: [rob] find a less hacky way to avoid duplication of `Client::state_at`.
 	pub fn replace_backend<T: Backend>(self, backend: T) -> State<T> {
@@ -388,6 +398,7 @@ impl<B: Backend> State<B> {
 			root: self.root,
 			cache: self.cache,
 			checkpoints: self.checkpoints,
+			accessed_storage: self.accessed_storage,
 			account_start_nonce: self.account_start_nonce,
 			factories: self.factories,
 		}
@@ -531,6 +542,8 @@ impl<B: Backend> State<B> {
 		// 2. If there's an entry for the account in the global cache check for the key or load it into that account.
 		// 3. If account is missing in the global cache load it into the local cache and cache the key there.
 
+		self.accessed_storage.borrow_mut().entry(address.clone()).or_insert_with(HashSet::new).insert(key.clone());
+
 		// check local cache first without updating
 		{
 			let local_cache = self.cache.borrow_mut();
@@ -833,6 +846,45 @@ impl<B: Backend> State<B> {
 		}))
 	}
 
+	/// Returns the accounts present in the local cache or whose storage has been accessed,
+	/// along with the storage keys read or written on them.
+	pub fn touched(&self) -> BTreeMap<Address, BTreeSet<H256>> {
+		let mut touched: BTreeMap<Address, BTreeSet<H256>> = self.cache.borrow().keys()
+			.map(|address| (address.clone(), BTreeSet::new()))
+			.collect();
+		for (address, keys) in self.accessed_storage.borrow().iter() {
+			touched.entry(address.clone()).or_insert_with(BTreeSet::new).extend(keys.iter().cloned());
+		}
+		touched
+	}
+
+	/// Populate a PodAccount map with the given accounts and storage items, as they are in this state.
+	/// Accounts which do not exist and zero storage values are left out.
+	pub fn to_pod_subset(&self, accounts: &BTreeMap<Address, BTreeSet<H256>>) -> trie::Result<PodState> {
+		let mut pod = BTreeMap::new();
+		for (address, keys) in accounts {
+			if !self.exists(address)? {
+				continue;
+			}
+
+			let mut storage = BTreeMap::new();
+			for key in keys {
+				let value = self.storage_at(address, key)?;
+				if !value.is_zero() {
+					storage.insert(key.clone(), value);
+				}
+			}
+
+			pod.insert(address.clone(), PodAccount {
+				balance: self.balance(address)?,
+				nonce: self.nonce(address)?,
+				code: self.code(address)?.map(|code| (*code).clone()),
+				storage: storage,
+			});
+		}
+		Ok(PodState::from(pod))
+	}
+
 	fn query_pod(&mut self, query: &PodState) -> trie::Result<()> {
 		for (address, pod_account) in query.get() {
 			if !self.ensure_cached(address, RequireCache::Code, true, |a| a.is_some())? {
@@ -1051,6 +1103,7 @@ impl Clone for State<StateDB> {
 			root: self.root.clone(),
 			cache: RefCell::new(cache),
 			checkpoints: RefCell::new(Vec::new()),
+			accessed_storage: RefCell::new(HashMap::new()),
 			account_start_nonce: self.account_start_nonce.clone(),
 			factories: self.factories.clone(),
 		}
@@ -1957,6 +2010,59 @@ mod tests {
 		assert_eq!(state.nonce(&a).unwrap(), U256::from(1u64));
 	}
 
+	#[test]
+	fn touched_accounts_to_pod() {
+		let a = Address::from(1);
+		let b = Address::from(2);
+		let c = Address::from(3);
+		let (root, db) = {
+			let mut state = get_temp_state();
+			state.add_balance(&a, &U256::from(69u64), CleanupMode::NoEmpty).unwrap();
+			state.set_storage(&a, H256::from(1), H256::from(2)).unwrap();
+			state.set_storage(&a, H256::from(3), H256::from(4)).unwrap();
+			state.add_balance(&b, &U256::from(1u64), CleanupMode::NoEmpty).unwrap();
+			state.commit().unwrap();
+			state.drop()
+		};
+
+		let pre = State::from_existing(db.boxed_clone(), root, U256::from(0u8), Default::default()).unwrap();
+		let mut state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+		state.storage_at(&a, &H256::from(1)).unwrap();
+		state.set_storage(&a, H256::from(5), H256::from(6)).unwrap();
+		state.balance(&c).unwrap();
+
+		let touched = state.touched();
+		assert_eq!(touched[&a], vec![H256::from(1), H256::from(5)].into_iter().collect());
+		assert!(!touched.contains_key(&b));
+
+		let pod = pre.to_pod_subset(&touched).unwrap();
+		assert_eq!(pod.get().len(), 1);
+		let account = &pod.get()[&a];
+		assert_eq!(account.balance, U256::from(69u64));
+		assert_eq!(account.storage, vec![(H256::from(1), H256::from(2))].into_iter().collect());
+	}
+
+	#[test]
+	fn touched_storage_outlives_storage_cache() {
+		let a = Address::from(1);
+		let (root, db) = {
+			let mut state = get_temp_state();
+			state.set_storage(&a, H256::from(1), H256::from(2)).unwrap();
+			state.commit().unwrap();
+			state.drop()
+		};
+
+		// read more keys than the account storage cache holds.
+		let state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+		for key in 1..10000u64 {
+			state.storage_at(&a, &H256::from(key)).unwrap();
+		}
+
+		let touched = state.touched();
+		assert_eq!(touched[&a].len(), 9999);
+		assert!(touched[&a].contains(&H256::from(1)));
+	}
+
 	#[test]
 	fn remove() {
 		let a = Address::zero();
@@ -18,14 +18,14 @@ use std::str::FromStr;
 use std::sync::Arc;
 use hash::keccak;
 use io::IoChannel;
-use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockId};
+use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockId, EvmTestClient};
 use state::{self, State, CleanupMode};
 use executive::{Executive, TransactOptions};
 use ethereum;
 use block::IsBlock;
 use tests::helpers::*;
 use types::filter::Filter;
-use ethereum_types::{U256, Address};
+use ethereum_types::{U256, H256, Address};
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use miner::Miner;
 use spec::Spec;
@@ -34,6 +34,10 @@ use ethkey::KeyPair;
 use transaction::{PendingTransaction, Transaction, Action, Condition};
 use miner::MinerService;
 use tempdir::TempDir;
+use rustc_hex::FromHex;
+use bytes::Bytes;
+use trace::NoopVMTracer;
+use vm::ActionParams;
 
 #[test]
 fn imports_from_empty() {
@@ -108,6 +112,94 @@ fn imports_good_block() {
 	assert!(!block.into_inner().is_empty());
 }
 
+#[test]
+fn evm_test_client_reads_client_database() {
+	let tempdir = TempDir::new("").unwrap();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client_db = Arc::new(Database::open(&db_config, tempdir.path().to_str().unwrap()).unwrap());
+	let config = ClientConfig::default();
+	let pruning = config.pruning;
+
+	let client = Client::new(
+		config,
+		&spec,
+		client_db.clone(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+	client.import_block(get_good_dummy_block()).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	let (_, block) = EvmTestClient::from_database(&spec, client_db.clone(), pruning, 1, true).unwrap();
+	assert_eq!(block.header.number(), 1);
+	assert_eq!(block.env_info.last_hashes[0], spec.genesis_header().hash());
+	assert!(block.transactions.is_empty());
+
+	assert!(EvmTestClient::from_database(&spec, client_db.clone(), pruning, 2, false).is_err());
+
+	let other_db = Arc::new(::kvdb_memorydb::create(::db::NUM_COLUMNS.unwrap()));
+	assert!(EvmTestClient::from_database(&spec, other_db, pruning, 0, false).is_err());
+}
+
+#[test]
+fn evm_test_client_replays_call_on_client_database() {
+	let tempdir = TempDir::new("").unwrap();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client_db = Arc::new(Database::open(&db_config, tempdir.path().to_str().unwrap()).unwrap());
+	let config = ClientConfig::default();
+	let pruning = config.pruning;
+
+	let client = Client::new(
+		config,
+		&spec,
+		client_db.clone(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+	client.import_block(get_good_dummy_block()).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	let (client, block) = EvmTestClient::from_database(&spec, client_db, pruning, 1, true).unwrap();
+	let address = Address::from_str("102e61f5d8f9bc71d0ad4a084df4e65e05ce0e1c").unwrap();
+	let balance = U256::from(1u64) << 200;
+	// PUSH1 1 PUSH1 1 SSTORE PUSH1 0 SLOAD POP STOP
+	let code: Bytes = "60016001556000545000".from_hex().unwrap();
+	let params = || {
+		let mut params = ActionParams::default();
+		params.address = address;
+		params.code_address = address;
+		params.sender = address;
+		params.origin = address;
+		params.gas = 100_000.into();
+		params.code_hash = None;
+		params.code = Some(Arc::new(code.clone()));
+		params
+	};
+
+	// first call writes on top of the database state.
+	let mut first = client.fork();
+	first.call_with_env_info(&block.env_info, params(), &mut NoopVMTracer).unwrap();
+
+	// replay on the state the first call left behind.
+	let pre = first.fork();
+	let mut replay = first.fork();
+	replay.call_with_env_info(&block.env_info, params(), &mut NoopVMTracer).unwrap();
+
+	let touched = replay.touched_pod_state(&pre).unwrap();
+	assert_eq!(touched.get().len(), 1);
+	let account = &touched.get()[&address];
+	assert_eq!(account.balance, balance);
+	assert_eq!(account.storage, vec![(H256::from(1), H256::from(1))].into_iter().collect());
+
+	// nothing leaks back into the database state.
+	let untouched = first.touched_pod_state(&client).unwrap();
+	assert!(untouched.get()[&address].storage.is_empty());
+}
+
 #[test]
 fn query_none_block() {
 	let tempdir = TempDir::new("").unwrap();
@@ -33,6 +33,7 @@ use rlp::{self, RlpStream};
 use spec::*;
 use state_db::StateDB;
 use state::*;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use transaction::{Action, Transaction, SignedTransaction};
 use views::BlockView;
@@ -367,7 +368,8 @@ pub fn get_default_ethash_params() -> EthashParams {
 		metropolis_difficulty_increment_divisor: 9,
 		homestead_transition: 1150000,
 		duration_limit: 13,
-		block_reward: 0.into(),
+		block_reward: vec![(0, 0.into())].into_iter().collect(),
+		difficulty_bomb_delays: BTreeMap::new(),
 		difficulty_hardfork_transition: u64::max_value(),
 		difficulty_hardfork_bound_divisor: U256::from(0),
 		bomb_defuse_transition: u64::max_value(),
@@ -381,9 +383,6 @@ pub fn get_default_ethash_params() -> EthashParams {
 		mcip3_ubi_contract: "0000000000000000000000000000000000000001".into(),
 		mcip3_dev_reward: 0.into(),
 		mcip3_dev_contract: "0000000000000000000000000000000000000001".into(),
-		eip649_transition: u64::max_value(),
-		eip649_delay: 3_000_000,
-		eip649_reward: None,
 		expip2_transition: u64::max_value(),
 		expip2_duration_limit: 30,
 	}
@@ -196,6 +196,7 @@ impl ExecutiveVMTracer {
 				code: vec![],
 				operations: vec![Default::default()],	// prefill with a single entry so that prepare_subtrace can get the parent_step
 				subs: vec![],
+				debug_log: vec![],
 			}
 		}
 	}
@@ -225,12 +226,17 @@ impl VMTracer for ExecutiveVMTracer {
 		self.data.operations.last_mut().expect("trace_executed is always called after a trace_prepare_execute").executed = Some(ex);
 	}
 
+	fn trace_debug_log(&mut self, message: &str) {
+		self.data.debug_log.push(message.to_owned());
+	}
+
 	fn prepare_subtrace(&self, code: &[u8]) -> Self {
 		ExecutiveVMTracer { data: VMTrace {
 			parent_step: self.data.operations.len() - 1,	// won't overflow since we must already have pushed an operation in trace_prepare_execute.
 			code: code.to_vec(),
 			operations: vec![],
 			subs: vec![],
+			debug_log: vec![],
 		}}
 	}
 
@@ -240,3 +246,36 @@ impl VMTracer for ExecutiveVMTracer {
 
 	fn drain(mut self) -> Option<VMTrace> { self.data.subs.pop() }
 }
+
+/// VM tracer collecting only the debug messages logged by contracts.
+/// Produces a VM trace without operations.
+pub struct DebugLogVMTracer {
+	data: VMTrace,
+}
+
+impl DebugLogVMTracer {
+	/// Create a new top-level instance.
+	pub fn toplevel() -> Self {
+		DebugLogVMTracer {
+			data: Default::default(),
+		}
+	}
+}
+
+impl VMTracer for DebugLogVMTracer {
+	type Output = VMTrace;
+
+	fn trace_debug_log(&mut self, message: &str) {
+		self.data.debug_log.push(message.to_owned());
+	}
+
+	fn prepare_subtrace(&self, _code: &[u8]) -> Self {
+		DebugLogVMTracer::toplevel()
+	}
+
+	fn done_subtrace(&mut self, sub: Self) {
+		self.data.subs.push(sub.data);
+	}
+
+	fn drain(mut self) -> Option<VMTrace> { self.data.subs.pop() }
+}
@@ -26,7 +26,7 @@ mod types;
 pub use self::config::Config;
 pub use self::db::TraceDB;
 pub use self::noop_tracer::{NoopTracer, NoopVMTracer};
-pub use self::executive_tracer::{ExecutiveTracer, ExecutiveVMTracer};
+pub use self::executive_tracer::{ExecutiveTracer, ExecutiveVMTracer, DebugLogVMTracer};
 pub use self::import::ImportRequest;
 pub use self::localized::LocalizedTrace;
 
@@ -112,6 +112,15 @@ pub trait VMTracer: Send {
 	/// Trace the finalised execution of a single valid instruction.
 	fn trace_executed(&mut self, _gas_used: U256, _stack_push: &[U256], _mem_diff: Option<(usize, &[u8])>, _store_diff: Option<(U256, U256)>) {}
 
+	/// Trace a debug message logged by the contract.
+	fn trace_debug_log(&mut self, _message: &str) {}
+
+	/// Trace the memory used by the contract once it finished executing.
+	fn trace_memory_usage(&mut self, _bytes: usize) {}
+
+	/// Trace a host function called by a wasm contract and the gas it charged.
+	fn trace_host_call(&mut self, _name: &str, _gas_cost: U256) {}
+
 	/// Spawn subtracer which will be used to trace deeper levels of execution.
 	fn prepare_subtrace(&self, code: &[u8]) -> Self where Self: Sized;
 
@@ -59,6 +59,7 @@ impl<'a> From<&'a VmError> for Error {
 			VmError::OutOfStack { .. } => Error::OutOfStack,
 			VmError::BuiltIn { .. } => Error::BuiltIn,
 			VmError::Wasm { .. } => Error::Wasm,
+			VmError::WasmPanic(_) => Error::Wasm,
 			VmError::Internal(_) => Error::Internal,
 			VmError::MutableCallInStaticContext => Error::MutableCallInStaticContext,
 			VmError::OutOfBounds => Error::OutOfBounds,
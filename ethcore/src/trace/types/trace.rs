@@ -141,6 +141,10 @@ pub enum RewardType {
 	Block,
 	/// Uncle
 	Uncle,
+	/// Reward determined by a contract
+	External,
+	/// Empty step (AuthorityRound)
+	EmptyStep,
 }
 
 impl Encodable for RewardType {
@@ -148,6 +152,8 @@ impl Encodable for RewardType {
 		let v = match *self {
 			RewardType::Block => 0u32,
 			RewardType::Uncle => 1,
+			RewardType::External => 2,
+			RewardType::EmptyStep => 3,
 		};
 		Encodable::rlp_append(&v, s);
 	}
@@ -158,6 +164,8 @@ impl Decodable for RewardType {
 		rlp.as_val().and_then(|v| Ok(match v {
 			0u32 => RewardType::Block,
 			1 => RewardType::Uncle,
+			2 => RewardType::External,
+			3 => RewardType::EmptyStep,
 			_ => return Err(DecoderError::Custom("Invalid value of RewardType item")),
 		}))
 	}
@@ -421,4 +429,6 @@ pub struct VMTrace {
 	/// The sub traces for each interior action performed as part of this call/create.
 	/// Thre is a 1:1 correspondance between these and a CALL/CREATE/CALLCODE/DELEGATECALL instruction.
 	pub subs: Vec<VMTrace>,
+	/// Debug messages logged by the contract during this call/create.
+	pub debug_log: Vec<String>,
 }
@@ -111,6 +111,25 @@ pub fn verify_block_unordered(header: Header, bytes: Bytes, engine: &EthEngine,
 	})
 }
 
+/// Verify a chain of headers which need not connect to the local chain, ordered from the most
+/// recent one to its ancestors. Each header is checked on its own, including its seal, and
+/// against its parent, except for the oldest one whose parent is not given.
+pub fn verify_header_chain(headers: &[Header], engine: &EthEngine) -> Result<(), Error> {
+	for (i, header) in headers.iter().enumerate() {
+		verify_header_params(header, engine, false)?;
+		engine.verify_block_basic(header)?;
+		engine.verify_block_unordered(header)?;
+		if let Some(parent) = headers.get(i + 1) {
+			if &parent.hash() != header.parent_hash() {
+				return Err(From::from(BlockError::InvalidParentHash(Mismatch { expected: parent.hash(), found: header.parent_hash().clone() })))
+			}
+			verify_parent(header, parent, engine.params().gas_limit_bound_divisor)?;
+			engine.verify_block_family(header, parent)?;
+		}
+	}
+	Ok(())
+}
+
 /// Parameters for full verification of block family: block bytes, transactions, blockchain, and state access.
 pub type FullFamilyParams<'a> = (&'a [u8], &'a [SignedTransaction], &'a BlockProvider, &'a BlockChainClient);
 
@@ -242,9 +261,10 @@ pub fn verify_block_final(expected: &Header, got: &Header) -> Result<(), Error>
 
 /// Check basic header parameters.
 pub fn verify_header_params(header: &Header, engine: &EthEngine, is_full: bool) -> Result<(), Error> {
-	if header.seal().len() != engine.seal_fields() {
+	let expected_seal_fields = engine.seal_fields(header);
+	if header.seal().len() != expected_seal_fields {
 		return Err(From::from(BlockError::InvalidSealArity(
-			Mismatch { expected: engine.seal_fields(), found: header.seal().len() }
+			Mismatch { expected: expected_seal_fields, found: header.seal().len() }
 		)));
 	}
 
@@ -685,6 +705,36 @@ mod tests {
 		// TODO: some additional uncle checks
 	}
 
+	#[test]
+	fn header_chain() {
+		let spec = Spec::new_test();
+		let engine = &*spec.engine;
+
+		let mut headers = Vec::new();
+		let mut parent = spec.genesis_header();
+		for _ in 0..3 {
+			let mut header = Header::new();
+			header.set_parent_hash(parent.hash());
+			header.set_number(parent.number() + 1);
+			header.set_timestamp(parent.timestamp() + 10);
+			header.set_gas_limit(*parent.gas_limit());
+			headers.insert(0, header.clone());
+			parent = header;
+		}
+		check_ok(verify_header_chain(&headers, engine));
+
+		// a zero parent hash doesn't skip the link check.
+		let mut unlinked = headers.clone();
+		unlinked[0].set_parent_hash(H256::zero());
+		check_fail(verify_header_chain(&unlinked, engine),
+			InvalidParentHash(Mismatch { expected: headers[1].hash(), found: H256::zero() }));
+
+		let mut gap = headers.clone();
+		gap.remove(1);
+		check_fail(verify_header_chain(&gap, engine),
+			InvalidParentHash(Mismatch { expected: headers[2].hash(), found: headers[1].hash() }));
+	}
+
 	#[test]
 	fn dust_protection() {
 		use ethkey::{Generator, Random};
@@ -23,6 +23,8 @@ pub struct CallAnalytics {
 	pub transaction_tracing: bool,
 	/// Make a VM trace.
 	pub vm_tracing: bool,
+	/// Collect debug messages logged by contracts. Implied by `vm_tracing`.
+	pub debug_logging: bool,
 	/// Make a diff.
 	pub state_diffing: bool,
 }
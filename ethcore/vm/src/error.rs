@@ -19,6 +19,32 @@
 use trie;
 use std::fmt;
 
+/// Panic details reported by a wasm contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicPayload {
+	/// Panic message, unless stripped from the contract
+	pub msg: Option<String>,
+	/// Source file the panic originated from
+	pub file: Option<String>,
+	/// Line in the source file
+	pub line: Option<u32>,
+	/// Column in the source file
+	pub col: Option<u32>,
+}
+
+impl fmt::Display for PanicPayload {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{msg}, {file}:{line}:{col}",
+			msg = self.msg.as_ref().map(String::as_ref).unwrap_or("<msg was stripped>"),
+			file = self.file.as_ref().map(String::as_ref).unwrap_or("<unknown>"),
+			line = self.line.unwrap_or(0),
+			col = self.col.unwrap_or(0)
+		)
+	}
+}
+
 /// VM errors.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Error {
@@ -65,6 +91,8 @@ pub enum Error {
 	Internal(String),
 	/// Wasm runtime error
 	Wasm(String),
+	/// Wasm contract panicked, with the payload decoded from the contract.
+	WasmPanic(PanicPayload),
 	/// Out of bounds access in RETURNDATACOPY.
 	OutOfBounds,
 	/// Execution has been reverted with REVERT.
@@ -97,10 +125,17 @@ impl fmt::Display for Error {
 			Internal(ref msg) => write!(f, "Internal error: {}", msg),
 			MutableCallInStaticContext => write!(f, "Mutable call in static context"),
 			Wasm(ref msg) => write!(f, "Internal error: {}", msg),
+			WasmPanic(ref payload) => write!(f, "Panic: {}", payload),
 			OutOfBounds => write!(f, "Out of bounds"),
 			Reverted => write!(f, "Reverted"),
 		}
 	}
 }
 
+impl From<PanicPayload> for Error {
+	fn from(payload: PanicPayload) -> Self {
+		Error::WasmPanic(payload)
+	}
+}
+
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -111,6 +111,15 @@ pub trait Ext {
 	/// Returns code size at given address
 	fn extcodesize(&self, address: &Address) -> Result<usize>;
 
+	/// Returns the cost of running the builtin contract with given spec name on `input`,
+	/// or `None` if no such builtin is active.
+	fn builtin_cost(&self, name: &str, input: &[u8]) -> Option<U256>;
+
+	/// Runs the builtin contract with given spec name on `input` and returns its output.
+	///
+	/// Doesn't charge any gas, use `builtin_cost` to price the operation.
+	fn builtin_execute(&self, name: &str, input: &[u8]) -> Result<Bytes>;
+
 	/// Creates log entry with given topics and data
 	fn log(&mut self, topics: Vec<H256>, data: &[u8]) -> Result<()>;
 
@@ -146,6 +155,15 @@ pub trait Ext {
 	/// Trace the finalised execution of a single instruction.
 	fn trace_executed(&mut self, _gas_used: U256, _stack_push: &[U256], _mem_diff: Option<(usize, &[u8])>, _store_diff: Option<(U256, U256)>) {}
 
+	/// Trace a debug message logged by the contract. Passthrough for the VM trace.
+	fn trace_debug_log(&mut self, _message: &str) {}
+
+	/// Trace the memory used by the contract once it finished executing. Passthrough for the VM trace.
+	fn trace_memory_usage(&mut self, _bytes: usize) {}
+
+	/// Trace a host function called by the contract and the gas it charged. Passthrough for the VM trace.
+	fn trace_host_call(&mut self, _name: &str, _gas_cost: U256) {}
+
 	/// Check if running in static context.
 	fn is_static(&self) -> bool;
 }
@@ -113,6 +113,8 @@ pub struct Schedule {
 	pub kill_dust: CleanDustMode,
 	/// Enable EIP-86 rules
 	pub eip86: bool,
+	/// Validate wasm contract code before deploying it.
+	pub wasm_validation: bool,
 	/// Wasm extra schedule settings
 	pub wasm: WasmCosts,
 }
@@ -141,6 +143,10 @@ pub struct WasmCosts {
 	pub static_u256: u32,
 	/// General static query of Address value from env-info
 	pub static_address: u32,
+	/// Maximum number of 64KiB linear memory pages a contract may declare.
+	pub max_memory_pages: u32,
+	/// Whether the externals backed by builtin contracts are available.
+	pub builtins: bool,
 }
 
 impl Default for WasmCosts {
@@ -157,6 +163,8 @@ impl Default for WasmCosts {
 			static_region: 1,
 			static_u256: 64,
 			static_address: 40,
+			max_memory_pages: 512,
+			builtins: false,
 		}
 	}
 }
@@ -233,6 +241,7 @@ impl Schedule {
 			have_static_call: false,
 			kill_dust: CleanDustMode::Off,
 			eip86: false,
+			wasm_validation: false,
 			wasm: Default::default(),
 		}
 	}
@@ -296,6 +305,7 @@ impl Schedule {
 			have_static_call: false,
 			kill_dust: CleanDustMode::Off,
 			eip86: false,
+			wasm_validation: false,
 			wasm: Default::default(),
 		}
 	}
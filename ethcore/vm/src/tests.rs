@@ -22,7 +22,7 @@ use bytes::Bytes;
 use {
 	CallType, Schedule, EnvInfo,
 	ReturnData, Ext, ContractCreateResult, MessageCallResult,
-	CreateContractAddress, Result, GasLeft,
+	CreateContractAddress, Result, GasLeft, Error,
 };
 
 pub struct FakeLogEntry {
@@ -46,6 +46,17 @@ pub struct FakeCall {
 	pub code_address: Option<Address>,
 }
 
+/// VM operation recorded by `FakeExt` when tracing.
+#[derive(PartialEq, Debug)]
+pub struct FakeTrace {
+	pub pc: usize,
+	pub instruction: u8,
+	pub gas_cost: U256,
+	pub stack_push: Option<Vec<U256>>,
+	pub mem_diff: Option<(usize, Bytes)>,
+	pub store_diff: Option<(U256, U256)>,
+}
+
 /// Fake externalities test structure.
 ///
 /// Can't do recursive calls.
@@ -63,6 +74,7 @@ pub struct FakeExt {
 	pub schedule: Schedule,
 	pub balances: HashMap<Address, U256>,
 	pub tracing: bool,
+	pub traces: Vec<FakeTrace>,
 	pub is_static: bool,
 }
 
@@ -161,6 +173,14 @@ impl Ext for FakeExt {
 		Ok(self.codes.get(address).map_or(0, |c| c.len()))
 	}
 
+	fn builtin_cost(&self, _name: &str, _input: &[u8]) -> Option<U256> {
+		None
+	}
+
+	fn builtin_execute(&self, _name: &str, _input: &[u8]) -> Result<Bytes> {
+		Err(Error::BuiltIn("No builtin contracts in FakeExt"))
+	}
+
 	fn log(&mut self, topics: Vec<H256>, data: &[u8]) -> Result<()> {
 		self.logs.push(FakeLogEntry {
 			topics: topics,
@@ -201,4 +221,22 @@ impl Ext for FakeExt {
 	fn trace_next_instruction(&mut self, _pc: usize, _instruction: u8, _gas: U256) -> bool {
 		self.tracing
 	}
+
+	fn trace_prepare_execute(&mut self, pc: usize, instruction: u8, gas_cost: U256) {
+		self.traces.push(FakeTrace {
+			pc: pc,
+			instruction: instruction,
+			gas_cost: gas_cost,
+			stack_push: None,
+			mem_diff: None,
+			store_diff: None,
+		});
+	}
+
+	fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], mem_diff: Option<(usize, &[u8])>, store_diff: Option<(U256, U256)>) {
+		let trace = self.traces.last_mut().expect("trace_executed is always called after a trace_prepare_execute");
+		trace.stack_push = Some(stack_push.to_vec());
+		trace.mem_diff = mem_diff.map(|(offset, data)| (offset, data.to_vec()));
+		trace.store_diff = store_diff;
+	}
 }
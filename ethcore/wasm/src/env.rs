@@ -172,6 +172,36 @@ pub const SIGNATURES: &'static [UserFunctionDescriptor] = &[
 		&[I32; 4],
 		None,
 	),
+	Static(
+		"_keccak256",
+		&[I32; 3],
+		None,
+	),
+	Static(
+		"_sha256",
+		&[I32; 3],
+		None,
+	),
+	Static(
+		"_ecrecover",
+		&[I32; 2],
+		Some(I32),
+	),
+	Static(
+		"_bn128_add",
+		&[I32; 2],
+		Some(I32),
+	),
+	Static(
+		"_bn128_mul",
+		&[I32; 2],
+		Some(I32),
+	),
+	Static(
+		"_bn128_pairing",
+		&[I32; 3],
+		Some(I32),
+	),
 
 	// TODO: Get rid of it also somehow?
 	Static(
@@ -187,6 +217,16 @@ pub const SIGNATURES: &'static [UserFunctionDescriptor] = &[
 	),
 ];
 
+/// Cryptographic externals, mostly backed by builtin contracts, available only once activated in the spec.
+pub const BUILTIN_EXTERNALS: &'static [&'static str] = &[
+	"_keccak256",
+	"_sha256",
+	"_ecrecover",
+	"_bn128_add",
+	"_bn128_mul",
+	"_bn128_pairing",
+];
+
 pub fn native_bindings<'a>(runtime: &'a mut Runtime) -> interpreter::UserDefinedElements<'a, UserTrap> {
 	interpreter::UserDefinedElements {
 		executor: Some(runtime),
@@ -24,6 +24,10 @@ extern crate byteorder;
 extern crate parity_wasm;
 extern crate libc;
 extern crate wasm_utils;
+extern crate heapsize;
+extern crate keccak_hash as hash;
+extern crate memory_cache;
+extern crate parking_lot;
 
 mod runtime;
 mod ptr;
@@ -32,16 +36,26 @@ mod result;
 mod tests;
 mod env;
 mod panic_payload;
+mod shared_cache;
+mod validation;
 
 const DEFAULT_STACK_SPACE: u32 = 5 * 1024 * 1024;
 
+/// Prefix identifying wasm contract code.
+pub const WASM_MAGIC_NUMBER: &'static [u8; 4] = b"\0asm";
+
+use std::sync::Arc;
 use parity_wasm::{interpreter, elements};
 use parity_wasm::interpreter::ModuleInstanceInterface;
+use wasm_utils::rules;
 
-use vm::{GasLeft, ReturnData, ActionParams};
+use vm::{GasLeft, ReturnData, ActionParams, WasmCosts};
 use self::runtime::{Runtime, RuntimeContext, UserTrap};
+use self::shared_cache::PreparedModule;
 
 pub use self::runtime::InterpreterError;
+pub use self::shared_cache::SharedCache;
+pub use self::validation::ValidationError;
 
 const DEFAULT_RESULT_BUFFER: usize = 1024;
 
@@ -57,7 +71,10 @@ impl From<InterpreterError> for Error {
 
 impl From<Error> for vm::Error {
 	fn from(e: Error) -> Self {
-		vm::Error::Wasm(format!("Wasm runtime error: {:?}", e.0))
+		match e.0 {
+			interpreter::Error::User(UserTrap::Panic(payload)) => payload.into(),
+			e => vm::Error::Wasm(format!("Wasm runtime error: {:?}", e)),
+		}
 	}
 }
 
@@ -65,18 +82,48 @@ impl From<UserTrap> for vm::Error {
 	fn from(e: UserTrap) -> Self { e.into() }
 }
 
+/// Check that contract code can be deployed: it must be a well-formed module
+/// without floating point operations, importing only functions provided by
+/// the runtime and staying within the memory limits of the cost table.
+pub fn validate(code: &[u8], costs: &WasmCosts) -> Result<(), ValidationError> {
+	use parity_wasm::elements::Deserialize;
+
+	let module = elements::Module::deserialize(&mut ::std::io::Cursor::new(code))
+		.map_err(|err| ValidationError::Deserialization(format!("{:?}", err)))?;
+
+	validation::validate_module(&module, costs)
+}
+
+fn gas_rules(costs: &WasmCosts) -> rules::Set {
+	rules::Set::new({
+		let mut vals = ::std::collections::HashMap::with_capacity(4);
+		vals.insert(rules::InstructionType::Load, costs.mem as u32);
+		vals.insert(rules::InstructionType::Store, costs.mem as u32);
+		vals.insert(rules::InstructionType::Div, costs.div as u32);
+		vals.insert(rules::InstructionType::Mul, costs.mul as u32);
+		vals
+	})
+}
+
 /// Wasm interpreter instance
 pub struct WasmInterpreter {
 	program: runtime::InterpreterProgramInstance,
 	result: Vec<u8>,
+	cache: Arc<SharedCache>,
 }
 
 impl WasmInterpreter {
 	/// New wasm interpreter instance
 	pub fn new() -> Result<WasmInterpreter, Error> {
+		WasmInterpreter::with_cache(Arc::new(SharedCache::default()))
+	}
+
+	/// New wasm interpreter instance sharing instrumented modules with `cache`
+	pub fn with_cache(cache: Arc<SharedCache>) -> Result<WasmInterpreter, Error> {
 		Ok(WasmInterpreter {
 			program: interpreter::ProgramInstance::new()?,
 			result: Vec::with_capacity(DEFAULT_RESULT_BUFFER),
+			cache: cache,
 		})
 	}
 }
@@ -102,6 +149,40 @@ impl vm::Vm for WasmInterpreter {
 			return Err(vm::Error::Wasm("Wasm interpreter cannot run contracts with gas >= 2^64".to_owned()));
 		}
 
+		let prepared = {
+			let costs = &ext.schedule().wasm;
+			let embedded = match params.params_type {
+				vm::ParamsType::Embedded => true,
+				vm::ParamsType::Separate => false,
+			};
+			let prepare = || -> vm::Result<PreparedModule> {
+				let (mut cursor, data_position) = match embedded {
+					true => {
+						let module_size = parity_wasm::peek_size(&*code);
+						(
+							::std::io::Cursor::new(&code[..module_size]),
+							module_size
+						)
+					},
+					false => (::std::io::Cursor::new(&code[..]), 0),
+				};
+
+				let module = elements::Module::deserialize(&mut cursor).map_err(|err| {
+					vm::Error::Wasm(format!("Error deserializing contract code ({:?})", err))
+				})?;
+
+				Ok(PreparedModule {
+					module: wasm_utils::inject_gas_counter(module, &gas_rules(costs)),
+					data_position: data_position,
+				})
+			};
+
+			match params.code_hash {
+				Some(ref code_hash) => self.cache.module(code_hash, code.len(), embedded, [costs.mem, costs.div, costs.mul], prepare)?,
+				None => Arc::new(prepare()?),
+			}
+		};
+
 		let mut runtime = Runtime::with_params(
 			ext,
 			env_memory,
@@ -117,27 +198,8 @@ impl vm::Vm for WasmInterpreter {
 			&self.program,
 		);
 
-		let (mut cursor, data_position) = match params.params_type {
-			vm::ParamsType::Embedded => {
-				let module_size = parity_wasm::peek_size(&*code);
-				(
-					::std::io::Cursor::new(&code[..module_size]),
-					module_size
-				)
-			},
-			vm::ParamsType::Separate => {
-				(::std::io::Cursor::new(&code[..]), 0)
-			},
-		};
-
-		let contract_module = wasm_utils::inject_gas_counter(
-			elements::Module::deserialize(
-				&mut cursor
-			).map_err(|err| {
-				vm::Error::Wasm(format!("Error deserializing contract code ({:?})", err))
-			})?,
-			runtime.gas_rules(),
-		);
+		let contract_module = prepared.module.clone();
+		let data_position = prepared.data_position;
 
 		let data_section_length = contract_module.data_section()
 			.map(|section| section.entries().iter().fold(0, |sum, entry| sum + entry.value().len()))
@@ -161,7 +223,7 @@ impl vm::Vm for WasmInterpreter {
 			}
 		};
 
-		{
+		let execution_result = {
 			let execution_params = runtime.execution_params()
 				.add_argument(interpreter::RuntimeValue::I32(d_ptr.as_raw() as i32));
 
@@ -171,13 +233,18 @@ impl vm::Vm for WasmInterpreter {
 					vm::Error::from(Error(err))
 				})?;
 
-			match module_instance.execute_export("_call", execution_params) {
-				Ok(_) => { },
-				Err(interpreter::Error::User(UserTrap::Suicide)) => { },
-				Err(err) => {
-					trace!(target: "wasm", "Error executing contract: {:?}", err);
-					return Err(vm::Error::from(Error(err)))
-				}
+			module_instance.execute_export("_call", execution_params)
+		};
+
+		let allocated_memory = runtime.allocated_memory() as usize;
+		runtime.ext().trace_memory_usage(allocated_memory);
+
+		match execution_result {
+			Ok(_) => { },
+			Err(interpreter::Error::User(UserTrap::Suicide)) => { },
+			Err(err) => {
+				trace!(target: "wasm", "Error executing contract: {:?}", err);
+				return Err(vm::Error::from(Error(err)))
 			}
 		}
 
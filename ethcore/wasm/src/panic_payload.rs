@@ -17,13 +17,7 @@
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::{self, Read};
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct PanicPayload {
-	pub msg: Option<String>,
-	pub file: Option<String>,
-	pub line: Option<u32>,
-	pub col: Option<u32>,
-}
+pub use vm::PanicPayload;
 
 fn read_string(rdr: &mut io::Cursor<&[u8]>) -> io::Result<Option<String>> {
 	let string_len = rdr.read_u32::<LittleEndian>()?;
@@ -54,6 +48,7 @@ pub fn decode(raw: &[u8]) -> PanicPayload {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use vm;
 	use byteorder::WriteBytesExt;
 
 	fn write_u32(payload: &mut Vec<u8>, val: u32) {
@@ -165,4 +160,26 @@ mod tests {
 			}
 		);
 	}
+
+	#[test]
+	fn into_vm_error() {
+		let mut raw = Vec::new();
+		write_bytes(&mut raw, b"msg");
+		write_bytes(&mut raw, b"file");
+		write_u32(&mut raw, 1);
+		write_u32(&mut raw, 2);
+
+		let error: vm::Error = decode(&raw).into();
+
+		assert_eq!(
+			error,
+			vm::Error::WasmPanic(PanicPayload {
+				msg: Some("msg".to_string()),
+				file: Some("file".to_string()),
+				line: Some(1),
+				col: Some(2),
+			})
+		);
+		assert_eq!(format!("{}", error), "Panic: msg, file:1:2");
+	}
 }
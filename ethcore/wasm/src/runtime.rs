@@ -22,9 +22,9 @@ use byteorder::{LittleEndian, ByteOrder};
 use libc::{memcmp, c_void};
 
 use vm;
-use panic_payload;
+use hash::keccak;
+use panic_payload::{self, PanicPayload};
 use parity_wasm::interpreter;
-use wasm_utils::rules;
 use ethereum_types::{U256, H256, Address};
 
 use vm::CallType;
@@ -59,8 +59,8 @@ pub enum UserTrap {
 	Log,
 	/// Other error in native code
 	Other,
-	/// Panic with message
-	Panic(String),
+	/// Panic with payload decoded from the contract
+	Panic(PanicPayload),
 }
 
 impl ::std::fmt::Display for UserTrap {
@@ -79,7 +79,7 @@ impl ::std::fmt::Display for UserTrap {
 			UserTrap::GasLimit => write!(f, "Invocation resulted in gas limit violated"),
 			UserTrap::Log => write!(f, "Error occured while logging an event"),
 			UserTrap::Other => write!(f, "Other unspecified error"),
-			UserTrap::Panic(ref msg) => write!(f, "Panic: {}", msg),
+			UserTrap::Panic(ref payload) => write!(f, "Panic: {}", payload),
 		}
 	}
 }
@@ -99,6 +99,51 @@ impl From<PtrError> for UserTrap {
 	}
 }
 
+/// External call being traced as a VM operation.
+struct TracedExternal {
+	pc: usize,
+	instruction: u8,
+	gas_counter: u64,
+	prepared: bool,
+	mem_diff: Option<(usize, Vec<u8>)>,
+	store_diff: Option<(U256, U256)>,
+}
+
+/// EVM instruction equivalent to the external, used to report it in VM traces.
+/// Memory management and gas metering externals are not traced.
+/// `_elog` is traced by `log` as LOG0..LOG4 once the number of topics is known.
+fn traced_instruction(name: &str) -> Option<u8> {
+	match name {
+		"_address" => Some(0x30),		// ADDRESS
+		"_balance" => Some(0x31),		// BALANCE
+		"_origin" => Some(0x32),		// ORIGIN
+		"_sender" => Some(0x33),		// CALLER
+		"_value" => Some(0x34),			// CALLVALUE
+		"_keccak256" => Some(0x20),		// SHA3
+		"_blockhash" => Some(0x40),		// BLOCKHASH
+		"_coinbase" => Some(0x41),		// COINBASE
+		"_timestamp" => Some(0x42),		// TIMESTAMP
+		"_blocknumber" => Some(0x43),	// NUMBER
+		"_difficulty" => Some(0x44),	// DIFFICULTY
+		"_gaslimit" => Some(0x45),		// GASLIMIT
+		"_storage_read" => Some(0x54),	// SLOAD
+		"_storage_write" => Some(0x55),	// SSTORE
+		"_create" => Some(0xf0),		// CREATE
+		"_ccall" => Some(0xf1),			// CALL
+		"_dcall" => Some(0xf4),			// DELEGATECALL
+		"_scall" => Some(0xfa),			// STATICCALL
+		"_suicide" => Some(0xff),		// SUICIDE
+		_ => None,
+	}
+}
+
+// Names of the builtin contracts backing crypto externals, as given in the chain spec.
+const ECRECOVER_BUILTIN: &'static str = "ecrecover";
+const SHA256_BUILTIN: &'static str = "sha256";
+const BN128_ADD_BUILTIN: &'static str = "alt_bn128_add";
+const BN128_MUL_BUILTIN: &'static str = "alt_bn128_mul";
+const BN128_PAIRING_BUILTIN: &'static str = "alt_bn128_pairing";
+
 pub struct RuntimeContext {
 	pub address: Address,
 	pub sender: Address,
@@ -116,7 +161,8 @@ pub struct Runtime<'a, 'b> {
 	memory: Arc<InterpreterMemoryInstance>,
 	context: RuntimeContext,
 	instance: &'b InterpreterProgramInstance,
-	gas_rules: rules::Set,
+	externals_called: usize,
+	traced: Option<TracedExternal>,
 }
 
 impl<'a, 'b> Runtime<'a, 'b> {
@@ -129,20 +175,6 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		context: RuntimeContext,
 		program_instance: &'d InterpreterProgramInstance,
 	) -> Runtime<'c, 'd> {
-
-		let rules = {
-			let schedule = ext.schedule();
-
-			rules::Set::new({
-				let mut vals = ::std::collections::HashMap::with_capacity(4);
-				vals.insert(rules::InstructionType::Load, schedule.wasm.mem as u32);
-				vals.insert(rules::InstructionType::Store, schedule.wasm.mem as u32);
-				vals.insert(rules::InstructionType::Div, schedule.wasm.div as u32);
-				vals.insert(rules::InstructionType::Mul, schedule.wasm.mul as u32);
-				vals
-			})
-		};
-
 		Runtime {
 			gas_counter: 0,
 			gas_limit: gas_limit,
@@ -151,7 +183,8 @@ impl<'a, 'b> Runtime<'a, 'b> {
 			ext: ext,
 			context: context,
 			instance: program_instance,
-			gas_rules: rules,
+			externals_called: 0,
+			traced: None,
 		}
 	}
 
@@ -167,6 +200,9 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		self.charge(|schedule| schedule.sstore_set_gas as u64)?;
 
 		self.ext.set_storage(key, val).map_err(|_| UserTrap::StorageUpdateError)?;
+		if let Some(ref mut traced) = self.traced {
+			traced.store_diff = Some((key.into(), val.into()));
+		}
 
 		Ok(None)
 	}
@@ -182,7 +218,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 
 		self.charge(|schedule| schedule.sload_gas as u64)?;
 
-		self.memory.set(val_ptr as u32, &*val)?;
+		self.set_result(val_ptr as u32, &*val)?;
 
 		Ok(None)
 	}
@@ -199,7 +235,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 
 		let balance = self.ext.balance(&address).map_err(|_| UserTrap::BalanceQueryError)?;
 		let value: H256 = balance.into();
-		self.memory.set(return_ptr, &*value)?;
+		self.set_result(return_ptr, &*value)?;
 		Ok(None)
 	}
 
@@ -280,9 +316,10 @@ impl<'a, 'b> Runtime<'a, 'b> {
 			.map_err(|_| UserTrap::InvalidGasState)?
 			.into();
 
+		self.trace_prepare();
 		match self.ext.create(&gas_left, &endowment, &code, vm::CreateContractAddress::FromSenderAndCodeHash) {
 			vm::ContractCreateResult::Created(address, gas_left) => {
-				self.memory.set(result_ptr, &*address)?;
+				self.set_result(result_ptr, &*address)?;
 				self.gas_counter = self.gas_limit - gas_left.low_u64();
 				trace!(target: "wasm", "runtime: create contract success (@{:?})", address);
 				Ok(Some(0i32.into()))
@@ -388,6 +425,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 
 		self.charge(|_| gas.into())?;
 
+		self.trace_prepare();
 		let call_result = self.ext.call(
 			&gas.into(),
 			match call_type { CallType::DelegateCall => &self.context.sender, _ => &self.context.address },
@@ -404,14 +442,14 @@ impl<'a, 'b> Runtime<'a, 'b> {
 				// cannot overflow, before making call gas_counter was incremented with gas, and gas_left < gas
 				self.gas_counter = self.gas_counter - gas_left.low_u64();
 
-				self.memory.set(result_ptr, &result)?;
+				self.set_result(result_ptr, &result)?;
 				Ok(Some(0i32.into()))
 			},
 			vm::MessageCallResult::Reverted(gas_left, _) => {
 				// cannot overflow, before making call gas_counter was incremented with gas, and gas_left < gas
 				self.gas_counter = self.gas_counter - gas_left.low_u64();
 
-				self.memory.set(result_ptr, &result)?;
+				self.set_result(result_ptr, &result)?;
 				Ok(Some((-1i32).into()))
 			},
 			vm::MessageCallResult::Failed  => {
@@ -480,6 +518,78 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		}
 	}
 
+	/// Write the result of an external to wasm memory, noting it in the trace.
+	fn set_result(&mut self, ptr: u32, data: &[u8]) -> Result<(), InterpreterError> {
+		self.memory.set(ptr, data)?;
+		if let Some(ref mut traced) = self.traced {
+			traced.mem_diff = Some((ptr as usize, data.to_vec()));
+		}
+		Ok(())
+	}
+
+	/// Start tracing the external about to be executed, if the tracer asks for it.
+	fn trace_external(&mut self, name: &str) {
+		if let Some(instruction) = traced_instruction(name) {
+			self.trace_instruction(instruction);
+		}
+	}
+
+	/// Start tracing an external as the given EVM instruction, if the tracer asks for it.
+	fn trace_instruction(&mut self, instruction: u8) {
+		// There is no program counter for host calls, externals are numbered instead.
+		let pc = self.externals_called;
+		self.externals_called += 1;
+
+		let gas_left = self.gas_limit.saturating_sub(self.gas_counter);
+		if self.ext.trace_next_instruction(pc, instruction, gas_left.into()) {
+			self.traced = Some(TracedExternal {
+				pc: pc,
+				instruction: instruction,
+				gas_counter: self.gas_counter,
+				prepared: false,
+				mem_diff: None,
+				store_diff: None,
+			});
+		}
+	}
+
+	/// Report the traced external with the gas charged so far.
+	/// Called right before calls and creates so that their subtraces attach to it.
+	fn trace_prepare(&mut self) {
+		if let Some(ref mut traced) = self.traced {
+			if !traced.prepared {
+				traced.prepared = true;
+				let gas_cost = self.gas_counter.saturating_sub(traced.gas_counter);
+				self.ext.trace_prepare_execute(traced.pc, traced.instruction, gas_cost.into());
+			}
+		}
+	}
+
+	/// Finish tracing the external with its outcome.
+	fn trace_executed(&mut self, result: &Result<Option<interpreter::RuntimeValue>, InterpreterError>) {
+		if self.traced.is_none() {
+			return;
+		}
+		self.trace_prepare();
+		let traced = self.traced.take().expect("checked to be some above; qed");
+
+		let stack_push = match *result {
+			Ok(Some(interpreter::RuntimeValue::I32(value))) => vec![U256::from(value as u32)],
+			Ok(Some(interpreter::RuntimeValue::I64(value))) => vec![U256::from(value as u64)],
+			Ok(_) | Err(interpreter::Error::User(UserTrap::Suicide)) => vec![],
+			// Failed operations are reported without an outcome, like in the EVM.
+			Err(_) => return,
+		};
+
+		let gas_left = self.gas_limit.saturating_sub(self.gas_counter);
+		self.ext.trace_executed(
+			gas_left.into(),
+			&stack_push,
+			traced.mem_diff.as_ref().map(|&(offset, ref data)| (offset, &data[..])),
+			traced.store_diff,
+		);
+	}
+
 	fn h256_at(&self, ptr: WasmPtr) -> Result<H256, InterpreterError> {
 		Ok(H256::from_slice(&ptr.slice(32, &*self.memory)
 			.map_err(|_| UserTrap::MemoryAccessViolation)?
@@ -553,6 +663,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 			.map_err(|_| UserTrap::BadUtf8)?;
 
 		trace!(target: "wasm", "Contract debug message: {}", msg);
+		self.ext.trace_debug_log(&msg);
 
 		Ok(None)
 	}
@@ -568,6 +679,11 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		&*self.memory
 	}
 
+	/// Bytes of linear memory handed out so far, stack space included.
+	pub fn allocated_memory(&self) -> u32 {
+		self.dynamic_top
+	}
+
 	fn mem_cmp(&mut self, context: InterpreterCallerContext)
 		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
 	{
@@ -666,24 +782,168 @@ impl<'a, 'b> Runtime<'a, 'b> {
 
 		let raw_payload = self.memory.get(payload_ptr, payload_len as usize)?;
 		let payload = panic_payload::decode(&raw_payload);
-		let msg = format!(
-			"{msg}, {file}:{line}:{col}",
-			msg = payload
-				.msg
-				.as_ref()
-				.map(String::as_ref)
-				.unwrap_or("<msg was stripped>"),
-			file = payload
-				.file
-				.as_ref()
-				.map(String::as_ref)
-				.unwrap_or("<unknown>"),
-			line = payload.line.unwrap_or(0),
-			col = payload.col.unwrap_or(0)
-		);
-		trace!(target: "wasm", "Contract custom panic message: {}", msg);
+		trace!(target: "wasm", "Contract custom panic message: {}", payload);
 
-		Err(UserTrap::Panic(msg).into())
+		Err(UserTrap::Panic(payload).into())
+	}
+
+	fn keccak256(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn keccak256(input_ptr: *const u8, input_len: u32, result_ptr: *mut u8);
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_len = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		if !self.ext.schedule().wasm.builtins {
+			trace!(target: "wasm", "Externals backed by builtins are not active");
+			return Err(UserTrap::Unknown.into());
+		}
+
+		let words = (input_len as u64 + 31) / 32;
+		self.charge(|schedule| schedule.sha3_gas as u64 + schedule.sha3_word_gas as u64 * words)?;
+
+		let hash = keccak(&self.memory.get(input_ptr, input_len as usize)?);
+		self.set_result(result_ptr, &*hash)?;
+
+		Ok(None)
+	}
+
+	fn sha256(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn sha256(input_ptr: *const u8, input_len: u32, result_ptr: *mut u8);
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_len = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		let input = self.memory.get(input_ptr, input_len as usize)?;
+		match self.builtin(SHA256_BUILTIN, &input)? {
+			Some(ref output) if output.len() == 32 => self.set_result(result_ptr, output)?,
+			_ => return Err(UserTrap::Other.into()),
+		}
+
+		Ok(None)
+	}
+
+	fn ecrecover(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn ecrecover(input_ptr: *const u8, result_ptr: *mut u8) -> i32;
+		//
+		// input is `hash ++ v ++ r ++ s`, 32 bytes each, result is a 20 byte address
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		let input = self.memory.get(input_ptr, 128)?;
+		match self.builtin(ECRECOVER_BUILTIN, &input)? {
+			Some(ref output) if output.len() == 32 => {
+				self.set_result(result_ptr, &output[12..])?;
+				Ok(Some(0i32.into()))
+			},
+			_ => Ok(Some((-1i32).into())),
+		}
+	}
+
+	/// Runs one of the alt_bn128 builtins on `input_len` bytes at `input_ptr`,
+	/// writing `result_len` bytes of output to `result_ptr`.
+	fn bn128(&mut self, builtin: &str, input_ptr: u32, input_len: u32, result_ptr: u32, result_len: usize)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		let input = self.memory.get(input_ptr, input_len as usize)?;
+		match self.builtin(builtin, &input)? {
+			Some(ref output) if output.len() == result_len => {
+				self.set_result(result_ptr, output)?;
+				Ok(Some(0i32.into()))
+			},
+			_ => Ok(Some((-1i32).into())),
+		}
+	}
+
+	fn bn128_add(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn bn128_add(input_ptr: *const u8, result_ptr: *mut u8) -> i32;
+		//
+		// input is two points (128 bytes), result is a point (64 bytes)
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		self.bn128(BN128_ADD_BUILTIN, input_ptr, 128, result_ptr, 64)
+	}
+
+	fn bn128_mul(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn bn128_mul(input_ptr: *const u8, result_ptr: *mut u8) -> i32;
+		//
+		// input is a point and a scalar (96 bytes), result is a point (64 bytes)
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		self.bn128(BN128_MUL_BUILTIN, input_ptr, 96, result_ptr, 64)
+	}
+
+	fn bn128_pairing(&mut self, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		//
+		// method signature:
+		//   fn bn128_pairing(input_ptr: *const u8, input_len: u32, result_ptr: *mut u8) -> i32;
+		//
+		// input is a sequence of (G1, G2) pairs (192 bytes each), result is a 32 byte word
+		//
+
+		let result_ptr = context.value_stack.pop_as::<i32>()? as u32;
+		let input_len = context.value_stack.pop_as::<i32>()? as u32;
+		let input_ptr = context.value_stack.pop_as::<i32>()? as u32;
+
+		self.bn128(BN128_PAIRING_BUILTIN, input_ptr, input_len, result_ptr, 32)
+	}
+
+	/// Runs the builtin contract with given name, charging gas as the chain prices it.
+	///
+	/// Returns `None` if the builtin failed on given input.
+	fn builtin(&mut self, name: &str, input: &[u8]) -> Result<Option<Vec<u8>>, InterpreterError> {
+		if !self.ext.schedule().wasm.builtins {
+			trace!(target: "wasm", "Externals backed by builtins are not active");
+			return Err(UserTrap::Unknown.into());
+		}
+
+		let cost = match self.ext.builtin_cost(name, input) {
+			Some(cost) => cost,
+			None => {
+				trace!(target: "wasm", "Builtin {} is not active", name);
+				return Err(UserTrap::Unknown.into());
+			}
+		};
+
+		if cost > U256::from(::std::u64::MAX) {
+			return Err(UserTrap::GasLimit.into());
+		}
+		self.charge(|_| cost.low_u64())?;
+
+		Ok(self.ext.builtin_execute(name, input).ok())
 	}
 
 	fn block_hash(&mut self, context: InterpreterCallerContext)
@@ -697,7 +957,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		trace!("Requesting block hash for block #{}", block_num);
 		let hash = self.ext.blockhash(&U256::from(block_num));
 
-		self.memory.set(return_ptr, &*hash)?;
+		self.set_result(return_ptr, &*hash)?;
 
 		Ok(None)
 	}
@@ -705,14 +965,14 @@ impl<'a, 'b> Runtime<'a, 'b> {
 	fn return_address_ptr(&mut self, ptr: u32, val: Address) -> Result<(), InterpreterError>
 	{
 		self.charge(|schedule| schedule.wasm.static_address as u64)?;
-		self.memory.set(ptr, &*val)?;
+		self.set_result(ptr, &*val)?;
 		Ok(())
 	}
 
 	fn return_u256_ptr(&mut self, ptr: u32, val: U256) -> Result<(), InterpreterError> {
 		let value: H256 = val.into();
 		self.charge(|schedule| schedule.wasm.static_u256 as u64)?;
-		self.memory.set(ptr, &*value)?;
+		self.set_result(ptr, &*value)?;
 		Ok(())
 	}
 
@@ -822,10 +1082,6 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		)
 	}
 
-	pub fn gas_rules(&self) -> &rules::Set {
-		&self.gas_rules
-	}
-
 	pub fn ext(&mut self) -> &mut vm::Ext {
 		self.ext
 	}
@@ -843,6 +1099,7 @@ impl<'a, 'b> Runtime<'a, 'b> {
 		if topic_count > 4 {
 			return Err(UserTrap::Log.into());
 		}
+		self.trace_instruction(0xa0 + topic_count as u8);
 
 		self.overflow_charge(|schedule|
 			{
@@ -872,6 +1129,23 @@ impl<'a, 'b> Runtime<'a, 'b> {
 impl<'a, 'b> interpreter::UserFunctionExecutor<UserTrap> for Runtime<'a, 'b> {
 	fn execute(&mut self, name: &str, context: InterpreterCallerContext)
 		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
+	{
+		let gas_counter = self.gas_counter;
+		self.trace_external(name);
+		let result = self.dispatch(name, context);
+		self.trace_executed(&result);
+		// Gas metering is injected into the contract code, it is not a host call.
+		if name != "gas" {
+			let gas_cost = self.gas_counter.saturating_sub(gas_counter);
+			self.ext.trace_host_call(name, gas_cost.into());
+		}
+		result
+	}
+}
+
+impl<'a, 'b> Runtime<'a, 'b> {
+	fn dispatch(&mut self, name: &str, context: InterpreterCallerContext)
+		-> Result<Option<interpreter::RuntimeValue>, InterpreterError>
 	{
 		match name {
 			"_ext_malloc" => {
@@ -936,6 +1210,24 @@ impl<'a, 'b> interpreter::UserFunctionExecutor<UserTrap> for Runtime<'a, 'b> {
 			"_blockhash" => {
 				self.block_hash(context)
 			},
+			"_keccak256" => {
+				self.keccak256(context)
+			},
+			"_sha256" => {
+				self.sha256(context)
+			},
+			"_ecrecover" => {
+				self.ecrecover(context)
+			},
+			"_bn128_add" => {
+				self.bn128_add(context)
+			},
+			"_bn128_mul" => {
+				self.bn128_mul(context)
+			},
+			"_bn128_pairing" => {
+				self.bn128_pairing(context)
+			},
 			"_coinbase" => {
 				self.coinbase(context)
 			},
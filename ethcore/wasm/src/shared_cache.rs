@@ -0,0 +1,101 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use hash::KECCAK_EMPTY;
+use heapsize::HeapSizeOf;
+use ethereum_types::H256;
+use parking_lot::Mutex;
+use memory_cache::MemoryLruCache;
+use parity_wasm::elements::Module;
+
+const DEFAULT_CACHE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Contract module with gas metering already injected.
+pub struct PreparedModule {
+	/// Instrumented module.
+	pub module: Module,
+	/// Offset of the call data in case of embedded params.
+	pub data_position: usize,
+}
+
+/// Cache key: the same code is instrumented differently depending on how it
+/// is called and on the gas costs in force.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+	code_hash: H256,
+	embedded: bool,
+	rules: [u32; 3],
+}
+
+// stub for a HeapSizeOf implementation.
+struct Prepared {
+	inner: Arc<PreparedModule>,
+	code_size: usize,
+}
+
+impl HeapSizeOf for Prepared {
+	fn heap_size_of_children(&self) -> usize {
+		// instrumented module is roughly the size of its code
+		self.code_size
+	}
+}
+
+/// Global cache for instrumented wasm modules
+pub struct SharedCache {
+	modules: Mutex<MemoryLruCache<Key, Prepared>>,
+}
+
+impl SharedCache {
+	/// Create a module cache with a maximum size in bytes to cache.
+	pub fn new(max_size: usize) -> Self {
+		SharedCache {
+			modules: Mutex::new(MemoryLruCache::new(max_size)),
+		}
+	}
+
+	/// Get the instrumented module for a contract, preparing it with `prepare` on a miss.
+	///
+	/// `rules` are the load/store, div and mul multipliers used for the gas metering.
+	pub fn module<F, E>(&self, code_hash: &H256, code_size: usize, embedded: bool, rules: [u32; 3], prepare: F) -> Result<Arc<PreparedModule>, E>
+		where F: FnOnce() -> Result<PreparedModule, E>
+	{
+		if code_hash == &KECCAK_EMPTY {
+			return prepare().map(Arc::new);
+		}
+
+		let key = Key {
+			code_hash: code_hash.clone(),
+			embedded: embedded,
+			rules: rules,
+		};
+
+		if let Some(prepared) = self.modules.lock().get_mut(&key) {
+			return Ok(prepared.inner.clone());
+		}
+
+		let prepared = Arc::new(prepare()?);
+		self.modules.lock().insert(key, Prepared { inner: prepared.clone(), code_size: code_size });
+
+		Ok(prepared)
+	}
+}
+
+impl Default for SharedCache {
+	fn default() -> Self {
+		SharedCache::new(DEFAULT_CACHE_SIZE)
+	}
+}
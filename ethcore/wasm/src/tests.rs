@@ -351,6 +351,40 @@ fn call_msg() {
 	assert_eq!(gas_left, U256::from(95_699));
 }
 
+// Same as `call_msg`, but checks the host calls reported to the VM tracer
+#[test]
+fn call_msg_traced() {
+	::ethcore_logger::init_log();
+
+	let sender: Address = "01030507090b0d0f11131517191b1d1f21232527".parse().unwrap();
+	let receiver: Address = "0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6".parse().unwrap();
+	let contract_address: Address = "0d461d4174b4ae35775c4a342f1e5e1e4e6c4db5".parse().unwrap();
+
+	let mut params = ActionParams::default();
+	params.sender = sender.clone();
+	params.address = receiver.clone();
+	params.code_address = contract_address.clone();
+	params.gas = U256::from(100_000);
+	params.code = Some(Arc::new(load_sample!("call.wasm")));
+	params.data = Some(Vec::new());
+
+	let mut ext = FakeExt::new();
+	ext.balances.insert(receiver.clone(), U256::from(10000000000u64));
+	ext.tracing = true;
+
+	{
+		let mut interpreter = wasm_interpreter();
+		interpreter.exec(params, &mut ext).expect("Interpreter to execute without any errors");
+	}
+
+	let calls: Vec<_> = ext.traces.iter().filter(|trace| trace.instruction == 0xf1).collect();
+	assert_eq!(calls.len(), 1);
+	// The forwarded gas is returned by the fake call, so the cost includes it until the call returns.
+	assert!(calls[0].gas_cost >= U256::from(33_000));
+	// Successful calls return 0 to the contract.
+	assert_eq!(calls[0].stack_push, Some(vec![U256::zero()]));
+}
+
 #[test]
 fn call_code() {
 	::ethcore_logger::init_log();
@@ -493,6 +527,33 @@ fn storage_read() {
 	assert_eq!(gas_left, U256::from(96_463));
 }
 
+// Same as `storage_read`, but checks the host calls reported to the VM tracer
+#[test]
+fn storage_read_traced() {
+	let code = load_sample!("storage_read.wasm");
+	let address: Address = "0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6".parse().unwrap();
+
+	let mut params = ActionParams::default();
+	params.gas = U256::from(100_000);
+	params.code = Some(Arc::new(code));
+	let mut ext = FakeExt::new();
+	ext.store.insert("0100000000000000000000000000000000000000000000000000000000000000".into(), address.into());
+	ext.tracing = true;
+
+	{
+		let mut interpreter = wasm_interpreter();
+		interpreter.exec(params, &mut ext).expect("Interpreter to execute without any errors");
+	}
+
+	let sload = ext.traces.iter().find(|trace| trace.instruction == 0x54).expect("storage read is traced");
+	let value: H256 = address.into();
+	assert_eq!(sload.gas_cost, U256::from(ext.schedule.sload_gas));
+	assert_eq!(sload.stack_push, Some(vec![]));
+	assert_eq!(sload.mem_diff.as_ref().map(|&(_, ref data)| data.clone()), Some(value.to_vec()));
+	assert_eq!(sload.store_diff, None);
+	assert!(ext.traces.iter().enumerate().all(|(i, trace)| trace.pc == i));
+}
+
 // Tests keccak calculation
 // keccak.wasm runs wasm-std::keccak function on data param and returns hash
 #[test]
@@ -901,3 +962,68 @@ fn events() {
 	assert_eq!(&result, b"gnihtemos");
 	assert_eq!(gas_left, U256::from(79_206));
 }
+
+#[test]
+fn builtin_externals_inactive() {
+	::ethcore_logger::init_log();
+
+	// Module whose `_call` calls `_sha256(0, 0, 0)`.
+	let code = vec![
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0b, 0x02, 0x60, 0x03, 0x7f, 0x7f, 0x7f,
+		0x00, 0x60, 0x01, 0x7f, 0x00, 0x02, 0x1d, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d,
+		0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x07, 0x5f, 0x73, 0x68, 0x61, 0x32,
+		0x35, 0x36, 0x00, 0x00, 0x03, 0x02, 0x01, 0x01, 0x07, 0x09, 0x01, 0x05, 0x5f, 0x63, 0x61, 0x6c,
+		0x6c, 0x00, 0x01, 0x0a, 0x0c, 0x01, 0x0a, 0x00, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0x10, 0x00,
+		0x0b,
+	];
+
+	let mut params = ActionParams::default();
+	params.gas = U256::from(100_000);
+	params.code = Some(Arc::new(code));
+	params.data = Some(Vec::new());
+
+	// Externals backed by builtins are not active in the default schedule.
+	let mut ext = FakeExt::new();
+	let result = wasm_interpreter().exec(params, &mut ext);
+
+	match result {
+		Err(vm::Error::Wasm(_)) => {},
+		_ => panic!("Unexpected result {:?}", result),
+	}
+}
+
+#[test]
+fn keccak_external_before_activation() {
+	::ethcore_logger::init_log();
+
+	// Module whose `_call` calls `_keccak256(0, 0, 0)`.
+	let code = vec![
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0b, 0x02, 0x60, 0x03, 0x7f, 0x7f, 0x7f,
+		0x00, 0x60, 0x01, 0x7f, 0x00, 0x02, 0x20, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d,
+		0x6f, 0x72, 0x79, 0x02, 0x00, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x0a, 0x5f, 0x6b, 0x65, 0x63, 0x63,
+		0x61, 0x6b, 0x32, 0x35, 0x36, 0x00, 0x00, 0x03, 0x02, 0x01, 0x01, 0x07, 0x09, 0x01, 0x05, 0x5f,
+		0x63, 0x61, 0x6c, 0x6c, 0x00, 0x01, 0x0a, 0x0c, 0x01, 0x0a, 0x00, 0x41, 0x00, 0x41, 0x00, 0x41,
+		0x00, 0x10, 0x00, 0x0b,
+	];
+
+	let mut params = ActionParams::default();
+	params.gas = U256::from(100_000);
+	params.code = Some(Arc::new(code));
+	params.data = Some(Vec::new());
+
+	// not active in the default schedule.
+	let mut ext = FakeExt::new();
+	let result = wasm_interpreter().exec(params.clone(), &mut ext);
+	match result {
+		Err(vm::Error::Wasm(_)) => {},
+		_ => panic!("Unexpected result {:?}", result),
+	}
+
+	let mut ext = FakeExt::new();
+	ext.schedule.wasm.builtins = true;
+	let result = wasm_interpreter().exec(params, &mut ext);
+	match result {
+		Ok(GasLeft::Known(_)) | Ok(GasLeft::NeedsReturn { .. }) => {},
+		_ => panic!("Unexpected result {:?}", result),
+	}
+}
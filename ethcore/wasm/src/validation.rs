@@ -0,0 +1,279 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deployment-time validation of wasm contract modules
+
+use std::fmt;
+use parity_wasm::elements::{self, External, Opcode, ValueType, BlockType, Type, ResizableLimits};
+use parity_wasm::interpreter::UserFunctionDescriptor;
+use vm::WasmCosts;
+use env;
+
+/// Reason for rejecting a contract module.
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+	/// Module could not be deserialized.
+	Deserialization(String),
+	/// Module imports from a module other than `env`.
+	ForeignImport(String),
+	/// Module imports a function the runtime does not provide.
+	UnknownFunction(String),
+	/// Module declares more initial linear memory than allowed.
+	MemoryLimit {
+		/// Pages requested by the module.
+		requested: u32,
+		/// Maximum number of pages allowed.
+		max: u32,
+	},
+	/// Module uses floating point types or instructions.
+	FloatingPoint,
+}
+
+impl fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::ValidationError::*;
+		match *self {
+			Deserialization(ref e) => write!(f, "Error deserializing contract code ({})", e),
+			ForeignImport(ref module) => write!(f, "Contract imports from unknown module '{}'", module),
+			UnknownFunction(ref name) => write!(f, "Contract imports unknown function '{}'", name),
+			MemoryLimit { requested, max } => write!(f, "Contract requests {} memory pages, at most {} are allowed", requested, max),
+			FloatingPoint => write!(f, "Contract uses floating point operations"),
+		}
+	}
+}
+
+/// Check a deserialized module against the rules for deployable contracts.
+pub fn validate_module(module: &elements::Module, costs: &WasmCosts) -> Result<(), ValidationError> {
+	if let Some(imports) = module.import_section() {
+		for entry in imports.entries() {
+			if entry.module() != "env" {
+				return Err(ValidationError::ForeignImport(entry.module().to_owned()));
+			}
+
+			match *entry.external() {
+				External::Function(_) if !is_env_function(entry.field(), costs) => {
+					return Err(ValidationError::UnknownFunction(entry.field().to_owned()));
+				},
+				External::Memory(ref memory) => check_memory(memory.limits(), costs)?,
+				External::Global(ref global) if is_float(global.content_type()) => {
+					return Err(ValidationError::FloatingPoint);
+				},
+				_ => {},
+			}
+		}
+	}
+
+	if let Some(memory) = module.memory_section() {
+		for entry in memory.entries() {
+			check_memory(entry.limits(), costs)?;
+		}
+	}
+
+	if let Some(types) = module.type_section() {
+		for ty in types.types() {
+			let Type::Function(ref func) = *ty;
+			if func.params().iter().cloned().any(is_float) || func.return_type().map_or(false, is_float) {
+				return Err(ValidationError::FloatingPoint);
+			}
+		}
+	}
+
+	if let Some(globals) = module.global_section() {
+		if globals.entries().iter().any(|global| is_float(global.global_type().content_type())) {
+			return Err(ValidationError::FloatingPoint);
+		}
+	}
+
+	if let Some(code) = module.code_section() {
+		for body in code.bodies() {
+			if body.locals().iter().any(|local| is_float(local.value_type())) {
+				return Err(ValidationError::FloatingPoint);
+			}
+			if body.code().elements().iter().any(is_float_opcode) {
+				return Err(ValidationError::FloatingPoint);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn is_env_function(name: &str, costs: &WasmCosts) -> bool {
+	if !costs.builtins && env::BUILTIN_EXTERNALS.contains(&name) {
+		return false;
+	}
+
+	env::SIGNATURES.iter().any(|descriptor| match *descriptor {
+		UserFunctionDescriptor::Static(signature, _, _) => signature == name,
+		_ => false,
+	})
+}
+
+// Only the initial size is checked, the declared maximum is not allocated upfront.
+fn check_memory(limits: &ResizableLimits, costs: &WasmCosts) -> Result<(), ValidationError> {
+	let requested = limits.initial();
+	if requested > costs.max_memory_pages {
+		return Err(ValidationError::MemoryLimit { requested: requested, max: costs.max_memory_pages });
+	}
+	Ok(())
+}
+
+fn is_float(value_type: ValueType) -> bool {
+	match value_type {
+		ValueType::F32 | ValueType::F64 => true,
+		_ => false,
+	}
+}
+
+fn is_float_opcode(opcode: &Opcode) -> bool {
+	use parity_wasm::elements::Opcode::*;
+
+	match *opcode {
+		Block(BlockType::Value(value_type)) |
+		Loop(BlockType::Value(value_type)) |
+		If(BlockType::Value(value_type)) => is_float(value_type),
+
+		F32Load(_, _) | F64Load(_, _) | F32Store(_, _) | F64Store(_, _) |
+		F32Const(_) | F64Const(_) |
+
+		F32Eq | F32Ne | F32Lt | F32Gt | F32Le | F32Ge |
+		F64Eq | F64Ne | F64Lt | F64Gt | F64Le | F64Ge |
+
+		F32Abs | F32Neg | F32Ceil | F32Floor | F32Trunc | F32Nearest | F32Sqrt |
+		F32Add | F32Sub | F32Mul | F32Div | F32Min | F32Max | F32Copysign |
+		F64Abs | F64Neg | F64Ceil | F64Floor | F64Trunc | F64Nearest | F64Sqrt |
+		F64Add | F64Sub | F64Mul | F64Div | F64Min | F64Max | F64Copysign |
+
+		I32TruncSF32 | I32TruncUF32 | I32TruncSF64 | I32TruncUF64 |
+		I64TruncSF32 | I64TruncUF32 | I64TruncSF64 | I64TruncUF64 |
+		F32ConvertSI32 | F32ConvertUI32 | F32ConvertSI64 | F32ConvertUI64 | F32DemoteF64 |
+		F64ConvertSI32 | F64ConvertUI32 | F64ConvertSI64 | F64ConvertUI64 | F64PromoteF32 |
+		I32ReinterpretF32 | I64ReinterpretF64 | F32ReinterpretI32 | F64ReinterpretI64 => true,
+
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use parity_wasm::builder;
+	use parity_wasm::elements::{Opcode, Opcodes};
+	use vm::WasmCosts;
+	use super::*;
+
+	#[test]
+	fn accepts_known_imports() {
+		let module = builder::module()
+			.import().module("env").field("_storage_read").external().func(0).build()
+			.build();
+
+		assert_eq!(validate_module(&module, &WasmCosts::default()), Ok(()));
+	}
+
+	#[test]
+	fn rejects_unknown_imports() {
+		let module = builder::module()
+			.import().module("env").field("_not_a_function").external().func(0).build()
+			.build();
+		assert_eq!(
+			validate_module(&module, &WasmCosts::default()),
+			Err(ValidationError::UnknownFunction("_not_a_function".into()))
+		);
+
+		let module = builder::module()
+			.import().module("other").field("_storage_read").external().func(0).build()
+			.build();
+		assert_eq!(
+			validate_module(&module, &WasmCosts::default()),
+			Err(ValidationError::ForeignImport("other".into()))
+		);
+	}
+
+	#[test]
+	fn rejects_inactive_builtin_externals() {
+		let module = builder::module()
+			.import().module("env").field("_sha256").external().func(0).build()
+			.build();
+		assert_eq!(
+			validate_module(&module, &WasmCosts::default()),
+			Err(ValidationError::UnknownFunction("_sha256".into()))
+		);
+
+		let mut costs = WasmCosts::default();
+		costs.builtins = true;
+		assert_eq!(validate_module(&module, &costs), Ok(()));
+
+		let module = builder::module()
+			.import().module("env").field("_keccak256").external().func(0).build()
+			.build();
+		assert_eq!(
+			validate_module(&module, &WasmCosts::default()),
+			Err(ValidationError::UnknownFunction("_keccak256".into()))
+		);
+		assert_eq!(validate_module(&module, &costs), Ok(()));
+	}
+
+	#[test]
+	fn rejects_excessive_memory() {
+		let costs = WasmCosts::default();
+		let module = builder::module()
+			.memory().with_min(costs.max_memory_pages + 1).build()
+			.build();
+
+		assert_eq!(
+			validate_module(&module, &costs),
+			Err(ValidationError::MemoryLimit { requested: costs.max_memory_pages + 1, max: costs.max_memory_pages })
+		);
+	}
+
+	#[test]
+	fn accepts_large_maximum_memory() {
+		let costs = WasmCosts::default();
+		let module = builder::module()
+			.memory().with_min(1).with_max(Some(costs.max_memory_pages * 2)).build()
+			.build();
+
+		assert_eq!(validate_module(&module, &costs), Ok(()));
+	}
+
+	#[test]
+	fn rejects_floats() {
+		let module = builder::module()
+			.function()
+				.signature().param().i32().build()
+				.body()
+					.with_opcodes(Opcodes::new(vec![
+						Opcode::GetLocal(0),
+						Opcode::F32ConvertSI32,
+						Opcode::Drop,
+						Opcode::End,
+					]))
+					.build()
+				.build()
+			.build();
+
+		assert_eq!(validate_module(&module, &WasmCosts::default()), Err(ValidationError::FloatingPoint));
+
+		let module = builder::module()
+			.function()
+				.signature().param().f64().build()
+				.body().build()
+				.build()
+			.build();
+
+		assert_eq!(validate_module(&module, &WasmCosts::default()), Err(ValidationError::FloatingPoint));
+	}
+}
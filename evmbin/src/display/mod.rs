@@ -22,6 +22,7 @@ use ethereum_types::U256;
 pub mod json;
 pub mod std_json;
 pub mod simple;
+pub mod wasm;
 
 /// Formats duration into human readable format.
 pub fn format_time(time: &Duration) -> String {
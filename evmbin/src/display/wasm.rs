@@ -0,0 +1,231 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wasm contract profiling output.
+
+use std::cmp;
+use std::collections::BTreeMap;
+
+use ethereum_types::U256;
+use ethcore::trace;
+
+use display;
+use info as vm;
+
+/// Gas spent in a single kind of host call.
+#[derive(Debug, Default, PartialEq)]
+pub struct HostCall {
+	/// Number of times the host function was called.
+	pub calls: usize,
+	/// Total gas charged by the host function.
+	pub gas: U256,
+}
+
+/// Summary of a wasm contract execution.
+#[derive(Debug, Default)]
+pub struct Profile {
+	/// Gas breakdown per host call, keyed by the name of the host function.
+	pub host_calls: BTreeMap<String, HostCall>,
+	/// Peak linear memory allocated by a contract, in bytes.
+	pub memory: usize,
+	json: bool,
+}
+
+impl Profile {
+	fn merge(&mut self, other: Profile) {
+		for (name, stats) in other.host_calls {
+			let entry = self.host_calls.entry(name).or_insert_with(HostCall::default);
+			entry.calls += stats.calls;
+			entry.gas = entry.gas.saturating_add(stats.gas);
+		}
+		self.memory = cmp::max(self.memory, other.memory);
+	}
+
+	fn print(&self) {
+		if self.json {
+			let calls = self.host_calls.iter()
+				.map(|(name, stats)| format!(
+					"\"{name}\":{{\"calls\":{calls},\"gas\":{gas}}}",
+					name = name,
+					calls = stats.calls,
+					gas = display::u256_as_str(&stats.gas),
+				))
+				.collect::<Vec<_>>();
+			println!("{{\"hostCalls\":{{{}}},\"memory\":{}}}", calls.join(","), self.memory);
+		} else {
+			println!("Host calls:");
+			for (name, stats) in &self.host_calls {
+				println!("  {}: {} calls, gas {:x}", name, stats.calls, stats.gas);
+			}
+			println!("Memory: {} bytes", self.memory);
+		}
+	}
+}
+
+/// Informant collecting a wasm execution profile on top of another informant.
+pub struct Informant<T> {
+	inner: T,
+	profile: Profile,
+	inner_tracing: bool,
+}
+
+impl<T: vm::Informant> Informant<T> {
+	/// Wrap given informant, printing the profile as JSON if `json` is set.
+	pub fn new(inner: T, json: bool) -> Self {
+		Informant {
+			inner: inner,
+			profile: Profile { json: json, ..Default::default() },
+			inner_tracing: false,
+		}
+	}
+}
+
+impl<T: vm::Informant> vm::Informant for Informant<T> {
+	fn before_test(&mut self, name: &str, action: &str) {
+		self.inner.before_test(name, action)
+	}
+
+	fn set_gas(&mut self, gas: U256) {
+		self.inner.set_gas(gas)
+	}
+
+	fn finish(result: vm::RunResult<Self::Output>) {
+		let (result, profile) = match result {
+			Ok(success) => {
+				let (traces, profile) = split(success.traces);
+				(Ok(vm::Success {
+					state_root: success.state_root,
+					gas_used: success.gas_used,
+					output: success.output,
+					time: success.time,
+					traces: traces,
+				}), profile)
+			},
+			Err(failure) => {
+				let (traces, profile) = split(failure.traces);
+				(Err(vm::Failure {
+					gas_used: failure.gas_used,
+					error: failure.error,
+					time: failure.time,
+					traces: traces,
+				}), profile)
+			},
+		};
+
+		T::finish(result);
+		if let Some(profile) = profile {
+			profile.print();
+		}
+	}
+}
+
+fn split<T>(traces: Option<(Option<T>, Profile)>) -> (Option<T>, Option<Profile>) {
+	match traces {
+		Some((traces, profile)) => (traces, Some(profile)),
+		None => (None, None),
+	}
+}
+
+impl<T: vm::Informant> trace::VMTracer for Informant<T> {
+	type Output = (Option<T::Output>, Profile);
+
+	fn trace_next_instruction(&mut self, pc: usize, instruction: u8, current_gas: U256) -> bool {
+		self.inner_tracing = self.inner.trace_next_instruction(pc, instruction, current_gas);
+		true
+	}
+
+	fn trace_prepare_execute(&mut self, pc: usize, instruction: u8, gas_cost: U256) {
+		if self.inner_tracing {
+			self.inner.trace_prepare_execute(pc, instruction, gas_cost)
+		}
+	}
+
+	fn trace_executed(&mut self, gas_used: U256, stack_push: &[U256], mem_diff: Option<(usize, &[u8])>, store_diff: Option<(U256, U256)>) {
+		if self.inner_tracing {
+			self.inner.trace_executed(gas_used, stack_push, mem_diff, store_diff)
+		}
+	}
+
+	fn trace_debug_log(&mut self, message: &str) {
+		self.inner.trace_debug_log(message)
+	}
+
+	fn trace_memory_usage(&mut self, bytes: usize) {
+		self.profile.memory = cmp::max(self.profile.memory, bytes);
+		self.inner.trace_memory_usage(bytes)
+	}
+
+	fn trace_host_call(&mut self, name: &str, gas_cost: U256) {
+		let entry = self.profile.host_calls.entry(name.to_owned()).or_insert_with(HostCall::default);
+		entry.calls += 1;
+		entry.gas = entry.gas.saturating_add(gas_cost);
+		self.inner.trace_host_call(name, gas_cost)
+	}
+
+	fn prepare_subtrace(&self, code: &[u8]) -> Self where Self: Sized {
+		Informant {
+			inner: self.inner.prepare_subtrace(code),
+			profile: Profile { json: self.profile.json, ..Default::default() },
+			inner_tracing: false,
+		}
+	}
+
+	fn done_subtrace(&mut self, sub: Self) {
+		self.profile.merge(sub.profile);
+		self.inner.done_subtrace(sub.inner)
+	}
+
+	fn drain(self) -> Option<Self::Output> {
+		Some((self.inner.drain(), self.profile))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethcore::trace::VMTracer;
+	use display::simple;
+	use super::*;
+
+	#[test]
+	fn should_collect_host_calls_and_memory() {
+		let mut informant = Informant::new(simple::Informant::default(), false);
+
+		assert!(informant.trace_next_instruction(0, 0x54, 100.into()));
+		informant.trace_prepare_execute(0, 0x54, 200.into());
+		informant.trace_executed(0.into(), &[], None, None);
+		informant.trace_host_call("_storage_read", 200.into());
+
+		let mut sub = informant.prepare_subtrace(b"\0asm\x01\0\0\0");
+		sub.trace_host_call("_storage_read", 200.into());
+		sub.trace_host_call("_debug", 0.into());
+		sub.trace_memory_usage(4096);
+		informant.done_subtrace(sub);
+
+		// EVM code called from the contract does not count as host calls.
+		let mut sub = informant.prepare_subtrace(&[0x60, 0x00]);
+		sub.trace_next_instruction(0, 0x60, 100.into());
+		sub.trace_prepare_execute(0, 0x60, 3.into());
+		informant.done_subtrace(sub);
+
+		informant.trace_memory_usage(1024);
+
+		let (_, profile) = informant.drain().unwrap();
+		assert_eq!(profile.host_calls.len(), 2);
+		assert_eq!(profile.host_calls["_storage_read"], HostCall { calls: 2, gas: 400.into() });
+		assert_eq!(profile.host_calls["_debug"], HostCall { calls: 1, gas: 0.into() });
+		assert_eq!(profile.memory, 4096);
+	}
+}
@@ -0,0 +1,192 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State test generation.
+
+use std::fs;
+use std::path::Path;
+use serde_json::{self, Map, Value};
+use ethereum_types::Address;
+use bytes::ToPretty;
+use ethcore::client::{EnvInfo, EvmTestClient, TransactResult};
+use ethcore::pod_state::PodState;
+use ethcore::trace::NoopVMTracer;
+use ethjson;
+use ethjson::state::test::ForkSpec;
+use ethjson::uint::Uint;
+use ethkey::KeyPair;
+use transaction::{Action, Transaction};
+
+/// Secret key signing the transaction of generated tests.
+pub const TEST_SECRET: &'static str = "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+
+/// Fork the post state of generated tests is computed for.
+pub const TEST_FORK: ForkSpec = ForkSpec::Byzantium;
+
+/// Build a state test running `transaction` from `sender` on top of `pre_state`.
+///
+/// The transaction has to be signed again, so the sender account is moved to the address
+/// of a well-known test key. Block hashes are the ones state tests use rather than the
+/// chain ones, and the post state root is computed with Byzantium rules.
+pub fn state_test(
+	name: &str,
+	env_info: &EnvInfo,
+	pre_state: PodState,
+	sender: Address,
+	transaction: Transaction,
+) -> Result<Value, String> {
+	let keypair = KeyPair::from_secret(TEST_SECRET.parse().expect("TEST_SECRET is a valid secret; qed"))
+		.expect("TEST_SECRET is a valid secret; qed");
+
+	let mut accounts = pre_state.drain();
+	if let Some(account) = accounts.remove(&sender) {
+		accounts.insert(keypair.address(), account);
+	}
+	let pre_state = PodState::from(accounts);
+
+	let env = ethjson::vm::Env {
+		author: env_info.author.into(),
+		difficulty: Uint(env_info.difficulty),
+		gas_limit: Uint(env_info.gas_limit),
+		number: Uint(env_info.number.into()),
+		timestamp: Uint(env_info.timestamp.into()),
+	};
+
+	let spec = EvmTestClient::spec_from_json(&TEST_FORK).expect("Spec for TEST_FORK exists; qed");
+	let mut client = EvmTestClient::from_pod_state(spec, pre_state.clone()).map_err(|e| e.to_string())?;
+	let signed = transaction.clone().sign(keypair.secret(), None);
+	let post_root = match client.transact(&env.into(), signed, NoopVMTracer) {
+		TransactResult::Ok { state_root, .. } => state_root,
+		TransactResult::Err { error, .. } => return Err(format!("Transaction is invalid on the touched state: {}", error)),
+	};
+
+	let pre = pre_state.get().iter().map(|(address, account)| {
+		let storage = account.storage.iter()
+			.map(|(key, value)| (format!("0x{:?}", key), Value::String(format!("0x{:?}", value))))
+			.collect::<Map<_, _>>();
+
+		(format!("{:?}", address), json!({
+			"balance": format!("0x{:x}", account.balance),
+			"code": format!("0x{}", account.code.as_ref().map_or_else(String::new, |code| code.to_hex())),
+			"nonce": format!("0x{:x}", account.nonce),
+			"storage": storage,
+		}))
+	}).collect::<Map<_, _>>();
+
+	let to = match transaction.action {
+		Action::Create => String::new(),
+		Action::Call(ref address) => format!("{:?}", address),
+	};
+
+	let test = json!({
+		"env": {
+			"currentCoinbase": format!("{:?}", env_info.author),
+			"currentDifficulty": format!("0x{:x}", env_info.difficulty),
+			"currentGasLimit": format!("0x{:x}", env_info.gas_limit),
+			"currentNumber": format!("0x{:x}", env_info.number),
+			"currentTimestamp": format!("0x{:x}", env_info.timestamp),
+		},
+		"pre": pre,
+		"transaction": {
+			"data": [format!("0x{}", transaction.data.to_hex())],
+			"gasLimit": [format!("0x{:x}", transaction.gas)],
+			"gasPrice": format!("0x{:x}", transaction.gas_price),
+			"nonce": format!("0x{:x}", transaction.nonce),
+			"secretKey": TEST_SECRET,
+			"to": to,
+			"value": [format!("0x{:x}", transaction.value)],
+		},
+		"post": {
+			format!("{:?}", TEST_FORK): [{
+				"hash": format!("0x{:?}", post_root),
+				"indexes": { "data": 0, "gas": 0, "value": 0 },
+			}],
+		},
+	});
+
+	let mut tests = Map::new();
+	tests.insert(name.to_owned(), test);
+	Ok(Value::Object(tests))
+}
+
+/// Write the state test built by `state_test` to a file.
+pub fn write_state_test(
+	path: &Path,
+	name: &str,
+	env_info: &EnvInfo,
+	pre_state: PodState,
+	sender: Address,
+	transaction: Transaction,
+) -> Result<(), String> {
+	let test = state_test(name, env_info, pre_state, sender, transaction)?;
+	let file = fs::File::create(path).map_err(|e| format!("Unable to create {:?}: {}", path, e))?;
+	serde_json::to_writer_pretty(file, &test).map_err(|e| format!("Unable to write {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethereum_types::Address;
+	use ethcore::client::{EvmTestClient, TransactResult};
+	use ethcore::trace::NoopVMTracer;
+	use ethjson::state::test::Test;
+	use transaction::{Action, Transaction};
+	use super::*;
+
+	#[test]
+	fn should_generate_passing_state_test() {
+		let sender = Address::from(0x10);
+		let contract = Address::from(0x20);
+		// SSTORE(0, CALLER)
+		let pre: ethjson::blockchain::State = serde_json::from_str(r#"{
+			"0000000000000000000000000000000000000010": {
+				"balance": "0x3b9aca00", "code": "0x", "nonce": "0x03", "storage": {}
+			},
+			"0000000000000000000000000000000000000020": {
+				"balance": "0x00", "code": "0x3360005500", "nonce": "0x00", "storage": { "0x01": "0x02" }
+			}
+		}"#).unwrap();
+
+		let env_info = EnvInfo {
+			number: 5,
+			gas_limit: 1_000_000.into(),
+			..Default::default()
+		};
+		let transaction = Transaction {
+			nonce: 3.into(),
+			gas_price: 1.into(),
+			gas: 100_000.into(),
+			action: Action::Call(contract),
+			value: 0.into(),
+			data: vec![],
+		};
+
+		let test = state_test("dump", &env_info, pre.into(), sender, transaction).unwrap();
+		let test: Test = serde_json::from_value(test).unwrap();
+
+		for (name, test) in test {
+			assert_eq!(name, "dump");
+			let spec = EvmTestClient::spec_from_json(&TEST_FORK).unwrap();
+			let mut client = EvmTestClient::from_pod_state(spec, test.pre_state.into()).unwrap();
+			let post = &test.post_states[&TEST_FORK][0];
+			let transaction = test.transaction.select(&post.indexes).into();
+			match client.transact(&test.env.into(), transaction, NoopVMTracer) {
+				TransactResult::Ok { state_root, .. } => assert_eq!(state_root, post.hash.clone().into()),
+				TransactResult::Err { error, .. } => panic!("Unexpected error: {:?}", error),
+			}
+		}
+	}
+}
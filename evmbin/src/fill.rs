@@ -0,0 +1,260 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State test filling.
+
+use std::collections::{BTreeMap, BTreeSet};
+use serde_json::{self, Map, Value};
+use ethereum_types::{H256, U256, Address};
+use ethcore::client::{EnvInfo, EvmTestClient, TransactResult, logs_hash};
+use ethcore::pod_state::PodState;
+use ethcore::spec::Spec;
+use ethcore::trace::NoopVMTracer;
+use ethjson::state::filler::{Filler, State, AccountExpect};
+use ethjson::state::test::PostStateIndexes;
+use ethjson::uint::Uint;
+
+/// Fill the state tests described by `filler`.
+///
+/// Every transaction covered by an expectation is executed on each fork the expectation lists
+/// and checked against it. The environment, pre state and transaction of the filler are copied
+/// as they are, the post section gets the resulting state roots and logs hashes.
+///
+/// If `spec` is given, it replaces the built-in spec of every fork.
+pub fn fill(filler: Value, spec: Option<&Spec>) -> Result<Value, String> {
+	let tests: Filler = serde_json::from_value(filler.clone()).map_err(|e| format!("Invalid filler: {}", e))?;
+
+	let mut filled = Map::new();
+	for (name, test) in tests {
+		let post = fill_test(&name, test, spec)?;
+		let raw = &filler[&name];
+		filled.insert(name.clone(), json!({
+			"env": raw["env"].clone(),
+			"pre": raw["pre"].clone(),
+			"transaction": raw["transaction"].clone(),
+			"post": post,
+		}));
+	}
+
+	Ok(Value::Object(filled))
+}
+
+fn fill_test(name: &str, test: State, spec: Option<&Spec>) -> Result<Value, String> {
+	let env_info: EnvInfo = test.env.into();
+	let pre: PodState = test.pre_state.into();
+	let transaction = test.transaction;
+
+	let mut indexes = Vec::new();
+	for data in 0..transaction.data.len() as u64 {
+		for gas in 0..transaction.gas_limit.len() as u64 {
+			for value in 0..transaction.value.len() as u64 {
+				indexes.push((data, gas, value));
+			}
+		}
+	}
+
+	let mut results = BTreeMap::new();
+	for expect in test.expect {
+		for network in &expect.network {
+			let spec = spec.or_else(|| EvmTestClient::spec_from_json(network))
+				.ok_or_else(|| format!("{}: no spec for {:?}", name, network))?;
+
+			for &(data, gas, value) in indexes.iter().filter(|&&(d, g, v)| expect.indexes.matches(d, g, v)) {
+				let info = format!("{} | {:?} (data: {}, gas: {}, value: {})", name, network, data, gas, value);
+				let selected = transaction.select(&PostStateIndexes { data, gas, value });
+
+				let mut client = EvmTestClient::from_pod_state(spec, pre.clone())
+					.map_err(|e| format!("{}: {}", info, e))?;
+				let (state_root, logs) = match client.transact(&env_info, selected.into(), NoopVMTracer) {
+					TransactResult::Ok { state_root, logs, .. } => (state_root, logs_hash(&logs)),
+					TransactResult::Err { state_root, .. } => (state_root, logs_hash(&[])),
+				};
+				check(&client, &expect.result).map_err(|e| format!("{}: {}", info, e))?;
+
+				results.entry(format!("{:?}", network))
+					.or_insert_with(BTreeMap::new)
+					.insert((data, gas, value), (state_root, logs));
+			}
+		}
+	}
+
+	let post = results.into_iter().map(|(network, states)| {
+		let states = states.into_iter().map(|((data, gas, value), (state_root, logs))| json!({
+			"hash": format!("0x{:?}", state_root),
+			"logs": format!("0x{:?}", logs),
+			"indexes": { "data": data, "gas": gas, "value": value },
+		})).collect();
+		(network, Value::Array(states))
+	}).collect::<Map<_, _>>();
+
+	Ok(Value::Object(post))
+}
+
+fn check(client: &EvmTestClient, expected: &BTreeMap<::ethjson::hash::Address, AccountExpect>) -> Result<(), String> {
+	let accounts: BTreeMap<Address, BTreeSet<H256>> = expected.iter().map(|(address, account)| {
+		let keys = account.storage.as_ref().map_or_else(BTreeSet::new, |storage| {
+			storage.keys().map(to_h256).collect()
+		});
+		(address.clone().into(), keys)
+	}).collect();
+	let post = client.pod_state_subset(&accounts).map_err(|e| e.to_string())?;
+
+	for (address, expected) in expected {
+		let address: Address = address.clone().into();
+		let account = post.get().get(&address).ok_or_else(|| format!("Expected account {:?} does not exist", address))?;
+
+		if let Some(balance) = expected.balance {
+			let balance: U256 = balance.into();
+			if account.balance != balance {
+				return Err(format!("Balance of {:?} mismatch (got: {}, expected: {})", address, account.balance, balance));
+			}
+		}
+
+		if let Some(nonce) = expected.nonce {
+			let nonce: U256 = nonce.into();
+			if account.nonce != nonce {
+				return Err(format!("Nonce of {:?} mismatch (got: {}, expected: {})", address, account.nonce, nonce));
+			}
+		}
+
+		if let Some(ref code) = expected.code {
+			if account.code.as_ref().map_or(&[][..], |code| &code[..]) != &code[..] {
+				return Err(format!("Code of {:?} mismatch", address));
+			}
+		}
+
+		for (key, value) in expected.storage.iter().flat_map(|storage| storage.iter()) {
+			let key = to_h256(key);
+			let value = to_h256(value);
+			let actual = account.storage.get(&key).cloned().unwrap_or_default();
+			if actual != value {
+				return Err(format!("Storage of {:?} at {:?} mismatch (got: {:?}, expected: {:?})", address, key, actual, value));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn to_h256(value: &Uint) -> H256 {
+	let value: U256 = (*value).into();
+	value.into()
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethcore::client::{EnvInfo, EvmTestClient, TransactResult, logs_hash};
+	use ethcore::ethereum;
+	use ethcore::pod_state::PodState;
+	use ethcore::trace::NoopVMTracer;
+	use ethjson::state::test::Test;
+	use super::fill;
+
+	// LOG1(0, 0, CALLVALUE); SSTORE(0, 1)
+	const FILLER: &'static str = r#"{
+		"logAndStore": {
+			"env" : {
+				"currentCoinbase" : "2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+				"currentDifficulty" : "0x020000",
+				"currentGasLimit" : "0x7fffffffffffffff",
+				"currentNumber" : "0x01",
+				"currentTimestamp" : "0x03e8"
+			},
+			"expect" : [
+				{
+					"indexes" : { "data" : -1, "gas" : -1, "value" : -1 },
+					"network" : [ "Byzantium" ],
+					"result" : {
+						"1000000000000000000000000000000000000000" : {
+							"storage" : { "0x00" : "0x01" }
+						},
+						"a94f5374fce5edbc8e2a8697c15331677e6ebf0b" : {
+							"nonce" : "0x01"
+						}
+					}
+				}
+			],
+			"pre" : {
+				"1000000000000000000000000000000000000000" : {
+					"balance" : "0x00",
+					"code" : "0x34600060006000a1600160005500",
+					"nonce" : "0x00",
+					"storage" : {}
+				},
+				"a94f5374fce5edbc8e2a8697c15331677e6ebf0b" : {
+					"balance" : "0x0de0b6b3a7640000",
+					"code" : "0x",
+					"nonce" : "0x00",
+					"storage" : {}
+				}
+			},
+			"transaction" : {
+				"data" : [ "0x" ],
+				"gasLimit" : [ "0x0186a0" ],
+				"gasPrice" : "0x01",
+				"nonce" : "0x00",
+				"secretKey" : "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+				"to" : "1000000000000000000000000000000000000000",
+				"value" : [ "0x00", "0x01" ]
+			}
+		}
+	}"#;
+
+	#[test]
+	fn should_fill_passing_state_test() {
+		let filled = fill(serde_json::from_str(FILLER).unwrap(), None).unwrap();
+		let test: Test = serde_json::from_value(filled).unwrap();
+
+		for (_, test) in test {
+			let pre: PodState = test.pre_state.into();
+			let env: EnvInfo = test.env.into();
+			for (fork, states) in test.post_states {
+				assert_eq!(states.len(), 2);
+				let spec = EvmTestClient::spec_from_json(&fork).unwrap();
+				for state in states {
+					let mut client = EvmTestClient::from_pod_state(spec, pre.clone()).unwrap();
+					let transaction = test.transaction.select(&state.indexes).into();
+					match client.transact(&env, transaction, NoopVMTracer) {
+						TransactResult::Ok { state_root, logs, .. } => {
+							assert_eq!(logs.len(), 1);
+							assert_eq!(state_root, state.hash.into());
+							assert_eq!(Some(logs_hash(&logs)), state.logs.map(Into::into));
+						},
+						TransactResult::Err { error, .. } => panic!("Unexpected error: {:?}", error),
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn should_reject_unmet_expectation() {
+		let filler = FILLER.replace(r#""nonce" : "0x01""#, r#""nonce" : "0x02""#);
+		assert!(fill(serde_json::from_str(&filler).unwrap(), None).is_err());
+	}
+
+	#[test]
+	fn should_fill_with_given_spec() {
+		let filler = FILLER.replace(r#"[ "Byzantium" ]"#, r#"[ "Constantinople" ]"#);
+		assert!(fill(serde_json::from_str(&filler).unwrap(), None).is_err());
+
+		let spec = ethereum::new_byzantium_test();
+		let filled = fill(serde_json::from_str(&filler).unwrap(), Some(&spec)).unwrap();
+		let expected = fill(serde_json::from_str(FILLER).unwrap(), None).unwrap();
+		assert_eq!(filled["logAndStore"]["post"]["Constantinople"], expected["logAndStore"]["post"]["Byzantium"]);
+	}
+}
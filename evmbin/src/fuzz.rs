@@ -0,0 +1,353 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Differential fuzzing of the available VMs.
+
+use std::{cmp, fmt, io};
+use std::sync::{Arc, Mutex};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use serde_json::{self, Map, Value};
+use ethereum_types::{U256, Address};
+use bytes::{Bytes, ToPretty};
+use ethcore::client::{EnvInfo, EvmTestClient};
+use ethcore::pod_state::PodState;
+use ethcore::spec::Spec;
+use ethjson;
+use ethjson::uint::Uint;
+use ethkey::KeyPair;
+use evm::{self, VMType, INSTRUCTIONS};
+use transaction::{Action, Transaction};
+
+use display::std_json;
+use dump;
+use info;
+
+/// Size of the jump destinations cache of the fuzzed VMs.
+const CACHE_SIZE: usize = 5 * 1024;
+/// Maximal gas of a generated transaction.
+const MAX_GAS: u64 = 1_000_000;
+/// Maximal size of the generated contract code.
+const MAX_CODE_SIZE: usize = 256;
+
+/// Randomly generated contract, the state it is deployed in and a transaction calling it.
+pub struct Program {
+	/// Accounts the transaction runs on.
+	pub pre_state: PodState,
+	/// Sender of the transaction.
+	pub sender: Address,
+	/// Transaction calling the contract.
+	pub transaction: Transaction,
+	/// Environment the transaction runs in.
+	pub env_info: EnvInfo,
+}
+
+impl Program {
+	/// Generate a program from given seed. The same seed always gives the same program.
+	pub fn generate(seed: u64) -> Self {
+		let mut rng = XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x193a_6754, 0xa8a7_d469]);
+
+		let sender = keypair().address();
+		let contract = Address::from(0x1000);
+		let callee = Address::from(0x2000);
+		// Precompiles and existing accounts, so that most calls hit something.
+		let mut addresses = (1..9).map(Address::from).collect::<Vec<_>>();
+		addresses.extend_from_slice(&[sender, contract, callee]);
+
+		let code_size = rng.gen_range(1, MAX_CODE_SIZE);
+		let code = random_code(&mut rng, code_size, &addresses);
+		let callee_code_size = rng.gen_range(0, MAX_CODE_SIZE / 4);
+		let callee_code = random_code(&mut rng, callee_code_size, &addresses);
+
+		let slots = rng.gen_range(0, 4);
+		let storage = (0..slots)
+			.map(|_| (format!("0x{:x}", rng.gen_range(0u8, 8)), Value::String(format!("0x{:x}", rng.gen::<u64>()))))
+			.collect::<Map<_, _>>();
+
+		let pre_state = json!({
+			format!("{:?}", sender): account(U256::from(10).pow(18.into()), &[], Map::new()),
+			format!("{:?}", contract): account(rng.gen_range(0u64, 1000).into(), &code, storage),
+			format!("{:?}", callee): account(0.into(), &callee_code, Map::new()),
+		});
+		let pre_state: ethjson::blockchain::State = serde_json::from_value(pre_state)
+			.expect("Generated state is a valid pre state; qed");
+
+		let input_size = rng.gen_range(0, 64);
+		let transaction = Transaction {
+			nonce: 0.into(),
+			gas_price: 1.into(),
+			gas: rng.gen_range(30_000, MAX_GAS).into(),
+			action: Action::Call(contract),
+			value: rng.gen_range(0u64, 1000).into(),
+			data: (0..input_size).map(|_| rng.gen()).collect(),
+		};
+
+		// Built from a state test environment, so that programs dumped as state tests run the same.
+		let env_info = ethjson::vm::Env {
+			author: Address::from(0x3000).into(),
+			difficulty: Uint(0x20000.into()),
+			gas_limit: Uint((10 * MAX_GAS).into()),
+			number: Uint(rng.gen_range(1u64, 300).into()),
+			timestamp: Uint(1000.into()),
+		}.into();
+
+		Program {
+			pre_state: pre_state.into(),
+			sender,
+			transaction,
+			env_info,
+		}
+	}
+}
+
+fn keypair() -> KeyPair {
+	KeyPair::from_secret(dump::TEST_SECRET.parse().expect("TEST_SECRET is a valid secret; qed"))
+		.expect("TEST_SECRET is a valid secret; qed")
+}
+
+fn account(balance: U256, code: &[u8], storage: Map<String, Value>) -> Value {
+	json!({
+		"balance": format!("0x{:x}", balance),
+		"code": format!("0x{}", code.to_hex()),
+		"nonce": "0x00",
+		"storage": storage,
+	})
+}
+
+/// Generate `size` bytes of code, made of valid instructions and pushes of values
+/// which are likely to be meaningful as memory offsets, jump destinations or addresses.
+fn random_code<R: Rng>(rng: &mut R, size: usize, addresses: &[Address]) -> Bytes {
+	let instructions = (0..INSTRUCTIONS.len())
+		.filter(|i| !INSTRUCTIONS[*i].name.is_empty())
+		.map(|i| i as u8)
+		.collect::<Vec<_>>();
+
+	let mut code = Vec::with_capacity(size + 32);
+	while code.len() < size {
+		match rng.gen_range(0, 8) {
+			0 | 1 => {
+				let value = rng.gen_range(0u8, 64);
+				push(&mut code, &[value]);
+			},
+			2 => {
+				let address = rng.choose(addresses).expect("addresses is not empty; qed").clone();
+				push(&mut code, &*address);
+			},
+			3 => {
+				let value: [u8; 32] = rng.gen();
+				push(&mut code, &value);
+			},
+			_ => {
+				let instruction = *rng.choose(&instructions).expect("there are valid instructions; qed");
+				code.push(instruction);
+				for _ in 0..evm::push_bytes(instruction) {
+					code.push(rng.gen());
+				}
+			},
+		}
+	}
+
+	code.truncate(size);
+	code
+}
+
+fn push(code: &mut Bytes, value: &[u8]) {
+	// PUSH1 is 0x60, PUSH32 is 0x7f
+	code.push(0x5f + value.len() as u8);
+	code.extend_from_slice(value);
+}
+
+/// VMs available in this build, along with their names.
+/// The interpreter runs with `U256` gas too, as it has a separate code path for it.
+pub fn vms() -> Vec<(String, evm::Factory)> {
+	let mut vms = VMType::all().into_iter()
+		.map(|vm| (vm.to_string(), evm::Factory::new(vm, CACHE_SIZE)))
+		.collect::<Vec<_>>();
+	vms.push(("INT-U256".into(), evm::Factory::new_u256(VMType::Interpreter, CACHE_SIZE)));
+	vms
+}
+
+/// First difference between the executions of a program by two VMs.
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+	/// VM the other one is compared to.
+	pub reference: String,
+	/// VM which diverged.
+	pub vm: String,
+	/// Index of the first diverging instruction in the trace, `None` if only the results differ.
+	pub step: Option<usize>,
+	/// Instruction trace line or result of the reference VM.
+	pub expected: String,
+	/// Instruction trace line or result of the diverging VM.
+	pub got: String,
+}
+
+impl fmt::Display for Divergence {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.step {
+			Some(step) => writeln!(f, "{} diverges from {} at instruction #{}", self.vm, self.reference, step)?,
+			None => writeln!(f, "{} gives a different result than {}", self.vm, self.reference)?,
+		}
+		writeln!(f, "  {}: {}", self.reference, self.expected)?;
+		write!(f, "  {}: {}", self.vm, self.got)
+	}
+}
+
+/// Execution of a program by a single VM.
+struct Execution {
+	vm: String,
+	trace: Vec<String>,
+	result: String,
+}
+
+/// Run the program with every available VM and return the first divergence from the first VM.
+pub fn check(program: &Program, spec: &Spec) -> Result<Option<Divergence>, String> {
+	let mut executions = Vec::new();
+	for (vm, factory) in vms() {
+		executions.push(execute(program, spec, vm, factory)?);
+	}
+
+	let (reference, others) = executions.split_first().expect("There is always at least one VM; qed");
+	Ok(others.iter().filter_map(|other| compare(reference, other)).next())
+}
+
+fn execute(program: &Program, spec: &Spec, vm: String, factory: evm::Factory) -> Result<Execution, String> {
+	let mut client = EvmTestClient::from_pod_state_with_vm(spec, program.pre_state.clone(), factory)
+		.map_err(|e| format!("{}: {}", vm, e))?;
+	let transaction = program.transaction.clone().sign(keypair().secret(), None);
+
+	let buffer = Buffer::default();
+	let informant = std_json::Informant::new(Buffer(buffer.0.clone()));
+	let result = match info::run_transaction_on_client(&mut client, &program.env_info, transaction, informant) {
+		Ok(success) => format!(
+			"{{\"stateRoot\":\"{:?}\",\"output\":\"0x{}\",\"gasUsed\":\"{:x}\"}}",
+			success.state_root,
+			success.output.to_hex(),
+			success.gas_used,
+		),
+		Err(failure) => format!("{{\"error\":\"{}\",\"gasUsed\":\"{:x}\"}}", failure.error, failure.gas_used),
+	};
+
+	let trace = String::from_utf8_lossy(&buffer.0.lock().expect("The trace buffer is not poisoned; qed"))
+		.lines()
+		.map(str::to_owned)
+		.collect();
+
+	Ok(Execution { vm, trace, result })
+}
+
+fn compare(reference: &Execution, other: &Execution) -> Option<Divergence> {
+	let divergence = |step, expected: &str, got: &str| Divergence {
+		reference: reference.vm.clone(),
+		vm: other.vm.clone(),
+		step: step,
+		expected: expected.to_owned(),
+		got: got.to_owned(),
+	};
+
+	// VMs which do not report instructions (JIT) can only be compared by results.
+	if !reference.trace.is_empty() && !other.trace.is_empty() {
+		for step in 0..cmp::max(reference.trace.len(), other.trace.len()) {
+			let expected = reference.trace.get(step).map_or("<end of trace>", |line| line.as_str());
+			let got = other.trace.get(step).map_or("<end of trace>", |line| line.as_str());
+			if expected != got {
+				return Some(divergence(Some(step), expected, got));
+			}
+		}
+	}
+
+	if reference.result != other.result {
+		return Some(divergence(None, &reference.result, &other.result));
+	}
+
+	None
+}
+
+/// Trace sink shared by the informants of a single execution.
+#[derive(Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl std_json::Writer for Buffer {
+	fn clone(&self) -> Self {
+		Buffer(self.0.clone())
+	}
+}
+
+impl io::Write for Buffer {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.lock().expect("The trace buffer is not poisoned; qed").write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethcore::client::EvmTestClient;
+	use dump::TEST_FORK;
+	use super::*;
+
+	fn execution(vm: &str, trace: &[&str], result: &str) -> Execution {
+		Execution {
+			vm: vm.into(),
+			trace: trace.iter().map(|line| line.to_string()).collect(),
+			result: result.into(),
+		}
+	}
+
+	#[test]
+	fn should_generate_the_same_program_for_a_seed() {
+		let a = Program::generate(42);
+		let b = Program::generate(42);
+		let c = Program::generate(43);
+
+		assert_eq!(a.pre_state, b.pre_state);
+		assert_eq!(a.transaction, b.transaction);
+		assert!(a.pre_state != c.pre_state || a.transaction != c.transaction);
+	}
+
+	#[test]
+	fn should_report_first_diverging_instruction() {
+		let reference = execution("INT", &["a", "b", "c"], "ok");
+		assert_eq!(compare(&reference, &execution("INT-U256", &["a", "b", "c"], "ok")), None);
+
+		let divergence = compare(&reference, &execution("INT-U256", &["a", "x", "c"], "ok")).unwrap();
+		assert_eq!(divergence.step, Some(1));
+		assert_eq!(divergence.expected, "b");
+		assert_eq!(divergence.got, "x");
+
+		let divergence = compare(&reference, &execution("INT-U256", &["a", "b"], "ok")).unwrap();
+		assert_eq!(divergence.step, Some(2));
+		assert_eq!(divergence.got, "<end of trace>");
+
+		// no trace to compare
+		let divergence = compare(&reference, &execution("JIT", &[], "failed")).unwrap();
+		assert_eq!(divergence.step, None);
+		assert_eq!(divergence.got, "failed");
+	}
+
+	#[test]
+	fn interpreters_should_not_diverge() {
+		let spec = EvmTestClient::spec_from_json(&TEST_FORK).unwrap();
+		for seed in 0..20 {
+			let program = Program::generate(seed);
+			if let Some(divergence) = check(&program, spec).unwrap() {
+				panic!("Seed {}: {}", seed, divergence);
+			}
+		}
+	}
+}
@@ -72,7 +72,7 @@ pub fn run_action<T: Informant>(
 	mut informant: T,
 ) -> RunResult<T::Output> {
 	informant.set_gas(params.gas);
-	run(spec, params.gas, None, |mut client| {
+	run(spec, params.gas, None, |client| {
 		let result = client
 			.call(params, &mut informant)
 			.map(|r| (0.into(), r.gas_left, r.return_data.to_vec()));
@@ -80,13 +80,52 @@ pub fn run_action<T: Informant>(
 	})
 }
 
-/// Execute given Transaction and verify resulting state root.
+/// Execute given `ActionParams` on the state of an existing client and return the result.
+pub fn run_action_on_client<T: Informant>(
+	client: &mut EvmTestClient,
+	env_info: &client::EnvInfo,
+	params: ActionParams,
+	mut informant: T,
+) -> RunResult<T::Output> {
+	informant.set_gas(params.gas);
+	run_on_client(client, params.gas, |client| {
+		let result = client
+			.call_with_env_info(env_info, params, &mut informant)
+			.map(|r| (0.into(), r.gas_left, r.return_data.to_vec()));
+		(result, informant.drain())
+	})
+}
+
+/// Execute given Transaction on the state of an existing client and return the result.
+pub fn run_transaction_on_client<T: Informant>(
+	client: &mut EvmTestClient,
+	env_info: &client::EnvInfo,
+	transaction: transaction::SignedTransaction,
+	mut informant: T,
+) -> RunResult<T::Output> {
+	informant.set_gas(transaction.gas);
+	run_on_client(client, transaction.gas, |client| {
+		match client.transact(env_info, transaction, informant) {
+			TransactResult::Ok { state_root, gas_left, output, vm_trace, .. } => {
+				(Ok((state_root, gas_left, output)), vm_trace)
+			},
+			TransactResult::Err { error, .. } => {
+				(Err(EvmTestError::PostCondition(format!(
+					"Unexpected execution error: {:?}", error
+				))), None)
+			},
+		}
+	})
+}
+
+/// Execute given Transaction and verify resulting state root and logs hash, if given.
 pub fn run_transaction<T: Informant>(
 	name: &str,
 	idx: usize,
 	spec: &ethjson::state::test::ForkSpec,
 	pre_state: &pod_state::PodState,
 	post_root: H256,
+	post_logs: Option<H256>,
 	env_info: &client::EnvInfo,
 	transaction: transaction::SignedTransaction,
 	mut informant: T,
@@ -105,7 +144,7 @@ pub fn run_transaction<T: Informant>(
 
 	informant.set_gas(env_info.gas_limit);
 
-	let result = run(spec, env_info.gas_limit, pre_state, |mut client| {
+	let result = run(spec, env_info.gas_limit, pre_state, |client| {
 		let result = client.transact(env_info, transaction, informant);
 		match result {
 			TransactResult::Ok { state_root, .. } if state_root != post_root => {
@@ -115,6 +154,13 @@ pub fn run_transaction<T: Informant>(
 					post_root,
 				))), None)
 			},
+			TransactResult::Ok { ref logs, .. } if post_logs.map_or(false, |post_logs| client::logs_hash(logs) != post_logs) => {
+				(Err(EvmTestError::PostCondition(format!(
+					"Logs hash mismatch (got: {}, expected: {})",
+					client::logs_hash(logs),
+					post_logs.expect("Checked by the guard; qed"),
+				))), None)
+			},
 			TransactResult::Ok { state_root, gas_left, output, vm_trace, .. } => {
 				(Ok((state_root, gas_left, output)), vm_trace)
 			},
@@ -136,10 +182,10 @@ pub fn run<'a, F, T, X>(
 	pre_state: T,
 	run: F,
 ) -> RunResult<X> where
-	F: FnOnce(EvmTestClient) -> (Result<(H256, U256, Vec<u8>), EvmTestError>, Option<X>),
+	F: FnOnce(&mut EvmTestClient<'a>) -> (Result<(H256, U256, Vec<u8>), EvmTestError>, Option<X>),
 	T: Into<Option<&'a pod_state::PodState>>,
 {
-	let test_client = match pre_state.into() {
+	let mut test_client = match pre_state.into() {
 		Some(pre_state) => EvmTestClient::from_pod_state(spec, pre_state.clone()),
 		None => EvmTestClient::new(spec),
 	}.map_err(|error| Failure {
@@ -149,6 +195,17 @@ pub fn run<'a, F, T, X>(
 		traces: None,
 	})?;
 
+	run_on_client(&mut test_client, initial_gas, run)
+}
+
+/// Execute VM on given test client
+pub fn run_on_client<'a, F, X>(
+	test_client: &mut EvmTestClient<'a>,
+	initial_gas: U256,
+	run: F,
+) -> RunResult<X> where
+	F: FnOnce(&mut EvmTestClient<'a>) -> (Result<(H256, U256, Vec<u8>), EvmTestError>, Option<X>),
+{
 	let start = Instant::now();
 	let result = run(test_client);
 	let time = start.elapsed();
@@ -31,23 +31,37 @@ extern crate ethereum_types;
 extern crate vm;
 extern crate evm;
 extern crate panic_hook;
+extern crate ethkey;
+extern crate journaldb;
+extern crate kvdb_rocksdb;
+extern crate rand;
+#[macro_use]
+extern crate serde_json;
 
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
+#[cfg(test)]
+extern crate tempdir;
 
 use std::sync::Arc;
 use std::{fmt, fs};
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use docopt::Docopt;
 use rustc_hex::FromHex;
-use ethereum_types::{U256, Address};
+use ethereum_types::{H256, U256, Address};
 use bytes::Bytes;
-use ethcore::spec;
+use ethcore::{spec, trace};
+use ethcore::client::{EvmTestClient, TransactResult, import_chain_test, import_archived_chain_test};
+use kvdb_rocksdb::{Database, DatabaseConfig};
 use vm::{ActionParams, CallType};
 
 mod info;
 mod display;
+mod dump;
+mod fill;
+mod fuzz;
 
 use info::Informant;
 
@@ -57,6 +71,10 @@ EVM implementation for Parity.
 
 Usage:
     parity-evm state-test <file> [--json --std-json --only NAME --chain CHAIN]
+    parity-evm blockchain-test <file> [--json --std-json --only NAME --chain CHAIN]
+    parity-evm fill <file> [--output FILE --chain CHAIN]
+    parity-evm fuzz [--seed SEED --runs RUNS --dump FILE]
+    parity-evm wasm <file> [--json --std-json --from ADDRESS --to ADDRESS --input DATA --gas GAS --gas-price WEI --chain CHAIN]
     parity-evm stats [options]
     parity-evm [options]
     parity-evm [-h | --help]
@@ -69,9 +87,30 @@ Transaction options:
     --gas GAS          Supplied gas as hex (without 0x).
     --gas-price WEI    Supplied gas price as hex (without 0x).
 
+Database options:
+    --db-path PATH     Run against the state of the client database at PATH
+                       instead of the genesis state. The node must not be running.
+                       State changes are never written, but RocksDB may still
+                       update its own files in the database directory.
+    --block NUMBER     Block to run at (decimal). Calls see the state after the block,
+                       replayed transactions the state they were executed on.
+    --pruning ALGO     Pruning algorithm of the database: archive, fast, light or basic.
+    --tx-index INDEX   Replay the transaction at INDEX in the block instead of a call.
+    --dump FILE        Write the accounts touched by the execution as a state test to FILE.
+
 State test options:
     --only NAME        Runs only a single test matching the name.
-    --chain CHAIN      Run only tests from specific chain.
+    --chain CHAIN      Run only tests from specific chain. Blockchain tests run
+                       every test with the rules of CHAIN instead if it is a
+                       chain spec file.
+
+Filler options:
+    --output FILE      Write the filled state tests to FILE instead of standard output.
+    --chain CHAIN      Fill every network with the rules of the chain spec file CHAIN.
+
+Fuzzing options:
+    --seed SEED        Seed of the first generated program (decimal). Random by default.
+    --runs RUNS        Number of programs to generate [default: 1000].
 
 General options:
     --json             Display verbose results in JSON.
@@ -88,6 +127,28 @@ fn main() {
 
 	if args.cmd_state_test {
 		run_state_test(args)
+	} else if args.cmd_blockchain_test {
+		run_blockchain_test(args)
+	} else if args.cmd_fill {
+		run_fill(args)
+	} else if args.cmd_fuzz {
+		run_fuzz(args)
+	} else if args.cmd_wasm {
+		if args.flag_json {
+			run_wasm(args, display::wasm::Informant::new(display::json::Informant::default(), true))
+		} else if args.flag_std_json {
+			run_wasm(args, display::wasm::Informant::new(display::std_json::Informant::default(), true))
+		} else {
+			run_wasm(args, display::wasm::Informant::new(display::simple::Informant::default(), false))
+		}
+	} else if args.flag_db_path.is_some() {
+		if args.flag_json {
+			run_on_database(args, display::json::Informant::default())
+		} else if args.flag_std_json {
+			run_on_database(args, display::std_json::Informant::default())
+		} else {
+			run_on_database(args, display::simple::Informant::default())
+		}
 	} else if args.flag_json {
 		run_call(args, display::json::Informant::default())
 	} else if args.flag_std_json {
@@ -128,21 +189,180 @@ fn run_state_test(args: Args) {
 
 			for (idx, state) in states.into_iter().enumerate() {
 				let post_root = state.hash.into();
+				let post_logs = state.logs.map(Into::into);
 				let transaction = multitransaction.select(&state.indexes).into();
 
 				if args.flag_json {
 					let i = display::json::Informant::default();
-					info::run_transaction(&name, idx, &spec, &pre, post_root, &env_info, transaction, i)
+					info::run_transaction(&name, idx, &spec, &pre, post_root, post_logs, &env_info, transaction, i)
 				} else if args.flag_std_json {
 					let i = display::std_json::Informant::default();
-					info::run_transaction(&name, idx, &spec, &pre, post_root, &env_info, transaction, i)
+					info::run_transaction(&name, idx, &spec, &pre, post_root, post_logs, &env_info, transaction, i)
 				} else {
 					let i = display::simple::Informant::default();
-					info::run_transaction(&name, idx, &spec, &pre, post_root, &env_info, transaction, i)
+					info::run_transaction(&name, idx, &spec, &pre, post_root, post_logs, &env_info, transaction, i)
+				}
+			}
+		}
+	}
+}
+
+fn run_blockchain_test(args: Args) {
+	use ethjson::blockchain::Test;
+
+	let file = args.arg_file.clone().expect("FILE is required");
+	let mut file = match fs::File::open(&file) {
+		Err(err) => die(format!("Unable to open: {:?}: {}", file, err)),
+		Ok(file) => file,
+	};
+	let blockchain_test = match Test::load(&mut file) {
+		Err(err) => die(format!("Unable to load the test file: {}", err)),
+		Ok(test) => test,
+	};
+	let chain_spec = arg(args.chain_spec_file(), "--chain");
+	let only_test = args.flag_only.map(|s| s.to_lowercase());
+	let only_chain = match chain_spec {
+		Some(_) => None,
+		None => args.flag_chain.map(|s| s.to_lowercase()),
+	};
+	let json = args.flag_json || args.flag_std_json;
+	let mut failed = 0;
+
+	for (name, blockchain) in blockchain_test {
+		if let Some(false) = only_test.as_ref().map(|only_test| &name.to_lowercase() == only_test) {
+			continue;
+		}
+
+		let spec_name = match chain_spec {
+			Some(ref spec) => spec.name.to_lowercase(),
+			None => format!("{:?}", blockchain.network).to_lowercase(),
+		};
+		if let Some(false) = only_chain.as_ref().map(|only_chain| &spec_name == only_chain) {
+			continue;
+		}
+
+		let test = format!("{}:{}", name, spec_name);
+		let expected: H256 = blockchain.best_block.clone().into();
+		let spec = chain_spec.as_ref().or_else(|| EvmTestClient::spec_from_json(&blockchain.network));
+		let result = match spec {
+			Some(spec) => match import_blockchain_test(&test, spec, &blockchain, args.flag_std_json) {
+				Ok(best_block) if best_block == expected => Ok(()),
+				Ok(best_block) => Err(format!("Best block mismatch (got: {}, expected: {})", best_block, expected)),
+				Err(err) => Err(format!("Unable to import: {}", err)),
+			},
+			None => {
+				if json {
+					println!("{}", json!({"test": test, "action": "skipping because of missing spec"}));
+				} else {
+					println!("Test: {} (skipping because of missing spec)", test);
 				}
+				continue;
+			},
+		};
+
+		if result.is_err() {
+			failed += 1;
+		}
+		if json {
+			println!("{}", blockchain_test_json(&test, &result));
+		} else {
+			match result {
+				Ok(()) => println!("Test: {} (ok)", test),
+				Err(err) => println!("Test: {} (failed: {})", test, err),
 			}
 		}
 	}
+
+	if failed > 0 {
+		die(format!("{} tests failed.", failed));
+	}
+}
+
+/// Imports the blocks of a blockchain test and returns the resulting best block.
+/// With `std_json` the transactions of every block of the canonical chain are
+/// replayed and traced afterwards.
+fn import_blockchain_test(
+	test: &str,
+	spec: &spec::Spec,
+	blockchain: &ethjson::blockchain::BlockChain,
+	std_json: bool,
+) -> Result<H256, String> {
+	if !std_json {
+		return import_chain_test(spec, blockchain).map_err(to_string);
+	}
+
+	let (spec, db, chain_info) = import_archived_chain_test(spec, blockchain).map_err(to_string)?;
+	for number in 1..chain_info.best_block_number + 1 {
+		let (mut client, block) = EvmTestClient::from_database(&spec, db.clone(), journaldb::Algorithm::Archive, number, true)
+			.map_err(to_string)?;
+		let mut env_info = block.env_info;
+		for (idx, transaction) in block.transactions.into_iter().enumerate() {
+			let mut informant = display::std_json::Informant::default();
+			informant.before_test(&format!("{}:block{}:{}", test, number, idx), "starting");
+			let result = info::run_transaction_on_client(&mut client, &env_info, transaction, informant);
+			if let Ok(ref success) = result {
+				env_info.gas_used = env_info.gas_used + success.gas_used;
+			}
+			<display::std_json::Informant as Informant>::finish(result);
+		}
+	}
+
+	Ok(chain_info.best_block_hash)
+}
+
+fn blockchain_test_json(test: &str, result: &Result<(), String>) -> serde_json::Value {
+	match *result {
+		Ok(()) => json!({"test": test, "pass": true}),
+		Err(ref err) => json!({"test": test, "pass": false, "error": err}),
+	}
+}
+
+fn run_fill(args: Args) {
+	let file = args.arg_file.clone().expect("FILE is required");
+	let filler = match fs::File::open(&file) {
+		Err(err) => die(format!("Unable to open: {:?}: {}", file, err)),
+		Ok(file) => serde_json::from_reader(file).unwrap_or_else(|err| die(format!("Unable to load the filler: {}", err))),
+	};
+	let chain_spec = arg(args.chain_spec_file(), "--chain");
+	let filled = fill::fill(filler, chain_spec.as_ref()).unwrap_or_else(|err| die(err));
+
+	let result = match args.flag_output {
+		Some(ref path) => fs::File::create(path)
+			.map_err(|e| format!("Unable to create {:?}: {}", path, e))
+			.and_then(|file| serde_json::to_writer_pretty(file, &filled).map_err(to_string)),
+		None => serde_json::to_string_pretty(&filled).map(|filled| println!("{}", filled)).map_err(to_string),
+	};
+	if let Err(err) = result {
+		die(err);
+	}
+}
+
+fn run_fuzz(args: Args) {
+	let seed = arg(args.seed(), "--seed");
+	let runs = arg(args.runs(), "--runs");
+	let spec = EvmTestClient::spec_from_json(&dump::TEST_FORK).expect("Spec for TEST_FORK exists; qed");
+
+	for run in 0..runs {
+		let seed = seed.wrapping_add(run);
+		let program = fuzz::Program::generate(seed);
+		match fuzz::check(&program, spec) {
+			Ok(None) => {},
+			Ok(Some(divergence)) => {
+				println!("{}", divergence);
+				if let Some(ref path) = args.flag_dump {
+					let name = format!("fuzz{}", seed);
+					let path = ::std::path::Path::new(path);
+					if let Err(e) = dump::write_state_test(path, &name, &program.env_info, program.pre_state, program.sender, program.transaction) {
+						die(e);
+					}
+				}
+				die(format!("Divergence found, run again with --seed {} --runs 1", seed));
+			},
+			Err(err) => die(format!("Unable to run the program of seed {}: {}", seed, err)),
+		}
+	}
+
+	println!("{} programs run on {} VMs without divergence.", runs, fuzz::vms().len());
 }
 
 fn run_call<T: Informant>(args: Args, informant: T) {
@@ -173,10 +393,146 @@ fn run_call<T: Informant>(args: Args, informant: T) {
 	T::finish(result);
 }
 
+fn run_on_database<T: Informant>(args: Args, informant: T) {
+	let spec = arg(args.spec(), "--chain");
+	let pruning = arg(args.pruning(), "--pruning");
+	let number = arg(args.block(), "--block");
+	let tx_index = arg(args.tx_index(), "--tx-index");
+	let db = arg(args.database(), "--db-path");
+
+	let (client, block) = EvmTestClient::from_database(&spec, db, pruning, number, tx_index.is_some())
+		.unwrap_or_else(|e| die(format!("Unable to load block #{}: {}", number, e)));
+	let mut env_info = block.env_info;
+
+	let (result, client, pre, sender, transaction) = match tx_index {
+		Some(index) => {
+			if index >= block.transactions.len() {
+				die(format!("Block #{} has {} transactions", number, block.transactions.len()));
+			}
+
+			let mut client = client;
+			for transaction in &block.transactions[..index] {
+				match client.transact(&env_info, transaction.clone(), trace::NoopVMTracer) {
+					TransactResult::Ok { gas_left, .. } => {
+						env_info.gas_used = env_info.gas_used + transaction.gas - gas_left;
+					},
+					TransactResult::Err { error, .. } => {
+						die(format!("Unable to replay transaction {:?}: {}", transaction.hash(), error));
+					},
+				}
+			}
+
+			let pre = client.fork();
+			let mut client = client.fork();
+			let transaction = block.transactions[index].clone();
+			let sender = transaction.sender();
+			let unsigned = transaction.as_unsigned().clone();
+			let result = info::run_transaction_on_client(&mut client, &env_info, transaction, informant);
+			(result, client, pre, sender, unsigned)
+		},
+		None => {
+			let from = arg(args.from(), "--from");
+			let to = arg(args.to(), "--to");
+			let code = arg(args.code(), "--code");
+			let gas = arg(args.gas(), "--gas");
+			let gas_price = arg(args.gas_price(), "--gas-price");
+			let data = arg(args.data(), "--input");
+
+			if code.is_none() && to == Address::default() {
+				die("Either --code or --to is required.");
+			}
+			if code.is_some() && args.flag_dump.is_some() {
+				die("--dump requires the contract to exist in the database, use --to instead of --code.");
+			}
+
+			let transaction = transaction::Transaction {
+				nonce: 0.into(),
+				gas_price: gas_price,
+				gas: ::std::cmp::min(gas, env_info.gas_limit),
+				action: transaction::Action::Call(to),
+				value: 0.into(),
+				data: data.clone().unwrap_or_default(),
+			};
+
+			let mut params = ActionParams::default();
+			params.call_type = if code.is_none() { CallType::Call } else { CallType::None };
+			params.code_address = to;
+			params.address = to;
+			params.sender = from;
+			params.origin = from;
+			params.gas = gas;
+			params.gas_price = gas_price;
+			params.code = code.map(Arc::new);
+			params.data = data;
+
+			let pre = client.fork();
+			let mut client = client;
+			let result = info::run_action_on_client(&mut client, &env_info, params, informant);
+			(result, client, pre, from, transaction)
+		},
+	};
+
+	T::finish(result);
+
+	if let Some(ref path) = args.flag_dump {
+		let pre_state = client.touched_pod_state(&pre)
+			.unwrap_or_else(|e| die(format!("Unable to read touched state: {}", e)));
+		// calls are not signed, the state test transaction starts from the sender's nonce
+		let transaction = match tx_index {
+			Some(_) => transaction,
+			None => transaction::Transaction {
+				nonce: pre_state.get().get(&sender).map_or_else(U256::zero, |account| account.nonce),
+				..transaction
+			},
+		};
+		let name = format!("block{}", number);
+		if let Err(e) = dump::write_state_test(::std::path::Path::new(path), &name, &env_info, pre_state, sender, transaction) {
+			die(e);
+		}
+	}
+}
+
+fn run_wasm<T: Informant>(args: Args, informant: T) {
+	let result = execute_wasm(&args, informant);
+	T::finish(result);
+}
+
+fn execute_wasm<T: Informant>(args: &Args, informant: T) -> info::RunResult<T::Output> {
+	let file = args.arg_file.clone().expect("FILE is required");
+	let mut code = Vec::new();
+	if let Err(err) = fs::File::open(&file).and_then(|mut f| f.read_to_end(&mut code)) {
+		die(format!("Unable to read: {:?}: {}", file, err));
+	}
+
+	let from = arg(args.from(), "--from");
+	let to = arg(args.to(), "--to");
+	let spec = arg(args.wasm_spec(), "--chain");
+	let gas = arg(args.gas(), "--gas");
+	let gas_price = arg(args.gas_price(), "--gas-price");
+	let data = arg(args.data(), "--input");
+
+	let mut params = ActionParams::default();
+	params.call_type = CallType::Call;
+	params.code_address = to;
+	params.address = to;
+	params.sender = from;
+	params.origin = from;
+	params.gas = gas;
+	params.gas_price = gas_price;
+	params.code = Some(Arc::new(code));
+	params.data = data;
+
+	info::run_action(&spec, params, informant)
+}
+
 #[derive(Debug, Deserialize)]
 struct Args {
 	cmd_stats: bool,
 	cmd_state_test: bool,
+	cmd_blockchain_test: bool,
+	cmd_fill: bool,
+	cmd_fuzz: bool,
+	cmd_wasm: bool,
 	arg_file: Option<PathBuf>,
 	flag_only: Option<String>,
 	flag_from: Option<String>,
@@ -186,6 +542,14 @@ struct Args {
 	flag_gas_price: Option<String>,
 	flag_input: Option<String>,
 	flag_chain: Option<String>,
+	flag_db_path: Option<String>,
+	flag_block: Option<String>,
+	flag_pruning: Option<String>,
+	flag_tx_index: Option<String>,
+	flag_dump: Option<String>,
+	flag_output: Option<String>,
+	flag_seed: Option<String>,
+	flag_runs: Option<String>,
 	flag_json: bool,
 	flag_std_json: bool,
 }
@@ -244,6 +608,65 @@ impl Args {
 			},
 		})
 	}
+
+	/// Loads the chain spec if `--chain` names a file instead of a test chain.
+	pub fn chain_spec_file(&self) -> Result<Option<spec::Spec>, String> {
+		match self.flag_chain {
+			Some(ref filename) if Path::new(filename).is_file() => self.spec().map(Some),
+			_ => Ok(None),
+		}
+	}
+
+	pub fn database(&self) -> Result<Arc<Database>, String> {
+		let path = self.flag_db_path.as_ref().ok_or_else(|| "No database path given".to_owned())?;
+		if !Path::new(path).exists() {
+			return Err(format!("{} does not exist", path));
+		}
+		let config = DatabaseConfig::with_columns(ethcore::db::NUM_COLUMNS);
+		Database::open_existing(&config, path).map(Arc::new).map_err(to_string)
+	}
+
+	pub fn block(&self) -> Result<u64, String> {
+		match self.flag_block {
+			Some(ref block) => block.parse().map_err(to_string),
+			None => Err("Block number is required".into()),
+		}
+	}
+
+	pub fn pruning(&self) -> Result<journaldb::Algorithm, String> {
+		match self.flag_pruning {
+			Some(ref pruning) => pruning.parse(),
+			None => Ok(journaldb::Algorithm::OverlayRecent),
+		}
+	}
+
+	pub fn tx_index(&self) -> Result<Option<usize>, String> {
+		match self.flag_tx_index {
+			Some(ref index) => index.parse().map(Some).map_err(to_string),
+			None => Ok(None),
+		}
+	}
+
+	pub fn seed(&self) -> Result<u64, String> {
+		match self.flag_seed {
+			Some(ref seed) => seed.parse().map_err(to_string),
+			None => Ok(rand::random()),
+		}
+	}
+
+	pub fn runs(&self) -> Result<u64, String> {
+		match self.flag_runs {
+			Some(ref runs) => runs.parse().map_err(to_string),
+			None => Ok(1000),
+		}
+	}
+
+	pub fn wasm_spec(&self) -> Result<spec::Spec, String> {
+		match self.flag_chain {
+			Some(_) => self.spec(),
+			None => Ok(spec::Spec::new_wasm_test()),
+		}
+	}
 }
 
 fn arg<T>(v: Result<T, String>, param: &str) -> T {
@@ -261,8 +684,14 @@ fn die<T: fmt::Display>(msg: T) -> ! {
 
 #[cfg(test)]
 mod tests {
+	use std::fs;
+	use std::io::Write;
 	use docopt::Docopt;
-	use super::{Args, USAGE};
+	use journaldb;
+	use rustc_hex::FromHex;
+	use tempdir::TempDir;
+	use display;
+	use super::{Args, USAGE, execute_wasm, blockchain_test_json};
 
 	fn run<T: AsRef<str>>(args: &[T]) -> Args {
 		Docopt::new(USAGE).and_then(|d| d.argv(args.into_iter()).deserialize()).unwrap()
@@ -313,4 +742,135 @@ mod tests {
 		assert_eq!(args.flag_chain, Some("homestead".to_owned()));
 		assert_eq!(args.flag_only, Some("add11".to_owned()));
 	}
+
+	#[test]
+	fn should_parse_database_options() {
+		let args = run(&[
+			"parity-evm",
+			"--db-path", "./db",
+			"--block", "4370000",
+			"--pruning", "archive",
+			"--tx-index", "3",
+			"--dump", "./test.json",
+			"--json",
+		]);
+
+		assert_eq!(args.flag_db_path, Some("./db".to_owned()));
+		assert_eq!(args.block(), Ok(4370000));
+		assert_eq!(args.pruning(), Ok(journaldb::Algorithm::Archive));
+		assert_eq!(args.tx_index(), Ok(Some(3)));
+		assert_eq!(args.flag_dump, Some("./test.json".to_owned()));
+		assert_eq!(args.flag_json, true);
+	}
+
+	#[test]
+	fn should_parse_wasm_command() {
+		let args = run(&[
+			"parity-evm",
+			"wasm",
+			"./contract.wasm",
+			"--gas", "1",
+			"--to", "0000000000000000000000000000000000000004",
+			"--input", "06",
+			"--std-json",
+		]);
+
+		assert_eq!(args.cmd_wasm, true);
+		assert_eq!(args.arg_file, Some("./contract.wasm".into()));
+		assert_eq!(args.flag_std_json, true);
+		assert_eq!(args.gas(), Ok(1.into()));
+		assert_eq!(args.to(), Ok(4.into()));
+		assert_eq!(args.data(), Ok(Some(vec![06])));
+		assert_eq!(args.flag_chain, None);
+	}
+
+	#[test]
+	fn should_parse_test_commands() {
+		let args = run(&[
+			"parity-evm",
+			"blockchain-test",
+			"./test.json",
+			"--only", "Name",
+			"--chain", "Byzantium",
+		]);
+
+		assert_eq!(args.cmd_blockchain_test, true);
+		assert_eq!(args.arg_file, Some("./test.json".into()));
+		assert_eq!(args.flag_only, Some("Name".into()));
+		assert_eq!(args.flag_chain, Some("Byzantium".into()));
+
+		let args = run(&[
+			"parity-evm",
+			"fill",
+			"./filler.json",
+			"--output", "./test.json",
+			"--chain", "./chain.json",
+		]);
+
+		assert_eq!(args.cmd_fill, true);
+		assert_eq!(args.arg_file, Some("./filler.json".into()));
+		assert_eq!(args.flag_output, Some("./test.json".into()));
+		assert_eq!(args.flag_chain, Some("./chain.json".into()));
+	}
+
+	#[test]
+	fn should_load_chain_spec_files_only() {
+		let args = run(&["parity-evm", "blockchain-test", "./test.json", "--chain", "Byzantium"]);
+		assert!(args.chain_spec_file().unwrap().is_none());
+
+		let dir = TempDir::new("evmbin").unwrap();
+		let file = dir.path().join("chain.json");
+		fs::File::create(&file).and_then(|mut f| f.write_all(include_bytes!("../../ethcore/res/null_morden.json"))).unwrap();
+		let args = run(&["parity-evm", "blockchain-test", "./test.json", "--chain", file.to_str().unwrap()]);
+		assert_eq!(args.chain_spec_file().unwrap().unwrap().name, "Morden");
+	}
+
+	#[test]
+	fn should_escape_blockchain_test_errors() {
+		let error = r#"Unable to import: "quoted" \ path"#;
+		let line = blockchain_test_json("test:frontier", &Err(error.into())).to_string();
+		let parsed: ::serde_json::Value = ::serde_json::from_str(&line).unwrap();
+
+		assert_eq!(parsed["test"], "test:frontier");
+		assert_eq!(parsed["pass"], false);
+		assert_eq!(parsed["error"], error);
+		assert_eq!(blockchain_test_json("test:frontier", &Ok(())).to_string(), r#"{"pass":true,"test":"test:frontier"}"#);
+	}
+
+	#[test]
+	fn should_parse_fuzz_options() {
+		let args = run(&[
+			"parity-evm",
+			"fuzz",
+			"--seed", "42",
+			"--dump", "./divergence.json",
+		]);
+
+		assert_eq!(args.cmd_fuzz, true);
+		assert_eq!(args.seed(), Ok(42));
+		assert_eq!(args.runs(), Ok(1000));
+		assert_eq!(args.flag_dump, Some("./divergence.json".into()));
+	}
+
+	#[test]
+	fn should_profile_wasm_file() {
+		// Module whose `_call` logs "hello" with `_debug`.
+		let code: Vec<u8> = "\
+			0061736d01000000010a0260027f7f0060017f00021c0203656e76066d656d6f727902000103656e76065f646562\
+			7567000003020101070901055f63616c6c00010a0b010900418008410510000b0b0c01004180080b0568656c6c6f\
+		".from_hex().unwrap();
+		let dir = TempDir::new("evmbin").unwrap();
+		let file = dir.path().join("debug.wasm");
+		fs::File::create(&file).and_then(|mut f| f.write_all(&code)).unwrap();
+
+		let args = run(&["parity-evm", "wasm", file.to_str().unwrap()]);
+		let informant = display::wasm::Informant::new(display::simple::Informant::default(), false);
+		let profile = match execute_wasm(&args, informant) {
+			Ok(success) => success.traces.expect("wasm informant always returns a profile").1,
+			Err(failure) => panic!("Unexpected failure: {}", failure.error),
+		};
+
+		assert_eq!(profile.host_calls.len(), 1);
+		assert_eq!(profile.host_calls["_debug"].calls, 1);
+	}
 }
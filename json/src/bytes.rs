@@ -19,8 +19,8 @@
 use std::fmt;
 use std::str::FromStr;
 use std::ops::Deref;
-use rustc_hex::FromHex;
-use serde::{Deserialize, Deserializer};
+use rustc_hex::{FromHex, ToHex};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{Error, Visitor};
 
 /// Lenient bytes json deserialization for test json files.
@@ -67,6 +67,14 @@ impl FromStr for Bytes {
 	}
 }
 
+impl Serialize for Bytes {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		let mut hex = "0x".to_owned();
+		hex.push_str(&self.0.to_hex());
+		serializer.serialize_str(&hex)
+	}
+}
+
 impl<'a> Deserialize<'a> for Bytes {
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 		where D: Deserializer<'a> {
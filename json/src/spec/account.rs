@@ -22,19 +22,25 @@ use bytes::Bytes;
 use spec::builtin::Builtin;
 
 /// Spec account.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Default, Deserialize, Serialize)]
 pub struct Account {
 	/// Builtin contract.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub builtin: Option<Builtin>,
 	/// Balance.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub balance: Option<Uint>,
 	/// Nonce.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub nonce: Option<Uint>,
 	/// Code.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub code: Option<Bytes>,
 	/// Storage.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub storage: Option<BTreeMap<Uint, Uint>>,
 	/// Constructor.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub constructor: Option<Bytes>,
 }
 
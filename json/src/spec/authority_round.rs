@@ -16,43 +16,72 @@
 
 //! Authority params deserialization.
 
+use std::collections::BTreeMap;
+use hash::Address;
 use uint::Uint;
 use super::ValidatorSet;
 
+/// Step duration, either fixed or changing at given blocks.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum StepDuration {
+	/// Duration used for the whole chain.
+	Single(Uint),
+	/// Duration indexed by the block number it applies from.
+	/// Must contain a duration for block 0.
+	Transitions(BTreeMap<Uint, Uint>),
+}
+
 /// Authority params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct AuthorityRoundParams {
 	/// Block duration, in seconds.
 	#[serde(rename="stepDuration")]
-	pub step_duration: Uint,
+	pub step_duration: StepDuration,
 	/// Valid authorities
 	pub validators: ValidatorSet,
 	/// Starting step. Determined automatically if not specified.
 	/// To be used for testing only.
-	#[serde(rename="startStep")]
+	#[serde(rename="startStep", skip_serializing_if="Option::is_none")]
 	pub start_step: Option<Uint>,
 	/// Block at which score validation should start.
-	#[serde(rename="validateScoreTransition")]
+	#[serde(rename="validateScoreTransition", skip_serializing_if="Option::is_none")]
 	pub validate_score_transition: Option<Uint>,
 	/// Block from which monotonic steps start.
-	#[serde(rename="validateStepTransition")]
+	#[serde(rename="validateStepTransition", skip_serializing_if="Option::is_none")]
 	pub validate_step_transition: Option<Uint>,
 	/// Whether transitions should be immediate.
-	#[serde(rename="immediateTransitions")]
+	#[serde(rename="immediateTransitions", skip_serializing_if="Option::is_none")]
 	pub immediate_transitions: Option<bool>,
 	/// Reward per block in wei.
-	#[serde(rename="blockReward")]
+	#[serde(rename="blockReward", skip_serializing_if="Option::is_none")]
 	pub block_reward: Option<Uint>,
 	/// Block at which maximum uncle count should be considered.
-	#[serde(rename="maximumUncleCountTransition")]
+	#[serde(rename="maximumUncleCountTransition", skip_serializing_if="Option::is_none")]
 	pub maximum_uncle_count_transition: Option<Uint>,
 	/// Maximum number of accepted uncles.
-	#[serde(rename="maximumUncleCount")]
+	#[serde(rename="maximumUncleCount", skip_serializing_if="Option::is_none")]
 	pub maximum_uncle_count: Option<Uint>,
+	/// Block at which the block reward contract should start being used.
+	#[serde(rename="blockRewardContractTransition", skip_serializing_if="Option::is_none")]
+	pub block_reward_contract_transition: Option<Uint>,
+	/// Block reward contract address (setting the block reward contract
+	/// overrides the static block reward definition).
+	#[serde(rename="blockRewardContractAddress", skip_serializing_if="Option::is_none")]
+	pub block_reward_contract_address: Option<Address>,
+	/// Gas provided to the block reward contract call.
+	#[serde(rename="blockRewardContractGasLimit", skip_serializing_if="Option::is_none")]
+	pub block_reward_contract_gas_limit: Option<Uint>,
+	/// Block at which empty step messages should start.
+	#[serde(rename="emptyStepsTransition", skip_serializing_if="Option::is_none")]
+	pub empty_steps_transition: Option<Uint>,
+	/// Maximum number of accepted empty steps.
+	#[serde(rename="maximumEmptySteps", skip_serializing_if="Option::is_none")]
+	pub maximum_empty_steps: Option<Uint>,
 }
 
 /// Authority engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct AuthorityRound {
 	/// Ethash params.
 	pub params: AuthorityRoundParams,
@@ -65,7 +94,7 @@ mod tests {
 	use serde_json;
 	use hash::Address;
 	use spec::validator_set::ValidatorSet;
-	use spec::authority_round::AuthorityRound;
+	use spec::authority_round::{AuthorityRound, StepDuration};
 
 	#[test]
 	fn authority_round_deserialization() {
@@ -79,17 +108,49 @@ mod tests {
 				"validateStepTransition": 150,
 				"blockReward": 5000000,
 				"maximumUncleCountTransition": 10000000,
-				"maximumUncleCount": 5
+				"maximumUncleCount": 5,
+				"blockRewardContractTransition": 100,
+				"blockRewardContractAddress": "0x2000000000000000000000000000000000000002",
+				"blockRewardContractGasLimit": 1000000,
+				"emptyStepsTransition": 200,
+				"maximumEmptySteps": 10
 			}
 		}"#;
 
 		let deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
-		assert_eq!(deserialized.params.step_duration, Uint(U256::from(0x02)));
+		assert_eq!(deserialized.params.step_duration, StepDuration::Single(Uint(U256::from(0x02))));
 		assert_eq!(deserialized.params.validators, ValidatorSet::List(vec![Address(H160::from("0xc6d9d2cd449a754c494264e1809c50e34d64562b"))]));
 		assert_eq!(deserialized.params.start_step, Some(Uint(U256::from(24))));
 		assert_eq!(deserialized.params.immediate_transitions, None);
 		assert_eq!(deserialized.params.maximum_uncle_count_transition, Some(Uint(10_000_000.into())));
 		assert_eq!(deserialized.params.maximum_uncle_count, Some(Uint(5.into())));
+		assert_eq!(deserialized.params.block_reward_contract_transition, Some(Uint(100.into())));
+		assert_eq!(deserialized.params.block_reward_contract_address, Some(Address(H160::from("0x2000000000000000000000000000000000000002"))));
+		assert_eq!(deserialized.params.block_reward_contract_gas_limit, Some(Uint(1_000_000.into())));
+		assert_eq!(deserialized.params.empty_steps_transition, Some(Uint(200.into())));
+		assert_eq!(deserialized.params.maximum_empty_steps, Some(Uint(10.into())));
+
+	}
+
+	#[test]
+	fn authority_round_step_duration_transitions_deserialization() {
+		let s = r#"{
+			"params": {
+				"stepDuration": {
+					"0": 5,
+					"0x64": "3"
+				},
+				"validators": {
+					"list" : ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+				}
+			}
+		}"#;
 
+		let deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
+		let expected = vec![
+			(Uint(0.into()), Uint(5.into())),
+			(Uint(100.into()), Uint(3.into())),
+		].into_iter().collect();
+		assert_eq!(deserialized.params.step_duration, StepDuration::Transitions(expected));
 	}
 }
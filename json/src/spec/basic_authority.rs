@@ -20,7 +20,7 @@ use uint::Uint;
 use super::ValidatorSet;
 
 /// Authority params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct BasicAuthorityParams {
 	/// Block duration.
 	#[serde(rename="durationLimit")]
@@ -30,7 +30,7 @@ pub struct BasicAuthorityParams {
 }
 
 /// Authority engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct BasicAuthority {
 	/// Ethash params.
 	pub params: BasicAuthorityParams,
@@ -19,7 +19,7 @@
 use uint::Uint;
 
 /// Linear pricing.
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct Linear {
 	/// Base price.
 	pub base: usize,
@@ -28,14 +28,14 @@ pub struct Linear {
 }
 
 /// Pricing for modular exponentiation.
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct Modexp {
 	/// Price divisor.
 	pub divisor: usize,
 }
 
 /// Pricing for alt_bn128_pairing.
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct AltBn128Pairing {
 	/// Base price.
 	pub base: usize,
@@ -43,8 +43,15 @@ pub struct AltBn128Pairing {
 	pub pair: usize,
 }
 
+/// Pricing for BLAKE2b compression function.
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct Blake2F {
+	/// Price per round.
+	pub gas_per_round: u64,
+}
+
 /// Pricing variants.
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub enum Pricing {
 	/// Linear pricing.
 	#[serde(rename="linear")]
@@ -55,23 +62,27 @@ pub enum Pricing {
 	/// Pricing for alt_bn128_pairing exponentiation.
 	#[serde(rename="alt_bn128_pairing")]
 	AltBn128Pairing(AltBn128Pairing),
+	/// Pricing for BLAKE2b compression function.
+	#[serde(rename="blake2_f")]
+	Blake2F(Blake2F),
 }
 
 /// Spec builtin.
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct Builtin {
 	/// Builtin name.
 	pub name: String,
 	/// Builtin pricing.
 	pub pricing: Pricing,
 	/// Activation block.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub activate_at: Option<Uint>,
 }
 
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use spec::builtin::{Builtin, Pricing, Linear, Modexp};
+	use spec::builtin::{Builtin, Pricing, Linear, Modexp, Blake2F};
 	use uint::Uint;
 
 	#[test]
@@ -99,4 +110,18 @@ mod tests {
 		assert_eq!(deserialized.pricing, Pricing::Modexp(Modexp { divisor: 5 }));
 		assert_eq!(deserialized.activate_at, Some(Uint(100000.into())));
 	}
+
+	#[test]
+	fn blake2_f_pricing() {
+		let s = r#"{
+			"name": "blake2_f",
+			"activate_at": "0x10",
+			"pricing": { "blake2_f": { "gas_per_round": 1 } }
+		}"#;
+
+		let deserialized: Builtin = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.name, "blake2_f");
+		assert_eq!(deserialized.pricing, Pricing::Blake2F(Blake2F { gas_per_round: 1 }));
+		assert_eq!(deserialized.activate_at, Some(Uint(16.into())));
+	}
 }
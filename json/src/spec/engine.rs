@@ -16,10 +16,12 @@
 
 //! Engine deserialization.
 
-use super::{Ethash, BasicAuthority, AuthorityRound, Tendermint, NullEngine};
+use std::collections::BTreeMap;
+use uint::Uint;
+use super::{Ethash, BasicAuthority, AuthorityRound, Tendermint, Clique, NullEngine};
 
 /// Engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub enum Engine {
 	/// Null engine.
 	#[serde(rename="null")]
@@ -37,12 +39,20 @@ pub enum Engine {
 	AuthorityRound(AuthorityRound),
 	/// Tendermint engine.
 	#[serde(rename="tendermint")]
-	Tendermint(Tendermint)
+	Tendermint(Tendermint),
+	/// Clique engine.
+	#[serde(rename="clique")]
+	Clique(Clique),
+	/// Sequence of engines, each one active from the given block on.
+	#[serde(rename="multi")]
+	Multi(BTreeMap<Uint, Engine>),
 }
 
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use ethereum_types::U256;
+	use uint::Uint;
 	use spec::Engine;
 
 	#[test]
@@ -140,6 +150,56 @@ mod tests {
 			Engine::Tendermint(_) => {}, // Tendermint is unit tested in its own file.
 			_ => panic!(),
 		};
+
+		let s = r#"{
+			"clique": {
+				"params": {
+					"period": 15,
+					"epoch": 30000
+				}
+			}
+		}"#;
+		let deserialized: Engine = serde_json::from_str(s).unwrap();
+		match deserialized {
+			Engine::Clique(_) => {}, // Clique is unit tested in its own file.
+			_ => panic!(),
+		};
+
+		let s = r#"{
+			"multi": {
+				"0": {
+					"basicAuthority": {
+						"params": {
+							"durationLimit": "0x0d",
+							"validators": {
+								"list": ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+							}
+						}
+					}
+				},
+				"100": {
+					"authorityRound": {
+						"params": {
+							"stepDuration": "0x02",
+							"validators": {
+								"list" : ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+							}
+						}
+					}
+				}
+			}
+		}"#;
+		let deserialized: Engine = serde_json::from_str(s).unwrap();
+		match deserialized {
+			Engine::Multi(engines) => {
+				assert_eq!(engines.len(), 2);
+				match engines[&Uint(U256::from(100))] {
+					Engine::AuthorityRound(_) => {},
+					_ => panic!(),
+				}
+			},
+			_ => panic!(),
+		};
 	}
 }
 
@@ -16,11 +16,22 @@
 
 //! Ethash params deserialization.
 
+use std::collections::BTreeMap;
 use uint::Uint;
 use hash::Address;
 
+/// Block reward, either fixed or changing at given blocks.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BlockReward {
+	/// Reward used for the whole chain.
+	Single(Uint),
+	/// Reward indexed by the block number it applies from.
+	Multi(BTreeMap<Uint, Uint>),
+}
+
 /// Deserializable doppelganger of EthashParams.
-#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct EthashParams {
 	/// See main EthashParams docs.
 	#[serde(rename="minimumDifficulty")]
@@ -29,114 +40,118 @@ pub struct EthashParams {
 	#[serde(rename="difficultyBoundDivisor")]
 	pub difficulty_bound_divisor: Uint,
 	/// See main EthashParams docs.
-	#[serde(rename="difficultyIncrementDivisor")]
+	#[serde(rename="difficultyIncrementDivisor", skip_serializing_if="Option::is_none")]
 	pub difficulty_increment_divisor: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="metropolisDifficultyIncrementDivisor")]
+	#[serde(rename="metropolisDifficultyIncrementDivisor", skip_serializing_if="Option::is_none")]
 	pub metropolis_difficulty_increment_divisor: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="durationLimit")]
+	#[serde(rename="durationLimit", skip_serializing_if="Option::is_none")]
 	pub duration_limit: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="homesteadTransition")]
+	#[serde(rename="homesteadTransition", skip_serializing_if="Option::is_none")]
 	pub homestead_transition: Option<Uint>,
-	/// Reward per block in wei.
-	#[serde(rename="blockReward")]
-	pub block_reward: Option<Uint>,
+	/// Reward per block in wei, zero if not given.
+	#[serde(rename="blockReward", skip_serializing_if="Option::is_none")]
+	pub block_reward: Option<BlockReward>,
+	/// See main EthashParams docs.
+	#[serde(rename="difficultyBombDelays", skip_serializing_if="Option::is_none")]
+	pub difficulty_bomb_delays: Option<BTreeMap<Uint, Uint>>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="daoHardforkTransition")]
+	#[serde(rename="daoHardforkTransition", skip_serializing_if="Option::is_none")]
 	pub dao_hardfork_transition: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="daoHardforkBeneficiary")]
+	#[serde(rename="daoHardforkBeneficiary", skip_serializing_if="Option::is_none")]
 	pub dao_hardfork_beneficiary: Option<Address>,
 	/// See main EthashParams docs.
-	#[serde(rename="daoHardforkAccounts")]
+	#[serde(rename="daoHardforkAccounts", skip_serializing_if="Option::is_none")]
 	pub dao_hardfork_accounts: Option<Vec<Address>>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="difficultyHardforkTransition")]
+	#[serde(rename="difficultyHardforkTransition", skip_serializing_if="Option::is_none")]
 	pub difficulty_hardfork_transition: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="difficultyHardforkBoundDivisor")]
+	#[serde(rename="difficultyHardforkBoundDivisor", skip_serializing_if="Option::is_none")]
 	pub difficulty_hardfork_bound_divisor: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="bombDefuseTransition")]
+	#[serde(rename="bombDefuseTransition", skip_serializing_if="Option::is_none")]
 	pub bomb_defuse_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="eip100bTransition")]
+	#[serde(rename="eip100bTransition", skip_serializing_if="Option::is_none")]
 	pub eip100b_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="eip150Transition")]
+	#[serde(rename="eip150Transition", skip_serializing_if="Option::is_none")]
 	pub eip150_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="eip160Transition")]
+	#[serde(rename="eip160Transition", skip_serializing_if="Option::is_none")]
 	pub eip160_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="eip161abcTransition")]
+	#[serde(rename="eip161abcTransition", skip_serializing_if="Option::is_none")]
 	pub eip161abc_transition: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="eip161dTransition")]
+	#[serde(rename="eip161dTransition", skip_serializing_if="Option::is_none")]
 	pub eip161d_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="ecip1010PauseTransition")]
+	#[serde(rename="ecip1010PauseTransition", skip_serializing_if="Option::is_none")]
 	pub ecip1010_pause_transition: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="ecip1010ContinueTransition")]
+	#[serde(rename="ecip1010ContinueTransition", skip_serializing_if="Option::is_none")]
 	pub ecip1010_continue_transition: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="ecip1017EraRounds")]
+	#[serde(rename="ecip1017EraRounds", skip_serializing_if="Option::is_none")]
 	pub ecip1017_era_rounds: Option<Uint>,
 
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3Transition")]
+	#[serde(rename="mcip3Transition", skip_serializing_if="Option::is_none")]
 	pub mcip3_transition: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3MinerReward")]
+	#[serde(rename="mcip3MinerReward", skip_serializing_if="Option::is_none")]
 	pub mcip3_miner_reward: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3UbiReward")]
+	#[serde(rename="mcip3UbiReward", skip_serializing_if="Option::is_none")]
 	pub mcip3_ubi_reward: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3UbiContract")]
+	#[serde(rename="mcip3UbiContract", skip_serializing_if="Option::is_none")]
 	pub mcip3_ubi_contract: Option<Address>,
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3DevReward")]
+	#[serde(rename="mcip3DevReward", skip_serializing_if="Option::is_none")]
 	pub mcip3_dev_reward: Option<Uint>,
 	/// See main EthashParams docs.
-	#[serde(rename="mcip3DevContract")]
+	#[serde(rename="mcip3DevContract", skip_serializing_if="Option::is_none")]
 	pub mcip3_dev_contract: Option<Address>,
 
-	/// EIP-649 transition block.
-	#[serde(rename="eip649Transition")]
+	/// EIP-649 transition block. Deprecated, converted to a `blockReward` and a
+	/// `difficultyBombDelays` transition.
+	#[serde(rename="eip649Transition", skip_serializing_if="Option::is_none")]
 	pub eip649_transition: Option<Uint>,
 
-	/// EIP-649 bomb delay.
-	#[serde(rename="eip649Delay")]
+	/// EIP-649 bomb delay. Deprecated, see `eip649Transition`.
+	#[serde(rename="eip649Delay", skip_serializing_if="Option::is_none")]
 	pub eip649_delay: Option<Uint>,
 
-	/// EIP-649 base reward.
-	#[serde(rename="eip649Reward")]
+	/// EIP-649 base reward. Deprecated, see `eip649Transition`.
+	#[serde(rename="eip649Reward", skip_serializing_if="Option::is_none")]
 	pub eip649_reward: Option<Uint>,
 
 	/// EXPIP-2 block height
-	#[serde(rename="expip2Transition")]
+	#[serde(rename="expip2Transition", skip_serializing_if="Option::is_none")]
 	pub expip2_transition: Option<Uint>,
 
 	/// EXPIP-2 duration limit
-	#[serde(rename="expip2DurationLimit")]
+	#[serde(rename="expip2DurationLimit", skip_serializing_if="Option::is_none")]
 	pub expip2_duration_limit: Option<Uint>,
 }
 
 /// Ethash engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Ethash {
 	/// Ethash params.
 	pub params: EthashParams,
@@ -148,7 +163,7 @@ mod tests {
 	use uint::Uint;
 	use ethereum_types::{H160, U256};
 	use hash::Address;
-	use spec::ethash::{Ethash, EthashParams};
+	use spec::ethash::{Ethash, EthashParams, BlockReward};
 
 	#[test]
 	fn ethash_deserialization() {
@@ -159,6 +174,9 @@ mod tests {
 				"durationLimit": "0x0d",
 				"homesteadTransition": "0x42",
 				"blockReward": "0x100",
+				"difficultyBombDelays": {
+					"0x50": "0x1000"
+				},
 				"daoHardforkTransition": "0x08",
 				"daoHardforkBeneficiary": "0xabcabcabcabcabcabcabcabcabcabcabcabcabca",
 				"daoHardforkAccounts": [
@@ -204,7 +222,10 @@ mod tests {
 				metropolis_difficulty_increment_divisor: None,
 				duration_limit: Some(Uint(U256::from(0x0d))),
 				homestead_transition: Some(Uint(U256::from(0x42))),
-				block_reward: Some(Uint(U256::from(0x100))),
+				block_reward: Some(BlockReward::Single(Uint(U256::from(0x100)))),
+				difficulty_bomb_delays: Some(vec![
+					(Uint(U256::from(0x50)), Uint(U256::from(0x1000))),
+				].into_iter().collect()),
 				dao_hardfork_transition: Some(Uint(U256::from(0x08))),
 				dao_hardfork_beneficiary: Some(Address(H160::from("0xabcabcabcabcabcabcabcabcabcabcabcabcabca"))),
 				dao_hardfork_accounts: Some(vec![
@@ -274,6 +295,7 @@ mod tests {
 				duration_limit: None,
 				homestead_transition: None,
 				block_reward: None,
+				difficulty_bomb_delays: None,
 				dao_hardfork_transition: None,
 				dao_hardfork_beneficiary: None,
 				dao_hardfork_accounts: None,
@@ -302,4 +324,18 @@ mod tests {
 			}
 		});
 	}
+
+	#[test]
+	fn ethash_block_reward_transitions_deserialization() {
+		let s = r#"{
+			"0": "0x4563918244f40000",
+			"4370000": "0x29a2241af62c0000"
+		}"#;
+
+		let deserialized: BlockReward = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, BlockReward::Multi(vec![
+			(Uint(U256::from(0)), Uint(U256::from(0x4563918244f40000u64))),
+			(Uint(U256::from(4370000)), Uint(U256::from(0x29a2241af62c0000u64))),
+		].into_iter().collect()));
+	}
 }
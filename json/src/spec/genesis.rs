@@ -22,36 +22,38 @@ use bytes::Bytes;
 use spec::Seal;
 
 /// Spec genesis.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Genesis {
 	/// Seal.
 	pub seal: Seal,
 	/// Difficulty.
 	pub difficulty: Uint,
 	/// Block author, defaults to 0.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub author: Option<Address>,
 	/// Block timestamp, defaults to 0.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub timestamp: Option<Uint>,
 	/// Parent hash, defaults to 0.
-	#[serde(rename="parentHash")]
+	#[serde(rename="parentHash", skip_serializing_if="Option::is_none")]
 	pub parent_hash: Option<H256>,
 	/// Gas limit.
 	#[serde(rename="gasLimit")]
 	pub gas_limit: Uint,
 	/// Transactions root.
-	#[serde(rename="transactionsRoot")]
+	#[serde(rename="transactionsRoot", skip_serializing_if="Option::is_none")]
 	pub transactions_root: Option<H256>,
 	/// Receipts root.
-	#[serde(rename="receiptsRoot")]
+	#[serde(rename="receiptsRoot", skip_serializing_if="Option::is_none")]
 	pub receipts_root: Option<H256>,
 	/// State root.
-	#[serde(rename="stateRoot")]
+	#[serde(rename="stateRoot", skip_serializing_if="Option::is_none")]
 	pub state_root: Option<H256>,
 	/// Gas used.
-	#[serde(rename="gasUsed")]
+	#[serde(rename="gasUsed", skip_serializing_if="Option::is_none")]
 	pub gas_used: Option<Uint>,
 	/// Extra data.
-	#[serde(rename="extraData")]
+	#[serde(rename="extraData", skip_serializing_if="Option::is_none")]
 	pub extra_data: Option<Bytes>,
 }
 
@@ -19,15 +19,15 @@
 use uint::Uint;
 
 /// Authority params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct NullEngineParams {
 	/// Block reward.
-	#[serde(rename="blockReward")]
+	#[serde(rename="blockReward", skip_serializing_if="Option::is_none")]
 	pub block_reward: Option<Uint>,
 }
 
 /// Null engine descriptor
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct NullEngine {
 	/// Ethash params.
 	pub params: NullEngineParams,
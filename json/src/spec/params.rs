@@ -21,10 +21,10 @@ use hash::{H256, Address};
 use bytes::Bytes;
 
 /// Spec params.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Default, Deserialize, Serialize)]
 pub struct Params {
 	/// Account start nonce, defaults to 0.
-	#[serde(rename="accountStartNonce")]
+	#[serde(rename="accountStartNonce", skip_serializing_if="Option::is_none")]
 	pub account_start_nonce: Option<Uint>,
 	/// Maximum size of extra data.
 	#[serde(rename="maximumExtraDataSize")]
@@ -37,85 +37,97 @@ pub struct Params {
 	#[serde(rename="networkID")]
 	pub network_id: Uint,
 	/// Chain id.
-	#[serde(rename="chainID")]
+	#[serde(rename="chainID", skip_serializing_if="Option::is_none")]
 	pub chain_id: Option<Uint>,
 
 	/// Name of the main ("eth") subprotocol.
-	#[serde(rename="subprotocolName")]
+	#[serde(rename="subprotocolName", skip_serializing_if="Option::is_none")]
 	pub subprotocol_name: Option<String>,
 
 	/// Option fork block number to check.
-	#[serde(rename="forkBlock")]
+	#[serde(rename="forkBlock", skip_serializing_if="Option::is_none")]
 	pub fork_block: Option<Uint>,
 	/// Expected fork block hash.
-	#[serde(rename="forkCanonHash")]
+	#[serde(rename="forkCanonHash", skip_serializing_if="Option::is_none")]
 	pub fork_hash: Option<H256>,
 
 	/// See `CommonParams` docs.
-	#[serde(rename="eip98Transition")]
+	#[serde(rename="eip98Transition", skip_serializing_if="Option::is_none")]
 	pub eip98_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip155Transition")]
+	#[serde(rename="eip155Transition", skip_serializing_if="Option::is_none")]
 	pub eip155_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="validateChainIdTransition")]
+	#[serde(rename="validateChainIdTransition", skip_serializing_if="Option::is_none")]
 	pub validate_chain_id_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="validateReceiptsTransition")]
+	#[serde(rename="validateReceiptsTransition", skip_serializing_if="Option::is_none")]
 	pub validate_receipts_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip86Transition")]
+	#[serde(rename="eip86Transition", skip_serializing_if="Option::is_none")]
 	pub eip86_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip140Transition")]
+	#[serde(rename="eip140Transition", skip_serializing_if="Option::is_none")]
 	pub eip140_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip210Transition")]
+	#[serde(rename="eip210Transition", skip_serializing_if="Option::is_none")]
 	pub eip210_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip210ContractAddress")]
+	#[serde(rename="eip210ContractAddress", skip_serializing_if="Option::is_none")]
 	pub eip210_contract_address: Option<Address>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip210ContractCode")]
+	#[serde(rename="eip210ContractCode", skip_serializing_if="Option::is_none")]
 	pub eip210_contract_code: Option<Bytes>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip210ContractGas")]
+	#[serde(rename="eip210ContractGas", skip_serializing_if="Option::is_none")]
 	pub eip210_contract_gas: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip211Transition")]
+	#[serde(rename="eip211Transition", skip_serializing_if="Option::is_none")]
 	pub eip211_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip214Transition")]
+	#[serde(rename="eip214Transition", skip_serializing_if="Option::is_none")]
 	pub eip214_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="eip658Transition")]
+	#[serde(rename="eip658Transition", skip_serializing_if="Option::is_none")]
 	pub eip658_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="dustProtectionTransition")]
+	#[serde(rename="dustProtectionTransition", skip_serializing_if="Option::is_none")]
 	pub dust_protection_transition: Option<Uint>,
 	/// See `CommonParams` docs.
-	#[serde(rename="nonceCapIncrement")]
+	#[serde(rename="nonceCapIncrement", skip_serializing_if="Option::is_none")]
 	pub nonce_cap_increment: Option<Uint>,
 	/// See `CommonParams` docs.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub remove_dust_contracts : Option<bool>,
 	/// Wasm support flag
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub wasm: Option<bool>,
 	/// See `CommonParams` docs.
+	#[serde(rename="wasmValidationTransition", skip_serializing_if="Option::is_none")]
+	pub wasm_validation_transition: Option<Uint>,
+	/// See `CommonParams` docs.
+	#[serde(rename="wasmMaxMemoryPages", skip_serializing_if="Option::is_none")]
+	pub wasm_max_memory_pages: Option<Uint>,
+	/// See `CommonParams` docs.
+	#[serde(rename="wasmBuiltinsTransition", skip_serializing_if="Option::is_none")]
+	pub wasm_builtins_transition: Option<Uint>,
+	/// See `CommonParams` docs.
 	#[serde(rename="gasLimitBoundDivisor")]
 	pub gas_limit_bound_divisor: Uint,
 	/// See `CommonParams` docs.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub registrar: Option<Address>,
 	/// Apply reward flag
-	#[serde(rename="applyReward")]
+	#[serde(rename="applyReward", skip_serializing_if="Option::is_none")]
 	pub apply_reward: Option<bool>,
 	/// Node permission contract address.
-	#[serde(rename="nodePermissionContract")]
+	#[serde(rename="nodePermissionContract", skip_serializing_if="Option::is_none")]
 	pub node_permission_contract: Option<Address>,
 	/// See main EthashParams docs.
-	#[serde(rename="maxCodeSize")]
+	#[serde(rename="maxCodeSize", skip_serializing_if="Option::is_none")]
 	pub max_code_size: Option<Uint>,
 	/// Transaction permission contract address.
-	#[serde(rename="transactionPermissionContract")]
+	#[serde(rename="transactionPermissionContract", skip_serializing_if="Option::is_none")]
 	pub transaction_permission_contract: Option<Address>,
 }
 
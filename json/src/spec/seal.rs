@@ -21,7 +21,7 @@ use uint::Uint;
 use bytes::Bytes;
 
 /// Ethereum seal.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Ethereum {
 	/// Seal nonce.
 	pub nonce: H64,
@@ -31,7 +31,7 @@ pub struct Ethereum {
 }
 
 /// AuthorityRound seal.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct AuthorityRoundSeal {
 	/// Seal step.
 	pub step: Uint,
@@ -40,7 +40,7 @@ pub struct AuthorityRoundSeal {
 }
 
 /// Tendermint seal.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct TendermintSeal {
 	/// Seal round.
 	pub round: Uint,
@@ -51,7 +51,7 @@ pub struct TendermintSeal {
 }
 
 /// Seal variants.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub enum Seal {
 	/// Ethereum seal.
 	#[serde(rename="ethereum")]
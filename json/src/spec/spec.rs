@@ -22,12 +22,12 @@ use serde_json::Error;
 use spec::{Params, Genesis, Engine, State};
 
 /// Spec deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Spec {
 	/// Spec name.
 	pub name: String,
 	/// Special fork name.
-	#[serde(rename="dataDir")]
+	#[serde(rename="dataDir", skip_serializing_if="Option::is_none")]
 	pub data_dir: Option<String>,
 	/// Engine.
 	pub engine: Engine,
@@ -38,6 +38,7 @@ pub struct Spec {
 	/// Genesis state.
 	pub accounts: State,
 	/// Boot nodes.
+	#[serde(skip_serializing_if="Option::is_none")]
 	pub nodes: Option<Vec<String>>,
 }
 
@@ -106,7 +107,10 @@ mod tests {
 		"102e61f5d8f9bc71d0ad4a084df4e65e05ce0e1c": { "balance": "1606938044258990275541962092341162602522202993782792835301376", "nonce": "1048576" }
 	}
 		}"#;
-		let _deserialized: Spec = serde_json::from_str(s).unwrap();
+		let deserialized: Spec = serde_json::from_str(s).unwrap();
 		// TODO: validate all fields
+
+		let serialized = serde_json::to_string(&deserialized).unwrap();
+		assert_eq!(serde_json::from_str::<Spec>(&serialized).unwrap(), deserialized);
 	}
 }
@@ -22,7 +22,7 @@ use bytes::Bytes;
 use spec::{Account, Builtin};
 
 /// Blockchain test state deserializer.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct State(BTreeMap<Address, Account>);
 
 impl State {
@@ -43,6 +43,12 @@ impl State {
 	}
 }
 
+impl From<BTreeMap<Address, Account>> for State {
+	fn from(accounts: BTreeMap<Address, Account>) -> Self {
+		State(accounts)
+	}
+}
+
 impl IntoIterator for State {
 	type Item = <BTreeMap<Address, Account> as IntoIterator>::Item;
 	type IntoIter = <BTreeMap<Address, Account> as IntoIterator>::IntoIter;
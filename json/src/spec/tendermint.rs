@@ -20,29 +20,29 @@ use uint::Uint;
 use super::ValidatorSet;
 
 /// Tendermint params deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct TendermintParams {
 	/// Valid validators.
 	pub validators: ValidatorSet,
 	/// Propose step timeout in milliseconds.
-	#[serde(rename="timeoutPropose")]
+	#[serde(rename="timeoutPropose", skip_serializing_if="Option::is_none")]
 	pub timeout_propose: Option<Uint>,
 	/// Prevote step timeout in milliseconds.
-	#[serde(rename="timeoutPrevote")]
+	#[serde(rename="timeoutPrevote", skip_serializing_if="Option::is_none")]
 	pub timeout_prevote: Option<Uint>,
 	/// Precommit step timeout in milliseconds.
-	#[serde(rename="timeoutPrecommit")]
+	#[serde(rename="timeoutPrecommit", skip_serializing_if="Option::is_none")]
 	pub timeout_precommit: Option<Uint>,
 	/// Commit step timeout in milliseconds.
-	#[serde(rename="timeoutCommit")]
+	#[serde(rename="timeoutCommit", skip_serializing_if="Option::is_none")]
 	pub timeout_commit: Option<Uint>,
 	/// Reward per block.
-	#[serde(rename="blockReward")]
+	#[serde(rename="blockReward", skip_serializing_if="Option::is_none")]
 	pub block_reward: Option<Uint>,
 }
 
 /// Tendermint engine deserialization.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Tendermint {
 	/// Ethash params.
 	pub params: TendermintParams,
@@ -21,7 +21,7 @@ use uint::Uint;
 use hash::Address;
 
 /// Different ways of specifying validators.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub enum ValidatorSet {
 	/// A simple list of authorities.
 	#[serde(rename="list")]
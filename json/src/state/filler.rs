@@ -0,0 +1,179 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State test filler deserialization.
+
+use std::io::Read;
+use std::collections::BTreeMap;
+use uint::Uint;
+use bytes::Bytes;
+use hash::Address;
+use state::{Env, AccountState};
+use state::test::{ForkSpec, MultiTransaction};
+use serde_json::{self, Error};
+
+/// State test filler deserializer.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Filler(BTreeMap<String, State>);
+
+impl IntoIterator for Filler {
+	type Item = <BTreeMap<String, State> as IntoIterator>::Item;
+	type IntoIter = <BTreeMap<String, State> as IntoIterator>::IntoIter;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl Filler {
+	/// Loads filler from json.
+	pub fn load<R>(reader: R) -> Result<Self, Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+}
+
+/// State test filler deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct State {
+	/// Environment.
+	pub env: Env,
+	/// Pre state.
+	#[serde(rename="pre")]
+	pub pre_state: AccountState,
+	/// Transaction.
+	pub transaction: MultiTransaction,
+	/// Expected results.
+	pub expect: Vec<Expect>,
+}
+
+/// Expected result of a set of transactions.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Expect {
+	/// Indexes of the transactions the expectation applies to. All of them if not given.
+	#[serde(default)]
+	pub indexes: ExpectIndexes,
+	/// Forks the expectation applies to.
+	pub network: Vec<ForkSpec>,
+	/// Expected accounts.
+	pub result: BTreeMap<Address, AccountExpect>,
+}
+
+/// Indexes of expected transactions. `-1` matches any index.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct ExpectIndexes {
+	/// Index into transaction data set.
+	pub data: i64,
+	/// Index into transaction gas limit set.
+	pub gas: i64,
+	/// Index into transaction value set.
+	pub value: i64,
+}
+
+impl Default for ExpectIndexes {
+	fn default() -> Self {
+		ExpectIndexes {
+			data: -1,
+			gas: -1,
+			value: -1,
+		}
+	}
+}
+
+impl ExpectIndexes {
+	/// Returns true if the expectation applies to transaction with given indexes.
+	pub fn matches(&self, data: u64, gas: u64, value: u64) -> bool {
+		let matches = |expected: i64, index: u64| expected < 0 || expected as u64 == index;
+		matches(self.data, data) && matches(self.gas, gas) && matches(self.value, value)
+	}
+}
+
+/// Expected account state. Fields which are not given are not checked.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct AccountExpect {
+	/// Balance.
+	pub balance: Option<Uint>,
+	/// Code.
+	pub code: Option<Bytes>,
+	/// Nonce.
+	pub nonce: Option<Uint>,
+	/// Storage items. Missing keys are expected to be zero.
+	pub storage: Option<BTreeMap<Uint, Uint>>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{Filler, ExpectIndexes};
+
+	#[test]
+	fn filler_deserialization() {
+		let s = r#"{
+			"sstore": {
+				"env" : {
+					"currentCoinbase" : "2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+					"currentDifficulty" : "0x0100",
+					"currentGasLimit" : "0x01c9c380",
+					"currentNumber" : "0x00",
+					"currentTimestamp" : "0x01"
+				},
+				"expect" : [
+					{
+						"indexes" : { "data" : 0, "gas" : -1, "value" : -1 },
+						"network" : [ "Byzantium" ],
+						"result" : {
+							"1000000000000000000000000000000000000000" : {
+								"storage" : { "0x00" : "0x01" }
+							}
+						}
+					},
+					{
+						"network" : [ "EIP150", "EIP158" ],
+						"result" : {
+							"a94f5374fce5edbc8e2a8697c15331677e6ebf0b" : {
+								"nonce" : "0x01"
+							}
+						}
+					}
+				],
+				"pre" : {
+					"1000000000000000000000000000000000000000" : {
+						"balance" : "0x00",
+						"code" : "0x6001600055",
+						"nonce" : "0x00",
+						"storage" : {
+						}
+					}
+				},
+				"transaction" : {
+					"data" : [ "" ],
+					"gasLimit" : [ "0x0186a0" ],
+					"gasPrice" : "0x01",
+					"nonce" : "0x00",
+					"secretKey" : "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+					"to" : "1000000000000000000000000000000000000000",
+					"value" : [ "0x00" ]
+				}
+			}
+		}"#;
+		let filler: Filler = serde_json::from_str(s).unwrap();
+		let (_, state) = filler.into_iter().next().unwrap();
+		assert_eq!(state.expect.len(), 2);
+		assert!(state.expect[0].indexes.matches(0, 3, 1));
+		assert!(!state.expect[0].indexes.matches(1, 0, 0));
+		assert_eq!(state.expect[1].indexes, ExpectIndexes::default());
+		assert_eq!(state.expect[0].result.values().next().unwrap().balance, None);
+	}
+}
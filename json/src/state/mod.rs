@@ -20,10 +20,12 @@ pub mod state;
 pub mod transaction;
 pub mod test;
 pub mod log;
+pub mod filler;
 
 pub use self::state::State;
 pub use self::transaction::Transaction;
 pub use self::test::Test;
 pub use self::log::Log;
+pub use self::filler::Filler;
 pub use vm::Env as Env;
 pub use blockchain::State as AccountState;
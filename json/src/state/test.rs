@@ -130,6 +130,8 @@ pub struct PostStateResult {
 	pub hash: H256,
 	/// Indexes
 	pub indexes: PostStateIndexes,
+	/// Hash of the rlp of the logs, if the test checks them
+	pub logs: Option<H256>,
 }
 
 #[cfg(test)]
@@ -18,7 +18,7 @@
 
 use std::fmt;
 use std::str::FromStr;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{Error, Visitor};
 use ethereum_types::U256;
 
@@ -50,6 +50,12 @@ impl Into<u8> for Uint {
 	}
 }
 
+impl Serialize for Uint {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&format!("0x{:x}", self.0))
+	}
+}
+
 impl<'a> Deserialize<'a> for Uint {
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 		where D: Deserializer<'a> {
@@ -0,0 +1,81 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Once, ONCE_INIT};
+use ethcore::builtin::{self, Impl};
+
+/// Register the chain-specific native builtins compiled into this binary.
+/// Must be called before any chain spec is loaded. Registration happens once per process.
+pub fn register() -> Result<(), String> {
+	static REGISTER: Once = ONCE_INIT;
+
+	let mut result = Ok(());
+	REGISTER.call_once(|| result = register_all(native::builtins()));
+	result
+}
+
+fn register_all(builtins: Vec<(&'static str, Arc<Impl>)>) -> Result<(), String> {
+	for (name, native) in builtins {
+		builtin::register_builtin(name, native)
+			.map_err(|e| format!("Unable to register builtin `{}`: {}", name, e.0))?;
+	}
+	Ok(())
+}
+
+#[cfg(not(feature = "native-builtins"))]
+mod native {
+	use std::sync::Arc;
+	use ethcore::builtin::Impl;
+
+	/// No chain-specific builtins without the `native-builtins` feature.
+	pub fn builtins() -> Vec<(&'static str, Arc<Impl>)> {
+		Vec::new()
+	}
+}
+
+#[cfg(feature = "native-builtins")]
+mod native {
+	pub use native_builtins::builtins;
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use bytes::BytesRef;
+	use ethcore::builtin::{Impl, Error, is_builtin_name};
+	use super::register_all;
+
+	struct Zero;
+
+	impl Impl for Zero {
+		fn execute(&self, _input: &[u8], _output: &mut BytesRef) -> Result<(), Error> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn registers_builtins() {
+		assert!(!is_builtin_name("parity_test_zero"));
+		register_all(vec![("parity_test_zero", Arc::new(Zero) as Arc<Impl>)]).unwrap();
+		assert!(is_builtin_name("parity_test_zero"));
+	}
+
+	#[test]
+	fn rejects_ethereum_builtin_names() {
+		let err = register_all(vec![("sha256", Arc::new(Zero) as Arc<Impl>)]).unwrap_err();
+		assert!(err.contains("`sha256`"));
+	}
+}
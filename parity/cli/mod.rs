@@ -221,6 +221,56 @@ usage! {
 				"<FILE>",
 				"File",
 			}
+
+			CMD cmd_tools_check_spec
+			{
+				"Check a chain spec file for problems",
+
+				ARG arg_tools_check_spec_file: (Option<String>) = None,
+				"<FILE>",
+				"Chain spec file",
+			}
+
+			CMD cmd_tools_new_chain
+			{
+				"Generate a chain spec for a private network",
+
+				ARG arg_tools_new_chain_name: (String) = "private",
+				"--name=[NAME]",
+				"Name of the chain, also used for its keys directory",
+
+				ARG arg_tools_new_chain_engine: (String) = "aura",
+				"--engine=[ENGINE]",
+				"Consensus engine: aura, tendermint or basic-authority",
+
+				ARG arg_tools_new_chain_fork: (String) = "byzantium",
+				"--fork=[FORK]",
+				"Latest hard fork active from genesis: frontier, spurious-dragon or byzantium",
+
+				ARG arg_tools_new_chain_block_time: (u64) = 5u64,
+				"--block-time=[SECONDS]",
+				"Target time between blocks",
+
+				ARG arg_tools_new_chain_validators: (Option<String>) = None,
+				"--validators=[ACCOUNTS]",
+				"Comma-separated existing accounts from the chain's keys directory to use as validators",
+
+				ARG arg_tools_new_chain_new_validators: (usize) = 0usize,
+				"--new-validators=[NUM]",
+				"Number of new validator accounts to create, encrypted with the first --password file",
+
+				ARG arg_tools_new_chain_fund: (Option<String>) = None,
+				"--fund=[BALANCES]",
+				"Comma-separated ADDRESS:WEI genesis balances",
+
+				ARG arg_tools_new_chain_node_configs: (Option<String>) = None,
+				"--node-configs=[DIR]",
+				"Write a config file and key for each validator node into DIR. Needs FILE and --password",
+
+				ARG arg_tools_new_chain_file: (Option<String>) = None,
+				"[FILE]",
+				"Chain spec file to write, standard output if not given",
+			}
 		}
 
 		CMD cmd_db
@@ -380,6 +430,10 @@ usage! {
 			"--no-ancient-blocks",
 			"Disable downloading old blocks after snapshot restoration or warp sync.",
 
+			FLAG flag_state_sync: (bool) = false, or |_| None,
+			"--state-sync",
+			"Download the state of a recent block from the network node by node instead of syncing from the genesis or a snapshot. Works with peers which do not serve snapshots.",
+
 			FLAG flag_no_serve_light: (bool) = false, or |c: &Config| otry!(c.network).no_serve_light.clone(),
 			"--no-serve-light",
 			"Disable serving of light peers.",
@@ -428,6 +482,26 @@ usage! {
 			"--reserved-peers=[FILE]",
 			"Provide a file containing enodes, one per line. These nodes will always have a reserved slot on top of the normal maximum peers.",
 
+			ARG arg_checkpoint: (Option<String>) = None, or |_| None,
+			"--checkpoint=[NUMBER:HASH:TOTAL_DIFFICULTY]",
+			"Download the state at the trusted block NUMBER with hash HASH and total difficulty TOTAL_DIFFICULTY from the network and continue syncing from there, instead of from the genesis or a snapshot.",
+
+			ARG arg_tx_propagation_reserved: (String) = "full", or |_| None,
+			"--tx-propagation-reserved=[MODE]",
+			"Specify how transactions are relayed to reserved peers. MODE must be one of: full - send full transactions; hashes - announce transaction hashes and let peers request the ones they miss; off - do not relay transactions.",
+
+			ARG arg_tx_propagation_peers: (String) = "full", or |_| None,
+			"--tx-propagation-peers=[MODE]",
+			"Specify how transactions are relayed to peers which are not reserved. MODE must be one of: full, hashes, off (see --tx-propagation-reserved).",
+
+			ARG arg_tx_propagation_limit: (Option<usize>) = None, or |_| None,
+			"--tx-propagation-limit=[NUM]",
+			"Relay at most NUM transactions per second to a single peer.",
+
+			ARG arg_local_tx_peers: (Option<String>) = None, or |_| None,
+			"--local-tx-peers=[NODES]",
+			"Never relay transactions submitted to this node, except to the reserved peers given in NODES. NODES should be comma-delimited enodes; an empty list keeps local transactions private.",
+
 		["API and console options – RPC"]
 			FLAG flag_no_jsonrpc: (bool) = false, or |c: &Config| otry!(c.rpc).disable.clone(),
 			"--no-jsonrpc",
@@ -1407,6 +1481,8 @@ mod tests {
 			cmd_restore: false,
 			cmd_tools: false,
 			cmd_tools_hash: false,
+			cmd_tools_check_spec: false,
+			cmd_tools_new_chain: false,
 			cmd_db: false,
 			cmd_db_kill: false,
 
@@ -1421,6 +1497,8 @@ mod tests {
 			arg_snapshot_file: None,
 			arg_restore_file: None,
 			arg_tools_hash_file: None,
+			arg_tools_check_spec_file: None,
+			arg_tools_new_chain_file: None,
 
 			arg_signer_sign_id: None,
 			arg_signer_reject_id: None,
@@ -1481,8 +1559,14 @@ mod tests {
 			flag_no_discovery: false,
 			arg_node_key: None,
 			arg_reserved_peers: Some("./path_to_file".into()),
+			arg_checkpoint: None,
+			arg_tx_propagation_reserved: "full".into(),
+			arg_tx_propagation_peers: "full".into(),
+			arg_tx_propagation_limit: None,
+			arg_local_tx_peers: None,
 			flag_reserved_only: false,
 			flag_no_ancient_blocks: false,
+			flag_state_sync: false,
 			flag_no_serve_light: false,
 
 			// -- API and Console Options
@@ -1601,6 +1685,16 @@ mod tests {
 			arg_snapshot_at: "latest".into(),
 			flag_no_periodic_snapshot: false,
 
+			// -- New Chain Options
+			arg_tools_new_chain_name: "private".into(),
+			arg_tools_new_chain_engine: "aura".into(),
+			arg_tools_new_chain_fork: "byzantium".into(),
+			arg_tools_new_chain_block_time: 5u64,
+			arg_tools_new_chain_validators: None,
+			arg_tools_new_chain_new_validators: 0usize,
+			arg_tools_new_chain_fund: None,
+			arg_tools_new_chain_node_configs: None,
+
 			// -- Virtual Machine Options
 			flag_jitvm: false,
 
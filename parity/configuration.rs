@@ -27,7 +27,7 @@ use ethereum_types::{U256, H256, Address};
 use parity_version::{version_data, version};
 use bytes::Bytes;
 use ansi_term::Colour;
-use ethsync::{NetworkConfiguration, validate_node_url, self};
+use ethsync::{NetworkConfiguration, TransactionPropagation, validate_node_url, self};
 use ethcore::ethstore::ethkey::{Secret, Public};
 use ethcore::client::{VMType};
 use ethcore::miner::{MinerOptions, Banning, StratumOptions};
@@ -38,7 +38,8 @@ use rpc_apis::ApiSet;
 use parity_rpc::NetworkSettings;
 use cache::CacheConfig;
 use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, geth_ipc_path, parity_ipc_path,
-to_bootnodes, to_addresses, to_address, to_gas_limit, to_queue_strategy};
+to_bootnodes, to_addresses, to_address, to_balances, to_gas_limit, to_queue_strategy, to_checkpoint,
+to_tx_propagation_mode, to_node_ids};
 use dir::helpers::{replace_home, replace_home_and_local};
 use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType};
 use ethcore_logger::Config as LogConfig;
@@ -50,6 +51,7 @@ use updater::{UpdatePolicy, UpdateFilter, ReleaseTrack};
 use run::RunCmd;
 use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, KillBlockchain, ExportState, DataFormat};
 use presale::ImportWallet;
+use new_chain::{NewChain, ChainEngine, Fork};
 use account::{AccountCmd, NewAccount, ListAccounts, ImportAccounts, ImportFromGethAccounts};
 use snapshot::{self, SnapshotCommand};
 use network::{IpFilter};
@@ -79,6 +81,8 @@ pub enum Cmd {
 	},
 	Snapshot(SnapshotCommand),
 	Hash(Option<String>),
+	CheckSpec(Option<String>),
+	NewChain(NewChain),
 }
 
 pub struct Execute {
@@ -168,6 +172,24 @@ impl Configuration {
 			}
 		} else if self.args.cmd_tools && self.args.cmd_tools_hash {
 			Cmd::Hash(self.args.arg_tools_hash_file)
+		} else if self.args.cmd_tools && self.args.cmd_tools_check_spec {
+			Cmd::CheckSpec(self.args.arg_tools_check_spec_file)
+		} else if self.args.cmd_tools && self.args.cmd_tools_new_chain {
+			Cmd::NewChain(NewChain {
+				name: self.args.arg_tools_new_chain_name.clone(),
+				engine: self.args.arg_tools_new_chain_engine.parse()?,
+				fork: self.args.arg_tools_new_chain_fork.parse()?,
+				block_time: self.args.arg_tools_new_chain_block_time,
+				network_id: self.network_id(),
+				validators: to_addresses(&self.args.arg_tools_new_chain_validators)?,
+				new_validators: self.args.arg_tools_new_chain_new_validators,
+				fund: to_balances(&self.args.arg_tools_new_chain_fund)?,
+				keys_path: dirs.keys,
+				iterations: self.args.arg_keys_iterations,
+				password_file: self.args.arg_password.first().map(|x| x.to_owned()),
+				node_configs: self.args.arg_tools_new_chain_node_configs.clone(),
+				file: self.args.arg_tools_new_chain_file.clone(),
+			})
 		} else if self.args.cmd_db && self.args.cmd_db_kill {
 			Cmd::Blockchain(BlockchainCmd::Kill(KillBlockchain {
 				spec: spec,
@@ -370,6 +392,14 @@ impl Configuration {
 				no_periodic_snapshot: self.args.flag_no_periodic_snapshot,
 				check_seal: !self.args.flag_no_seal_check,
 				download_old_blocks: !self.args.flag_no_ancient_blocks,
+				checkpoint: to_checkpoint(&self.args.arg_checkpoint)?,
+				state_sync: self.args.flag_state_sync,
+				transaction_propagation: TransactionPropagation {
+					reserved_peers: to_tx_propagation_mode(&self.args.arg_tx_propagation_reserved)?,
+					other_peers: to_tx_propagation_mode(&self.args.arg_tx_propagation_peers)?,
+					max_per_peer_per_sec: self.args.arg_tx_propagation_limit,
+					local_transactions_peers: to_node_ids(&self.args.arg_local_tx_peers)?,
+				},
 				verifier_settings: verifier_settings,
 				serve_light: !self.args.flag_no_serve_light,
 				light: self.args.flag_light,
@@ -1181,6 +1211,34 @@ mod tests {
 		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Version);
 	}
 
+	#[test]
+	fn test_command_tools_new_chain() {
+		let args = vec!["parity", "tools", "new-chain", "--engine", "tendermint", "--new-validators", "3", "--fund", "0x0000000000000000000000000000000000000001:100", "--network-id", "17", "spec.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::NewChain(NewChain {
+			name: "private".into(),
+			engine: ChainEngine::Tendermint,
+			fork: Fork::Byzantium,
+			block_time: 5,
+			network_id: Some(17),
+			validators: vec![],
+			new_validators: 3,
+			fund: vec![(1.into(), 100.into())],
+			keys_path: Directories::default().keys,
+			iterations: 10240,
+			password_file: None,
+			node_configs: None,
+			file: Some("spec.json".into()),
+		}));
+	}
+
+	#[test]
+	fn test_command_tools_check_spec() {
+		let args = vec!["parity", "tools", "check-spec", "spec.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::CheckSpec(Some("spec.json".into())));
+	}
+
 	#[test]
 	fn test_command_account_new() {
 		let args = vec!["parity", "account", "new"];
@@ -1405,6 +1463,9 @@ mod tests {
 			stratum: None,
 			check_seal: true,
 			download_old_blocks: true,
+			checkpoint: None,
+			state_sync: false,
+			transaction_propagation: Default::default(),
 			verifier_settings: Default::default(),
 			serve_light: true,
 			light: false,
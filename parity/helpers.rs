@@ -15,10 +15,11 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::io;
+use std::collections::HashSet;
 use std::io::{Write, BufReader, BufRead};
 use std::time::Duration;
 use std::fs::File;
-use ethereum_types::{U256, clean_0x, Address};
+use ethereum_types::{U256, H256, H512, clean_0x, Address};
 use kvdb_rocksdb::CompactionProfile;
 use journaldb::Algorithm;
 use ethcore::client::{Mode, BlockId, VMType, DatabaseCompactionProfile, ClientConfig, VerifierType};
@@ -29,7 +30,7 @@ use dir::DatabaseDirectories;
 use dir::helpers::replace_home;
 use upgrade::{upgrade, upgrade_data_paths};
 use migration::migrate;
-use ethsync::{validate_node_url, self};
+use ethsync::{validate_node_url, TransactionPropagationMode, self};
 use path;
 
 pub fn to_duration(s: &str) -> Result<Duration, String> {
@@ -130,6 +131,64 @@ pub fn to_addresses(s: &Option<String>) -> Result<Vec<Address>, String> {
 	}
 }
 
+/// Parses a comma-separated list of `ADDRESS:BALANCE` pairs.
+pub fn to_balances(s: &Option<String>) -> Result<Vec<(Address, U256)>, String> {
+	match *s {
+		Some(ref balances) if !balances.is_empty() => balances.split(',')
+			.map(|b| {
+				let mut parts = b.splitn(2, ':');
+				let address = parts.next().and_then(|a| clean_0x(a).parse().ok());
+				match (address, parts.next()) {
+					(Some(address), Some(balance)) => Ok((address, to_u256(balance)?)),
+					_ => Err(format!("Invalid balance: {:?}", b)),
+				}
+			})
+			.collect(),
+		_ => Ok(Vec::new()),
+	}
+}
+
+/// Parses a checkpoint given as `NUMBER:HASH:TOTAL_DIFFICULTY`.
+pub fn to_checkpoint(s: &Option<String>) -> Result<Option<(u64, H256, U256)>, String> {
+	match *s {
+		Some(ref checkpoint) => {
+			let mut parts = checkpoint.splitn(3, ':');
+			let number = parts.next().and_then(|n| n.parse().ok());
+			let hash = parts.next().and_then(|h| clean_0x(h).parse().ok());
+			let difficulty = parts.next().and_then(|d| to_u256(d).ok());
+			match (number, hash, difficulty) {
+				(Some(number), Some(hash), Some(difficulty)) => Ok(Some((number, hash, difficulty))),
+				_ => Err(format!("Invalid checkpoint: {:?}. Expected NUMBER:HASH:TOTAL_DIFFICULTY.", checkpoint)),
+			}
+		},
+		None => Ok(None),
+	}
+}
+
+pub fn to_tx_propagation_mode(s: &str) -> Result<TransactionPropagationMode, String> {
+	match s {
+		"full" => Ok(TransactionPropagationMode::Full),
+		"hashes" => Ok(TransactionPropagationMode::Hashes),
+		"off" => Ok(TransactionPropagationMode::Off),
+		other => Err(format!("Invalid transaction propagation mode: {}", other)),
+	}
+}
+
+/// Parses comma-delimited enodes into node ids.
+pub fn to_node_ids(s: &Option<String>) -> Result<Option<HashSet<H512>>, String> {
+	match *s {
+		Some(ref nodes) => nodes.split(',')
+			.filter(|node| !node.is_empty())
+			.map(|node| {
+				let id = node.trim_left_matches("enode://").split('@').next().unwrap_or("");
+				clean_0x(id).parse().map_err(|_| format!("Invalid node: {:?}", node))
+			})
+			.collect::<Result<_, _>>()
+			.map(Some),
+		None => Ok(None),
+	}
+}
+
 /// Tries to parse string as a price.
 pub fn to_price(s: &str) -> Result<f32, String> {
 	s.parse::<f32>().map_err(|_| format!("Invalid transaciton price 's' given. Must be a decimal number."))
@@ -332,7 +391,7 @@ mod tests {
 	use ethereum_types::U256;
 	use ethcore::client::{Mode, BlockId};
 	use ethcore::miner::PendingSet;
-	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_address, to_addresses, to_price, geth_ipc_path, to_bootnodes, password_from_file};
+	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_address, to_addresses, to_balances, to_checkpoint, to_price, geth_ipc_path, to_bootnodes, password_from_file};
 
 	#[test]
 	fn test_to_duration() {
@@ -415,6 +474,48 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_to_balances() {
+		let balances = to_balances(&Some("0xD9A111feda3f362f55Ef1744347CDC8Dd9964a41:100,D9A111feda3f362f55Ef1744347CDC8Dd9964a42:0x10".into())).unwrap();
+		assert_eq!(
+			balances,
+			vec![
+				("D9A111feda3f362f55Ef1744347CDC8Dd9964a41".parse().unwrap(), 100.into()),
+				("D9A111feda3f362f55Ef1744347CDC8Dd9964a42".parse().unwrap(), 16.into()),
+			]
+		);
+		assert!(to_balances(&Some("0xD9A111feda3f362f55Ef1744347CDC8Dd9964a41".into())).is_err());
+	}
+
+	#[test]
+	fn test_to_checkpoint() {
+		let hash = "9fc84d84f6a785dc1bd5abacfcf9cbdd3b6afb80c0f799bfb2fd42c44a0c224e";
+		assert_eq!(to_checkpoint(&None).unwrap(), None);
+		assert_eq!(
+			to_checkpoint(&Some(format!("4000000:0x{}:0x1f0", hash))).unwrap(),
+			Some((4000000, hash.parse().unwrap(), 0x1f0.into()))
+		);
+		assert_eq!(
+			to_checkpoint(&Some(format!("4000000:0x{}:496", hash))).unwrap(),
+			Some((4000000, hash.parse().unwrap(), 496.into()))
+		);
+		assert!(to_checkpoint(&Some(hash.into())).is_err());
+		assert!(to_checkpoint(&Some(format!("4000000:0x{}", hash))).is_err());
+		assert!(to_checkpoint(&Some("4000000:0x12:1".into())).is_err());
+	}
+
+	#[test]
+	fn test_to_node_ids() {
+		let id = "a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c";
+		assert_eq!(to_node_ids(&None).unwrap(), None);
+		assert_eq!(to_node_ids(&Some("".into())).unwrap(), Some(HashSet::new()));
+		assert_eq!(
+			to_node_ids(&Some(format!("enode://{}@22.99.55.44:7770,0x{}", id, id))).unwrap(),
+			Some(vec![id.parse().unwrap()].into_iter().collect())
+		);
+		assert!(to_node_ids(&Some("enode://12@22.99.55.44:7770".into())).is_err());
+	}
+
 	#[test]
 	fn test_password() {
 		let tempdir = TempDir::new("").unwrap();
@@ -56,6 +56,7 @@ extern crate ethcore_miner as miner;
 extern crate ethcore_network as network;
 extern crate ethcore_transaction as transaction;
 extern crate ethereum_types;
+extern crate ethjson;
 extern crate migration as migr;
 extern crate kvdb;
 extern crate kvdb_rocksdb;
@@ -89,6 +90,9 @@ extern crate ethcore_secretstore;
 #[cfg(feature = "dapps")]
 extern crate parity_dapps;
 
+#[cfg(feature = "native-builtins")]
+extern crate native_builtins;
+
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
@@ -101,6 +105,7 @@ extern crate tempdir;
 
 mod account;
 mod blockchain;
+mod builtins;
 mod cache;
 mod cli;
 mod configuration;
@@ -112,6 +117,7 @@ mod informant;
 mod light_helpers;
 mod migration;
 mod modules;
+mod new_chain;
 mod params;
 mod presale;
 mod rpc;
@@ -150,6 +156,22 @@ fn print_hash_of(maybe_file: Option<String>) -> Result<String, String> {
 	}
 }
 
+fn check_spec_file(maybe_file: Option<String>) -> Result<String, String> {
+	let file = maybe_file.ok_or_else(|| "Specify a chain spec file.".to_owned())?;
+	let mut json = Vec::new();
+	File::open(&file).and_then(|mut f| f.read_to_end(&mut json)).map_err(|_| "Unable to read the chain spec file".to_owned())?;
+
+	let problems = ethcore::spec::check_spec(&json);
+	let report = problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+	if problems.iter().any(ethcore::spec::SpecProblem::is_error) {
+		Err(report)
+	} else if problems.is_empty() {
+		Ok(format!("{} is valid", file))
+	} else {
+		Ok(format!("{}\n{} is valid", report, file))
+	}
+}
+
 enum PostExecutionAction {
 	Print(String),
 	Restart(Option<String>),
@@ -166,6 +188,8 @@ fn execute(command: Execute, can_restart: bool) -> Result<PostExecutionAction, S
 		},
 		Cmd::Version => Ok(PostExecutionAction::Print(Args::print_version())),
 		Cmd::Hash(maybe_file) => print_hash_of(maybe_file).map(|s| PostExecutionAction::Print(s)),
+		Cmd::CheckSpec(maybe_file) => check_spec_file(maybe_file).map(|s| PostExecutionAction::Print(s)),
+		Cmd::NewChain(new_chain_cmd) => new_chain::execute(new_chain_cmd).map(|s| PostExecutionAction::Print(s)),
 		Cmd::Account(account_cmd) => account::execute(account_cmd).map(|s| PostExecutionAction::Print(s)),
 		Cmd::ImportPresaleWallet(presale_cmd) => presale::execute(presale_cmd).map(|s| PostExecutionAction::Print(s)),
 		Cmd::Blockchain(blockchain_cmd) => blockchain::execute(blockchain_cmd).map(|_| PostExecutionAction::Quit),
@@ -178,6 +202,8 @@ fn execute(command: Execute, can_restart: bool) -> Result<PostExecutionAction, S
 }
 
 fn start(can_restart: bool) -> Result<PostExecutionAction, String> {
+	builtins::register()?;
+
 	let args: Vec<String> = env::args().collect();
 	let conf = Configuration::parse(&args, take_spec_name_override()).unwrap_or_else(|e| e.exit());
 
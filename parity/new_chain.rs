@@ -0,0 +1,487 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain spec generator for private networks.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ethereum_types::{Address, U256};
+use ethcore::account_provider::{AccountProvider, AccountProviderSettings};
+use ethcore::ethstore::EthStore;
+use ethcore::ethstore::accounts_dir::RootDiskDirectory;
+use ethcore::spec::{check_spec, SpecProblem};
+use ethjson::bytes::Bytes;
+use ethjson::hash::{H520, Address as JsonAddress};
+use ethjson::spec::{
+	Account, AuthorityRound, AuthorityRoundParams, AuthorityRoundSeal, BasicAuthority, BasicAuthorityParams,
+	Builtin, Engine, Genesis, Linear, Params, Pricing, Seal, Spec, StepDuration, Tendermint, TendermintParams,
+	TendermintSeal, ValidatorSet,
+};
+use ethjson::spec::builtin::{AltBn128Pairing, Modexp};
+use ethjson::uint::Uint;
+use ethkey::{Generator, Random};
+use hash::keccak;
+use rustc_hex::ToHex;
+use serde_json;
+use helpers::{password_prompt, password_from_file};
+
+/// First network, RPC and WebSockets ports of the generated node configs. Every node
+/// uses the port following the one of the previous node.
+const NETWORK_PORT: u16 = 30300;
+const RPC_PORT: u16 = 8540;
+const WS_PORT: u16 = 8450;
+
+/// Maximum number of node configs, so that WebSockets ports stay below the RPC ports.
+const MAX_NODES: usize = (RPC_PORT - WS_PORT) as usize;
+
+/// Consensus engine of a new chain.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChainEngine {
+	AuthorityRound,
+	Tendermint,
+	BasicAuthority,
+}
+
+impl FromStr for ChainEngine {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"aura" | "authority-round" => Ok(ChainEngine::AuthorityRound),
+			"tendermint" => Ok(ChainEngine::Tendermint),
+			"basic-authority" => Ok(ChainEngine::BasicAuthority),
+			other => Err(format!("Invalid engine: {}", other)),
+		}
+	}
+}
+
+/// Latest hard fork enabled from the genesis block.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Fork {
+	Frontier,
+	SpuriousDragon,
+	Byzantium,
+}
+
+impl FromStr for Fork {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"frontier" => Ok(Fork::Frontier),
+			"spurious-dragon" => Ok(Fork::SpuriousDragon),
+			"byzantium" => Ok(Fork::Byzantium),
+			other => Err(format!("Invalid fork: {}", other)),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewChain {
+	pub name: String,
+	pub engine: ChainEngine,
+	pub fork: Fork,
+	pub block_time: u64,
+	pub network_id: Option<u64>,
+	/// Existing accounts in the chain's key store to use as validators.
+	pub validators: Vec<Address>,
+	/// Number of new validator accounts to create.
+	pub new_validators: usize,
+	/// Genesis balances.
+	pub fund: Vec<(Address, U256)>,
+	pub keys_path: String,
+	pub iterations: u32,
+	pub password_file: Option<String>,
+	/// Directory to write a key and a node config for each validator to.
+	pub node_configs: Option<String>,
+	/// Spec file to write, standard output if `None`.
+	pub file: Option<String>,
+}
+
+/// Validator of a new chain, with the node it runs when node configs are generated.
+struct Validator {
+	address: Address,
+	node: Option<Node>,
+}
+
+struct Node {
+	dir: PathBuf,
+	index: u16,
+	enode: String,
+	key: String,
+}
+
+pub fn execute(cmd: NewChain) -> Result<String, String> {
+	if cmd.block_time == 0 {
+		return Err("Block time must not be zero.".into());
+	}
+	if cmd.node_configs.is_some() && (cmd.file.is_none() || cmd.password_file.is_none()) {
+		return Err("Node configs need the spec to be written to a file and a --password file.".into());
+	}
+	if cmd.node_configs.is_some() && cmd.validators.len() + cmd.new_validators > MAX_NODES {
+		return Err(format!("Node configs can be generated for at most {} validators.", MAX_NODES));
+	}
+
+	let mut keys_path = PathBuf::from(&cmd.keys_path);
+	keys_path.push(&cmd.name);
+	let dir = RootDiskDirectory::create(&keys_path).map_err(|e| format!("Could not open keys directory: {}", e))?;
+	let secret_store = EthStore::open_with_iterations(Box::new(dir), cmd.iterations)
+		.map_err(|e| format!("Could not open keys store: {}", e))?;
+	let acc_provider = AccountProvider::new(Box::new(secret_store), AccountProviderSettings::default());
+
+	let password = match cmd.password_file {
+		Some(ref file) => Some(password_from_file(file.clone())?),
+		None if cmd.new_validators > 0 => Some(password_prompt()?),
+		None => None,
+	};
+
+	let mut addresses = Vec::new();
+	for address in &cmd.validators {
+		if !acc_provider.has_account(*address).map_err(|e| format!("{}", e))? {
+			return Err(format!("Validator 0x{:?} not found in {}", address, keys_path.display()));
+		}
+		addresses.push(*address);
+	}
+	for _ in 0..cmd.new_validators {
+		let password = password.as_ref().expect("password is read when new validators are requested; qed");
+		let address = acc_provider.new_account(password).map_err(|e| format!("Could not create new account: {}", e))?;
+		addresses.push(address);
+	}
+	if addresses.is_empty() {
+		return Err("A chain needs at least one validator. Use --validators or --new-validators.".into());
+	}
+
+	let validators: Vec<_> = addresses.into_iter().enumerate().map(|(i, address)| Validator {
+		address: address,
+		node: cmd.node_configs.as_ref().map(|dir| {
+			let key = Random.generate().expect("secp context has generation capabilities; qed");
+			let index = i as u16;
+			Node {
+				dir: Path::new(dir).join(format!("node{}", i)),
+				index: index,
+				enode: format!("enode://{:?}@127.0.0.1:{}", key.public(), NETWORK_PORT + index),
+				key: key.secret().to_hex(),
+			}
+		}),
+	}).collect();
+
+	let spec = chain_spec(&cmd, &validators);
+	let json = serde_json::to_string_pretty(&spec).map_err(|e| format!("Could not serialize the spec: {}", e))?;
+	if let Some(problem) = check_spec(json.as_bytes()).into_iter().find(SpecProblem::is_error) {
+		return Err(format!("Generated an invalid spec: {}", problem));
+	}
+
+	let file = match cmd.file {
+		Some(ref file) => file,
+		None => return Ok(json),
+	};
+	File::create(file).and_then(|mut f| f.write_all(json.as_bytes()))
+		.map_err(|e| format!("Could not write {}: {}", file, e))?;
+
+	if let Some(ref password_file) = cmd.password_file {
+		let password = password.as_ref().expect("password is read from the given file; qed");
+		for validator in &validators {
+			if let Some(ref node) = validator.node {
+				write_node(&cmd.name, file, password_file, password, &acc_provider, validator.address, node)?;
+			}
+		}
+	}
+
+	let addresses: Vec<_> = validators.iter().map(|v| format!("0x{:?}", v.address)).collect();
+	Ok(format!("Chain spec written to {}\nValidators: {}", file, addresses.join(", ")))
+}
+
+fn chain_spec(cmd: &NewChain, validators: &[Validator]) -> Spec {
+	let list = ValidatorSet::List(validators.iter().map(|v| JsonAddress(v.address)).collect());
+	let zero_signature = H520(Default::default());
+	let (engine, seal) = match cmd.engine {
+		ChainEngine::AuthorityRound => (
+			Engine::AuthorityRound(AuthorityRound {
+				params: AuthorityRoundParams {
+					step_duration: StepDuration::Single(Uint(cmd.block_time.into())),
+					validators: list,
+					start_step: None,
+					validate_score_transition: None,
+					validate_step_transition: None,
+					immediate_transitions: None,
+					block_reward: None,
+					maximum_uncle_count_transition: None,
+					maximum_uncle_count: None,
+					block_reward_contract_transition: None,
+					block_reward_contract_address: None,
+					block_reward_contract_gas_limit: None,
+					empty_steps_transition: None,
+					maximum_empty_steps: None,
+				},
+			}),
+			Seal::AuthorityRound(AuthorityRoundSeal {
+				step: Uint(0.into()),
+				signature: zero_signature,
+			}),
+		),
+		ChainEngine::Tendermint => (
+			Engine::Tendermint(Tendermint {
+				params: TendermintParams {
+					validators: list,
+					timeout_propose: None,
+					timeout_prevote: None,
+					timeout_precommit: None,
+					timeout_commit: Some(Uint((cmd.block_time * 1000).into())),
+					block_reward: None,
+				},
+			}),
+			Seal::Tendermint(TendermintSeal {
+				round: Uint(0.into()),
+				proposal: zero_signature.clone(),
+				precommits: vec![zero_signature],
+			}),
+		),
+		ChainEngine::BasicAuthority => (
+			Engine::BasicAuthority(BasicAuthority {
+				params: BasicAuthorityParams {
+					duration_limit: Uint(cmd.block_time.into()),
+					validators: list,
+				},
+			}),
+			Seal::Generic(Bytes::new(vec![0xc1, 0x80])),
+		),
+	};
+
+	let genesis_fork = Some(Uint(0.into()));
+	let spurious_dragon = if cmd.fork >= Fork::SpuriousDragon { genesis_fork } else { None };
+	let byzantium = if cmd.fork >= Fork::Byzantium { genesis_fork } else { None };
+	let network_id = cmd.network_id.unwrap_or_else(|| keccak(&cmd.name).low_u64() & 0x7fff_ffff);
+
+	let params = Params {
+		maximum_extra_data_size: Uint(0x20.into()),
+		min_gas_limit: Uint(0x1388.into()),
+		gas_limit_bound_divisor: Uint(0x400.into()),
+		network_id: Uint(network_id.into()),
+		eip155_transition: spurious_dragon,
+		validate_chain_id_transition: spurious_dragon,
+		validate_receipts_transition: genesis_fork,
+		max_code_size: spurious_dragon.map(|_| Uint(0x6000.into())),
+		eip140_transition: byzantium,
+		eip211_transition: byzantium,
+		eip214_transition: byzantium,
+		eip658_transition: byzantium,
+		..Default::default()
+	};
+
+	let mut accounts = BTreeMap::new();
+	{
+		let mut builtin = |address: u64, name: &str, activate: bool, pricing: Pricing| {
+			let account = Account {
+				balance: Some(Uint(1.into())),
+				builtin: Some(Builtin {
+					name: name.into(),
+					pricing: pricing,
+					activate_at: if activate { byzantium } else { None },
+				}),
+				..Default::default()
+			};
+			accounts.insert(JsonAddress(address.into()), account);
+		};
+		let linear = |base, word| Pricing::Linear(Linear { base: base, word: word });
+		builtin(1, "ecrecover", false, linear(3000, 0));
+		builtin(2, "sha256", false, linear(60, 12));
+		builtin(3, "ripemd160", false, linear(600, 120));
+		builtin(4, "identity", false, linear(15, 3));
+		if cmd.fork >= Fork::Byzantium {
+			builtin(5, "modexp", true, Pricing::Modexp(Modexp { divisor: 20 }));
+			builtin(6, "alt_bn128_add", true, linear(500, 0));
+			builtin(7, "alt_bn128_mul", true, linear(40000, 0));
+			builtin(8, "alt_bn128_pairing", true, Pricing::AltBn128Pairing(AltBn128Pairing { base: 100000, pair: 80000 }));
+		}
+	}
+	for &(address, balance) in &cmd.fund {
+		let account = accounts.entry(JsonAddress(address)).or_insert_with(Account::default);
+		account.balance = Some(Uint(balance));
+	}
+
+	let nodes: Vec<_> = validators.iter().filter_map(|v| v.node.as_ref().map(|node| node.enode.clone())).collect();
+
+	Spec {
+		name: cmd.name.clone(),
+		data_dir: None,
+		engine: engine,
+		params: params,
+		genesis: Genesis {
+			seal: seal,
+			difficulty: Uint(0x20000.into()),
+			author: None,
+			timestamp: None,
+			parent_hash: None,
+			gas_limit: Uint(0x5b8d80.into()),
+			transactions_root: None,
+			receipts_root: None,
+			state_root: None,
+			gas_used: None,
+			extra_data: None,
+		},
+		accounts: accounts.into(),
+		nodes: if nodes.is_empty() { None } else { Some(nodes) },
+	}
+}
+
+fn write_node(
+	chain: &str,
+	spec_file: &str,
+	password_file: &str,
+	password: &str,
+	acc_provider: &AccountProvider,
+	address: Address,
+	node: &Node,
+) -> Result<(), String> {
+	let absolute = |path: &Path| fs::canonicalize(path)
+		.map_err(|e| format!("Could not resolve {}: {}", path.display(), e));
+
+	let keys_dir = node.dir.join("keys").join(chain);
+	fs::create_dir_all(&keys_dir).map_err(|e| format!("Could not create {}: {}", keys_dir.display(), e))?;
+
+	let key = acc_provider.export_account(&address, password.into())
+		.map_err(|e| format!("Could not export validator 0x{:?}: {}", address, e))?;
+	let key_path = keys_dir.join(format!("{:?}.json", address));
+	File::create(&key_path).map_err(|e| e.to_string())
+		.and_then(|mut f| serde_json::to_writer(&mut f, &key).map_err(|e| e.to_string()))
+		.map_err(|e| format!("Could not write {}: {}", key_path.display(), e))?;
+
+	let config = format!(r#"[parity]
+chain = "{chain}"
+base_path = "{base_path}"
+
+[network]
+port = {port}
+node_key = "{node_key}"
+
+[rpc]
+port = {rpc_port}
+
+[websockets]
+port = {ws_port}
+
+[ui]
+disable = true
+
+[account]
+unlock = ["0x{address:?}"]
+password = ["{password}"]
+
+[mining]
+engine_signer = "0x{address:?}"
+reseal_on_txs = "none"
+"#,
+		chain = absolute(Path::new(spec_file))?.display(),
+		base_path = absolute(&node.dir)?.display(),
+		port = NETWORK_PORT + node.index,
+		node_key = node.key,
+		rpc_port = RPC_PORT + node.index,
+		ws_port = WS_PORT + node.index,
+		address = address,
+		password = absolute(Path::new(password_file))?.display(),
+	);
+
+	let config_path = node.dir.join("config.toml");
+	File::create(&config_path).and_then(|mut f| f.write_all(config.as_bytes()))
+		.map_err(|e| format!("Could not write {}: {}", config_path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::{self, File};
+	use std::io::{Read, Write};
+	use ethcore::spec::check_spec;
+	use ethjson::spec::Spec;
+	use serde_json;
+	use tempdir::TempDir;
+	use super::{NewChain, ChainEngine, Fork, Validator, MAX_NODES, chain_spec, execute};
+
+	fn new_chain(engine: ChainEngine, fork: Fork) -> NewChain {
+		NewChain {
+			name: "test".into(),
+			engine: engine,
+			fork: fork,
+			block_time: 4,
+			network_id: Some(0x2323),
+			validators: vec![],
+			new_validators: 0,
+			fund: vec![(1.into(), 1000.into()), (0x100.into(), 1000.into())],
+			keys_path: "".into(),
+			iterations: 1,
+			password_file: None,
+			node_configs: None,
+			file: None,
+		}
+	}
+
+	#[test]
+	fn generates_valid_specs() {
+		let validators = vec![Validator { address: 0x10.into(), node: None }];
+		for &engine in &[ChainEngine::AuthorityRound, ChainEngine::Tendermint, ChainEngine::BasicAuthority] {
+			for &fork in &[Fork::Frontier, Fork::SpuriousDragon, Fork::Byzantium] {
+				let spec = chain_spec(&new_chain(engine, fork), &validators);
+				let json = serde_json::to_string(&spec).unwrap();
+				assert_eq!(check_spec(json.as_bytes()), vec![]);
+			}
+		}
+	}
+
+	#[test]
+	fn writes_spec_and_node_configs() {
+		let dir = TempDir::new("").unwrap();
+		let password_file = dir.path().join("password");
+		File::create(&password_file).and_then(|mut f| f.write_all(b"password")).unwrap();
+		let spec_file = dir.path().join("spec.json");
+		let nodes = dir.path().join("nodes");
+
+		let mut cmd = new_chain(ChainEngine::AuthorityRound, Fork::Byzantium);
+		cmd.new_validators = 2;
+		cmd.keys_path = dir.path().join("keys").to_str().unwrap().into();
+		cmd.password_file = Some(password_file.to_str().unwrap().into());
+		cmd.node_configs = Some(nodes.to_str().unwrap().into());
+		cmd.file = Some(spec_file.to_str().unwrap().into());
+		execute(cmd).unwrap();
+
+		let mut json = Vec::new();
+		File::open(&spec_file).and_then(|mut f| f.read_to_end(&mut json)).unwrap();
+		assert_eq!(check_spec(&json), vec![]);
+		let spec: Spec = serde_json::from_slice(&json).unwrap();
+		assert_eq!(spec.nodes.map(|nodes| nodes.len()), Some(2));
+
+		for (i, &(port, rpc_port, ws_port)) in [(30300, 8540, 8450), (30301, 8541, 8451)].iter().enumerate() {
+			let node = nodes.join(format!("node{}", i));
+			let mut config = String::new();
+			File::open(node.join("config.toml")).and_then(|mut f| f.read_to_string(&mut config)).unwrap();
+			assert!(config.contains(&format!("[network]\nport = {}\n", port)));
+			assert!(config.contains(&format!("[rpc]\nport = {}\n", rpc_port)));
+			assert!(config.contains(&format!("[websockets]\nport = {}\n", ws_port)));
+			assert_eq!(fs::read_dir(node.join("keys").join("test")).unwrap().count(), 1);
+		}
+	}
+
+	#[test]
+	fn limits_node_configs() {
+		let mut cmd = new_chain(ChainEngine::BasicAuthority, Fork::Byzantium);
+		cmd.new_validators = MAX_NODES + 1;
+		cmd.password_file = Some("password".into());
+		cmd.node_configs = Some("nodes".into());
+		cmd.file = Some("spec.json".into());
+		assert_eq!(execute(cmd), Err(format!("Node configs can be generated for at most {} validators.", MAX_NODES)));
+	}
+}
@@ -31,8 +31,9 @@ use ethcore::service::ClientService;
 use ethcore::snapshot;
 use ethcore::spec::{SpecParams, OptimizeFor};
 use ethcore::verification::queue::VerifierSettings;
+use ethereum_types::{H256, U256};
 use ethcore_logger::{Config as LogConfig, RotatingLogger};
-use ethsync::{self, SyncConfig};
+use ethsync::{self, SyncConfig, TransactionPropagation};
 use fdlimit::raise_fd_limit;
 use hash_fetch::fetch::{Fetch, Client as FetchClient};
 use informant::{Informant, LightNodeInformantData, FullNodeInformantData};
@@ -123,6 +124,9 @@ pub struct RunCmd {
 	pub no_periodic_snapshot: bool,
 	pub check_seal: bool,
 	pub download_old_blocks: bool,
+	pub checkpoint: Option<(u64, H256, U256)>,
+	pub state_sync: bool,
+	pub transaction_propagation: TransactionPropagation,
 	pub verifier_settings: VerifierSettings,
 	pub serve_light: bool,
 	pub light: bool,
@@ -508,6 +512,29 @@ pub fn execute_impl(cmd: RunCmd, can_restart: bool, logger: Arc<RotatingLogger>)
 	}
 	sync_config.warp_sync = spec.engine.supports_warp() && warp_sync;
 	sync_config.download_old_blocks = cmd.download_old_blocks;
+	sync_config.checkpoint = match cmd.checkpoint {
+		Some(_) if fat_db => {
+			warn!("Warning: Checkpoint sync is disabled because Fat DB is turned on.");
+			None
+		},
+		Some(_) if tracing => {
+			warn!("Warning: Checkpoint sync is disabled because tracing is turned on.");
+			None
+		},
+		checkpoint => checkpoint,
+	};
+	let mut state_sync = cmd.state_sync;
+	if state_sync {
+		if fat_db {
+			warn!("Warning: State sync is disabled because Fat DB is turned on.");
+			state_sync = false;
+		} else if tracing {
+			warn!("Warning: State sync is disabled because tracing is turned on.");
+			state_sync = false;
+		}
+	}
+	sync_config.state_sync = state_sync;
+	sync_config.transaction_propagation = cmd.transaction_propagation;
 	sync_config.serve_light = cmd.serve_light;
 
 	let passwords = passwords_from_files(&cmd.acc_conf.password_files)?;
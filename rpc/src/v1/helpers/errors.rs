@@ -23,6 +23,7 @@ use ethcore::error::{Error as EthcoreError, CallError};
 use jsonrpc_core::{futures, Error, ErrorCode, Value};
 use rlp::DecoderError;
 use transaction::Error as TransactionError;
+use v1::types::WasmPanic;
 
 mod codes {
 	// NOTE [ToDr] Codes from [-32099, -32000]
@@ -140,6 +141,14 @@ pub fn execution<T: fmt::Debug>(data: T) -> Error {
 	}
 }
 
+pub fn wasm_panic(panic: WasmPanic) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::EXECUTION_ERROR),
+		message: "The execution failed due to a panic in the contract.".into(),
+		data: ::serde_json::to_value(panic).ok(),
+	}
+}
+
 pub fn state_pruned() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -50,7 +50,7 @@ use v1::helpers::accounts::unwrap_provider;
 use v1::traits::Eth;
 use v1::types::{
 	RichBlock, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
-	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
+	Transaction, CallRequest, Index, Filter, Log, Receipt, Work, WasmPanic,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
 use v1::metadata::Metadata;
@@ -633,8 +633,11 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		let result = self.client.call(&signed, Default::default(), num.into());
 
 		Box::new(future::done(result
-			.map(|b| b.output.into())
 			.map_err(errors::call)
+			.and_then(|executed| match WasmPanic::from_exception(&executed.exception) {
+				Some(panic) => Err(errors::wasm_panic(panic)),
+				None => Ok(executed.output.into()),
+			})
 		))
 	}
 
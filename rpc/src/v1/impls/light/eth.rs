@@ -46,7 +46,7 @@ use v1::helpers::light_fetch::{self, LightFetch};
 use v1::traits::Eth;
 use v1::types::{
 	RichBlock, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
-	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
+	Transaction, CallRequest, Index, Filter, Log, Receipt, Work, WasmPanic,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
 use v1::metadata::Metadata;
@@ -379,7 +379,10 @@ impl<T: LightChainClient + 'static> Eth for EthClient<T> {
 	fn call(&self, _meta: Self::Metadata, req: CallRequest, num: Trailing<BlockNumber>) -> BoxFuture<Bytes> {
 		Box::new(self.fetcher().proved_execution(req, num).and_then(|res| {
 			match res {
-				Ok(exec) => Ok(exec.output.into()),
+				Ok(exec) => match WasmPanic::from_exception(&exec.exception) {
+					Some(panic) => Err(errors::wasm_panic(panic)),
+					None => Ok(exec.output.into()),
+				},
 				Err(e) => Err(errors::execution(e)),
 			}
 		}))
@@ -41,7 +41,7 @@ use v1::traits::Parity;
 use v1::types::{
 	Bytes, U256, U64, H160, H256, H512, CallRequest,
 	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	TransactionStats, LocalTransactionStatus, MisbehaviourEvidence,
 	BlockNumber, ConsensusCapability, VersionInfo,
 	OperationsInfo, DappId, ChainStatus,
 	AccountInfo, HwAccountInfo, Header, RichHeader,
@@ -312,6 +312,15 @@ impl Parity for ParityClient {
 		Ok(map)
 	}
 
+	fn misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>> {
+		// light clients never report validators.
+		Ok(Vec::new())
+	}
+
+	fn misbehaviour_evidence_by_hash(&self, _hash: H256) -> Result<Option<MisbehaviourEvidence>> {
+		Ok(None)
+	}
+
 	fn dapps_url(&self) -> Result<String> {
 		helpers::to_url(&self.dapps_address)
 			.ok_or_else(|| errors::dapps_disabled())
@@ -45,7 +45,7 @@ use v1::traits::Parity;
 use v1::types::{
 	Bytes, U256, U64, H160, H256, H512, CallRequest,
 	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	TransactionStats, LocalTransactionStatus, MisbehaviourEvidence,
 	BlockNumber, ConsensusCapability, VersionInfo,
 	OperationsInfo, DappId, ChainStatus,
 	AccountInfo, HwAccountInfo, RichHeader
@@ -325,6 +325,15 @@ impl<C, M, U> Parity for ParityClient<C, M, U> where
 		)
 	}
 
+	fn misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>> {
+		Ok(self.client.misbehaviour_evidence().into_iter().map(Into::into).collect())
+	}
+
+	fn misbehaviour_evidence_by_hash(&self, hash: H256) -> Result<Option<MisbehaviourEvidence>> {
+		let hash: ::ethereum_types::H256 = hash.into();
+		Ok(self.client.misbehaviour_evidence().into_iter().find(|e| e.hash == hash).map(Into::into))
+	}
+
 	fn dapps_url(&self) -> Result<String> {
 		helpers::to_url(&self.dapps_address)
 			.ok_or_else(|| errors::dapps_disabled())
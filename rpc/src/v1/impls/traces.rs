@@ -29,10 +29,11 @@ use v1::traits::Traces;
 use v1::helpers::{errors, fake_sign};
 use v1::types::{TraceFilter, LocalizedTrace, BlockNumber, Index, CallRequest, Bytes, TraceResults, TraceOptions, H256};
 
-fn to_call_analytics(flags: TraceOptions) -> CallAnalytics {
+fn to_call_analytics(flags: &TraceOptions) -> CallAnalytics {
 	CallAnalytics {
 		transaction_tracing: flags.contains(&("trace".to_owned())),
 		vm_tracing: flags.contains(&("vmTrace".to_owned())),
+		debug_logging: flags.contains(&("debugLog".to_owned())),
 		state_diffing: flags.contains(&("stateDiff".to_owned())),
 	}
 }
@@ -85,24 +86,30 @@ impl<C> Traces for TracesClient<C> where C: MiningBlockChainClient + 'static {
 		let request = CallRequest::into(request);
 		let signed = fake_sign::sign_call(request, meta.is_dapp())?;
 
-		self.client.call(&signed, to_call_analytics(flags), block.into())
-			.map(TraceResults::from)
+		self.client.call(&signed, to_call_analytics(&flags), block.into())
+			.map(|executed| TraceResults::with_options(executed, &flags))
 			.map_err(errors::call)
 	}
 
 	fn call_many(&self, meta: Self::Metadata, requests: Vec<(CallRequest, TraceOptions)>, block: Trailing<BlockNumber>) -> Result<Vec<TraceResults>> {
 		let block = block.unwrap_or_default();
 
-		let requests = requests.into_iter()
+		let (requests, flags): (Vec<_>, Vec<_>) = requests.into_iter()
 			.map(|(request, flags)| {
 				let request = CallRequest::into(request);
 				let signed = fake_sign::sign_call(request, meta.is_dapp())?;
-				Ok((signed, to_call_analytics(flags)))
+				Ok(((signed, to_call_analytics(&flags)), flags))
 			})
-			.collect::<Result<Vec<_>>>()?;
+			.collect::<Result<Vec<_>>>()?
+			.into_iter()
+			.unzip();
 
 		self.client.call_many(&requests, block.into())
-			.map(|results| results.into_iter().map(TraceResults::from).collect())
+			.map(|results| results.into_iter()
+				.zip(flags.iter())
+				.map(|(executed, flags)| TraceResults::with_options(executed, flags))
+				.collect()
+			)
 			.map_err(errors::call)
 	}
 
@@ -112,20 +119,20 @@ impl<C> Traces for TracesClient<C> where C: MiningBlockChainClient + 'static {
 		let tx = UntrustedRlp::new(&raw_transaction.into_vec()).as_val().map_err(|e| errors::invalid_params("Transaction is not valid RLP", e))?;
 		let signed = SignedTransaction::new(tx).map_err(errors::transaction)?;
 
-		self.client.call(&signed, to_call_analytics(flags), block.into())
-			.map(TraceResults::from)
+		self.client.call(&signed, to_call_analytics(&flags), block.into())
+			.map(|executed| TraceResults::with_options(executed, &flags))
 			.map_err(errors::call)
 	}
 
 	fn replay_transaction(&self, transaction_hash: H256, flags: TraceOptions) -> Result<TraceResults> {
-		self.client.replay(TransactionId::Hash(transaction_hash.into()), to_call_analytics(flags))
-			.map(TraceResults::from)
+		self.client.replay(TransactionId::Hash(transaction_hash.into()), to_call_analytics(&flags))
+			.map(|executed| TraceResults::with_options(executed, &flags))
 			.map_err(errors::call)
 	}
 
 	fn replay_block_transactions(&self, block_number: BlockNumber, flags: TraceOptions) -> Result<Vec<TraceResults>> {
-		self.client.replay_block_transactions(block_number.into(), to_call_analytics(flags))
-			.map(|results| results.into_iter().map(TraceResults::from).collect())
+		self.client.replay_block_transactions(block_number.into(), to_call_analytics(&flags))
+			.map(|results| results.into_iter().map(|executed| TraceResults::with_options(executed, &flags)).collect())
 			.map_err(errors::call)
 	}
 }
@@ -17,7 +17,7 @@
 use ethcore::snapshot::{ManifestData, RestorationStatus, SnapshotService};
 
 use bytes::Bytes;
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use parking_lot::Mutex;
 
 /// Mocked snapshot service (used for sync info extensions).
@@ -49,4 +49,8 @@ impl SnapshotService for TestSnapshotService {
 	fn abort_restore(&self) { }
 	fn restore_state_chunk(&self, _hash: H256, _chunk: Bytes) { }
 	fn restore_block_chunk(&self, _hash: H256, _chunk: Bytes) { }
+	fn begin_node_restore(&self, _block: Bytes, _receipts: Bytes, _total_difficulty: U256) { }
+	fn move_node_restore(&self, _block: Bytes, _receipts: Bytes, _total_difficulty: U256) { }
+	fn missing_state_nodes(&self, _count: usize) -> Vec<H256> { Vec::new() }
+	fn restore_state_nodes(&self, _nodes: Vec<Bytes>) { }
 }
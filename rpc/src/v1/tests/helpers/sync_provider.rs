@@ -54,6 +54,8 @@ impl TestSyncProvider {
 				num_snapshot_chunks: 0,
 				snapshot_chunks_done: 0,
 				last_imported_old_block_number: None,
+				num_retried_requests: 0,
+				download_rate: 0,
 			}),
 		}
 	}
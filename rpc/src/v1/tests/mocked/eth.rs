@@ -33,6 +33,7 @@ use rlp;
 use rustc_hex::{FromHex, ToHex};
 use time::get_time;
 use transaction::{Transaction, Action};
+use vm;
 
 use jsonrpc_core::IoHandler;
 use v1::{Eth, EthClient, EthClientOptions, EthFilter, EthFilterClient, EthSigning, SigningUnsafeClient};
@@ -602,6 +603,45 @@ fn rpc_eth_code() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_call_wasm_panic() {
+	let tester = EthTester::default();
+	tester.client.set_execution_result(Ok(Executed {
+		exception: Some(vm::Error::WasmPanic(vm::PanicPayload {
+			msg: Some("boom".into()),
+			file: Some("src/lib.rs".into()),
+			line: Some(10),
+			col: Some(5),
+		})),
+		gas: U256::zero(),
+		gas_used: U256::from(0xff30),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![],
+		trace: vec![],
+		vm_trace: None,
+		state_diff: None,
+	}));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"data": "0xd46e8dd6"
+		},
+		"latest"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"The execution failed due to a panic in the contract.","data":{"column":5,"file":"src/lib.rs","line":10,"message":"boom"}},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_call_latest() {
 	let tester = EthTester::default();
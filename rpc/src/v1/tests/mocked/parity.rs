@@ -495,6 +495,33 @@ fn rpc_parity_local_transactions() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_misbehaviour_evidence() {
+	use ethcore::engines::evidence::{Evidence, Misbehaviour, ReportStatus};
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+	let mut evidence = Evidence::new(Address::from(1), 0, 5, Misbehaviour::Benign, Vec::new(), "skipped step");
+	evidence.status = ReportStatus::Failed("No client!".into());
+	evidence.attempts = 1;
+	let hash = evidence.hash;
+	deps.client.misbehaviour_evidence.write().push(evidence);
+
+	let entry = format!(r#"{{"hash":"0x{:x}","validator":"0x0000000000000000000000000000000000000001","blockNumber":"0x5","kind":"benign","reason":"skipped step","proof":"0x","status":"failed","error":"No client!","attempts":1,"transactionHash":null}}"#, hash);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_misbehaviourEvidence", "params":[], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":[{}],"id":1}}"#, entry);
+	assert_eq!(io.handle_request_sync(request), Some(response));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_misbehaviourEvidenceByHash", "params":["0x{:x}"], "id": 1}}"#, hash);
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, entry);
+	assert_eq!(io.handle_request_sync(&request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_misbehaviourEvidenceByHash", "params":["0x0000000000000000000000000000000000000000000000000000000000000000"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_chain_status() {
 	let deps = Dependencies::new();
@@ -25,7 +25,7 @@ use node_health::Health;
 use v1::types::{
 	H160, H256, H512, U256, U64, Bytes, CallRequest,
 	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
+	TransactionStats, LocalTransactionStatus, MisbehaviourEvidence,
 	BlockNumber, ConsensusCapability, VersionInfo,
 	OperationsInfo, DappId, ChainStatus,
 	AccountInfo, HwAccountInfo, RichHeader,
@@ -155,6 +155,14 @@ build_rpc_trait! {
 		#[rpc(name = "parity_localTransactions")]
 		fn local_transactions(&self) -> Result<BTreeMap<H256, LocalTransactionStatus>>;
 
+		/// Returns the misbehaviour of validators reported by this node, oldest first.
+		#[rpc(name = "parity_misbehaviourEvidence")]
+		fn misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>>;
+
+		/// Returns the misbehaviour report with the given hash.
+		#[rpc(name = "parity_misbehaviourEvidenceByHash")]
+		fn misbehaviour_evidence_by_hash(&self, H256) -> Result<Option<MisbehaviourEvidence>>;
+
 		/// Returns current Dapps Server interface and port or an error if dapps server is disabled.
 		#[rpc(name = "parity_dappsUrl")]
 		fn dapps_url(&self) -> Result<String>;
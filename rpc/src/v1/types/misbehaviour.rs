@@ -0,0 +1,104 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::engines::evidence;
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Severity of a validator misbehaviour.
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Misbehaviour {
+	/// Benign misbehaviour.
+	#[serde(rename="benign")]
+	Benign,
+	/// Malicious misbehaviour.
+	#[serde(rename="malicious")]
+	Malicious,
+}
+
+/// Submission status of a misbehaviour report.
+#[derive(Debug, PartialEq, Serialize)]
+pub enum ReportStatus {
+	/// Not submitted yet.
+	#[serde(rename="pending")]
+	Pending,
+	/// Report transaction accepted by the local queue.
+	#[serde(rename="submitted")]
+	Submitted,
+	/// Report transaction included in the chain.
+	#[serde(rename="included")]
+	Included,
+	/// Submission failed, will be retried.
+	#[serde(rename="failed")]
+	Failed,
+	/// Submission failed too many times.
+	#[serde(rename="abandoned")]
+	Abandoned,
+}
+
+/// Evidence of a reported validator misbehaviour.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MisbehaviourEvidence {
+	/// Report identifier.
+	pub hash: H256,
+	/// Reported validator.
+	pub validator: H160,
+	/// Block at which the misbehaviour happened.
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Severity of the misbehaviour.
+	pub kind: Misbehaviour,
+	/// Reason of the report.
+	pub reason: String,
+	/// Proof of the misbehaviour.
+	pub proof: Bytes,
+	/// Submission status.
+	pub status: ReportStatus,
+	/// Error of the last failed submission.
+	pub error: Option<String>,
+	/// Number of submission attempts.
+	pub attempts: usize,
+	/// Hash of the last report transaction.
+	#[serde(rename="transactionHash")]
+	pub transaction_hash: Option<H256>,
+}
+
+impl From<evidence::Evidence> for MisbehaviourEvidence {
+	fn from(e: evidence::Evidence) -> Self {
+		let (status, error) = match e.status {
+			evidence::ReportStatus::Pending => (ReportStatus::Pending, None),
+			evidence::ReportStatus::Submitted => (ReportStatus::Submitted, None),
+			evidence::ReportStatus::Included => (ReportStatus::Included, None),
+			evidence::ReportStatus::Failed(e) => (ReportStatus::Failed, Some(e)),
+			evidence::ReportStatus::Abandoned(e) => (ReportStatus::Abandoned, Some(e)),
+		};
+
+		MisbehaviourEvidence {
+			hash: e.hash.into(),
+			validator: e.validator.into(),
+			block_number: e.block.into(),
+			kind: match e.kind {
+				evidence::Misbehaviour::Benign => Misbehaviour::Benign,
+				evidence::Misbehaviour::Malicious => Misbehaviour::Malicious,
+			},
+			reason: e.reason,
+			proof: e.proof.into(),
+			status: status,
+			error: error,
+			attempts: e.attempts,
+			transaction_hash: e.transaction.map(Into::into),
+		}
+	}
+}
@@ -113,6 +113,68 @@ impl From<(et::VMOperation, Option<et::VMTrace>)> for VMOperation {
 	}
 }
 
+#[derive(Debug, Serialize)]
+/// Debug messages logged by the contract during a single CALL/CREATE.
+pub struct DebugLog {
+	/// Position of the CALL/CREATE in the trace tree.
+	#[serde(rename="traceAddress")]
+	pub trace_address: Vec<usize>,
+	/// Messages in the order they were logged.
+	pub messages: Vec<String>,
+}
+
+impl DebugLog {
+	/// Collect debug messages of every CALL/CREATE in the VM trace.
+	pub fn collect(trace: &et::VMTrace) -> Vec<DebugLog> {
+		fn collect_into(trace: &et::VMTrace, trace_address: &mut Vec<usize>, log: &mut Vec<DebugLog>) {
+			if !trace.debug_log.is_empty() {
+				log.push(DebugLog {
+					trace_address: trace_address.clone(),
+					messages: trace.debug_log.clone(),
+				});
+			}
+
+			for (i, sub) in trace.subs.iter().enumerate() {
+				trace_address.push(i);
+				collect_into(sub, trace_address, log);
+				trace_address.pop();
+			}
+		}
+
+		let mut log = Vec::new();
+		collect_into(trace, &mut Vec::new(), &mut log);
+		log
+	}
+}
+
+#[derive(Debug, Serialize)]
+/// Decoded panic of a Wasm contract.
+pub struct WasmPanic {
+	/// Panic message, unless stripped from the contract.
+	pub message: Option<String>,
+	/// Source file the panic originated from.
+	pub file: Option<String>,
+	/// Line in the source file.
+	pub line: Option<u32>,
+	/// Column in the source file.
+	pub column: Option<u32>,
+}
+
+impl WasmPanic {
+	/// Extract the panic from the exception execution ended with, if it was one.
+	pub fn from_exception(exception: &Option<vm::Error>) -> Option<Self> {
+		match *exception {
+			Some(vm::Error::WasmPanic(ref payload)) => Some(WasmPanic {
+				message: payload.msg.clone(),
+				file: payload.file.clone(),
+				line: payload.line,
+				column: payload.col,
+			}),
+			_ => None,
+		}
+	}
+}
+
 #[derive(Debug, Serialize)]
 /// A record of a full VM trace for a CALL/CREATE.
 pub struct VMTrace {
@@ -308,6 +370,12 @@ pub enum RewardType {
 	/// Uncle
 	#[serde(rename="uncle")]
 	Uncle,
+	/// Reward determined by a contract
+	#[serde(rename="external")]
+	External,
+	/// Empty step (AuthorityRound)
+	#[serde(rename="emptyStep")]
+	EmptyStep,
 }
 
 impl From<trace::RewardType> for RewardType {
@@ -315,6 +383,8 @@ impl From<trace::RewardType> for RewardType {
 		match c {
 			trace::RewardType::Block => RewardType::Block,
 			trace::RewardType::Uncle => RewardType::Uncle,
+			trace::RewardType::External => RewardType::External,
+			trace::RewardType::EmptyStep => RewardType::EmptyStep,
 		}
 	}
 }
@@ -611,15 +681,46 @@ pub struct TraceResults {
 	/// The transaction trace.
 	#[serde(rename="stateDiff")]
 	pub state_diff: Option<StateDiff>,
+	/// Debug messages logged during execution.
+	#[serde(rename="debugLog", skip_serializing_if="Option::is_none")]
+	pub debug_log: Option<Vec<DebugLog>>,
+	/// Panic the execution ended with.
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub panic: Option<WasmPanic>,
+}
+
+impl TraceResults {
+	/// Convert execution results, keeping only the traces requested in `flags`.
+	///
+	/// Debug messages are collected by the VM tracer, so the VM trace is dropped
+	/// unless it was asked for explicitly.
+	pub fn with_options(mut t: Executed, flags: &[String]) -> Self {
+		let has_flag = |flag: &str| flags.iter().any(|f| f == flag);
+
+		let debug_log = match has_flag("debugLog") {
+			true => Some(t.vm_trace.as_ref().map(DebugLog::collect).unwrap_or_default()),
+			false => None,
+		};
+		if !has_flag("vmTrace") {
+			t.vm_trace = None;
+		}
+
+		TraceResults {
+			debug_log: debug_log,
+			..t.into()
+		}
+	}
 }
 
 impl From<Executed> for TraceResults {
 	fn from(t: Executed) -> Self {
 		TraceResults {
+			panic: WasmPanic::from_exception(&t.exception),
 			output: t.output.into(),
 			trace: t.trace.into_iter().map(Into::into).collect(),
 			vm_trace: t.vm_trace.map(Into::into),
 			state_diff: t.state_diff.map(Into::into),
+			debug_log: None,
 		}
 	}
 }
@@ -639,11 +740,57 @@ mod tests {
 			trace: vec![],
 			vm_trace: None,
 			state_diff: None,
+			debug_log: None,
+			panic: None,
 		};
 		let serialized = serde_json::to_string(&r).unwrap();
 		assert_eq!(serialized, r#"{"output":"0x60","trace":[],"vmTrace":null,"stateDiff":null}"#);
 	}
 
+	#[test]
+	fn should_serialize_debug_log_and_panic() {
+		let r = TraceResults {
+			output: Bytes::new(vec![]),
+			trace: vec![],
+			vm_trace: None,
+			state_diff: None,
+			debug_log: Some(vec![DebugLog {
+				trace_address: vec![0],
+				messages: vec!["hello".into()],
+			}]),
+			panic: Some(WasmPanic {
+				message: Some("boom".into()),
+				file: Some("src/lib.rs".into()),
+				line: Some(10),
+				column: Some(5),
+			}),
+		};
+		let serialized = serde_json::to_string(&r).unwrap();
+		assert_eq!(serialized, r#"{"output":"0x","trace":[],"vmTrace":null,"stateDiff":null,"debugLog":[{"traceAddress":[0],"messages":["hello"]}],"panic":{"message":"boom","file":"src/lib.rs","line":10,"column":5}}"#);
+	}
+
+	#[test]
+	fn should_collect_debug_log() {
+		let trace = et::VMTrace {
+			debug_log: vec!["outer".into()],
+			subs: vec![
+				Default::default(),
+				et::VMTrace {
+					debug_log: vec!["inner".into(), "again".into()],
+					..Default::default()
+				},
+			],
+			..Default::default()
+		};
+
+		let log = DebugLog::collect(&trace);
+		assert_eq!(log.len(), 2);
+		assert_eq!(log[0].trace_address, Vec::<usize>::new());
+		assert_eq!(log[0].messages, vec!["outer".to_owned()]);
+		assert_eq!(log[1].trace_address, vec![1]);
+		assert_eq!(log[1].messages, vec!["inner".to_owned(), "again".to_owned()]);
+	}
+
 	#[test]
 	fn test_trace_call_serialize() {
 		let t = LocalizedTrace {
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::io;
 use bytes::Bytes;
 use network::{NetworkProtocolHandler, NetworkService, NetworkContext, HostInfo, PeerId, ProtocolId,
@@ -44,8 +44,45 @@ pub const ETH_PROTOCOL: ProtocolId = *b"eth";
 /// Ethereum light protocol
 pub const LIGHT_PROTOCOL: ProtocolId = *b"pip";
 
+/// How transactions are relayed to a class of peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionPropagationMode {
+	/// Send full transactions.
+	Full,
+	/// Announce transaction hashes only and let the peer request the ones it is missing.
+	/// Peers that do not support announcements receive full transactions.
+	Hashes,
+	/// Do not relay transactions.
+	Off,
+}
+
+/// Transaction propagation policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionPropagation {
+	/// Propagation mode for reserved peers. These are always included.
+	pub reserved_peers: TransactionPropagationMode,
+	/// Propagation mode for all other peers. A random subset of them is picked each time.
+	pub other_peers: TransactionPropagationMode,
+	/// Maximal number of transactions sent or announced to a single peer per second.
+	pub max_per_peer_per_sec: Option<usize>,
+	/// If set, transactions submitted to this node are never relayed
+	/// except to the reserved peers with these node ids.
+	pub local_transactions_peers: Option<HashSet<H512>>,
+}
+
+impl Default for TransactionPropagation {
+	fn default() -> Self {
+		TransactionPropagation {
+			reserved_peers: TransactionPropagationMode::Full,
+			other_peers: TransactionPropagationMode::Full,
+			max_per_peer_per_sec: None,
+			local_transactions_peers: None,
+		}
+	}
+}
+
 /// Sync configuration
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct SyncConfig {
 	/// Max blocks to download ahead
 	pub max_download_ahead_blocks: usize,
@@ -63,6 +100,12 @@ pub struct SyncConfig {
 	pub warp_sync: bool,
 	/// Enable light client server.
 	pub serve_light: bool,
+	/// Trusted checkpoint block to sync state from instead of the genesis or a snapshot.
+	pub checkpoint: Option<(BlockNumber, H256, U256)>,
+	/// Enable state sync from a recent pivot block via `GetNodeData`.
+	pub state_sync: bool,
+	/// Transaction propagation policy.
+	pub transaction_propagation: TransactionPropagation,
 }
 
 impl Default for SyncConfig {
@@ -76,6 +119,9 @@ impl Default for SyncConfig {
 			fork_block: None,
 			warp_sync: false,
 			serve_light: false,
+			checkpoint: None,
+			state_sync: false,
+			transaction_propagation: TransactionPropagation::default(),
 		}
 	}
 }
@@ -252,7 +298,7 @@ impl EthSync {
 			})
 		};
 
-		let chain_sync = ChainSync::new(params.config, &*params.chain);
+		let chain_sync = ChainSync::new(params.config.clone(), &*params.chain);
 		let service = NetworkService::new(params.network_config.clone().into_basic()?, connection_filter)?;
 
 		let sync = Arc::new(EthSync {
@@ -415,7 +461,7 @@ impl ChainNotify for EthSync {
 		self.network.register_protocol(self.eth_handler.clone(), self.subprotocol_name, ETH_PACKET_COUNT, &[62u8, 63u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
 		// register the warp sync subprotocol
-		self.network.register_protocol(self.eth_handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8, 2u8])
+		self.network.register_protocol(self.eth_handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8, 2u8, 3u8])
 			.unwrap_or_else(|e| warn!("Error registering snapshot sync protocol: {:?}", e));
 
 		// register the light protocol.
@@ -37,6 +37,11 @@ const MAX_RECEPITS_TO_REQUEST: usize = 128;
 const SUBCHAIN_SIZE: u64 = 256;
 const MAX_ROUND_PARENTS: usize = 16;
 const MAX_PARALLEL_SUBCHAIN_DOWNLOAD: usize = 5;
+const MIN_ITEMS_TO_REQUEST: usize = 4;
+// Request size is chosen so that a peer is expected to respond within this time.
+const TARGET_REQUEST_DURATION_SEC: f64 = 2.0;
+// Weight of the latest sample in the throughput moving average.
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Downloader state
@@ -74,6 +79,72 @@ pub enum DownloadAction {
 	Reset
 }
 
+/// Maximum number of items to request from a single peer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RequestLimits {
+	/// Block bodies.
+	pub bodies: usize,
+	/// Block receipts.
+	pub receipts: usize,
+}
+
+impl Default for RequestLimits {
+	fn default() -> Self {
+		RequestLimits {
+			bodies: MAX_BODIES_TO_REQUEST,
+			receipts: MAX_RECEPITS_TO_REQUEST,
+		}
+	}
+}
+
+/// Measured rate at which a peer delivers requested items.
+#[derive(Clone, Debug, Default)]
+pub struct Throughput {
+	/// Moving average of items per second.
+	rate: Option<f64>,
+}
+
+impl Throughput {
+	/// Record a response of `items` items that took `elapsed_ns` to arrive.
+	pub fn update(&mut self, items: usize, elapsed_ns: u64) {
+		let elapsed = cmp::max(elapsed_ns, 1_000_000) as f64 / 1_000_000_000f64;
+		let sample = items as f64 / elapsed;
+		self.rate = Some(match self.rate {
+			Some(rate) => rate * (1.0 - THROUGHPUT_SMOOTHING) + sample * THROUGHPUT_SMOOTHING,
+			None => sample,
+		});
+	}
+
+	/// Record a request of `items` items which is still unanswered after `elapsed_ns`.
+	/// The peer delivers at most at this rate, so the estimate is only ever lowered.
+	pub fn timeout(&mut self, items: usize, elapsed_ns: u64) {
+		let elapsed = cmp::max(elapsed_ns, 1_000_000) as f64 / 1_000_000_000f64;
+		let sample = items as f64 / elapsed;
+		if self.rate.map_or(true, |rate| rate > sample) {
+			self.update(items, elapsed_ns);
+		}
+	}
+
+	/// Items per second, if measured.
+	pub fn rate(&self) -> Option<f64> {
+		self.rate
+	}
+
+	/// Number of items to request so that the response arrives in reasonable time.
+	/// Peers without measurements get the maximum.
+	pub fn request_size(&self, max: usize) -> usize {
+		match self.rate {
+			Some(rate) => cmp::max(cmp::min((rate * TARGET_REQUEST_DURATION_SEC) as usize, max), cmp::min(MIN_ITEMS_TO_REQUEST, max)),
+			None => max,
+		}
+	}
+
+	/// Expected time in seconds for a peer to deliver `items` items.
+	pub fn expected_duration(&self, items: usize) -> Option<f64> {
+		self.rate.map(|rate| items as f64 / rate.max(::std::f64::EPSILON))
+	}
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum BlockDownloaderImportError {
 	/// Imported data is rejected as invalid.
@@ -315,8 +386,9 @@ impl BlockDownloader {
 		Ok(DownloadAction::None)
 	}
 
-	/// Called by peer once it has new block bodies
-	pub fn import_bodies(&mut self, _io: &mut SyncIo, r: &UntrustedRlp) -> Result<(), BlockDownloaderImportError> {
+	/// Called by peer once it has new block bodies. `allow_stale` accepts bodies that are no longer
+	/// needed, e.g. when the request has been re-assigned to another peer in the meantime.
+	pub fn import_bodies(&mut self, _io: &mut SyncIo, r: &UntrustedRlp, allow_stale: bool) -> Result<(), BlockDownloaderImportError> {
 		let item_count = r.item_count().unwrap_or(0);
 		if item_count == 0 {
 			return Err(BlockDownloaderImportError::Useless);
@@ -333,7 +405,7 @@ impl BlockDownloader {
 				})?;
 				bodies.push(body.as_raw().to_vec());
 			}
-			if self.blocks.insert_bodies(bodies) != item_count {
+			if self.blocks.insert_bodies(bodies) != item_count && !allow_stale {
 				trace!(target: "sync", "Deactivating peer for giving invalid block bodies");
 				return Err(BlockDownloaderImportError::Invalid);
 			}
@@ -341,8 +413,8 @@ impl BlockDownloader {
 		Ok(())
 	}
 
-	/// Called by peer once it has new block bodies
-	pub fn import_receipts(&mut self, _io: &mut SyncIo, r: &UntrustedRlp) -> Result<(), BlockDownloaderImportError> {
+	/// Called by peer once it has new block receipts. See `import_bodies` for `allow_stale`.
+	pub fn import_receipts(&mut self, _io: &mut SyncIo, r: &UntrustedRlp, allow_stale: bool) -> Result<(), BlockDownloaderImportError> {
 		let item_count = r.item_count().unwrap_or(0);
 		if item_count == 0 {
 			return Err(BlockDownloaderImportError::Useless);
@@ -359,7 +431,7 @@ impl BlockDownloader {
 				})?;
 				receipts.push(receipt.as_raw().to_vec());
 			}
-			if self.blocks.insert_receipts(receipts) != item_count {
+			if self.blocks.insert_receipts(receipts) != item_count && !allow_stale {
 				trace!(target: "sync", "Deactivating peer for giving invalid block receipts");
 				return Err(BlockDownloaderImportError::Invalid);
 			}
@@ -415,12 +487,12 @@ impl BlockDownloader {
 	}
 
 	/// Find some headers or blocks to download for a peer.
-	pub fn request_blocks(&mut self, io: &mut SyncIo, num_active_peers: usize) -> Option<BlockRequest> {
+	pub fn request_blocks(&mut self, io: &mut SyncIo, num_active_peers: usize, limits: RequestLimits) -> Option<BlockRequest> {
 		match self.state {
 			State::Idle => {
 				self.start_sync_round(io);
 				if self.state == State::ChainHead {
-					return self.request_blocks(io, num_active_peers);
+					return self.request_blocks(io, num_active_peers, limits);
 				}
 			},
 			State::ChainHead => {
@@ -438,7 +510,7 @@ impl BlockDownloader {
 			},
 			State::Blocks => {
 				// check to see if we need to download any block bodies first
-				let needed_bodies = self.blocks.needed_bodies(MAX_BODIES_TO_REQUEST, false);
+				let needed_bodies = self.blocks.needed_bodies(limits.bodies, false);
 				if !needed_bodies.is_empty() {
 					return Some(BlockRequest::Bodies {
 						hashes: needed_bodies,
@@ -446,7 +518,7 @@ impl BlockDownloader {
 				}
 
 				if self.download_receipts {
-					let needed_receipts = self.blocks.needed_receipts(MAX_RECEPITS_TO_REQUEST, false);
+					let needed_receipts = self.blocks.needed_receipts(limits.receipts, false);
 					if !needed_receipts.is_empty() {
 						return Some(BlockRequest::Receipts {
 							hashes: needed_receipts,
@@ -558,4 +630,46 @@ impl BlockDownloader {
 	}
 }
 
-//TODO: module tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn throughput_limits_request_size() {
+		let mut throughput = Throughput::default();
+		assert_eq!(throughput.request_size(32), 32);
+		assert_eq!(throughput.expected_duration(32), None);
+
+		// 10 items in 5 seconds
+		throughput.update(10, 5_000_000_000);
+		assert_eq!(throughput.request_size(32), 4);
+		assert_eq!(throughput.expected_duration(4), Some(2.0));
+
+		// fast responses grow the request up to the maximum
+		for _ in 0..20 {
+			throughput.update(32, 100_000_000);
+		}
+		assert_eq!(throughput.request_size(32), 32);
+	}
+
+	#[test]
+	fn throughput_request_size_is_never_zero() {
+		let mut throughput = Throughput::default();
+		throughput.update(0, 20_000_000_000);
+		assert_eq!(throughput.request_size(32), MIN_ITEMS_TO_REQUEST);
+		assert_eq!(throughput.request_size(2), 2);
+	}
+
+	#[test]
+	fn throughput_timeout_only_lowers_rate() {
+		let mut throughput = Throughput::default();
+		// 32 items still missing after 8 seconds
+		throughput.timeout(32, 8_000_000_000);
+		assert_eq!(throughput.rate(), Some(4.0));
+		assert_eq!(throughput.request_size(32), 8);
+
+		// a timeout slower than the measured rate says nothing new
+		throughput.timeout(4, 100_000_000);
+		assert_eq!(throughput.rate(), Some(4.0));
+	}
+}
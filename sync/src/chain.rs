@@ -99,18 +99,20 @@ use bytes::Bytes;
 use rlp::*;
 use network::{self, PeerId, PacketId};
 use ethcore::header::{BlockNumber, Header as BlockHeader};
-use ethcore::client::{BlockChainClient, BlockStatus, BlockId, BlockChainInfo, BlockImportError, BlockQueueInfo};
+use ethcore::client::{BlockChainClient, BlockStatus, BlockId, TransactionId, BlockChainInfo, BlockImportError, BlockQueueInfo};
 use ethcore::error::*;
 use ethcore::snapshot::{ManifestData, RestorationStatus};
 use transaction::PendingTransaction;
 use sync_io::SyncIo;
 use time;
 use super::SyncConfig;
-use block_sync::{BlockDownloader, BlockRequest, BlockDownloaderImportError as DownloaderImportError, DownloadAction};
+use block_sync::{BlockDownloader, BlockRequest, BlockDownloaderImportError as DownloaderImportError, DownloadAction, RequestLimits, Throughput};
 use rand::Rng;
 use snapshot::{Snapshot, ChunkType};
-use api::{EthProtocolInfo as PeerInfoDigest, WARP_SYNC_PROTOCOL_ID};
+use checkpoint::Checkpoint;
+use api::{EthProtocolInfo as PeerInfoDigest, WARP_SYNC_PROTOCOL_ID, TransactionPropagation, TransactionPropagationMode};
 use transactions_stats::{TransactionsStats, Stats as TransactionStats};
+use transactions_propagation::RateLimit;
 
 known_heap_size!(0, PeerInfo);
 
@@ -120,11 +122,19 @@ const PROTOCOL_VERSION_63: u8 = 63;
 const PROTOCOL_VERSION_62: u8 = 62;
 const PROTOCOL_VERSION_1: u8 = 1;
 const PROTOCOL_VERSION_2: u8 = 2;
+const PROTOCOL_VERSION_3: u8 = 3;
 const MAX_BODIES_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_SEND: usize = 512;
 const MAX_NODE_DATA_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
+const MAX_NODE_DATA_TO_REQUEST: usize = 384;
+// State sync pivot is picked this many blocks behind the head of the best peer.
+const PIVOT_DISTANCE: BlockNumber = 64;
+// Pivot is moved once the chain head is this far ahead of it, before peers prune its state.
+const MAX_PIVOT_AGE: BlockNumber = 96;
+// Pivot is moved after this many peers in a row returned no state nodes at all.
+const MAX_EMPTY_NODE_DATA: usize = 3;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 const MAX_PEER_LAG_PROPAGATION: BlockNumber = 20;
@@ -135,6 +145,10 @@ const MAX_TRANSACTION_SIZE: usize = 300*1024;
 const MAX_TRANSACTION_PACKET_SIZE: usize = 8 * 1024 * 1024;
 // Maximal number of transactions in sent in single packet.
 const MAX_TRANSACTIONS_TO_PROPAGATE: usize = 64;
+// Maximal number of announced transactions requested in a single packet.
+const MAX_TRANSACTIONS_TO_REQUEST: usize = 256;
+// Announced transaction is not requested again for this many seconds.
+const TRANSACTION_REQUEST_TIMEOUT_SEC: u64 = 20;
 // Min number of blocks to be behind for a snapshot sync
 const SNAPSHOT_RESTORE_THRESHOLD: BlockNumber = 30000;
 const SNAPSHOT_MIN_PEERS: usize = 3;
@@ -160,8 +174,10 @@ const SNAPSHOT_MANIFEST_PACKET: u8 = 0x12;
 const GET_SNAPSHOT_DATA_PACKET: u8 = 0x13;
 const SNAPSHOT_DATA_PACKET: u8 = 0x14;
 const CONSENSUS_DATA_PACKET: u8 = 0x15;
+const TRANSACTION_HASHES_PACKET: u8 = 0x16;
+const GET_TRANSACTIONS_PACKET: u8 = 0x17;
 
-pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x16;
+pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x18;
 
 const MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD: usize = 3;
 
@@ -173,6 +189,10 @@ const RECEIPTS_TIMEOUT_SEC: u64 = 10;
 const FORK_HEADER_TIMEOUT_SEC: u64 = 3;
 const SNAPSHOT_MANIFEST_TIMEOUT_SEC: u64 = 5;
 const SNAPSHOT_DATA_TIMEOUT_SEC: u64 = 120;
+const NODE_DATA_TIMEOUT_SEC: u64 = 10;
+// A body or receipts request taking this many times longer than expected is re-assigned to another peer.
+const RETRY_REQUEST_FACTOR: f64 = 3.0;
+const MIN_RETRY_REQUEST_SEC: f64 = 4.0;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Sync state
@@ -193,6 +213,10 @@ pub enum SyncState {
 	Waiting,
 	/// Downloading blocks learned from `NewHashes` packet
 	NewBlocks,
+	/// Downloading the trusted checkpoint block
+	CheckpointBlock,
+	/// Downloading state trie nodes at the checkpoint block
+	CheckpointState,
 }
 
 /// Syncing status and statistics
@@ -226,6 +250,10 @@ pub struct SyncStatus {
 	pub snapshot_chunks_done: usize,
 	/// Last fully downloaded and imported ancient block number (if any).
 	pub last_imported_old_block_number: Option<BlockNumber>,
+	/// Number of stalled block body and receipt requests re-assigned to other peers.
+	pub num_retried_requests: usize,
+	/// Combined rate at which peers deliver block bodies and receipts, in items per second.
+	pub download_rate: u64,
 }
 
 impl SyncStatus {
@@ -263,6 +291,11 @@ enum PeerAsking {
 	BlockReceipts,
 	SnapshotManifest,
 	SnapshotData,
+	CheckpointHeader,
+	CheckpointBody,
+	CheckpointReceipts,
+	StateNodes,
+	PivotHeaders,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -304,6 +337,8 @@ struct PeerInfo {
 	asking_hash: Option<H256>,
 	/// Holds requested snapshot chunk hash if any.
 	asking_snapshot_data: Option<H256>,
+	/// Hashes of state trie nodes being requested
+	asking_state_nodes: Vec<H256>,
 	/// Request timestamp
 	ask_time: u64,
 	/// Holds a set of transactions recently sent to this peer to avoid spamming.
@@ -318,6 +353,10 @@ struct PeerInfo {
 	snapshot_number: Option<BlockNumber>,
 	/// Block set requested
 	block_set: Option<BlockSet>,
+	/// Measured block bodies download rate
+	bodies_throughput: Throughput,
+	/// Measured block receipts download rate
+	receipts_throughput: Throughput,
 }
 
 impl PeerInfo {
@@ -329,8 +368,17 @@ impl PeerInfo {
 		self.confirmation != ForkConfirmation::Unconfirmed && !self.expired
 	}
 
+	fn request_limits(&self) -> RequestLimits {
+		let max = RequestLimits::default();
+		RequestLimits {
+			bodies: self.bodies_throughput.request_size(max.bodies),
+			receipts: self.receipts_throughput.request_size(max.receipts),
+		}
+	}
+
 	fn reset_asking(&mut self) {
 		self.asking_blocks.clear();
+		self.asking_state_nodes.clear();
 		self.asking_hash = None;
 		// mark any pending requests as expired
 		if self.asking != PeerAsking::Nothing && self.is_allowed() {
@@ -386,6 +434,20 @@ pub struct ChainSync {
 	download_old_blocks: bool,
 	/// Enable warp sync.
 	enable_warp_sync: bool,
+	/// Number of stalled requests re-assigned to other peers.
+	retried_requests: usize,
+	/// Trusted checkpoint to download the state at, until it is reached.
+	checkpoint: Option<Checkpoint>,
+	/// Whether the checkpoint is a pivot block picked from the network, which moves along with the chain head.
+	pivot_sync: bool,
+	/// Number of empty state node responses in a row.
+	empty_node_responses: usize,
+	/// Transaction propagation policy.
+	transaction_propagation: TransactionPropagation,
+	/// Transaction propagation allowance for each peer, if limited.
+	transaction_rate_limits: HashMap<PeerId, RateLimit>,
+	/// Announced transactions requested from peers, with request timestamp.
+	requested_transactions: HashMap<H256, u64>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -394,8 +456,17 @@ impl ChainSync {
 	/// Create a new instance of syncing strategy.
 	pub fn new(config: SyncConfig, chain: &BlockChainClient) -> ChainSync {
 		let chain_info = chain.chain_info();
+		let checkpoint = config.checkpoint
+			.and_then(|(number, hash, td)| if number > chain_info.best_block_number { Some(Checkpoint::new(number, hash, td)) } else { None });
+		let pivot_sync = checkpoint.is_none() && config.state_sync && chain_info.best_block_number == 0;
 		let mut sync = ChainSync {
-			state: if config.warp_sync { SyncState::WaitingPeers } else { SyncState::Idle },
+			state: if checkpoint.is_some() || pivot_sync {
+				SyncState::CheckpointBlock
+			} else if config.warp_sync {
+				SyncState::WaitingPeers
+			} else {
+				SyncState::Idle
+			},
 			starting_block: chain.chain_info().best_block_number,
 			highest_block: None,
 			peers: HashMap::new(),
@@ -411,6 +482,13 @@ impl ChainSync {
 			sync_start_time: None,
 			transactions_stats: TransactionsStats::default(),
 			enable_warp_sync: config.warp_sync,
+			retried_requests: 0,
+			checkpoint: checkpoint,
+			pivot_sync: pivot_sync,
+			empty_node_responses: 0,
+			transaction_propagation: config.transaction_propagation,
+			transaction_rate_limits: HashMap::new(),
+			requested_transactions: HashMap::new(),
 		};
 		sync.update_targets(chain);
 		sync
@@ -433,6 +511,11 @@ impl ChainSync {
 			num_active_peers: self.peers.values().filter(|p| p.is_allowed() && p.asking != PeerAsking::Nothing).count(),
 			num_snapshot_chunks: self.snapshot.total_chunks(),
 			snapshot_chunks_done: self.snapshot.done_chunks(),
+			num_retried_requests: self.retried_requests,
+			download_rate: self.peers.values()
+				.filter(|p| p.is_allowed())
+				.map(|p| p.bodies_throughput.rate().unwrap_or(0.0) + p.receipts_throughput.rate().unwrap_or(0.0))
+				.sum::<f64>() as u64,
 			mem_used:
 				self.new_blocks.heap_size()
 				+ self.old_blocks.as_ref().map_or(0, |d| d.heap_size())
@@ -482,7 +565,13 @@ impl ChainSync {
 				}
 			}
 		}
-		self.state = SyncState::Idle;
+		// checkpoint sync carries on until the state is restored.
+		if !self.is_checkpoint_syncing() {
+			self.state = SyncState::Idle;
+		}
+		if let Some(ref mut checkpoint) = self.checkpoint {
+			checkpoint.clear_node_downloads();
+		}
 		// Reactivate peers only if some progress has been made
 		// since the last sync round of if starting fresh.
 		self.active_peers = self.peers.keys().cloned().collect();
@@ -500,6 +589,10 @@ impl ChainSync {
 		self.continue_sync(io);
 	}
 
+	fn is_checkpoint_syncing(&self) -> bool {
+		self.state == SyncState::CheckpointBlock || self.state == SyncState::CheckpointState
+	}
+
 	/// Remove peer from active peer set. Peer will be reactivated on the next sync
 	/// round.
 	fn deactivate_peer(&mut self, _io: &mut SyncIo, peer_id: PeerId) {
@@ -621,9 +714,12 @@ impl ChainSync {
 			expired: false,
 			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
 			asking_snapshot_data: None,
+			asking_state_nodes: Vec::new(),
 			snapshot_hash: if warp_protocol { Some(r.val_at(5)?) } else { None },
 			snapshot_number: if warp_protocol { Some(r.val_at(6)?) } else { None },
 			block_set: None,
+			bodies_throughput: Throughput::default(),
+			receipts_throughput: Throughput::default(),
 		};
 
 		if self.sync_start_time.is_none() {
@@ -652,7 +748,7 @@ impl ChainSync {
 			trace!(target: "sync", "Peer {} network id mismatch (ours: {}, theirs: {})", peer_id, self.network_id, peer.network_id);
 			return Ok(());
 		}
-		if (warp_protocol && peer.protocol_version != PROTOCOL_VERSION_1 && peer.protocol_version != PROTOCOL_VERSION_2) || (!warp_protocol && peer.protocol_version != PROTOCOL_VERSION_63 && peer.protocol_version != PROTOCOL_VERSION_62) {
+		if (warp_protocol && peer.protocol_version != PROTOCOL_VERSION_1 && peer.protocol_version != PROTOCOL_VERSION_2 && peer.protocol_version != PROTOCOL_VERSION_3) || (!warp_protocol && peer.protocol_version != PROTOCOL_VERSION_63 && peer.protocol_version != PROTOCOL_VERSION_62) {
 			io.disable_peer(peer_id);
 			trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, peer.protocol_version);
 			return Ok(());
@@ -673,6 +769,12 @@ impl ChainSync {
 
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::CheckpointHeader) {
+			return self.on_checkpoint_header(io, peer_id, r);
+		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::PivotHeaders) {
+			return self.on_pivot_headers(io, peer_id, r);
+		}
 		let confirmed = match self.peers.get_mut(&peer_id) {
 			Some(ref mut peer) if peer.asking == PeerAsking::ForkHeader => {
 				peer.asking = PeerAsking::Nothing;
@@ -774,8 +876,17 @@ impl ChainSync {
 
 	/// Called by peer once it has new block bodies
 	fn on_peer_block_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::CheckpointBody) {
+			return self.on_checkpoint_body(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
 		let block_set = self.peers.get(&peer_id).and_then(|p| p.block_set).unwrap_or(BlockSet::NewBlocks);
+		let expired = self.peers.get(&peer_id).map_or(false, |p| p.expired);
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			if peer.asking == PeerAsking::BlockBodies {
+				peer.bodies_throughput.update(r.item_count().unwrap_or(0), time::precise_time_ns() - peer.ask_time);
+			}
+		}
 		if !self.reset_peer_asking(peer_id, PeerAsking::BlockBodies) {
 			trace!(target: "sync", "{}: Ignored unexpected bodies", peer_id);
 			self.continue_sync(io);
@@ -803,7 +914,7 @@ impl ChainSync {
 						Some(ref mut blocks) => blocks,
 					}
 				};
-				downloader.import_bodies(io, r)
+				downloader.import_bodies(io, r, expired)
 			};
 
 			match result {
@@ -828,8 +939,17 @@ impl ChainSync {
 
 	/// Called by peer once it has new block receipts
 	fn on_peer_block_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::CheckpointReceipts) {
+			return self.on_checkpoint_receipts(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
 		let block_set = self.peers.get(&peer_id).and_then(|p| p.block_set).unwrap_or(BlockSet::NewBlocks);
+		let expired = self.peers.get(&peer_id).map_or(false, |p| p.expired);
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			if peer.asking == PeerAsking::BlockReceipts {
+				peer.receipts_throughput.update(r.item_count().unwrap_or(0), time::precise_time_ns() - peer.ask_time);
+			}
+		}
 		if !self.reset_peer_asking(peer_id, PeerAsking::BlockReceipts) {
 			trace!(target: "sync", "{}: Ignored unexpected receipts", peer_id);
 			self.continue_sync(io);
@@ -857,7 +977,7 @@ impl ChainSync {
 						Some(ref mut blocks) => blocks,
 					}
 				};
-				downloader.import_receipts(io, r)
+				downloader.import_receipts(io, r, expired)
 			};
 
 			match result {
@@ -906,6 +1026,11 @@ impl ChainSync {
 				peer.latest_hash = header.hash();
 			}
 		}
+		if self.is_checkpoint_syncing() {
+			trace!(target: "sync", "Ignoring new block while syncing to the checkpoint");
+			self.maybe_move_pivot(io);
+			return Ok(());
+		}
 		let last_imported_number = self.new_blocks.last_imported_block_number();
 		if last_imported_number > header.number() && last_imported_number - header.number() > MAX_NEW_BLOCK_AGE {
 			trace!(target: "sync", "Ignored ancient new block {:?}", h);
@@ -1130,6 +1255,239 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called by peer once it has sent the checkpoint block header.
+	fn on_checkpoint_header(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::CheckpointHeader);
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{} -> CheckpointHeader ({} entries)", peer_id, item_count);
+		let downloading = self.state == SyncState::CheckpointBlock;
+		let result = match self.checkpoint {
+			Some(ref mut checkpoint) if downloading && item_count > 0 && !checkpoint.have_header() =>
+				Some(checkpoint.import_header(r.at(0)?.as_raw())),
+			_ => None,
+		};
+		self.on_checkpoint_response(io, peer_id, item_count, result);
+		Ok(())
+	}
+
+	/// Called by peer once it has sent the checkpoint block body.
+	fn on_checkpoint_body(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::CheckpointBody);
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{} -> CheckpointBody ({} entries)", peer_id, item_count);
+		let downloading = self.state == SyncState::CheckpointBlock;
+		let result = match self.checkpoint {
+			Some(ref mut checkpoint) if downloading && item_count > 0 && !checkpoint.have_body() =>
+				Some(checkpoint.import_body(&r.at(0)?)),
+			_ => None,
+		};
+		self.on_checkpoint_response(io, peer_id, item_count, result);
+		Ok(())
+	}
+
+	/// Called by peer once it has sent the checkpoint block receipts.
+	fn on_checkpoint_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::CheckpointReceipts);
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{} -> CheckpointReceipts ({} entries)", peer_id, item_count);
+		let downloading = self.state == SyncState::CheckpointBlock;
+		let result = match self.checkpoint {
+			Some(ref mut checkpoint) if downloading && item_count > 0 && !checkpoint.is_block_complete() =>
+				Some(checkpoint.import_receipts(&r.at(0)?)),
+			_ => None,
+		};
+		self.on_checkpoint_response(io, peer_id, item_count, result);
+		Ok(())
+	}
+
+	/// Called by peer once it has sent its head and the state sync pivot header.
+	fn on_pivot_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::PivotHeaders);
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{} -> PivotHeaders ({} entries)", peer_id, item_count);
+		if self.state != SyncState::CheckpointBlock || self.checkpoint.is_some() || !self.pivot_sync {
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		let mut result = None;
+		if item_count > 0 {
+			let headers: Vec<BlockHeader> = r.as_list()?;
+			let head_number = headers[0].number();
+			if head_number > self.highest_block.unwrap_or(0) {
+				self.highest_block = Some(head_number);
+			}
+			let restoring = match io.snapshot_service().status() {
+				RestorationStatus::Ongoing { .. } => true,
+				_ => false,
+			};
+			if head_number < PIVOT_DISTANCE && !restoring {
+				trace!(target: "sync", "Chain is too short for state sync, syncing blocks instead");
+				self.pivot_sync = false;
+				self.state = SyncState::Idle;
+				self.continue_sync(io);
+				return Ok(());
+			}
+			result = self.pick_pivot(io, peer_id, headers);
+		}
+		self.on_checkpoint_response(io, peer_id, item_count, result);
+		Ok(())
+	}
+
+	// make the oldest of the headers from a peer's head down to the pivot the checkpoint,
+	// once they're checked to form a valid chain. Its total difficulty is derived from the
+	// one the peer announced for its head. `None` if the peer's head has moved meanwhile.
+	fn pick_pivot(&mut self, io: &mut SyncIo, peer_id: PeerId, headers: Vec<BlockHeader>) -> Option<Result<(), ()>> {
+		if headers.len() as BlockNumber != PIVOT_DISTANCE + 1 {
+			return Some(Err(()));
+		}
+		let head_difficulty = match self.peers.get(&peer_id) {
+			Some(peer) if peer.latest_hash == headers[0].hash() => peer.difficulty,
+			_ => None,
+		};
+		let head_difficulty = match head_difficulty {
+			Some(difficulty) => difficulty,
+			None => return None,
+		};
+		if let Err(e) = io.chain().verify_header_chain(&headers) {
+			trace!(target: "sync", "{}: Invalid state sync pivot headers: {}", peer_id, e);
+			return Some(Err(()));
+		}
+		let above_pivot = headers[..PIVOT_DISTANCE as usize].iter()
+			.fold(Some(U256::zero()), |sum, h| sum.and_then(|sum| sum.checked_add(*h.difficulty())));
+		let total_difficulty = match above_pivot.and_then(|d| head_difficulty.checked_sub(d)) {
+			Some(difficulty) => difficulty,
+			None => return Some(Err(())),
+		};
+
+		let pivot = &headers[PIVOT_DISTANCE as usize];
+		trace!(target: "sync", "Picked state sync pivot #{} {}", pivot.number(), pivot.hash());
+		let mut checkpoint = Checkpoint::new(pivot.number(), pivot.hash(), total_difficulty);
+		let imported = checkpoint.import_header(&::rlp::encode(pivot));
+		if imported.is_ok() {
+			self.checkpoint = Some(checkpoint);
+			self.empty_node_responses = 0;
+		}
+		Some(imported)
+	}
+
+	// common handling of responses to checkpoint block requests.
+	fn on_checkpoint_response(&mut self, io: &mut SyncIo, peer_id: PeerId, item_count: usize, result: Option<Result<(), ()>>) {
+		if item_count == 0 {
+			trace!(target: "sync", "{}: Peer does not have the checkpoint block", peer_id);
+			self.deactivate_peer(io, peer_id);
+		} else if let Some(Err(())) = result {
+			trace!(target: "sync", "{}: Got bad checkpoint block data", peer_id);
+			io.disable_peer(peer_id);
+			self.deactivate_peer(io, peer_id);
+		}
+		self.maybe_begin_node_restore(io);
+		self.sync_peer(io, peer_id, false);
+		self.continue_sync(io);
+	}
+
+	/// Called when state trie nodes are downloaded from a peer.
+	fn on_peer_node_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		if !self.reset_peer_asking(peer_id, PeerAsking::StateNodes) || self.state != SyncState::CheckpointState {
+			trace!(target: "sync", "{}: Ignored unexpected node data", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{} -> NodeData ({} entries)", peer_id, item_count);
+		if item_count == 0 {
+			self.empty_node_responses += 1;
+			self.deactivate_peer(io, peer_id);
+		} else {
+			self.empty_node_responses = 0;
+			// nodes are checked against the requested hashes by the restoration.
+			io.snapshot_service().restore_state_nodes(r.as_list()?);
+		}
+		self.check_node_restore(io);
+		self.maybe_move_pivot(io);
+		// give a task to the same peer first.
+		self.sync_peer(io, peer_id, false);
+		// give tasks to other peers
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Start restoring the checkpoint state once the checkpoint block is downloaded.
+	fn maybe_begin_node_restore(&mut self, io: &mut SyncIo) {
+		if self.state != SyncState::CheckpointBlock {
+			return;
+		}
+		let data = match self.checkpoint {
+			Some(ref checkpoint) if checkpoint.is_block_complete() => checkpoint.block().and_then(|b| checkpoint.receipts().map(|r| (b, r, checkpoint.total_difficulty()))),
+			_ => None,
+		};
+		if let Some((block, receipts, total_difficulty)) = data {
+			let restoring = match io.snapshot_service().status() {
+				RestorationStatus::Ongoing { .. } => true,
+				_ => false,
+			};
+			if self.pivot_sync && restoring {
+				trace!(target: "sync", "Pivot block downloaded, moving state download");
+				io.snapshot_service().move_node_restore(block, receipts, total_difficulty);
+			} else {
+				trace!(target: "sync", "Checkpoint block downloaded, starting state download");
+				io.snapshot_service().begin_node_restore(block, receipts, total_difficulty);
+			}
+			self.state = SyncState::CheckpointState;
+			// moving may leave nothing to download.
+			self.check_node_restore(io);
+		}
+	}
+
+	/// Pick a new state sync pivot if the current one is too old to be served by peers.
+	fn maybe_move_pivot(&mut self, io: &mut SyncIo) {
+		if !self.pivot_sync || self.state != SyncState::CheckpointState {
+			return;
+		}
+		let pivot = match self.checkpoint {
+			Some(ref checkpoint) => checkpoint.number(),
+			None => return,
+		};
+		let too_old = self.highest_block.map_or(false, |highest| highest > pivot + MAX_PIVOT_AGE);
+		if too_old || self.empty_node_responses >= MAX_EMPTY_NODE_DATA {
+			trace!(target: "sync", "State sync pivot #{} is stale, picking a new one", pivot);
+			self.checkpoint = None;
+			self.empty_node_responses = 0;
+			self.state = SyncState::CheckpointBlock;
+			// peers which had pruned the old state may serve the new one.
+			self.active_peers = self.peers.keys().cloned().collect();
+			self.continue_sync(io);
+		}
+	}
+
+	/// Check the progress of the checkpoint state restoration.
+	fn check_node_restore(&mut self, io: &mut SyncIo) {
+		if self.state != SyncState::CheckpointState {
+			return;
+		}
+		match io.snapshot_service().status() {
+			RestorationStatus::Inactive => {
+				trace!(target: "sync", "Checkpoint state restoration is complete");
+				self.checkpoint = None;
+				self.pivot_sync = false;
+				self.state = SyncState::Idle;
+				self.restart(io);
+			},
+			RestorationStatus::Failed => {
+				trace!(target: "sync", "Checkpoint state restoration failed");
+				if self.pivot_sync {
+					self.checkpoint = None;
+				} else if let Some(ref mut checkpoint) = self.checkpoint {
+					checkpoint.clear();
+				}
+				self.state = SyncState::CheckpointBlock;
+				self.continue_sync(io);
+			},
+			RestorationStatus::Ongoing { .. } => (),
+		}
+	}
+
 	/// Called by peer when it is disconnecting
 	pub fn on_peer_aborting(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Disconnecting {}: {}", peer, io.peer_info(peer));
@@ -1139,6 +1497,7 @@ impl ChainSync {
 			self.clear_peer_download(peer);
 			self.peers.remove(&peer);
 			self.active_peers.remove(&peer);
+			self.transaction_rate_limits.remove(&peer);
 			self.continue_sync(io);
 		}
 	}
@@ -1168,6 +1527,7 @@ impl ChainSync {
 			}
 		}
 		if (self.state != SyncState::WaitingPeers && self.state != SyncState::SnapshotWaiting && self.state != SyncState::Waiting && self.state != SyncState::Idle)
+			&& !self.is_checkpoint_syncing()
 			&& !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && p.block_set != Some(BlockSet::OldBlocks) && p.can_sync()) {
 
 			self.complete_sync(io);
@@ -1193,7 +1553,7 @@ impl ChainSync {
 			trace!(target: "sync", "Skipping deactivated peer {}", peer_id);
 			return;
 		}
-		let (peer_latest, peer_difficulty, peer_snapshot_number, peer_snapshot_hash) = {
+		let (peer_latest, peer_difficulty, peer_snapshot_number, peer_snapshot_hash, limits) = {
 			if let Some(peer) = self.peers.get_mut(&peer_id) {
 				if peer.asking != PeerAsking::Nothing || !peer.can_sync() {
 					trace!(target: "sync", "Skipping busy peer {}", peer_id);
@@ -1207,7 +1567,7 @@ impl ChainSync {
 					trace!(target: "sync", "Waiting for the snapshot restoration");
 					return;
 				}
-				(peer.latest_hash.clone(), peer.difficulty.clone(), peer.snapshot_number.as_ref().cloned().unwrap_or(0), peer.snapshot_hash.as_ref().cloned())
+				(peer.latest_hash.clone(), peer.difficulty.clone(), peer.snapshot_number.as_ref().cloned().unwrap_or(0), peer.snapshot_hash.as_ref().cloned(), peer.request_limits())
 			} else {
 				return;
 			}
@@ -1234,7 +1594,7 @@ impl ChainSync {
 					if !have_latest && (higher_difficulty || force || self.state == SyncState::NewBlocks) {
 						// check if got new blocks to download
 						trace!(target: "sync", "Syncing with peer {}, force={}, td={:?}, our td={}, state={:?}", peer_id, force, peer_difficulty, syncing_difficulty, self.state);
-						if let Some(request) = self.new_blocks.request_blocks(io, num_active_peers) {
+						if let Some(request) = self.new_blocks.request_blocks(io, num_active_peers, limits) {
 							self.request_blocks(io, peer_id, request, BlockSet::NewBlocks);
 							if self.state == SyncState::Idle {
 								self.state = SyncState::Blocks;
@@ -1243,7 +1603,7 @@ impl ChainSync {
 						}
 					}
 
-					if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(io, num_active_peers)) {
+					if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(io, num_active_peers, limits)) {
 						self.request_blocks(io, peer_id, request, BlockSet::OldBlocks);
 						return;
 					}
@@ -1260,6 +1620,12 @@ impl ChainSync {
 						self.request_snapshot_data(io, peer_id);
 					}
 				},
+				SyncState::CheckpointBlock => {
+					self.request_checkpoint_block(io, peer_id);
+				},
+				SyncState::CheckpointState => {
+					self.request_state_nodes(io, peer_id);
+				},
 				SyncState::SnapshotManifest | //already downloading from other peer
 					SyncState::Waiting | SyncState::SnapshotWaiting => ()
 			}
@@ -1324,6 +1690,11 @@ impl ChainSync {
 						self.snapshot.clear_chunk_download(&hash);
 					}
 				},
+				PeerAsking::StateNodes => {
+					if let Some(ref mut checkpoint) = self.checkpoint {
+						checkpoint.clear_node_download(&peer.asking_state_nodes);
+					}
+				},
 				_ => (),
 			}
 		}
@@ -1373,6 +1744,89 @@ impl ChainSync {
 		self.send_request(sync, peer_id, PeerAsking::ForkHeader, GET_BLOCK_HEADERS_PACKET, rlp.out());
 	}
 
+	/// Request the next missing part of the checkpoint block. Only one peer is asked at a time.
+	fn request_checkpoint_block(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
+		let asked = self.peers.values().any(|p| match p.asking {
+			PeerAsking::CheckpointHeader | PeerAsking::CheckpointBody | PeerAsking::CheckpointReceipts | PeerAsking::PivotHeaders => true,
+			_ => false,
+		});
+		if asked {
+			return;
+		}
+		let (hash, have_header, have_body) = match self.checkpoint {
+			Some(ref checkpoint) => (checkpoint.hash(), checkpoint.have_header(), checkpoint.have_body()),
+			None if self.pivot_sync => return self.request_pivot_headers(sync, peer_id),
+			None => return,
+		};
+		if !have_header {
+			trace!(target: "sync", "{} <- GetCheckpointHeader: {}", peer_id, hash);
+			let mut rlp = RlpStream::new_list(4);
+			rlp.append(&hash);
+			rlp.append(&1u32);
+			rlp.append(&0u32);
+			rlp.append(&0u32);
+			self.send_request(sync, peer_id, PeerAsking::CheckpointHeader, GET_BLOCK_HEADERS_PACKET, rlp.out());
+		} else {
+			let (asking, packet_id) = if !have_body {
+				(PeerAsking::CheckpointBody, GET_BLOCK_BODIES_PACKET)
+			} else {
+				(PeerAsking::CheckpointReceipts, GET_RECEIPTS_PACKET)
+			};
+			trace!(target: "sync", "{} <- {:?}: {}", peer_id, asking, hash);
+			let mut rlp = RlpStream::new_list(1);
+			rlp.append(&hash);
+			self.send_request(sync, peer_id, asking, packet_id, rlp.out());
+		}
+	}
+
+	/// Request the headers from the head down to the state sync pivot from the peer with the best chain.
+	fn request_pivot_headers(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
+		let best_peer = {
+			let active_peers = &self.active_peers;
+			self.peers.iter()
+				.filter(|&(id, p)| p.can_sync() && active_peers.contains(id))
+				.max_by_key(|&(_, p)| p.difficulty)
+				.map(|(id, _)| *id)
+		};
+		if best_peer != Some(peer_id) {
+			return;
+		}
+		let head = match self.peers.get(&peer_id) {
+			Some(peer) => peer.latest_hash,
+			None => return,
+		};
+		trace!(target: "sync", "{} <- GetPivotHeaders: {}", peer_id, head);
+		let mut rlp = RlpStream::new_list(4);
+		rlp.append(&head);
+		rlp.append(&((PIVOT_DISTANCE + 1) as u32));
+		rlp.append(&0u32);
+		rlp.append(&1u32);
+		self.send_request(sync, peer_id, PeerAsking::PivotHeaders, GET_BLOCK_HEADERS_PACKET, rlp.out());
+	}
+
+	/// Request missing state trie nodes of the checkpoint from a peer.
+	fn request_state_nodes(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
+		let hashes = match self.checkpoint {
+			Some(ref mut checkpoint) => {
+				let missing = sync.snapshot_service().missing_state_nodes(checkpoint.downloading_nodes() + MAX_NODE_DATA_TO_REQUEST);
+				checkpoint.needed_nodes(missing, MAX_NODE_DATA_TO_REQUEST)
+			},
+			None => return,
+		};
+		if hashes.is_empty() {
+			return;
+		}
+		trace!(target: "sync", "{} <- GetNodeData: {} entries", peer_id, hashes.len());
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for h in &hashes {
+			rlp.append(h);
+		}
+		self.send_request(sync, peer_id, PeerAsking::StateNodes, GET_NODE_DATA_PACKET, rlp.out());
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			peer.asking_state_nodes = hashes;
+		}
+	}
+
 	/// Request snapshot manifest from a peer.
 	fn request_snapshot_manifest(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
 		trace!(target: "sync", "{} <- GetSnapshotManifest", peer_id);
@@ -1459,6 +1913,14 @@ impl ChainSync {
 		}
 	}
 
+	/// Sender for packets only defined by the warp sync protocol
+	fn send_warp_packet(&mut self, sync: &mut SyncIo, peer_id: PeerId, packet_id: PacketId, packet: Bytes) {
+		if let Err(e) = sync.send_protocol(WARP_SYNC_PROTOCOL_ID, peer_id, packet_id, packet) {
+			debug!(target:"sync", "Error sending packet: {:?}", e);
+			sync.disconnect_peer(peer_id);
+		}
+	}
+
 	/// Called when peer sends us new transactions
 	fn on_peer_transactions(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		// Accept transactions only when fully synced
@@ -1486,6 +1948,83 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called when peer announces new transactions by hash
+	fn on_peer_transaction_hashes(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		// Accept transactions only when fully synced
+		if !io.is_chain_queue_empty() || (self.state != SyncState::Idle && self.state != SyncState::NewBlocks) {
+			trace!(target: "sync", "{} Ignoring transaction hashes while syncing", peer_id);
+			return Ok(());
+		}
+
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{:02} -> Transaction hashes ({} entries)", peer_id, item_count);
+		// Hashes beyond the limit are ignored, known or not.
+		let item_count = cmp::min(item_count, MAX_TRANSACTIONS_TO_REQUEST);
+		let now = time::precise_time_ns();
+		let mut announced = HashSet::with_capacity(item_count);
+		let mut to_request = Vec::new();
+		for i in 0 .. item_count {
+			let hash: H256 = r.val_at(i)?;
+			announced.insert(hash);
+			if self.requested_transactions.contains_key(&hash)
+				|| io.chain().queued_transaction(&hash).is_some()
+				|| io.chain().transaction(TransactionId::Hash(hash)).is_some() {
+				continue;
+			}
+			self.requested_transactions.insert(hash, now);
+			to_request.push(hash);
+		}
+		// The peer already has these, no need to send them back.
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.last_sent_transactions.extend(announced);
+		}
+
+		if !to_request.is_empty() {
+			let mut packet = RlpStream::new_list(to_request.len());
+			for hash in &to_request {
+				packet.append(hash);
+			}
+			trace!(target: "sync", "{:02} <- GetTransactions ({} entries)", peer_id, to_request.len());
+			self.send_warp_packet(io, peer_id, GET_TRANSACTIONS_PACKET, packet.out());
+		}
+		Ok(())
+	}
+
+	/// Called when peer requests announced transactions
+	fn on_peer_get_transactions(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let item_count = r.item_count()?;
+		trace!(target: "sync", "{:02} -> GetTransactions ({} entries)", peer_id, item_count);
+		let mut requested = HashSet::new();
+		for i in 0 .. cmp::min(item_count, MAX_TRANSACTIONS_TO_REQUEST) {
+			requested.insert(r.val_at::<H256>(i)?);
+		}
+		let hidden = match self.receives_local_transactions(io, peer_id) {
+			true => HashSet::new(),
+			false => io.chain().local_transactions(),
+		};
+
+		let mut packet = RlpStream::new();
+		packet.begin_unbounded_list();
+		let mut pushed = 0;
+		for hash in requested.difference(&hidden) {
+			let tx = match io.chain().queued_transaction(hash) {
+				Some(tx) => tx,
+				None => continue,
+			};
+			let mut transaction = RlpStream::new();
+			tx.transaction.rlp_append(&mut transaction);
+			if !packet.append_raw_checked(&transaction.drain(), 1, MAX_TRANSACTION_PACKET_SIZE) {
+				break;
+			}
+			pushed += 1;
+		}
+		packet.complete_unbounded_list();
+		trace!(target: "sync", "{:02} <- Transactions ({} entries)", peer_id, pushed);
+		io.respond(TRANSACTIONS_PACKET, packet.out())
+			.unwrap_or_else(|e| debug!(target: "sync", "Error sending transactions: {:?}", e));
+		Ok(())
+	}
+
 	/// Send Status message
 	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), network::Error> {
 		let warp_protocol_version = io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer);
@@ -1767,6 +2306,9 @@ impl ChainSync {
 			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
 			SNAPSHOT_MANIFEST_PACKET => self.on_snapshot_manifest(io, peer, &rlp),
 			SNAPSHOT_DATA_PACKET => self.on_snapshot_data(io, peer, &rlp),
+			NODE_DATA_PACKET => self.on_peer_node_data(io, peer, &rlp),
+			TRANSACTION_HASHES_PACKET => self.on_peer_transaction_hashes(io, peer, &rlp),
+			GET_TRANSACTIONS_PACKET => self.on_peer_get_transactions(io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "{}: Unknown packet {}", peer, packet_id);
 				Ok(())
@@ -1790,6 +2332,11 @@ impl ChainSync {
 				PeerAsking::ForkHeader => elapsed > FORK_HEADER_TIMEOUT_SEC,
 				PeerAsking::SnapshotManifest => elapsed > SNAPSHOT_MANIFEST_TIMEOUT_SEC,
 				PeerAsking::SnapshotData => elapsed > SNAPSHOT_DATA_TIMEOUT_SEC,
+				PeerAsking::CheckpointHeader => elapsed > HEADERS_TIMEOUT_SEC,
+				PeerAsking::CheckpointBody => elapsed > BODIES_TIMEOUT_SEC,
+				PeerAsking::CheckpointReceipts => elapsed > RECEIPTS_TIMEOUT_SEC,
+				PeerAsking::StateNodes => elapsed > NODE_DATA_TIMEOUT_SEC,
+				PeerAsking::PivotHeaders => elapsed > HEADERS_TIMEOUT_SEC,
 			};
 			if timeout {
 				trace!(target:"sync", "Timeout {}", peer_id);
@@ -1801,6 +2348,48 @@ impl ChainSync {
 			self.on_peer_aborting(io, p);
 		}
 
+		// Let announced transactions that were never delivered be requested again.
+		self.requested_transactions.retain(|_, ask_time| (tick - *ask_time) / 1_000_000_000 < TRANSACTION_REQUEST_TIMEOUT_SEC);
+
+		// Re-assign stalled body and receipt requests so that a single slow peer does not hold up the import.
+		let mut stalled = Vec::new();
+		for (peer_id, peer) in &self.peers {
+			if peer.expired || peer.asking_blocks.is_empty() {
+				continue;
+			}
+			let expected = match peer.asking {
+				PeerAsking::BlockBodies => peer.bodies_throughput.expected_duration(peer.asking_blocks.len()),
+				PeerAsking::BlockReceipts => peer.receipts_throughput.expected_duration(peer.asking_blocks.len()),
+				_ => continue,
+			};
+			let elapsed = (tick - peer.ask_time) as f64 / 1_000_000_000f64;
+			let limit = expected.map_or(MIN_RETRY_REQUEST_SEC, |e| (e * RETRY_REQUEST_FACTOR).max(MIN_RETRY_REQUEST_SEC));
+			if elapsed > limit {
+				stalled.push(*peer_id);
+			}
+		}
+		let idle_peers = self.peers.values().filter(|p| p.can_sync() && p.asking == PeerAsking::Nothing).count();
+		if idle_peers > 0 && !stalled.is_empty() {
+			for p in stalled {
+				trace!(target:"sync", "Re-assigning stalled request of {}", p);
+				if let Some(ref mut peer) = self.peers.get_mut(&p) {
+					// the reply may never arrive, so account for the stall now.
+					let (items, elapsed) = (peer.asking_blocks.len(), tick - peer.ask_time);
+					match peer.asking {
+						PeerAsking::BlockBodies => peer.bodies_throughput.timeout(items, elapsed),
+						PeerAsking::BlockReceipts => peer.receipts_throughput.timeout(items, elapsed),
+						_ => {},
+					}
+				}
+				self.clear_peer_download(p);
+				if let Some(ref mut peer) = self.peers.get_mut(&p) {
+					peer.reset_asking();
+				}
+				self.retried_requests += 1;
+			}
+			self.continue_sync(io);
+		}
+
 		// Check for handshake timeouts
 		for (peer, ask_time) in &self.handshaking_peers {
 			let elapsed = (tick - ask_time) / 1_000_000_000;
@@ -1836,6 +2425,12 @@ impl ChainSync {
 					self.continue_sync(io);
 				},
 			}
+		} else if self.state == SyncState::CheckpointState {
+			self.check_node_restore(io);
+			// nodes are restored in the background, so more may be missing by now.
+			if self.state == SyncState::CheckpointState {
+				self.continue_sync(io);
+			}
 		}
 	}
 
@@ -1920,7 +2515,7 @@ impl ChainSync {
 	}
 
 	fn get_consensus_peers(&self) -> Vec<PeerId> {
-		self.peers.iter().filter_map(|(id, p)| if p.protocol_version == PROTOCOL_VERSION_2 { Some(*id) } else { None }).collect()
+		self.peers.iter().filter_map(|(id, p)| if p.protocol_version == PROTOCOL_VERSION_2 || p.protocol_version == PROTOCOL_VERSION_3 { Some(*id) } else { None }).collect()
 	}
 
 	/// propagates latest block to a set of peers
@@ -1979,43 +2574,77 @@ impl ChainSync {
 			return 0;
 		}
 
+		let local_transactions = match self.transaction_propagation.local_transactions_peers {
+			Some(_) => io.chain().local_transactions(),
+			None => HashSet::new(),
+		};
+
 		let (transactions, service_transactions): (Vec<_>, Vec<_>) = transactions.into_iter()
 			.partition(|tx| !tx.transaction.gas_price.is_zero());
 
 		// usual transactions could be propagated to all peers
 		let mut affected_peers = HashSet::new();
 		if !transactions.is_empty() {
-			let peers = self.select_peers_for_transactions(|_| true);
-			affected_peers = self.propagate_transactions_to_peers(io, peers, transactions);
+			let peers = self.select_peers_for_transactions(io, |_| true);
+			affected_peers = self.propagate_transactions_to_peers(io, peers, transactions, &local_transactions);
 		}
 
 		// most of times service_transactions will be empty
 		// => there's no need to merge packets
 		if !service_transactions.is_empty() {
-			let service_transactions_peers = self.select_peers_for_transactions(|peer_id| accepts_service_transaction(&io.peer_info(*peer_id)));
-			let service_transactions_affected_peers = self.propagate_transactions_to_peers(io, service_transactions_peers, service_transactions);
+			let service_transactions_peers = self.select_peers_for_transactions(io, |peer_id| accepts_service_transaction(&io.peer_info(*peer_id)));
+			let service_transactions_affected_peers = self.propagate_transactions_to_peers(io, service_transactions_peers, service_transactions, &local_transactions);
 			affected_peers.extend(&service_transactions_affected_peers);
 		}
 
 		affected_peers.len()
 	}
 
-	fn select_peers_for_transactions<F>(&self, filter: F) -> Vec<PeerId>
+	/// Selects peers to relay transactions to, along with the way they are relayed to each peer.
+	/// Reserved peers are always selected; a random subset of other peers gets full transactions.
+	fn select_peers_for_transactions<F>(&self, io: &SyncIo, filter: F) -> Vec<(PeerId, TransactionPropagationMode)>
 		where F: Fn(&PeerId) -> bool {
 		// sqrt(x)/x scaled to max u32
 		let fraction = ((self.peers.len() as f64).powf(-0.5) * (u32::max_value() as f64).round()) as u32;
 		let small = self.peers.len() < MIN_PEERS_PROPAGATION;
 
 		let mut random = random::new();
-		self.peers.keys()
+		let (reserved, others): (Vec<_>, Vec<_>) = self.peers.keys()
 			.cloned()
 			.filter(filter)
-			.filter(|_| small || random.next_u32() < fraction)
-			.take(MAX_PEERS_PROPAGATION)
-			.collect()
+			.partition(|peer_id| io.is_reserved_peer(*peer_id));
+
+		let reserved = reserved.into_iter()
+			.filter_map(|peer_id| self.transaction_propagation_mode(peer_id, self.transaction_propagation.reserved_peers).map(|mode| (peer_id, mode)));
+		let others = others.into_iter()
+			.filter_map(|peer_id| self.transaction_propagation_mode(peer_id, self.transaction_propagation.other_peers).map(|mode| (peer_id, mode)))
+			// announcements are cheap, so they go to all peers
+			.filter(|&(_, mode)| mode == TransactionPropagationMode::Hashes || small || random.next_u32() < fraction)
+			.take(MAX_PEERS_PROPAGATION);
+		reserved.chain(others).collect()
+	}
+
+	/// Effective propagation mode for a peer. Peers that do not understand announcements get full transactions.
+	fn transaction_propagation_mode(&self, peer_id: PeerId, mode: TransactionPropagationMode) -> Option<TransactionPropagationMode> {
+		match mode {
+			TransactionPropagationMode::Off => None,
+			TransactionPropagationMode::Hashes if self.peers.get(&peer_id).map_or(false, |p| p.protocol_version == PROTOCOL_VERSION_3) =>
+				Some(TransactionPropagationMode::Hashes),
+			_ => Some(TransactionPropagationMode::Full),
+		}
+	}
+
+	/// Checks if transactions submitted to this node may be relayed to the given peer.
+	fn receives_local_transactions(&self, io: &SyncIo, peer_id: PeerId) -> bool {
+		match self.transaction_propagation.local_transactions_peers {
+			Some(ref peers) => io.is_reserved_peer(peer_id) && io.peer_session_info(peer_id)
+				.and_then(|info| info.id)
+				.map_or(false, |id| peers.contains(&id)),
+			None => true,
+		}
 	}
 
-	fn propagate_transactions_to_peers(&mut self, io: &mut SyncIo, peers: Vec<PeerId>, transactions: Vec<PendingTransaction>) -> HashSet<PeerId> {
+	fn propagate_transactions_to_peers(&mut self, io: &mut SyncIo, peers: Vec<(PeerId, TransactionPropagationMode)>, transactions: Vec<PendingTransaction>, local_transactions: &HashSet<H256>) -> HashSet<PeerId> {
 		let all_transactions_hashes = transactions.iter()
 			.map(|tx| tx.transaction.hash())
 			.collect::<HashSet<H256>>();
@@ -2028,87 +2657,108 @@ impl ChainSync {
 		// Clear old transactions from stats
 		self.transactions_stats.retain(&all_transactions_hashes);
 
-		// sqrt(x)/x scaled to max u32
 		let block_number = io.chain().chain_info().best_block_number;
+		let now = time::precise_time_ns();
+
+		let mut lucky_peers = Vec::new();
+		for (peer_id, mode) in peers {
+			let id = io.peer_session_info(peer_id).and_then(|info| info.id);
+			let hide_local = !local_transactions.is_empty() && !self.receives_local_transactions(io, peer_id);
+			let peer_info = self.peers.get_mut(&peer_id)
+				.expect("peer_id is form peers; peers is result of select_peers_for_transactions; select_peers_for_transactions selects peers from self.peers; qed");
+
+			// Get hashes of all transactions to send to this peer
+			let mut to_send = if peer_info.last_sent_transactions.is_empty() && !hide_local {
+				all_transactions_hashes.clone()
+			} else {
+				all_transactions_hashes.difference(&peer_info.last_sent_transactions)
+					.filter(|hash| !hide_local || !local_transactions.contains(*hash))
+					.take(MAX_TRANSACTIONS_TO_PROPAGATE)
+					.cloned()
+					.collect::<HashSet<_>>()
+			};
 
-		let lucky_peers = {
-			peers.into_iter()
-				.filter_map(|peer_id| {
-					let stats = &mut self.transactions_stats;
-					let peer_info = self.peers.get_mut(&peer_id)
-						.expect("peer_id is form peers; peers is result of select_peers_for_transactions; select_peers_for_transactions selects peers from self.peers; qed");
-
-					// Send all transactions
-					if peer_info.last_sent_transactions.is_empty() {
-						// update stats
-						for hash in &all_transactions_hashes {
-							let id = io.peer_session_info(peer_id).and_then(|info| info.id);
-							stats.propagated(hash, id, block_number);
-						}
-						peer_info.last_sent_transactions = all_transactions_hashes.clone();
-						return Some((peer_id, all_transactions_hashes.len(), all_transactions_rlp.clone()));
-					}
+			if let Some(limit) = self.transaction_propagation.max_per_peer_per_sec {
+				let available = self.transaction_rate_limits.entry(peer_id)
+					.or_insert_with(|| RateLimit::new(limit, now))
+					.available(now);
+				if available < to_send.len() {
+					trace!(target: "sync", "{:02} Transaction rate limit reached, sending {}/{}", peer_id, available, to_send.len());
+					to_send = to_send.into_iter().take(available).collect();
+				}
+			}
+			if to_send.is_empty() {
+				continue;
+			}
 
-					// Get hashes of all transactions to send to this peer
-					let to_send = all_transactions_hashes.difference(&peer_info.last_sent_transactions)
-						.take(MAX_TRANSACTIONS_TO_PROPAGATE)
-						.cloned()
-						.collect::<HashSet<_>>();
-					if to_send.is_empty() {
-						return None;
+			// Construct RLP
+			let (packet_id, packet, to_send) = if mode == TransactionPropagationMode::Hashes {
+				let mut packet = RlpStream::new_list(to_send.len());
+				for tx in &transactions {
+					let hash = tx.transaction.hash();
+					if to_send.contains(&hash) {
+						packet.append(&hash);
 					}
-
-					// Construct RLP
-					let (packet, to_send) = {
-						let mut to_send = to_send;
-						let mut packet = RlpStream::new();
-						packet.begin_unbounded_list();
-						let mut pushed = 0;
-						for tx in &transactions {
-							let hash = tx.transaction.hash();
-							if to_send.contains(&hash) {
-								let mut transaction = RlpStream::new();
-								tx.transaction.rlp_append(&mut transaction);
-								let appended = packet.append_raw_checked(&transaction.drain(), 1, MAX_TRANSACTION_PACKET_SIZE);
-								if !appended {
-									// Maximal packet size reached just proceed with sending
-									debug!("Transaction packet size limit reached. Sending incomplete set of {}/{} transactions.", pushed, to_send.len());
-									to_send = to_send.into_iter().take(pushed).collect();
-									break;
-								}
-								pushed += 1;
-							}
+				}
+				(TRANSACTION_HASHES_PACKET, packet.out(), to_send)
+			} else if to_send.len() == all_transactions_hashes.len() {
+				// Send all transactions
+				(TRANSACTIONS_PACKET, all_transactions_rlp.clone(), to_send)
+			} else {
+				let mut to_send = to_send;
+				let mut packet = RlpStream::new();
+				packet.begin_unbounded_list();
+				let mut pushed = 0;
+				for tx in &transactions {
+					let hash = tx.transaction.hash();
+					if to_send.contains(&hash) {
+						let mut transaction = RlpStream::new();
+						tx.transaction.rlp_append(&mut transaction);
+						let appended = packet.append_raw_checked(&transaction.drain(), 1, MAX_TRANSACTION_PACKET_SIZE);
+						if !appended {
+							// Maximal packet size reached just proceed with sending
+							debug!("Transaction packet size limit reached. Sending incomplete set of {}/{} transactions.", pushed, to_send.len());
+							to_send = to_send.into_iter().take(pushed).collect();
+							break;
 						}
-						packet.complete_unbounded_list();
-						(packet, to_send)
-					};
-
-					// Update stats
-					let id = io.peer_session_info(peer_id).and_then(|info| info.id);
-					for hash in &to_send {
-						// update stats
-						stats.propagated(hash, id, block_number);
+						pushed += 1;
 					}
+				}
+				packet.complete_unbounded_list();
+				(TRANSACTIONS_PACKET, packet.out(), to_send)
+			};
 
-					peer_info.last_sent_transactions = all_transactions_hashes
-						.intersection(&peer_info.last_sent_transactions)
-						.chain(&to_send)
-						.cloned()
-						.collect();
-					Some((peer_id, to_send.len(), packet.out()))
-				})
-				.collect::<Vec<_>>()
-		};
+			if let Some(rate_limit) = self.transaction_rate_limits.get_mut(&peer_id) {
+				rate_limit.take(to_send.len(), now);
+			}
+
+			// Update stats
+			for hash in &to_send {
+				self.transactions_stats.propagated(hash, id, block_number);
+			}
+
+			peer_info.last_sent_transactions = all_transactions_hashes
+				.intersection(&peer_info.last_sent_transactions)
+				.chain(&to_send)
+				.cloned()
+				.collect();
+			lucky_peers.push((peer_id, packet_id, to_send.len(), packet));
+		}
 
 		// Send RLPs
 		let mut peers = HashSet::new();
 		if lucky_peers.len() > 0 {
 			let mut max_sent = 0;
 			let lucky_peers_len = lucky_peers.len();
-			for (peer_id, sent, rlp) in lucky_peers {
+			for (peer_id, packet_id, sent, rlp) in lucky_peers {
 				peers.insert(peer_id);
-				self.send_packet(io, peer_id, TRANSACTIONS_PACKET, rlp);
-				trace!(target: "sync", "{:02} <- Transactions ({} entries)", peer_id, sent);
+				if packet_id == TRANSACTION_HASHES_PACKET {
+					self.send_warp_packet(io, peer_id, packet_id, rlp);
+					trace!(target: "sync", "{:02} <- Transaction hashes ({} entries)", peer_id, sent);
+				} else {
+					self.send_packet(io, peer_id, packet_id, rlp);
+					trace!(target: "sync", "{:02} <- Transactions ({} entries)", peer_id, sent);
+				}
 				max_sent = cmp::max(max_sent, sent);
 			}
 			debug!(target: "sync", "Sent up to {} transactions to {} peers.", max_sent, lucky_peers_len);
@@ -2224,7 +2874,7 @@ mod tests {
 	use network::PeerId;
 	use tests::helpers::*;
 	use tests::snapshot::TestSnapshotService;
-	use ethereum_types::{H256, U256, Address};
+	use ethereum_types::{H256, H512, U256, Address};
 	use parking_lot::RwLock;
 	use bytes::Bytes;
 	use rlp::{Rlp, RlpStream, UntrustedRlp};
@@ -2302,6 +2952,8 @@ mod tests {
 			num_snapshot_chunks: 0,
 			snapshot_chunks_done: 0,
 			last_imported_old_block_number: None,
+			num_retried_requests: 0,
+			download_rate: 0,
 		}
 	}
 
@@ -2329,6 +2981,34 @@ mod tests {
 		assert!(result.is_ok());
 	}
 
+	#[test]
+	fn stalled_request_is_reassigned_and_measured() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let queue = RwLock::new(VecDeque::new());
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(10), &client);
+		insert_dummy_peer(&mut sync, 1, client.block_hash_delta_minus(10));
+		{
+			// peer 0 has not answered a full bodies request for 6 seconds
+			let peer = sync.peers.get_mut(&0).unwrap();
+			peer.asking = PeerAsking::BlockBodies;
+			peer.asking_blocks = (0..32).map(|_| H256::random()).collect();
+			peer.ask_time = time::precise_time_ns() - 6_000_000_000;
+		}
+		let full = sync.peers[&0].request_limits().bodies;
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		sync.maintain_peers(&mut io);
+
+		assert_eq!(sync.retried_requests, 1);
+		let peer = &sync.peers[&0];
+		assert!(peer.expired);
+		assert!(peer.asking_blocks.is_empty());
+		assert!(peer.bodies_throughput.rate().is_some());
+		assert!(peer.request_limits().bodies < full);
+	}
+
 	#[test]
 	fn return_receipts() {
 		let mut client = TestBlockChainClient::new();
@@ -2481,7 +3161,10 @@ mod tests {
 				snapshot_number: None,
 				snapshot_hash: None,
 				asking_snapshot_data: None,
+				asking_state_nodes: Vec::new(),
 				block_set: None,
+				bodies_throughput: Throughput::default(),
+				receipts_throughput: Throughput::default(),
 			});
 
 	}
@@ -2602,7 +3285,10 @@ mod tests {
 				snapshot_number: None,
 				snapshot_hash: None,
 				asking_snapshot_data: None,
+				asking_state_nodes: Vec::new(),
 				block_set: None,
+				bodies_throughput: Throughput::default(),
+				receipts_throughput: Throughput::default(),
 			});
 		let ss = TestSnapshotService::new();
 		let mut io = TestIo::new(&mut client, &ss, &queue, None);
@@ -2808,6 +3494,208 @@ mod tests {
 		assert!(sent_transactions.iter().any(|tx| tx.hash() == tx2_hash));
 	}
 
+	#[test]
+	fn should_announce_transaction_hashes_to_capable_peers() {
+		let mut client = TestBlockChainClient::new();
+		let tx_hash = client.insert_transaction_to_queue();
+		let block_hash = client.block_hash_delta_minus(1);
+		let mut config = SyncConfig::default();
+		config.transaction_propagation.other_peers = TransactionPropagationMode::Hashes;
+		let mut sync = ChainSync::new(config, &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		// when peer#1 supports announcements
+		insert_dummy_peer(&mut sync, 1, block_hash);
+		sync.peers.get_mut(&1).unwrap().protocol_version = PROTOCOL_VERSION_3;
+		// and peer#2 does not
+		insert_dummy_peer(&mut sync, 2, block_hash);
+		sync.peers.get_mut(&2).unwrap().protocol_version = PROTOCOL_VERSION_2;
+
+		let peer_count = sync.propagate_new_transactions(&mut io);
+
+		// then
+		assert_eq!(peer_count, 2);
+		assert_eq!(io.packets.len(), 2);
+		let announcement = io.packets.iter().find(|p| p.recipient == 1).unwrap();
+		assert_eq!(announcement.packet_id, TRANSACTION_HASHES_PACKET);
+		assert_eq!(UntrustedRlp::new(&announcement.data).as_list::<H256>().unwrap(), vec![tx_hash]);
+		assert_eq!(io.packets.iter().find(|p| p.recipient == 2).unwrap().packet_id, TRANSACTIONS_PACKET);
+	}
+
+	#[test]
+	fn should_propagate_to_reserved_peers_only() {
+		let mut client = TestBlockChainClient::new();
+		client.insert_transaction_to_queue();
+		let block_hash = client.block_hash_delta_minus(1);
+		let mut config = SyncConfig::default();
+		config.transaction_propagation.other_peers = TransactionPropagationMode::Off;
+		let mut sync = ChainSync::new(config, &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		for peer in 1..6 {
+			insert_dummy_peer(&mut sync, peer, block_hash);
+		}
+		io.reserved_peers.insert(3);
+
+		let peer_count = sync.propagate_new_transactions(&mut io);
+
+		assert_eq!(peer_count, 1);
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(io.packets[0].recipient, 3);
+		assert_eq!(io.packets[0].packet_id, TRANSACTIONS_PACKET);
+	}
+
+	#[test]
+	fn should_relay_local_transactions_to_chosen_reserved_peers_only() {
+		let mut client = TestBlockChainClient::new();
+		let tx_hash = client.insert_transaction_to_queue();
+		let local_hash = client.insert_local_transaction_to_queue();
+		let block_hash = client.block_hash_delta_minus(1);
+		let chosen = H512::from(1);
+		let mut config = SyncConfig::default();
+		config.transaction_propagation.local_transactions_peers = Some(vec![chosen].into_iter().collect());
+		let mut sync = ChainSync::new(config, &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		// peer#1 is a chosen reserved peer
+		insert_dummy_peer(&mut sync, 1, block_hash);
+		io.reserved_peers.insert(1);
+		io.node_ids.insert(1, chosen);
+		// peer#2 is reserved but not chosen
+		insert_dummy_peer(&mut sync, 2, block_hash);
+		io.reserved_peers.insert(2);
+		io.node_ids.insert(2, H512::from(2));
+		// peer#3 is a regular peer
+		insert_dummy_peer(&mut sync, 3, block_hash);
+
+		sync.propagate_new_transactions(&mut io);
+
+		let sent_to = |peer| io.packets.iter()
+			.filter(|p| p.recipient == peer && p.packet_id == TRANSACTIONS_PACKET)
+			.flat_map(|p| UntrustedRlp::new(&p.data).as_list::<UnverifiedTransaction>().unwrap())
+			.map(|tx| tx.hash())
+			.collect::<HashSet<_>>();
+		assert_eq!(sent_to(1), vec![tx_hash, local_hash].into_iter().collect());
+		assert_eq!(sent_to(2), vec![tx_hash].into_iter().collect());
+		assert_eq!(sent_to(3), vec![tx_hash].into_iter().collect());
+	}
+
+	#[test]
+	fn should_limit_transactions_sent_to_a_peer() {
+		let mut client = TestBlockChainClient::new();
+		client.insert_transaction_to_queue();
+		client.insert_transaction_to_queue();
+		let mut config = SyncConfig::default();
+		config.transaction_propagation.max_per_peer_per_sec = Some(1);
+		let mut sync = ChainSync::new(config, &client);
+		insert_dummy_peer(&mut sync, 0, client.block_hash_delta_minus(1));
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+		let peer_count = sync.propagate_new_transactions(&mut io);
+		let peer_count2 = sync.propagate_new_transactions(&mut io);
+
+		assert_eq!(peer_count, 1);
+		assert_eq!(peer_count2, 0);
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(UntrustedRlp::new(&io.packets[0].data).item_count().unwrap(), 1);
+	}
+
+	#[test]
+	fn should_request_announced_transactions_once() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		insert_dummy_peer(&mut sync, 1, client.block_hash_delta_minus(1));
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+		let hashes = vec![H256::from(1), H256::from(2)];
+		let mut announcement = RlpStream::new_list(hashes.len());
+		for hash in &hashes {
+			announcement.append(hash);
+		}
+		let announcement = announcement.out();
+
+		sync.on_packet(&mut io, 0, TRANSACTION_HASHES_PACKET, &announcement);
+		sync.on_packet(&mut io, 1, TRANSACTION_HASHES_PACKET, &announcement);
+
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(io.packets[0].packet_id, GET_TRANSACTIONS_PACKET);
+		assert_eq!(io.packets[0].recipient, 0);
+		assert_eq!(UntrustedRlp::new(&io.packets[0].data).as_list::<H256>().unwrap(), hashes);
+		assert!(sync.peers[&1].last_sent_transactions.contains(&hashes[0]));
+	}
+
+	#[test]
+	fn should_not_request_queued_transactions() {
+		let mut client = TestBlockChainClient::new();
+		let tx_hash = client.insert_transaction_to_queue();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+		let mut announcement = RlpStream::new_list(2);
+		announcement.append(&tx_hash);
+		announcement.append(&H256::from(1));
+
+		sync.on_packet(&mut io, 0, TRANSACTION_HASHES_PACKET, &announcement.out());
+
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(UntrustedRlp::new(&io.packets[0].data).as_list::<H256>().unwrap(), vec![H256::from(1)]);
+	}
+
+	#[test]
+	fn should_ignore_hashes_beyond_announcement_limit() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, None);
+		let count = MAX_TRANSACTIONS_TO_REQUEST + 10;
+		let mut announcement = RlpStream::new_list(count);
+		for i in 0..count {
+			announcement.append(&H256::from(i as u64 + 1));
+		}
+
+		sync.on_packet(&mut io, 0, TRANSACTION_HASHES_PACKET, &announcement.out());
+
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(UntrustedRlp::new(&io.packets[0].data).item_count().unwrap(), MAX_TRANSACTIONS_TO_REQUEST);
+		assert_eq!(sync.requested_transactions.len(), MAX_TRANSACTIONS_TO_REQUEST);
+		assert_eq!(sync.peers[&0].last_sent_transactions.len(), MAX_TRANSACTIONS_TO_REQUEST);
+	}
+
+	#[test]
+	fn should_return_requested_transactions() {
+		let mut client = TestBlockChainClient::new();
+		let tx_hash = client.insert_transaction_to_queue();
+		client.insert_transaction_to_queue();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let queue = RwLock::new(VecDeque::new());
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &queue, Some(0));
+		let mut request = RlpStream::new_list(2);
+		request.append(&tx_hash);
+		request.append(&H256::from(1));
+
+		sync.on_packet(&mut io, 0, GET_TRANSACTIONS_PACKET, &request.out());
+
+		assert_eq!(io.packets.len(), 1);
+		assert_eq!(io.packets[0].packet_id, TRANSACTIONS_PACKET);
+		let sent = UntrustedRlp::new(&io.packets[0].data).as_list::<UnverifiedTransaction>().unwrap();
+		assert_eq!(sent.len(), 1);
+		assert_eq!(sent[0].hash(), tx_hash);
+	}
+
 	#[test]
 	fn handles_peer_new_block_malformed() {
 		let mut client = TestBlockChainClient::new();
@@ -0,0 +1,263 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use hash::{keccak, KECCAK_NULL_RLP, KECCAK_EMPTY_LIST_RLP};
+use ethereum_types::{H256, U256};
+use triehash::ordered_trie_root;
+use bytes::Bytes;
+use rlp::{RlpStream, UntrustedRlp};
+use ethcore::header::{BlockNumber, Header as BlockHeader};
+
+/// Download of a trusted checkpoint block and of the state at it.
+pub struct Checkpoint {
+	number: BlockNumber,
+	hash: H256,
+	total_difficulty: U256,
+	header: Option<BlockHeader>,
+	body: Option<Bytes>,
+	receipts: Option<Bytes>,
+	downloading_nodes: HashSet<H256>,
+}
+
+impl Checkpoint {
+	/// Create a new instance for the block with the given number, hash and total difficulty.
+	pub fn new(number: BlockNumber, hash: H256, total_difficulty: U256) -> Checkpoint {
+		Checkpoint {
+			number: number,
+			hash: hash,
+			total_difficulty: total_difficulty,
+			header: None,
+			body: None,
+			receipts: None,
+			downloading_nodes: HashSet::new(),
+		}
+	}
+
+	/// Clear everything downloaded so far.
+	pub fn clear(&mut self) {
+		self.header = None;
+		self.body = None;
+		self.receipts = None;
+		self.downloading_nodes.clear();
+	}
+
+	pub fn number(&self) -> BlockNumber {
+		self.number
+	}
+
+	pub fn hash(&self) -> H256 {
+		self.hash
+	}
+
+	pub fn total_difficulty(&self) -> U256 {
+		self.total_difficulty
+	}
+
+	pub fn have_header(&self) -> bool {
+		self.header.is_some()
+	}
+
+	pub fn have_body(&self) -> bool {
+		self.body.is_some()
+	}
+
+	/// Validate the checkpoint header and keep it.
+	/// Empty bodies and receipts are filled in right away as they need not be downloaded.
+	pub fn import_header(&mut self, header_rlp: &[u8]) -> Result<(), ()> {
+		if keccak(header_rlp) != self.hash {
+			trace!(target: "sync", "Ignored checkpoint header with wrong hash");
+			return Err(());
+		}
+		let header: BlockHeader = UntrustedRlp::new(header_rlp).as_val().map_err(|_| ())?;
+		if header.number() != self.number {
+			trace!(target: "sync", "Checkpoint hash {} belongs to block #{}", self.hash, header.number());
+			return Err(());
+		}
+
+		if *header.transactions_root() == KECCAK_NULL_RLP && *header.uncles_hash() == KECCAK_EMPTY_LIST_RLP {
+			let mut body = RlpStream::new_list(2);
+			body.begin_list(0);
+			body.begin_list(0);
+			self.body = Some(body.out());
+		}
+		if *header.receipts_root() == KECCAK_NULL_RLP {
+			self.receipts = Some(RlpStream::new_list(0).out());
+		}
+		self.header = Some(header);
+		Ok(())
+	}
+
+	/// Validate the checkpoint block body against the header and keep it.
+	pub fn import_body(&mut self, body: &UntrustedRlp) -> Result<(), ()> {
+		let matches = match self.header {
+			Some(ref header) => {
+				let tx_root = ordered_trie_root(body.at(0).map_err(|_| ())?.iter().map(|r| r.as_raw().to_vec()));
+				let uncles = keccak(body.at(1).map_err(|_| ())?.as_raw());
+				tx_root == *header.transactions_root() && uncles == *header.uncles_hash()
+			},
+			None => false,
+		};
+		if !matches {
+			trace!(target: "sync", "Ignored checkpoint body not matching the header");
+			return Err(());
+		}
+		self.body = Some(body.as_raw().to_vec());
+		Ok(())
+	}
+
+	/// Validate the checkpoint block receipts against the header and keep them.
+	pub fn import_receipts(&mut self, receipts: &UntrustedRlp) -> Result<(), ()> {
+		let matches = match self.header {
+			Some(ref header) => ordered_trie_root(receipts.iter().map(|r| r.as_raw().to_vec())) == *header.receipts_root(),
+			None => false,
+		};
+		if !matches {
+			trace!(target: "sync", "Ignored checkpoint receipts not matching the header");
+			return Err(());
+		}
+		self.receipts = Some(receipts.as_raw().to_vec());
+		Ok(())
+	}
+
+	/// Whether the block and its receipts are downloaded.
+	pub fn is_block_complete(&self) -> bool {
+		self.header.is_some() && self.body.is_some() && self.receipts.is_some()
+	}
+
+	/// Get the RLP of the whole checkpoint block, if downloaded.
+	pub fn block(&self) -> Option<Bytes> {
+		match (self.header.as_ref(), self.body.as_ref()) {
+			(Some(header), Some(body)) => {
+				let body = UntrustedRlp::new(body);
+				let mut block = RlpStream::new_list(3);
+				block.append(header);
+				block.append_raw(body.at(0).expect("body structure is checked on import; qed").as_raw(), 1);
+				block.append_raw(body.at(1).expect("body structure is checked on import; qed").as_raw(), 1);
+				Some(block.out())
+			},
+			_ => None,
+		}
+	}
+
+	/// Get the RLP of the checkpoint block receipts, if downloaded.
+	pub fn receipts(&self) -> Option<Bytes> {
+		self.receipts.clone()
+	}
+
+	/// Pick up to `count` of the given missing state nodes which are not being downloaded yet
+	/// and mark them as being downloaded.
+	pub fn needed_nodes(&mut self, missing: Vec<H256>, count: usize) -> Vec<H256> {
+		let needed: Vec<_> = missing.into_iter()
+			.filter(|h| !self.downloading_nodes.contains(h))
+			.take(count)
+			.collect();
+		self.downloading_nodes.extend(needed.iter().cloned());
+		needed
+	}
+
+	pub fn clear_node_download(&mut self, hashes: &[H256]) {
+		for hash in hashes {
+			self.downloading_nodes.remove(hash);
+		}
+	}
+
+	pub fn clear_node_downloads(&mut self) {
+		self.downloading_nodes.clear();
+	}
+
+	pub fn downloading_nodes(&self) -> usize {
+		self.downloading_nodes.len()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use hash::keccak;
+	use ethereum_types::H256;
+	use rlp::{self, RlpStream, UntrustedRlp};
+	use ethcore::header::Header as BlockHeader;
+	use super::*;
+
+	fn test_header() -> BlockHeader {
+		let mut header = BlockHeader::new();
+		header.set_number(42);
+		header
+	}
+
+	#[test]
+	fn validates_header() {
+		let header = test_header();
+		let header_rlp = rlp::encode(&header).into_vec();
+		let mut checkpoint = Checkpoint::new(42, keccak(&header_rlp), 0.into());
+
+		assert!(checkpoint.import_header(&H256::random().to_vec()).is_err());
+		assert!(!checkpoint.have_header());
+
+		let mut wrong_number = Checkpoint::new(41, keccak(&header_rlp), 0.into());
+		assert!(wrong_number.import_header(&header_rlp).is_err());
+
+		assert!(checkpoint.import_header(&header_rlp).is_ok());
+		// an empty block needs no body or receipts.
+		assert!(checkpoint.is_block_complete());
+		assert_eq!(keccak(UntrustedRlp::new(&checkpoint.block().unwrap()).at(0).unwrap().as_raw()), checkpoint.hash());
+	}
+
+	#[test]
+	fn validates_body() {
+		let mut header = test_header();
+		let uncles = {
+			let mut uncles = RlpStream::new_list(1);
+			uncles.append(&test_header());
+			uncles.out()
+		};
+		header.set_uncles_hash(keccak(&uncles));
+		let header_rlp = rlp::encode(&header).into_vec();
+
+		let mut checkpoint = Checkpoint::new(42, keccak(&header_rlp), 0.into());
+		checkpoint.import_header(&header_rlp).unwrap();
+		assert!(!checkpoint.have_body());
+
+		let mut empty = RlpStream::new_list(2);
+		empty.begin_list(0);
+		empty.begin_list(0);
+		let empty = empty.out();
+		assert!(checkpoint.import_body(&UntrustedRlp::new(&empty)).is_err());
+
+		let mut body = RlpStream::new_list(2);
+		body.begin_list(0);
+		body.append_raw(&uncles, 1);
+		let body = body.out();
+		assert!(checkpoint.import_body(&UntrustedRlp::new(&body)).is_ok());
+		assert!(checkpoint.is_block_complete());
+	}
+
+	#[test]
+	fn tracks_node_downloads() {
+		let mut checkpoint = Checkpoint::new(1, H256::random(), 0.into());
+		let missing: Vec<H256> = (0..10).map(|_| H256::random()).collect();
+
+		let first = checkpoint.needed_nodes(missing.clone(), 4);
+		assert_eq!(&first[..], &missing[0..4]);
+		let second = checkpoint.needed_nodes(missing.clone(), 10);
+		assert_eq!(&second[..], &missing[4..10]);
+		assert!(checkpoint.needed_nodes(missing.clone(), 10).is_empty());
+
+		checkpoint.clear_node_download(&first);
+		assert_eq!(checkpoint.needed_nodes(missing.clone(), 10), first);
+		assert_eq!(checkpoint.downloading_nodes(), 10);
+	}
+}
@@ -42,6 +42,7 @@ extern crate kvdb;
 
 extern crate ethcore_light as light;
 
+#[cfg(test)] extern crate ethash;
 #[cfg(test)] extern crate ethkey;
 #[cfg(test)] extern crate kvdb_memorydb;
 
@@ -57,7 +58,9 @@ mod blocks;
 mod block_sync;
 mod sync_io;
 mod snapshot;
+mod checkpoint;
 mod transactions_stats;
+mod transactions_propagation;
 
 pub mod light_sync;
 
@@ -46,6 +46,8 @@ pub trait SyncIo {
 	}
 	/// Returns information on p2p session
 	fn peer_session_info(&self, peer_id: PeerId) -> Option<SessionInfo>;
+	/// Checks if the peer is one of the configured reserved nodes.
+	fn is_reserved_peer(&self, peer_id: PeerId) -> bool;
 	/// Maximum mutually supported ETH protocol version
 	fn eth_protocol_version(&self, peer_id: PeerId) -> u8;
 	/// Maximum mutually supported version of a gien protocol.
@@ -120,6 +122,10 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 		self.network.session_info(peer_id)
 	}
 
+	fn is_reserved_peer(&self, peer_id: PeerId) -> bool {
+		self.network.is_reserved_peer(peer_id)
+	}
+
 	fn is_expired(&self) -> bool {
 		self.network.is_expired()
 	}
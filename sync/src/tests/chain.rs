@@ -18,6 +18,7 @@ use std::sync::Arc;
 use ethcore::client::{TestBlockChainClient, BlockChainClient, BlockId, EachBlockWith};
 use chain::{SyncState};
 use super::helpers::*;
+use super::simulator::{Simulator, LinkConfig};
 use SyncConfig;
 
 #[test]
@@ -268,3 +269,34 @@ fn disconnect_on_unrelated_chain() {
 	assert_eq!(net.disconnect_events, vec![(0, 0)]);
 }
 
+#[test]
+fn sync_over_slow_lossy_links() {
+	::env_logger::init().ok();
+	let net = TestNet::new(3);
+	net.peer(1).chain.add_blocks(1000, EachBlockWith::Uncle);
+	net.peer(2).chain.add_blocks(1000, EachBlockWith::Uncle);
+	let mut sim = Simulator::new(net, [1, 2, 3, 4]);
+	sim.set_default_link(LinkConfig { latency: 3, loss: 0.02, reconnect_delay: 5 });
+	assert!(sim.run_until(10_000, |net| net.peer(0).chain.block(BlockId::Number(1000)).is_some()));
+	assert!(sim.broken_sessions > 0);
+	assert_eq!(*sim.peer(0).chain.blocks.read(), *sim.peer(1).chain.blocks.read());
+}
+
+#[test]
+fn sync_after_partition_heals() {
+	::env_logger::init().ok();
+	let net = TestNet::new(3);
+	net.peer(1).chain.add_blocks(100, EachBlockWith::Uncle);
+	let mut sim = Simulator::new(net, [5, 6, 7, 8]);
+	sim.partition(&[&[0]]);
+	sim.run(1000);
+	assert!(!sim.is_connected(0, 1));
+	assert_eq!(sim.peer(0).chain.chain_info().best_block_number, 0);
+	assert_eq!(sim.peer(2).chain.chain_info().best_block_number, 100);
+
+	sim.heal();
+	sim.run(1000);
+	assert!(sim.is_connected(0, 1));
+	assert!(sim.converged());
+	assert_eq!(sim.peer(0).chain.chain_info().best_block_number, 100);
+}
@@ -16,8 +16,11 @@
 
 use std::sync::Arc;
 use hash::keccak;
-use ethereum_types::{U256, Address};
+use rlp;
+use ethereum_types::{H64, H256, U256, Address};
+use ethash::EthashManager;
 use io::{IoHandler, IoContext, IoChannel};
+use ethcore::block::IsBlock;
 use ethcore::client::{BlockChainClient, Client};
 use ethcore::service::ClientIoMessage;
 use ethcore::spec::Spec;
@@ -26,6 +29,7 @@ use ethcore::account_provider::AccountProvider;
 use ethkey::{KeyPair, Secret};
 use transaction::{Action, PendingTransaction, Transaction};
 use super::helpers::*;
+use super::simulator::{Simulator, LinkConfig};
 use SyncConfig;
 
 struct TestIoHandler {
@@ -55,6 +59,15 @@ fn new_tx(secret: &Secret, nonce: U256, chain_id: u64) -> PendingTransaction {
 	PendingTransaction::new(signed, None)
 }
 
+// Seal the pending block of `client`. Any nonce is valid with trivial difficulty.
+fn mine_block(client: &Arc<Client>, ethash: &EthashManager) {
+	let (pow_hash, number) = client.miner().map_sealing_work(&**client, |b| (b.hash(), b.header().number()))
+		.expect("Ethash produces work packages; qed");
+	let pow = ethash.compute_light(number, &pow_hash.0, 0);
+	let seal = vec![rlp::encode(&H256(pow.mix_hash)).into_vec(), rlp::encode(&H64::zero()).into_vec()];
+	client.miner().submit_seal(&**client, pow_hash, seal).unwrap();
+}
+
 #[test]
 fn authority_round() {
 	let s0 = KeyPair::from_secret_slice(&keccak("1")).unwrap();
@@ -219,3 +232,142 @@ fn tendermint() {
 	assert_eq!(ci1.best_block_number, 3);
 	assert_eq!(ci0.best_block_hash, ci1.best_block_hash);
 }
+
+#[test]
+fn authority_round_partition() {
+	let s0 = KeyPair::from_secret_slice(&keccak("1")).unwrap();
+	let s1 = KeyPair::from_secret_slice(&keccak("0")).unwrap();
+	let ap = Arc::new(AccountProvider::transient_provider());
+	ap.insert_account(s0.secret().clone(), "").unwrap();
+	ap.insert_account(s1.secret().clone(), "").unwrap();
+
+	let chain_id = Spec::new_test_round().chain_id();
+	let net = TestNet::with_spec_and_accounts(2, SyncConfig::default(), Spec::new_test_round, Some(ap));
+	let io_handler0: Arc<IoHandler<ClientIoMessage>> = Arc::new(TestIoHandler { client: net.peer(0).chain.clone() });
+	let io_handler1: Arc<IoHandler<ClientIoMessage>> = Arc::new(TestIoHandler { client: net.peer(1).chain.clone() });
+	net.peer(0).chain.miner().set_engine_signer(s0.address(), "".to_owned()).unwrap();
+	net.peer(1).chain.miner().set_engine_signer(s1.address(), "".to_owned()).unwrap();
+	net.peer(0).chain.engine().register_client(Arc::downgrade(&net.peer(0).chain) as _);
+	net.peer(1).chain.engine().register_client(Arc::downgrade(&net.peer(1).chain) as _);
+	net.peer(0).chain.set_io_channel(IoChannel::to_handler(Arc::downgrade(&io_handler1)));
+	net.peer(1).chain.set_io_channel(IoChannel::to_handler(Arc::downgrade(&io_handler0)));
+
+	let mut sim = Simulator::new(net, [1, 2, 3, 4]);
+	sim.set_default_link(LinkConfig { latency: 2, loss: 0.0, reconnect_delay: 3 });
+	// exchange statuses
+	sim.run(100);
+
+	// Split the validators and let each of them seal on its own side.
+	sim.partition(&[&[0], &[1]]);
+	sim.peer(0).chain.miner().import_own_transaction(&*sim.peer(0).chain, new_tx(s0.secret(), 0.into(), chain_id)).unwrap();
+	sim.peer(1).chain.miner().import_own_transaction(&*sim.peer(1).chain, new_tx(s1.secret(), 0.into(), chain_id)).unwrap();
+	sim.peer(0).chain.engine().step();
+	sim.peer(1).chain.engine().step();
+	sim.peer(0).chain.engine().step();
+	sim.peer(1).chain.engine().step();
+	sim.run(100);
+	let best = sim.best_blocks();
+	assert_eq!(best[0].0, 1);
+	assert_eq!(best[1].0, 1);
+	assert!(best[0].1 != best[1].1);
+
+	// Both sides reorganise to a single chain once the partition heals.
+	sim.heal();
+	sim.run(1000);
+	assert!(sim.converged());
+	assert_eq!(sim.best_blocks()[0].0, 1);
+}
+
+#[test]
+fn ethash_partition() {
+	let ethash = EthashManager::new(&::std::env::temp_dir(), None);
+	let net = TestNet::with_spec_and_accounts(3, SyncConfig::default(), Spec::new_test_ethash, None);
+	for i in 0..3 {
+		net.peer(i).chain.miner().set_author(Address::from(i as u64 + 1));
+	}
+
+	let mut sim = Simulator::new(net, [9, 10, 11, 12]);
+	sim.set_default_link(LinkConfig { latency: 2, loss: 0.0, reconnect_delay: 3 });
+	// exchange statuses
+	sim.run(100);
+
+	// Peer 0 mines on its own while peers 1 and 2 build a longer chain.
+	sim.partition(&[&[0]]);
+	mine_block(&sim.peer(0).chain, &ethash);
+	mine_block(&sim.peer(0).chain, &ethash);
+	for _ in 0..3 {
+		mine_block(&sim.peer(1).chain, &ethash);
+	}
+	sim.run(1000);
+	let best = sim.best_blocks();
+	assert_eq!(best[0].0, 2);
+	assert_eq!(best[1].0, 3);
+	assert_eq!(best[1], best[2]);
+
+	// The isolated miner reorganises to the heavier chain once the partition heals.
+	sim.heal();
+	sim.run(1000);
+	assert!(sim.converged());
+	assert_eq!(sim.best_blocks()[0], best[1]);
+}
+
+#[test]
+fn tendermint_partition() {
+	let s0 = KeyPair::from_secret_slice(&keccak("1")).unwrap();
+	let s1 = KeyPair::from_secret_slice(&keccak("0")).unwrap();
+	let ap = Arc::new(AccountProvider::transient_provider());
+	ap.insert_account(s0.secret().clone(), "").unwrap();
+	ap.insert_account(s1.secret().clone(), "").unwrap();
+
+	let chain_id = Spec::new_test_tendermint().chain_id();
+	let net = TestNet::with_spec_and_accounts(2, SyncConfig::default(), Spec::new_test_tendermint, Some(ap));
+	let io_handler0: Arc<IoHandler<ClientIoMessage>> = Arc::new(TestIoHandler { client: net.peer(0).chain.clone() });
+	let io_handler1: Arc<IoHandler<ClientIoMessage>> = Arc::new(TestIoHandler { client: net.peer(1).chain.clone() });
+	net.peer(0).chain.miner().set_engine_signer(s0.address(), "".to_owned()).unwrap();
+	net.peer(1).chain.miner().set_engine_signer(s1.address(), "".to_owned()).unwrap();
+	net.peer(0).chain.engine().register_client(Arc::downgrade(&net.peer(0).chain) as _);
+	net.peer(1).chain.engine().register_client(Arc::downgrade(&net.peer(1).chain) as _);
+	net.peer(0).chain.set_io_channel(IoChannel::to_handler(Arc::downgrade(&io_handler0)));
+	net.peer(1).chain.set_io_channel(IoChannel::to_handler(Arc::downgrade(&io_handler1)));
+
+	let mut sim = Simulator::new(net, [13, 14, 15, 16]);
+	sim.set_default_link(LinkConfig { latency: 2, loss: 0.0, reconnect_delay: 3 });
+	// Exchange statuses
+	sim.run(100);
+	// Propose
+	sim.peer(0).chain.miner().import_own_transaction(&*sim.peer(0).chain, new_tx(s0.secret(), 0.into(), chain_id)).unwrap();
+	sim.run(100);
+	// Propose timeout
+	sim.peer(0).chain.engine().step();
+	sim.peer(1).chain.engine().step();
+	// Prevote, precommit and commit
+	sim.run(100);
+	assert_eq!(sim.best_blocks()[0].0, 1);
+	assert!(sim.converged());
+
+	// Neither validator has a majority on its own, so a partition stalls the chain.
+	sim.partition(&[&[0], &[1]]);
+	sim.peer(0).chain.miner().import_own_transaction(&*sim.peer(0).chain, new_tx(s0.secret(), 1.into(), chain_id)).unwrap();
+	sim.peer(1).chain.miner().import_own_transaction(&*sim.peer(1).chain, new_tx(s1.secret(), 0.into(), chain_id)).unwrap();
+	for _ in 0..4 {
+		sim.peer(0).chain.engine().step();
+		sim.peer(1).chain.engine().step();
+		sim.run(100);
+	}
+	assert_eq!(sim.best_blocks()[0].0, 1);
+	assert_eq!(sim.best_blocks()[1].0, 1);
+
+	// Rounds time out until the validators agree again after the partition heals.
+	sim.heal();
+	sim.run(100);
+	for _ in 0..20 {
+		if sim.best_blocks().iter().all(|b| b.0 >= 2) {
+			break;
+		}
+		sim.peer(0).chain.engine().step();
+		sim.peer(1).chain.engine().step();
+		sim.run(100);
+	}
+	assert!(sim.best_blocks()[0].0 >= 2);
+	assert!(sim.converged());
+}
@@ -16,7 +16,7 @@
 
 use std::collections::{VecDeque, HashSet, HashMap};
 use std::sync::Arc;
-use ethereum_types::H256;
+use ethereum_types::{H256, H512};
 use parking_lot::RwLock;
 use bytes::Bytes;
 use network::{self, PeerId, ProtocolId, PacketId, SessionInfo};
@@ -53,6 +53,8 @@ pub struct TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 	pub to_disconnect: HashSet<PeerId>,
 	pub packets: Vec<TestPacket>,
 	pub peers_info: HashMap<PeerId, String>,
+	pub reserved_peers: HashSet<PeerId>,
+	pub node_ids: HashMap<PeerId, H512>,
 	overlay: RwLock<HashMap<BlockNumber, Bytes>>,
 }
 
@@ -67,6 +69,8 @@ impl<'p, C> TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 			overlay: RwLock::new(HashMap::new()),
 			packets: Vec::new(),
 			peers_info: HashMap::new(),
+			reserved_peers: HashSet::new(),
+			node_ids: HashMap::new(),
 		}
 	}
 }
@@ -126,8 +130,22 @@ impl<'p, C> SyncIo for TestIo<'p, C> where C: FlushingBlockChainClient, C: 'p {
 		self.snapshot_service
 	}
 
-	fn peer_session_info(&self, _peer_id: PeerId) -> Option<SessionInfo> {
-		None
+	fn peer_session_info(&self, peer_id: PeerId) -> Option<SessionInfo> {
+		self.node_ids.get(&peer_id).map(|id| SessionInfo {
+			id: Some(id.clone()),
+			client_version: self.peer_info(peer_id),
+			protocol_version: 5,
+			capabilities: Vec::new(),
+			peer_capabilities: Vec::new(),
+			ping_ms: None,
+			originated: false,
+			remote_address: String::new(),
+			local_address: String::new(),
+		})
+	}
+
+	fn is_reserved_peer(&self, peer_id: PeerId) -> bool {
+		self.reserved_peers.contains(&peer_id)
 	}
 
 	fn eth_protocol_version(&self, _peer: PeerId) -> u8 {
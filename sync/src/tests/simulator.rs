@@ -0,0 +1,315 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! In-process network simulator built on top of `TestNet`.
+//!
+//! Packets travel over simulated links which can delay them, break sessions
+//! and be split into partitions. devp2p runs over TCP, so a lost packet is
+//! modelled as a dropped session which is re-established after a delay rather
+//! than as a silently missing message.
+
+use std::collections::{HashMap, HashSet};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use ethereum_types::H256;
+use ethcore::header::BlockNumber;
+use network::PeerId;
+use super::helpers::*;
+
+/// Properties of a link between two peers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkConfig {
+	/// Number of ticks a packet spends in flight.
+	pub latency: usize,
+	/// Probability that sending a packet breaks the session, in `[0, 1]`.
+	pub loss: f64,
+	/// Number of ticks before a broken session is re-established.
+	pub reconnect_delay: usize,
+}
+
+impl Default for LinkConfig {
+	fn default() -> Self {
+		LinkConfig {
+			latency: 0,
+			loss: 0.0,
+			reconnect_delay: 1,
+		}
+	}
+}
+
+struct InFlight<M> {
+	deliver_at: usize,
+	from: usize,
+	message: M,
+}
+
+/// Drives a `TestNet` over simulated links.
+pub struct Simulator<P: Peer> {
+	/// Underlying network.
+	pub net: TestNet<P>,
+	default_link: LinkConfig,
+	links: HashMap<(usize, usize), LinkConfig>,
+	groups: Option<Vec<usize>>,
+	sessions: HashSet<(usize, usize)>,
+	banned: HashSet<(usize, usize)>,
+	reconnects: HashMap<(usize, usize), usize>,
+	in_flight: Vec<InFlight<P::Message>>,
+	rng: XorShiftRng,
+	tick: usize,
+	/// Number of sessions broken by packet loss.
+	pub broken_sessions: usize,
+}
+
+fn link_key(a: usize, b: usize) -> (usize, usize) {
+	if a < b { (a, b) } else { (b, a) }
+}
+
+impl<P: Peer> Simulator<P> {
+	/// Create a simulator over the given network. All randomness is derived from `seed`.
+	pub fn new(net: TestNet<P>, seed: [u32; 4]) -> Self {
+		Simulator {
+			net: net,
+			default_link: LinkConfig::default(),
+			links: HashMap::new(),
+			groups: None,
+			sessions: HashSet::new(),
+			banned: HashSet::new(),
+			reconnects: HashMap::new(),
+			in_flight: Vec::new(),
+			rng: XorShiftRng::from_seed(seed),
+			tick: 0,
+			broken_sessions: 0,
+		}
+	}
+
+	/// Get a peer by index.
+	pub fn peer(&self, i: usize) -> &P {
+		self.net.peer(i)
+	}
+
+	/// Number of ticks executed so far.
+	pub fn ticks(&self) -> usize {
+		self.tick
+	}
+
+	/// Set the link configuration used for all links without an explicit one.
+	pub fn set_default_link(&mut self, config: LinkConfig) {
+		self.default_link = config;
+	}
+
+	/// Set the configuration of the link between `a` and `b`.
+	pub fn set_link(&mut self, a: usize, b: usize, config: LinkConfig) {
+		self.links.insert(link_key(a, b), config);
+	}
+
+	/// Whether there is an active session between `a` and `b`.
+	pub fn is_connected(&self, a: usize, b: usize) -> bool {
+		self.sessions.contains(&link_key(a, b))
+	}
+
+	/// Split the network. Each slice of `groups` becomes an isolated partition;
+	/// peers not mentioned in any group form one more partition together.
+	pub fn partition(&mut self, groups: &[&[usize]]) {
+		let mut assignment = vec![groups.len(); self.net.peers.len()];
+		for (group, members) in groups.iter().enumerate() {
+			for &peer in members.iter() {
+				assignment[peer] = group;
+			}
+		}
+		self.groups = Some(assignment.clone());
+
+		let split: Vec<_> = self.sessions.iter()
+			.filter(|&&(a, b)| !self.reachable(a, b))
+			.cloned()
+			.collect();
+		for (a, b) in split {
+			self.disconnect(a, b);
+		}
+		self.reconnects.retain(|&(a, b), _| assignment[a] == assignment[b]);
+	}
+
+	/// Remove any partition and schedule reconnection of all split peers.
+	pub fn heal(&mut self) {
+		self.groups = None;
+		let tick = self.tick;
+		for a in 0..self.net.peers.len() {
+			for b in (a + 1)..self.net.peers.len() {
+				let key = (a, b);
+				if !self.sessions.contains(&key) && !self.banned.contains(&key) {
+					let delay = self.link(a, b).reconnect_delay;
+					self.reconnects.entry(key).or_insert(tick + delay);
+				}
+			}
+		}
+	}
+
+	/// Connect all reachable peers. Called implicitly by `tick`.
+	pub fn start(&mut self) {
+		if self.net.started {
+			return;
+		}
+		for a in 0..self.net.peers.len() {
+			for b in (a + 1)..self.net.peers.len() {
+				if self.reachable(a, b) {
+					self.connect(a, b);
+				}
+			}
+		}
+		self.net.started = true;
+	}
+
+	/// Advance the simulation by one tick: re-establish due sessions, send
+	/// pending packets, deliver arrived packets and run a sync step on every peer.
+	pub fn tick(&mut self) {
+		self.start();
+
+		let tick = self.tick;
+		let due: Vec<_> = self.reconnects.iter()
+			.filter(|&(_, &at)| at <= tick)
+			.map(|(&key, _)| key)
+			.collect();
+		for (a, b) in due {
+			self.reconnects.remove(&(a, b));
+			if self.reachable(a, b) && !self.sessions.contains(&(a, b)) {
+				self.connect(a, b);
+			}
+		}
+
+		for from in 0..self.net.peers.len() {
+			while let Some(message) = self.net.peers[from].pending_message() {
+				self.send(from, message);
+			}
+		}
+
+		let (arrived, pending): (Vec<_>, Vec<_>) = self.in_flight.drain(..).partition(|p| p.deliver_at <= tick);
+		self.in_flight = pending;
+		for packet in arrived {
+			self.deliver(packet);
+		}
+
+		for peer in 0..self.net.peers.len() {
+			self.net.sync_step_peer(peer);
+		}
+		self.tick += 1;
+	}
+
+	/// Run until there is nothing left to send or deliver, or `max_ticks` is reached.
+	/// Returns the number of ticks executed.
+	pub fn run(&mut self, max_ticks: usize) -> usize {
+		let start = self.tick;
+		while self.tick - start < max_ticks {
+			self.tick();
+			if self.is_idle() {
+				break;
+			}
+		}
+		self.tick - start
+	}
+
+	/// Run until `condition` holds or `max_ticks` is reached. Returns whether the condition was met.
+	pub fn run_until<F>(&mut self, max_ticks: usize, condition: F) -> bool
+		where F: Fn(&TestNet<P>) -> bool
+	{
+		for _ in 0..max_ticks {
+			if condition(&self.net) {
+				return true;
+			}
+			self.tick();
+		}
+		condition(&self.net)
+	}
+
+	/// Whether there are no packets queued or in flight and no pending reconnections.
+	pub fn is_idle(&self) -> bool {
+		self.in_flight.is_empty() && self.reconnects.is_empty() && self.net.done()
+	}
+
+	fn link(&self, a: usize, b: usize) -> LinkConfig {
+		self.links.get(&link_key(a, b)).cloned().unwrap_or_else(|| self.default_link.clone())
+	}
+
+	fn reachable(&self, a: usize, b: usize) -> bool {
+		self.groups.as_ref().map_or(true, |g| g[a] == g[b])
+	}
+
+	fn connect(&mut self, a: usize, b: usize) {
+		trace!(target: "sync", "simulator: connecting {} <-> {}", a, b);
+		self.sessions.insert(link_key(a, b));
+		self.net.peers[a].on_connect(b as PeerId);
+		self.net.peers[b].on_connect(a as PeerId);
+	}
+
+	fn disconnect(&mut self, a: usize, b: usize) {
+		trace!(target: "sync", "simulator: disconnecting {} <-> {}", a, b);
+		let key = link_key(a, b);
+		if !self.sessions.remove(&key) {
+			return;
+		}
+		// packets of a broken session are lost with it.
+		self.in_flight.retain(|p| link_key(p.from, p.message.recipient()) != key);
+		self.net.peers[a].on_disconnect(b as PeerId);
+		self.net.peers[b].on_disconnect(a as PeerId);
+	}
+
+	fn send(&mut self, from: usize, message: P::Message) {
+		let to = message.recipient();
+		if !self.is_connected(from, to) {
+			trace!(target: "sync", "simulator: discarding packet {} -> {} without session", from, to);
+			return;
+		}
+		let link = self.link(from, to);
+		if link.loss > 0.0 && self.rng.gen::<f64>() < link.loss {
+			trace!(target: "sync", "simulator: packet {} -> {} lost", from, to);
+			self.broken_sessions += 1;
+			self.disconnect(from, to);
+			self.reconnects.insert(link_key(from, to), self.tick + link.reconnect_delay);
+			return;
+		}
+		self.in_flight.push(InFlight {
+			deliver_at: self.tick + link.latency,
+			from: from,
+			message: message,
+		});
+	}
+
+	fn deliver(&mut self, packet: InFlight<P::Message>) {
+		let to = packet.message.recipient();
+		trace!(target: "sync", "--- {} -> {} ---", packet.from, to);
+		let to_disconnect = self.net.peers[to].receive_message(packet.from as PeerId, packet.message);
+		for d in to_disconnect {
+			self.net.disconnect_events.push((to, d));
+			self.banned.insert(link_key(to, d));
+			self.disconnect(to, d);
+		}
+	}
+}
+
+impl<C: FlushingBlockChainClient> Simulator<EthPeer<C>> {
+	/// Best block number and hash of every peer.
+	pub fn best_blocks(&self) -> Vec<(BlockNumber, H256)> {
+		self.net.peers.iter()
+			.map(|p| {
+				let info = p.chain.chain_info();
+				(info.best_block_number, info.best_block_hash)
+			})
+			.collect()
+	}
+
+	/// Whether all peers agree on the best block.
+	pub fn converged(&self) -> bool {
+		let best = self.best_blocks();
+		best.iter().all(|b| b == &best[0])
+	}
+}
@@ -14,15 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use hash::keccak;
-use ethereum_types::H256;
-use parking_lot::Mutex;
+use ethereum_types::{H256, U256};
+use parking_lot::{Mutex, RwLock};
 use bytes::Bytes;
+use rlp::{self, UntrustedRlp};
 use ethcore::snapshot::{SnapshotService, ManifestData, RestorationStatus};
 use ethcore::header::BlockNumber;
-use ethcore::client::{EachBlockWith};
+use ethcore::client::{BlockChainClient, BlockId, EachBlockWith};
+use chain::ChainSync;
 use super::helpers::*;
 use SyncConfig;
 
@@ -33,6 +35,13 @@ pub struct TestSnapshotService {
 	restoration_manifest: Mutex<Option<ManifestData>>,
 	state_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
 	block_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
+
+	state_nodes: Vec<H256>,
+	stale_state_nodes: Vec<H256>,
+	node_restoration_block: Mutex<Option<Bytes>>,
+	node_restoration_difficulty: Mutex<Option<U256>>,
+	node_restoration_moves: Mutex<usize>,
+	missing_state_nodes: Mutex<HashSet<H256>>,
 }
 
 impl TestSnapshotService {
@@ -43,9 +52,30 @@ impl TestSnapshotService {
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
 			block_restoration_chunks: Mutex::new(HashMap::new()),
+			state_nodes: Vec::new(),
+			stale_state_nodes: Vec::new(),
+			node_restoration_block: Mutex::new(None),
+			node_restoration_difficulty: Mutex::new(None),
+			node_restoration_moves: Mutex::new(0),
+			missing_state_nodes: Mutex::new(HashSet::new()),
 		}
 	}
 
+	/// Make node restorations require the given state nodes.
+	pub fn new_with_state_nodes(state_nodes: Vec<H256>) -> TestSnapshotService {
+		let mut service = TestSnapshotService::new();
+		service.state_nodes = state_nodes;
+		service
+	}
+
+	/// Make node restorations require the given state nodes, and the stale ones
+	/// only until the restoration is moved to another block.
+	pub fn new_with_stale_state_nodes(state_nodes: Vec<H256>, stale_state_nodes: Vec<H256>) -> TestSnapshotService {
+		let mut service = TestSnapshotService::new_with_state_nodes(state_nodes);
+		service.stale_state_nodes = stale_state_nodes;
+		service
+	}
+
 	pub fn new_with_snapshot(num_chunks: usize, block_hash: H256, block_number: BlockNumber) -> TestSnapshotService {
 		let num_state_chunks = num_chunks / 2;
 		let num_block_chunks = num_chunks - num_state_chunks;
@@ -67,6 +97,12 @@ impl TestSnapshotService {
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
 			block_restoration_chunks: Mutex::new(HashMap::new()),
+			state_nodes: Vec::new(),
+			stale_state_nodes: Vec::new(),
+			node_restoration_block: Mutex::new(None),
+			node_restoration_difficulty: Mutex::new(None),
+			node_restoration_moves: Mutex::new(0),
+			missing_state_nodes: Mutex::new(HashSet::new()),
 		}
 	}
 }
@@ -85,6 +121,18 @@ impl SnapshotService for TestSnapshotService {
 	}
 
 	fn status(&self) -> RestorationStatus {
+		if self.node_restoration_block.lock().is_some() {
+			let missing = self.missing_state_nodes.lock().len();
+			return match missing {
+				0 => RestorationStatus::Inactive,
+				_ => RestorationStatus::Ongoing {
+					state_chunks: self.state_nodes.len() as u32,
+					block_chunks: 0,
+					state_chunks_done: self.state_nodes.len().saturating_sub(missing) as u32,
+					block_chunks_done: 0,
+				},
+			};
+		}
 		match *self.restoration_manifest.lock() {
 			Some(ref manifest) if self.state_restoration_chunks.lock().len() == manifest.state_hashes.len() &&
 				self.block_restoration_chunks.lock().len() == manifest.block_hashes.len() => RestorationStatus::Inactive,
@@ -121,6 +169,35 @@ impl SnapshotService for TestSnapshotService {
 			self.block_restoration_chunks.lock().insert(hash, chunk);
 		}
 	}
+
+	fn begin_node_restore(&self, block: Bytes, _receipts: Bytes, total_difficulty: U256) {
+		*self.node_restoration_block.lock() = Some(block);
+		*self.node_restoration_difficulty.lock() = Some(total_difficulty);
+		*self.missing_state_nodes.lock() = self.state_nodes.iter().chain(self.stale_state_nodes.iter()).cloned().collect();
+	}
+
+	fn move_node_restore(&self, block: Bytes, receipts: Bytes, total_difficulty: U256) {
+		if self.node_restoration_block.lock().is_none() {
+			return self.begin_node_restore(block, receipts, total_difficulty);
+		}
+		*self.node_restoration_block.lock() = Some(block);
+		*self.node_restoration_difficulty.lock() = Some(total_difficulty);
+		*self.node_restoration_moves.lock() += 1;
+		let stale = &self.stale_state_nodes;
+		self.missing_state_nodes.lock().retain(|h| !stale.contains(h));
+	}
+
+	fn missing_state_nodes(&self, count: usize) -> Vec<H256> {
+		self.missing_state_nodes.lock().iter().take(count).cloned().collect()
+	}
+
+	fn restore_state_nodes(&self, nodes: Vec<Bytes>) {
+		// test clients serve the RLP of the requested hash as node data.
+		let mut missing = self.missing_state_nodes.lock();
+		for node in nodes {
+			missing.remove(&rlp::decode::<H256>(&node));
+		}
+	}
 }
 
 #[test]
@@ -139,3 +216,96 @@ fn snapshot_sync() {
 	assert_eq!(net.peer(4).snapshot_service.block_restoration_chunks.lock().len(), net.peer(0).snapshot_service.manifest.as_ref().unwrap().block_hashes.len());
 }
 
+
+#[test]
+fn checkpoint_sync() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(4);
+	for i in 0..3 {
+		net.peer(i).chain.add_blocks(100, EachBlockWith::Nothing);
+	}
+	let checkpoint_hash = net.peer(0).chain.block_hash(BlockId::Number(50)).unwrap();
+
+	// test clients only serve node data for hashes starting with 'f'.
+	let state_nodes: Vec<H256> = (0..1000).map(|_| {
+		let mut hash = H256::random();
+		hash[0] = 0xff;
+		hash
+	}).collect();
+
+	let mut config = SyncConfig::default();
+	let checkpoint_difficulty = net.peer(0).chain.block_total_difficulty(BlockId::Number(50)).unwrap();
+	config.checkpoint = Some((50, checkpoint_hash, checkpoint_difficulty));
+	{
+		let peer = net.peer_mut(3);
+		peer.snapshot_service = Arc::new(TestSnapshotService::new_with_state_nodes(state_nodes));
+		peer.sync = RwLock::new(ChainSync::new(config, &*peer.chain));
+	}
+	net.sync_steps(50);
+
+	let service = &net.peer(3).snapshot_service;
+	let block = service.node_restoration_block.lock().clone().expect("checkpoint block is downloaded");
+	assert_eq!(keccak(UntrustedRlp::new(&block).at(0).unwrap().as_raw()), checkpoint_hash);
+	assert_eq!(*service.node_restoration_difficulty.lock(), Some(checkpoint_difficulty));
+	assert!(service.missing_state_nodes.lock().is_empty());
+}
+
+#[test]
+fn pivot_state_sync() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(4);
+	for i in 0..3 {
+		net.peer(i).chain.add_blocks(200, EachBlockWith::Nothing);
+	}
+	let pivot_hash = net.peer(0).chain.block_hash(BlockId::Number(200 - 64)).unwrap();
+
+	// test clients only serve node data for hashes starting with 'f', so the stale
+	// nodes are never served and the pivot has to be moved to finish.
+	let state_nodes: Vec<H256> = (0..500).map(|_| {
+		let mut hash = H256::random();
+		hash[0] = 0xff;
+		hash
+	}).collect();
+	let stale_state_nodes: Vec<H256> = (0..5).map(|_| {
+		let mut hash = H256::random();
+		hash[0] = 0;
+		hash
+	}).collect();
+
+	let mut config = SyncConfig::default();
+	config.state_sync = true;
+	{
+		let peer = net.peer_mut(3);
+		peer.snapshot_service = Arc::new(TestSnapshotService::new_with_stale_state_nodes(state_nodes, stale_state_nodes));
+		peer.sync = RwLock::new(ChainSync::new(config, &*peer.chain));
+	}
+	net.sync_steps(100);
+
+	let service = &net.peer(3).snapshot_service;
+	let block = service.node_restoration_block.lock().clone().expect("pivot block is downloaded");
+	assert_eq!(keccak(UntrustedRlp::new(&block).at(0).unwrap().as_raw()), pivot_hash);
+	// test clients give block N a difficulty of N.
+	let head_difficulty = net.peer(0).chain.chain_info().total_difficulty;
+	let above_pivot = (200 - 63..201u64).fold(U256::zero(), |sum, n| sum + U256::from(n));
+	assert_eq!(*service.node_restoration_difficulty.lock(), Some(head_difficulty - above_pivot));
+	assert!(*service.node_restoration_moves.lock() > 0);
+	assert!(service.missing_state_nodes.lock().is_empty());
+}
+
+#[test]
+fn state_sync_short_chain() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer(0).chain.add_blocks(30, EachBlockWith::Nothing);
+	let mut config = SyncConfig::default();
+	config.state_sync = true;
+	{
+		let peer = net.peer_mut(1);
+		peer.sync = RwLock::new(ChainSync::new(config, &*peer.chain));
+	}
+	net.sync();
+
+	// the chain is too short to pick a pivot, so blocks are synced instead.
+	assert!(net.peer(1).snapshot_service.node_restoration_block.lock().is_none());
+	assert_eq!(net.peer(1).chain.chain_info().best_block_number, 30);
+}
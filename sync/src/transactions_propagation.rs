@@ -0,0 +1,87 @@
+// Copyright 2015-2017 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp;
+
+const SECOND_NS: u64 = 1_000_000_000;
+
+/// Limits the number of transactions sent to a single peer per second.
+#[derive(Debug)]
+pub struct RateLimit {
+	limit: usize,
+	available: usize,
+	window_start: u64,
+}
+
+impl RateLimit {
+	/// Create a new limiter allowing `limit` transactions per second, starting at `now` (in nanoseconds).
+	pub fn new(limit: usize, now: u64) -> Self {
+		RateLimit {
+			limit: limit,
+			available: limit,
+			window_start: now,
+		}
+	}
+
+	/// Number of transactions that may be sent at `now` without taking any.
+	pub fn available(&mut self, now: u64) -> usize {
+		if now.saturating_sub(self.window_start) >= SECOND_NS {
+			self.available = self.limit;
+			self.window_start = now;
+		}
+		self.available
+	}
+
+	/// Take up to `count` transactions from the allowance. Returns the number taken.
+	pub fn take(&mut self, count: usize, now: u64) -> usize {
+		let taken = cmp::min(count, self.available(now));
+		self.available -= taken;
+		taken
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{RateLimit, SECOND_NS};
+
+	#[test]
+	fn should_limit_within_a_second() {
+		// given
+		let mut limit = RateLimit::new(10, 0);
+
+		// when
+		let first = limit.take(6, 1);
+		let second = limit.take(6, SECOND_NS / 2);
+
+		// then
+		assert_eq!(first, 6);
+		assert_eq!(second, 4);
+		assert_eq!(limit.available(SECOND_NS - 1), 0);
+	}
+
+	#[test]
+	fn should_refill_after_a_second() {
+		// given
+		let mut limit = RateLimit::new(10, 0);
+		limit.take(10, 0);
+
+		// when
+		let taken = limit.take(20, SECOND_NS);
+
+		// then
+		assert_eq!(taken, 10);
+	}
+}
@@ -248,6 +248,8 @@ pub struct Database {
 	read_opts: ReadOptions,
 	block_opts: BlockBasedOptions,
 	path: String,
+	// Rejects writes through this handle when set.
+	writes_disabled: bool,
 	// Dirty values added with `write_buffered`. Cleaned on `flush`.
 	overlay: RwLock<Vec<HashMap<ElasticArray32<u8>, KeyState>>>,
 	// Values currently being flushed. Cleared when `flush` completes.
@@ -283,13 +285,25 @@ impl Database {
 
 	/// Open database file. Creates if it does not exist.
 	pub fn open(config: &DatabaseConfig, path: &str) -> Result<Database> {
+		Database::open_with_mode(config, path, false)
+	}
+
+	/// Open an existing database file. Column families are not created, no repair is
+	/// attempted and writes through the returned handle are rejected. Note that RocksDB
+	/// still opens the database read-write: it takes the lock and may update its own
+	/// files (e.g. the manifest or write-ahead log) or run compactions.
+	pub fn open_existing(config: &DatabaseConfig, path: &str) -> Result<Database> {
+		Database::open_with_mode(config, path, true)
+	}
+
+	fn open_with_mode(config: &DatabaseConfig, path: &str, writes_disabled: bool) -> Result<Database> {
 		let mut opts = Options::new();
 
 		if let Some(rate_limit) = config.compaction.write_rate_limit {
 			opts.set_parsed_options(&format!("rate_limiter_bytes_per_sec={}", rate_limit))?;
 		}
 		opts.set_use_fsync(false);
-		opts.create_if_missing(true);
+		opts.create_if_missing(!writes_disabled);
 		opts.set_max_open_files(config.max_open_files);
 		opts.set_parsed_options("keep_log_file_num=1")?;
 		opts.set_parsed_options("bytes_per_sync=1048576")?;
@@ -308,6 +322,9 @@ impl Database {
 		// attempt database repair if it has been previously marked as corrupted
 		let db_corrupted = Path::new(path).join(Database::CORRUPTION_FILE_NAME);
 		if db_corrupted.exists() {
+			if writes_disabled {
+				return Err("DB has been previously marked as corrupted, refusing to open without repair".into());
+			}
 			warn!("DB has been previously marked as corrupted, attempting repair");
 			DB::repair(&opts, path)?;
 			fs::remove_file(db_corrupted)?;
@@ -339,7 +356,9 @@ impl Database {
 							.expect("rocksdb opens a cf_handle for each cfname; qed")).collect();
 						Ok(db)
 					}
-					Err(_) => {
+					Err(e) => if writes_disabled {
+						Err(e)
+					} else {
 						// retry and create CFs
 						match DB::open_cf(&opts, path, &[], &[]) {
 							Ok(mut db) => {
@@ -348,7 +367,7 @@ impl Database {
 							},
 							err => err,
 						}
-					}
+					},
 				}
 			},
 			None => DB::open(&opts, path)
@@ -356,7 +375,7 @@ impl Database {
 
 		let db = match db {
 			Ok(db) => db,
-			Err(ref s) if is_corrupted(s) => {
+			Err(ref s) if is_corrupted(s) && !writes_disabled => {
 				warn!("DB corrupted: {}, attempting repair", s);
 				DB::repair(&opts, path)?;
 
@@ -381,6 +400,7 @@ impl Database {
 			flushing: RwLock::new((0..(num_cols + 1)).map(|_| HashMap::new()).collect()),
 			flushing_lock: Mutex::new(false),
 			path: path.to_owned(),
+			writes_disabled: writes_disabled,
 			read_opts: read_opts,
 			block_opts: block_opts,
 		})
@@ -420,6 +440,9 @@ impl Database {
 
 	/// Commit buffered changes to database. Must be called under `flush_lock`
 	fn write_flushing_with_lock(&self, _lock: &mut MutexGuard<bool>) -> Result<()> {
+		if self.writes_disabled {
+			return Err("Database writes are disabled".into());
+		}
 		match *self.db.read() {
 			Some(DBAndColumns { ref db, ref cfs }) => {
 				let batch = WriteBatch::new();
@@ -486,6 +509,9 @@ impl Database {
 
 	/// Commit transaction to database.
 	pub fn write(&self, tr: DBTransaction) -> Result<()> {
+		if self.writes_disabled {
+			return Err("Database writes are disabled".into());
+		}
 		match *self.db.read() {
 			Some(DBAndColumns { ref db, ref cfs }) => {
 				let batch = WriteBatch::new();
@@ -606,6 +632,9 @@ impl Database {
 
 	/// Restore the database from a copy at given path.
 	pub fn restore(&self, new_db: &str) -> Result<()> {
+		if self.writes_disabled {
+			return Err("Database writes are disabled".into());
+		}
 		self.close();
 
 		let mut backup_db = PathBuf::from(&self.path);
@@ -655,6 +684,9 @@ impl Database {
 
 	/// Drop a column family.
 	pub fn drop_column(&self) -> Result<()> {
+		if self.writes_disabled {
+			return Err("Database writes are disabled".into());
+		}
 		match *self.db.write() {
 			Some(DBAndColumns { ref mut db, ref mut cfs }) => {
 				if let Some(col) = cfs.pop() {
@@ -670,6 +702,9 @@ impl Database {
 
 	/// Add a column family.
 	pub fn add_column(&self) -> Result<()> {
+		if self.writes_disabled {
+			return Err("Database writes are disabled".into());
+		}
 		match *self.db.write() {
 			Some(DBAndColumns { ref mut db, ref mut cfs }) => {
 				let col = cfs.len() as u32;
@@ -857,4 +892,46 @@ mod tests {
 			assert_eq!(db.num_columns(), 0);
 		}
 	}
+
+	#[test]
+	fn open_existing() {
+		let config_2 = DatabaseConfig::with_columns(Some(2));
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().join("db");
+		let path = path.to_str().unwrap();
+		let key = H256::from_str("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+
+		// does not create missing databases.
+		assert!(Database::open_existing(&config_2, path).is_err());
+
+		{
+			let db = Database::open(&config_2, path).unwrap();
+			let mut batch = db.transaction();
+			batch.put(Some(1), &key, b"cat");
+			db.write(batch).unwrap();
+		}
+
+		{
+			let db = Database::open_existing(&config_2, path).unwrap();
+			assert_eq!(db.num_columns(), 2);
+			assert_eq!(&*db.get(Some(1), &key).unwrap().unwrap(), b"cat");
+
+			let mut batch = db.transaction();
+			batch.delete(Some(1), &key);
+			assert!(db.write(batch).is_err());
+			assert!(db.add_column().is_err());
+
+			let mut batch = db.transaction();
+			batch.put(Some(0), &key, b"dog");
+			db.write_buffered(batch);
+			assert!(db.flush().is_err());
+		}
+
+		// does not create missing columns.
+		assert!(Database::open_existing(&DatabaseConfig::with_columns(Some(3)), path).is_err());
+
+		let db = Database::open(&config_2, path).unwrap();
+		assert_eq!(&*db.get(Some(1), &key).unwrap().unwrap(), b"cat");
+		assert!(db.get(Some(0), &key).unwrap().is_none());
+	}
 }
@@ -222,7 +222,7 @@ pub struct NetworkContext<'s> {
 	sessions: Arc<RwLock<Slab<SharedSession>>>,
 	session: Option<SharedSession>,
 	session_id: Option<StreamToken>,
-	_reserved_peers: &'s HashSet<NodeId>,
+	reserved_peers: &'s HashSet<NodeId>,
 }
 
 impl<'s> NetworkContext<'s> {
@@ -238,7 +238,7 @@ impl<'s> NetworkContext<'s> {
 			session_id: id,
 			session: session,
 			sessions: sessions,
-			_reserved_peers: reserved_peers,
+			reserved_peers: reserved_peers,
 		}
 	}
 
@@ -313,6 +313,13 @@ impl<'s> NetworkContext<'s> {
 		self.resolve_session(peer).map(|s| s.lock().info.clone())
 	}
 
+	/// Checks if the peer is one of the configured reserved nodes.
+	pub fn is_reserved_peer(&self, peer: PeerId) -> bool {
+		self.session_info(peer)
+			.and_then(|info| info.id)
+			.map_or(false, |id| self.reserved_peers.contains(&id))
+	}
+
 	/// Returns max version for a given protocol.
 	pub fn protocol_version(&self, protocol: ProtocolId, peer: PeerId) -> Option<u8> {
 		let session = self.resolve_session(peer);